@@ -1,7 +1,10 @@
 #[macro_use]
 extern crate lalrpop_util;
 lalrpop_mod!(pub riscv_spec_lang, "/spec_lang/riscv_spec_lang.rs"); // synthesized by LALRPOP
+lalrpop_mod!(veriv_lang, "/veriv_lang.rs"); // synthesized by LALRPOP
 
 pub mod veriv_ast;
 pub mod spec_lang;
-mod utils;
+pub mod veriv_json;
+pub mod veriv_text;
+pub mod utils;
@@ -1,3 +1,4 @@
 pub mod sl_ast;
 pub mod sl_lexer;
 pub mod sl_parser;
+pub mod sl_type_checker;
@@ -1,14 +1,19 @@
 use std::{
     cell::RefCell,
     collections::{HashMap, HashSet},
+    fmt,
 };
 
 use crate::veriv_ast as ast;
 
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+
 // ==================================================================
 /// # AST Types
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum VType {
     Unknown,
     Bv(u16),
@@ -20,6 +25,7 @@ pub enum VType {
     },
     Struct {
         id: String,
+        #[serde(serialize_with = "utils::ordered_map")]
         fields: HashMap<String, Box<VType>>,
         size: u64,
     },
@@ -68,7 +74,7 @@ impl VType {
                 } => *out_type.clone(),
                 _ => panic!("ArrayIndex should have an array typed first argument."),
             },
-            ValueOp::Slice { lo, hi } => Self::Bv(hi - lo),
+            ValueOp::Slice { lo, hi } => Self::Bv(hi - lo + 1),
             ValueOp::GetField => match &exprs[0].typ() {
                 Self::Struct {
                     id,
@@ -156,22 +162,58 @@ impl VType {
             }
         }
     }
+
+    /// Returns the VERI-V IR type of the specification type
+    pub fn to_ast_type(&self) -> ast::Type {
+        match self {
+            Self::Unknown => ast::Type::Unknown,
+            Self::Bool => ast::Type::Bool,
+            Self::Int => ast::Type::Int,
+            Self::Bv(width) => ast::Type::Bv { w: *width as u64 },
+            Self::Array { in_type, out_type } => ast::Type::Array {
+                in_typs: vec![Box::new(in_type.to_ast_type())],
+                out_typ: Box::new(out_type.to_ast_type()),
+            },
+            Self::Struct { id, fields, size } => ast::Type::Struct {
+                id: id.clone(),
+                fields: fields
+                    .iter()
+                    .map(|(field_name, field_type)| (field_name.clone(), Box::new(field_type.to_ast_type())))
+                    .collect(),
+                w: *size,
+            },
+        }
+    }
+}
+impl fmt::Display for VType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "unknown"),
+            Self::Bv(width) => write!(f, "bv{}", width),
+            Self::Int => write!(f, "int"),
+            Self::Bool => write!(f, "bool"),
+            Self::Array { in_type, out_type } => write!(f, "[{}]{}", in_type, out_type),
+            Self::Struct { id, .. } => write!(f, "struct {}", id),
+        }
+    }
 }
 
 // ==================================================================
 /// # AST Expressions
 
 // Boolean expression
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BExpr {
     Bool(bool),
     // Boolean operator application
     BOpApp(BoolOp, Vec<BExpr>),
     // Comparison operator application
     COpApp(CompOp, Vec<VExpr>),
+    // Application of a predicate declared with `define` (expanded by the spec passes)
+    PredApp(String, Vec<VExpr>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BoolOp {
     Conj,                 // &&
     Disj,                 // ||
@@ -181,7 +223,7 @@ pub enum BoolOp {
     Exists(VExpr, VType), // exists
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CompOp {
     Equal,  // ==
     Nequal, // !=
@@ -196,7 +238,7 @@ pub enum CompOp {
 }
 
 // Value expression
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum VExpr {
     Bv { value: u64, typ: VType },
     Int(i64, VType),
@@ -252,7 +294,7 @@ impl VExpr {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ValueOp {
     Add,                        // +
     Sub,                        // -
@@ -271,27 +313,158 @@ pub enum ValueOp {
     Deref,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Spec {
-    Requires(BExpr),
-    Ensures(BExpr),
-    Modifies(HashSet<String>),
-    Track(String, VExpr),
+    /// Precondition and its location in the spec file (None if generated)
+    Requires(BExpr, Option<SpecLoc>),
+    /// Postcondition and its location in the spec file (None if generated)
+    Ensures(BExpr, Option<SpecLoc>),
+    Modifies(#[serde(serialize_with = "utils::ordered_set")] HashSet<String>),
+    /// Value recorded at the entry and exit of the function and at its call sites, and
+    /// its location in the spec file
+    Track(String, VExpr, Option<SpecLoc>),
+    /// Update of a ghost variable before the instruction at the address (the entry of
+    /// the function if None) and its location in the spec file
+    Update(String, VExpr, Option<u64>, Option<SpecLoc>),
 }
 impl Spec {
     pub fn get_bexpr(&self) -> Result<&BExpr, ()> {
         match self {
-            Self::Requires(e) => Ok(e),
-            Self::Ensures(e) => Ok(e),
+            Self::Requires(e, _) => Ok(e),
+            Self::Ensures(e, _) => Ok(e),
             _ => Err(()),
         }
     }
+    /// Returns the location of a clause in the spec file
+    pub fn loc(&self) -> Option<&SpecLoc> {
+        match self {
+            Self::Requires(_, loc) | Self::Ensures(_, loc) | Self::Track(_, _, loc) => loc.as_ref(),
+            Self::Update(_, _, _, loc) => loc.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Location (1-indexed line and column) of a specification in its file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpecLoc {
+    pub file: String,
+    pub line: usize,
+    pub col: usize,
+}
+impl SpecLoc {
+    /// Returns the location of the byte `offset` in the spec file `input`
+    pub fn from_offset(file: &str, input: &str, offset: usize) -> Self {
+        let before = &input[..offset.min(input.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        SpecLoc {
+            file: file.to_string(),
+            line: before.matches('\n').count() + 1,
+            col: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+impl fmt::Display for SpecLoc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.col)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct FuncSpec {
     pub fname: String,
     pub specs: Vec<Spec>,
+    /// Location of the `fun` block in the spec file
+    pub loc: Option<SpecLoc>,
+}
+
+/// Specification over the machine state of the whole-machine transition system
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MachineSpec {
+    /// Assumption on the initial state and its location in the spec file (None if generated)
+    Init(BExpr, Option<SpecLoc>),
+    /// Named invariant and its location in the spec file
+    Invariant(String, BExpr, Option<SpecLoc>),
+}
+
+/// Predicate or value macro `define name(params): type = body;` that the specifications
+/// can call by name
+#[derive(Debug, Clone)]
+pub struct Define {
+    pub name: String,
+    pub params: Vec<(String, VType)>,
+    pub body: DefineBody,
+    pub loc: Option<SpecLoc>,
+}
+
+#[derive(Debug, Clone)]
+pub enum DefineBody {
+    /// Body of a predicate (of type `bool`)
+    Pred(BExpr),
+    /// Body of a value macro and its declared type
+    Value(VExpr, VType),
+}
+
+/// Directive `include "path";` and its location. The path is relative to the
+/// directory of the including file
+#[derive(Debug, Clone)]
+pub struct Include {
+    pub path: String,
+    pub loc: Option<SpecLoc>,
+}
+
+/// Ghost variable `ghost name: type = init;` of the model that only the specifications
+/// refer to. The initial value is only assumed by the transition system
+#[derive(Debug, Clone)]
+pub struct Ghost {
+    pub name: String,
+    pub typ: VType,
+    pub init: Option<VExpr>,
+    pub loc: Option<SpecLoc>,
+}
+
+/// Top-level declaration of a spec file
+#[derive(Debug, Clone)]
+pub enum SpecItem {
+    Fun(FuncSpec),
+    Machine(Box<MachineSpec>),
+    Define(Box<Define>),
+    Include(Include),
+    Ghost(Box<Ghost>),
+}
+
+/// Declarations of one or more spec files
+#[derive(Debug, Clone, Default)]
+pub struct SpecFile {
+    pub func_specs: Vec<FuncSpec>,
+    pub machine_specs: Vec<MachineSpec>,
+    /// Definitions in the order of declaration
+    pub defines: Vec<Define>,
+    pub includes: Vec<Include>,
+    pub ghosts: Vec<Ghost>,
+}
+impl SpecFile {
+    pub fn from_items(items: Vec<SpecItem>) -> Self {
+        let mut spec_file = SpecFile::default();
+        for item in items {
+            match item {
+                SpecItem::Fun(func_spec) => spec_file.func_specs.push(func_spec),
+                SpecItem::Machine(machine_spec) => spec_file.machine_specs.push(*machine_spec),
+                SpecItem::Define(define) => spec_file.defines.push(*define),
+                SpecItem::Include(include) => spec_file.includes.push(include),
+                SpecItem::Ghost(ghost) => spec_file.ghosts.push(*ghost),
+            }
+        }
+        spec_file
+    }
+    /// Adds the declarations of `other` after the ones of this file
+    pub fn append(&mut self, mut other: SpecFile) {
+        self.func_specs.append(&mut other.func_specs);
+        self.machine_specs.append(&mut other.machine_specs);
+        self.defines.append(&mut other.defines);
+        self.includes.append(&mut other.includes);
+        self.ghosts.append(&mut other.ghosts);
+    }
 }
 
 // ==================================================================
@@ -302,6 +475,7 @@ pub trait ASTRewriter<C> {
     fn rewrite_bexpr_bool(bool_expr: BExpr, _ctx: &RefCell<C>) -> BExpr { bool_expr }
     fn rewrite_bexpr_bopapp(bopapp: BExpr, _ctx: &RefCell<C>) -> BExpr { bopapp }
     fn rewrite_bexpr_copapp(copapp: BExpr, _ctx: &RefCell<C>) -> BExpr { copapp }
+    fn rewrite_bexpr_predapp(predapp: BExpr, _ctx: &RefCell<C>) -> BExpr { predapp }
     fn rewrite_bexpr_boolop(bop: BoolOp, _ctx: &RefCell<C>) -> BoolOp { bop }
     fn rewrite_bexpr_compop(cop: CompOp, _ctx: &RefCell<C>) -> CompOp { cop }
 
@@ -311,6 +485,7 @@ pub trait ASTRewriter<C> {
             BExpr::Bool(_) => Self::visit_bexpr_bool(bexpr, context),
             BExpr::BOpApp(_, _) => Self::visit_bexpr_bopapp(bexpr, context),
             BExpr::COpApp(_, _) => Self::visit_bexpr_copapp(bexpr, context),
+            BExpr::PredApp(_, _) => Self::visit_bexpr_predapp(bexpr, context),
         };
         Self::rewrite_bexpr(rw_bexpr, context)
     }
@@ -343,6 +518,17 @@ pub trait ASTRewriter<C> {
         Self::rewrite_bexpr_copapp(rw_copapp, context)
     }
 
+    fn visit_bexpr_predapp(predapp: BExpr, context: &RefCell<C>) -> BExpr {
+        let rw_predapp = match predapp {
+            BExpr::PredApp(name, exprs) => {
+                let rw_vexprs = Self::visit_vexprs(exprs, context);
+                BExpr::PredApp(name, rw_vexprs)
+            }
+            _ => panic!("Implementation error; expected `BExpr::PredApp`."),
+        };
+        Self::rewrite_bexpr_predapp(rw_predapp, context)
+    }
+
     fn visit_bexpr_boolop(bop: BoolOp, context: &RefCell<C>) -> BoolOp {
         Self::rewrite_bexpr_boolop(bop, context)
     }
@@ -1,20 +1,45 @@
 use regex::Regex;
 use std::str::CharIndices;
 
+/// Lexer error with the byte range of the offending text
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct LexError {
     pub location: usize,
+    pub end: usize,
     pub code: ErrorCode,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ErrorCode {
+    /// Text that is not a keyword, literal, type, identifier or symbol
     UnrecognizedToken,
+    /// Number, bit-vector literal or type that is malformed or out of range
+    InvalidLiteral,
+    /// `_` that is not followed by `u`
+    ExpectedUnsignedSuffix,
+    /// Value expression where a boolean expression is expected
+    ExpectedBoolean,
+    /// String without a closing `"` on the same line
+    UnterminatedString,
 }
 
-fn error<T>(c: ErrorCode, l: usize) -> Result<T, LexError> {
+impl ErrorCode {
+    /// Returns the description of the error
+    pub fn description(&self) -> &'static str {
+        match self {
+            ErrorCode::UnrecognizedToken => "unrecognized token",
+            ErrorCode::InvalidLiteral => "invalid literal",
+            ErrorCode::ExpectedUnsignedSuffix => "expected \"_u\"",
+            ErrorCode::ExpectedBoolean => "expected a comparison or a predicate call",
+            ErrorCode::UnterminatedString => "unterminated string",
+        }
+    }
+}
+
+fn error<T>(c: ErrorCode, l: usize, e: usize) -> Result<T, LexError> {
     Err(LexError {
         location: l,
+        end: e,
         code: c,
     })
 }
@@ -27,7 +52,13 @@ pub enum Tok<'input> {
     Requires,
     Modifies,
     Track,
+    Update,
     Fun,
+    Init,
+    Invariant,
+    Define,
+    Include,
+    Ghost,
     True,
     False,
     Old,
@@ -42,12 +73,15 @@ pub enum Tok<'input> {
     Concat,      // ++
     // Identifier:
     Id(&'input str),
+    // String (without the quotes):
+    Str(&'input str),
     // Primitives:
     Int(i64),
     Bv { value: u64, width: u16 },
     Bool(bool),
     // Types:
     BvType(u16),
+    BoolType,
     // Symbols:
     Colon,           // :
     ColonColon,      // ::
@@ -55,6 +89,7 @@ pub enum Tok<'input> {
     Comma,           // ,
     Dot,             // .
     Equals,          // =
+    Implies,         // ==>
     GreaterThan,     // >
     LessThan,        // <
     Plus,            // +
@@ -85,7 +120,14 @@ const KEYWORDS: &'static [(&'static str, Tok<'static>)] = &[
     ("requires", Tok::Requires),
     ("modifies", Tok::Modifies),
     ("track", Tok::Track),
+    ("update", Tok::Update),
     ("fun", Tok::Fun),
+    ("init", Tok::Init),
+    ("invariant", Tok::Invariant),
+    ("define", Tok::Define),
+    ("include", Tok::Include),
+    ("ghost", Tok::Ghost),
+    ("bool", Tok::BoolType),
     ("false", Tok::False),
     ("true", Tok::True),
     ("old", Tok::Old),
@@ -139,34 +181,46 @@ impl<'input> Iterator for Lexer<'input> {
             if let Ok(i) = word.parse::<i64>() {
                 return Some(Ok((start, Tok::Int(i), end)));
             }
+            // Hexadecimal numbers (e.g. instruction addresses)
+            if let Some(hex) = word.strip_prefix("0x") {
+                return match u64::from_str_radix(hex, 16) {
+                    Ok(i) => Some(Ok((start, Tok::Int(i as i64), end))),
+                    Err(_) => Some(error(ErrorCode::InvalidLiteral, start, end)),
+                };
+            }
             // Convert types
             if Regex::new(r"^bv[0-9]+").unwrap().is_match(word) {
                 let split = word.split("bv").collect::<Vec<&'input str>>();
-                let width = split[1].parse::<u64>().unwrap();
-                return Some(Ok((start, Tok::BvType(width as u16), end)));
+                return match split[1].parse::<u16>() {
+                    Ok(width) => Some(Ok((start, Tok::BvType(width), end))),
+                    Err(_) => Some(error(ErrorCode::InvalidLiteral, start, end)),
+                };
             }
             // Convert bitvectors
             if Regex::new(r"^[0-9]+bv[0-9]+").unwrap().is_match(word) {
-                let split: Vec<i64> = word
-                    .split("bv")
-                    .map(|num_str| num_str.parse::<i64>().unwrap())
-                    .collect();
-                let value = split[0] as u64;
-                let width = split[1] as u16;
-                return Some(Ok((start, Tok::Bv { value, width }, end)));
+                let split = word.split("bv").collect::<Vec<&'input str>>();
+                return match (split[0].parse::<i64>(), split[1].parse::<u16>()) {
+                    (Ok(value), Ok(width)) => {
+                        Some(Ok((start, Tok::Bv { value: value as u64, width }, end)))
+                    }
+                    _ => Some(error(ErrorCode::InvalidLiteral, start, end)),
+                };
             }
             // Check if it's as an identifier
             // This would be for function names, variables, etc
             if Regex::new(r"^[[:alpha:]]\w*").unwrap().is_match(word) {
                 return Some(Ok((start, Tok::Id(word), end)));
             }
+            // Numbers that do not fit
+            if word.chars().all(|c| c.is_ascii_digit()) {
+                return Some(error(ErrorCode::InvalidLiteral, start, end));
+            }
             // Invalid token
-            // TODO: Return more informative error message
-            Some(error(ErrorCode::UnrecognizedToken, start))
+            Some(error(ErrorCode::UnrecognizedToken, start, end))
         }
         loop {
             match self.chars.next() {
-                Some((_, ' ')) | Some((_, '\n')) | Some((_, '\t')) => continue,
+                Some((_, ' ')) | Some((_, '\n')) | Some((_, '\t')) | Some((_, '\r')) => continue,
                 Some((i, ':')) => {
                     if let Some((_, ':')) = self.chars.peek() {
                         self.chars.next();
@@ -178,7 +232,14 @@ impl<'input> Iterator for Lexer<'input> {
                 Some((i, ';')) => return Some(Ok((i, Tok::Semi, i + 1))), // ;
                 Some((i, ',')) => return Some(Ok((i, Tok::Comma, i + 1))), // ,
                 Some((i, '.')) => return Some(Ok((i, Tok::Dot, i + 1))),  // .
-                Some((i, '=')) => return Some(Ok((i, Tok::Equals, i + 1))), // =
+                Some((i, '=')) => {
+                    if self.input[i..].starts_with("==>") {
+                        self.chars.nth(1);
+                        return Some(Ok((i, Tok::Implies, i + 3))); // ==>
+                    } else {
+                        return Some(Ok((i, Tok::Equals, i + 1))); // =
+                    }
+                }
                 Some((i, '>')) => {
                     if let Some((_, '>')) = self.chars.peek() {
                         self.chars.next();
@@ -270,13 +331,34 @@ impl<'input> Iterator for Lexer<'input> {
                         return Some(Ok((i, Tok::UnsignedCompExt, i + 2)));
                     } else {
                         // Expected _u
-                        return None;
+                        return Some(error(ErrorCode::ExpectedUnsignedSuffix, i, i + 1));
+                    }
+                }
+                Some((i, '"')) => {
+                    // "..." on a single line
+                    let start = i + 1;
+                    let len = self.input[start..]
+                        .find(['"', '\n'])
+                        .unwrap_or(self.input.len() - start);
+                    let end = start + len;
+                    while let Some((j, _)) = self.chars.peek() {
+                        if *j >= end {
+                            break;
+                        }
+                        self.chars.next();
+                    }
+                    if self.input[end..].starts_with('"') {
+                        self.chars.next();
+                        return Some(Ok((i, Tok::Str(&self.input[start..end]), end + 1)));
+                    } else {
+                        return Some(error(ErrorCode::UnterminatedString, i, end));
                     }
                 }
                 None => return None, // End of file
                 Some((i, _)) => loop {
                     match self.chars.peek() {
-                        Some((j, ' ')) | Some((j, ':')) | Some((j, ';')) | Some((j, ','))
+                        Some((j, ' ')) | Some((j, '\n')) | Some((j, '\t')) | Some((j, '\r'))
+                        | Some((j, ':')) | Some((j, ';')) | Some((j, ','))
                         | Some((j, '.')) | Some((j, '=')) | Some((j, '>')) | Some((j, '<'))
                         | Some((j, '+')) | Some((j, '-')) | Some((j, '?')) | Some((j, '*'))
                         | Some((j, '/')) | Some((j, '&')) | Some((j, '|')) | Some((j, '~'))
@@ -1,42 +1,256 @@
-use std::fs;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use lalrpop_util::ParseError;
 
 use crate::spec_lang::{sl_ast, sl_lexer};
 
-use crate::riscv_spec_lang::FuncSpecsParser;
+use crate::riscv_spec_lang::SpecFileParser;
 
 use crate::utils;
 
 pub struct SpecParser;
 
+/// Spec files that are loaded by `process_spec_files`
+#[derive(Default)]
+struct IncludeState {
+    /// Canonical paths of the loaded files
+    loaded: HashSet<PathBuf>,
+    /// Canonical paths and names of the files whose includes are being loaded
+    stack: Vec<(PathBuf, String)>,
+    specs: sl_ast::SpecFile,
+    errors: Vec<String>,
+}
+
 impl SpecParser {
     pub fn new() -> Self {
         SpecParser {}
     }
 
+    /// Parses the spec files and the files they include. The errors of all the
+    /// files are reported together
     pub fn process_spec_files(
         &self,
         spec_file_paths: &Vec<&str>,
-    ) -> Result<Vec<sl_ast::FuncSpec>, utils::Error> {
-        let mut specs_map = vec![];
+    ) -> Result<sl_ast::SpecFile, utils::Error> {
+        let mut state = IncludeState::default();
         for spec_file_path in spec_file_paths {
-            specs_map.append(&mut self.process_spec_file(spec_file_path)?);
+            self.load_spec_file(Path::new(spec_file_path), None, &mut state);
+        }
+        // A function has a single `fun` block
+        let mut first_locs: HashMap<&String, Option<&sl_ast::SpecLoc>> = HashMap::new();
+        for func_spec in &state.specs.func_specs {
+            let loc = func_spec.loc.as_ref();
+            match first_locs.get(&func_spec.fname) {
+                Some(first_loc) => {
+                    let at = loc.map_or(String::new(), |loc| format!("{}: ", loc));
+                    let first_at = first_loc.map_or(String::new(), |loc| format!(" at {}", loc));
+                    state.errors.push(format!(
+                        "{}duplicate specification of {}; the first one is{}",
+                        at, func_spec.fname, first_at
+                    ));
+                }
+                None => {
+                    first_locs.insert(&func_spec.fname, loc);
+                }
+            }
+        }
+        if state.errors.is_empty() {
+            Ok(state.specs)
+        } else {
+            Err(utils::Error::SpecParseError(state.errors.join("\n")))
         }
-        Ok(specs_map)
     }
 
-    fn process_spec_file(
+    /// Parses the spec file `path` after the files that it includes. `include_loc`
+    /// is the location of the include directive of the file
+    fn load_spec_file(
         &self,
-        spec_file_path: &str,
-    ) -> Result<Vec<sl_ast::FuncSpec>, utils::Error> {
-        let specs_str = match fs::read_to_string(spec_file_path) {
-            Ok(res) => res,
-            Err(e) => panic!("Failed to read spec file: {}. {}", spec_file_path, e),
+        path: &Path,
+        include_loc: Option<&sl_ast::SpecLoc>,
+        state: &mut IncludeState,
+    ) {
+        let at = include_loc.map_or(String::new(), |loc| format!("{}: ", loc));
+        let name = path.display().to_string();
+        let canonical = match fs::canonicalize(path) {
+            Ok(canonical) => canonical,
+            Err(e) => {
+                state.errors.push(format!(
+                    "{}{}: unable to read the spec file: {}",
+                    at, name, e
+                ));
+                return;
+            }
         };
-        Ok(self.parse(&specs_str))
+        if let Some(i) = state.stack.iter().position(|(p, _)| *p == canonical) {
+            let cycle = state.stack[i..]
+                .iter()
+                .map(|(_, name)| &name[..])
+                .chain(std::iter::once(&name[..]))
+                .collect::<Vec<_>>();
+            state
+                .errors
+                .push(format!("{}include cycle {}", at, cycle.join(" -> ")));
+            return;
+        }
+        // Files that are included more than once are only loaded once
+        if !state.loaded.insert(canonical.clone()) {
+            return;
+        }
+        let res = fs::read_to_string(path)
+            .map_err(|e| {
+                utils::Error::SpecParseError(format!(
+                    "{}{}: unable to read the spec file: {}",
+                    at, name, e
+                ))
+            })
+            .and_then(|specs_str| self.parse(&specs_str, &name));
+        let spec_file = match res {
+            Ok(spec_file) => spec_file,
+            Err(e) => {
+                state.errors.push(e.to_string());
+                return;
+            }
+        };
+        state.stack.push((canonical, name));
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for include in &spec_file.includes {
+            self.load_spec_file(&dir.join(&include.path), include.loc.as_ref(), state);
+        }
+        state.stack.pop();
+        state.specs.append(spec_file);
     }
 
-    fn parse(&self, input: &str) -> Vec<sl_ast::FuncSpec> {
-        let lexer = sl_lexer::Lexer::new(input);
-        FuncSpecsParser::new().parse(input, lexer).unwrap()
+    /// Parses the specifications of the spec file `file` and reports all of its errors
+    pub fn parse(&self, input: &str, file: &str) -> Result<sl_ast::SpecFile, utils::Error> {
+        // Invalid tokens are skipped so that the parser sees the rest of the file.
+        // The syntax errors at the token after an invalid token are not reported
+        let mut lex_errors = vec![];
+        let mut after_lex_error = HashSet::new();
+        let mut skipped = false;
+        let mut tokens = vec![];
+        for res in sl_lexer::Lexer::new(input) {
+            match res {
+                Ok(tok) => {
+                    if skipped {
+                        after_lex_error.insert(tok.0);
+                        skipped = false;
+                    }
+                    tokens.push(Ok(tok));
+                }
+                Err(e) => {
+                    lex_errors.push(e);
+                    skipped = true;
+                }
+            }
+        }
+        let mut recovered = vec![];
+        let res = SpecFileParser::new().parse(input, file, &mut recovered, tokens);
+        let mut errors = lex_errors
+            .into_iter()
+            .map(|e| {
+                let text = &input[e.location..e.end.min(input.len())];
+                (e.location, format!("{} \"{}\"", e.code.description(), text))
+            })
+            .collect::<Vec<_>>();
+        let parse_errors = recovered
+            .into_iter()
+            .map(|rec| rec.error)
+            .chain(res.as_ref().err().cloned());
+        for error in parse_errors {
+            let (loc, msg) = match error {
+                ParseError::InvalidToken { location } => (location, "invalid token".to_string()),
+                ParseError::UnrecognizedEOF { location, expected } => {
+                    if skipped {
+                        continue;
+                    }
+                    (
+                        location,
+                        format!("unexpected end of file; {}", expected_to_text(&expected)),
+                    )
+                }
+                ParseError::UnrecognizedToken {
+                    token: (start, _, end),
+                    expected,
+                } => {
+                    if after_lex_error.contains(&start) {
+                        continue;
+                    }
+                    (
+                        start,
+                        format!("unexpected \"{}\"; {}", &input[start..end], expected_to_text(&expected)),
+                    )
+                }
+                ParseError::ExtraToken {
+                    token: (start, _, end),
+                } => (start, format!("extra token \"{}\"", &input[start..end])),
+                ParseError::User { error } => (error.location, error.code.description().to_string()),
+            };
+            errors.push((loc, msg));
+        }
+        if errors.is_empty() {
+            // The parser only fails without recovering if there are errors
+            return Ok(res.expect("The parser should have reported the error."));
+        }
+        errors.sort_by_key(|(loc, _)| *loc);
+        let msgs = errors
+            .iter()
+            .map(|(loc, msg)| error_to_text(input, file, *loc, msg))
+            .collect::<Vec<_>>();
+        Err(utils::Error::SpecParseError(msgs.join("\n")))
+    }
+}
+
+/// Returns the expected terminals with readable names for the tokens with values
+fn expected_to_text(expected: &[String]) -> String {
+    let names = expected
+        .iter()
+        .map(|t| match t.trim_matches('"') {
+            "id" => "identifier".to_string(),
+            "str" => "string".to_string(),
+            "int" => "number".to_string(),
+            "bv" => "bit-vector literal".to_string(),
+            "bv_type" => "bit-vector type".to_string(),
+            "bool_type" => "\"bool\"".to_string(),
+            "bool" => "\"$tt\" or \"$ff\"".to_string(),
+            t => format!("\"{}\"", t),
+        })
+        .collect::<Vec<_>>();
+    match names.len() {
+        1 => format!("expected {}", names[0]),
+        _ => format!("expected one of {}", names.join(", ")),
     }
 }
+
+/// Returns the error message followed by the line of the error with a caret under
+/// the byte offset `loc`, e.g.
+///
+/// ```text
+/// specs.rvspecs:3:21: unexpected ";"; expected one of "(", identifier
+///   3 |     requires a0 >_u ;
+///     |                     ^
+/// ```
+fn error_to_text(input: &str, file: &str, loc: usize, msg: &str) -> String {
+    let spec_loc = sl_ast::SpecLoc::from_offset(file, input, loc);
+    let loc = loc.min(input.len());
+    let line_start = input[..loc].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = input[loc..].find('\n').map_or(input.len(), |i| loc + i);
+    // Tabs are kept so that the caret lines up with the source line
+    let pad = input[line_start..loc]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect::<String>();
+    let gutter = " ".repeat(spec_loc.line.to_string().len());
+    format!(
+        "{}: {}\n {} | {}\n {} | {}^",
+        spec_loc,
+        msg,
+        spec_loc.line,
+        &input[line_start..line_end],
+        gutter,
+        pad
+    )
+}
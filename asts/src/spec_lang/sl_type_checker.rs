@@ -0,0 +1,555 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::spec_lang::sl_ast::{self, BExpr, BoolOp, CompOp, VExpr, VType, ValueOp};
+
+use crate::utils;
+
+/// Types of the variables that the specifications can refer to
+pub trait TypeEnv {
+    /// Returns the type of the variable `name` in the specifications of the function
+    /// `fname` (empty for the specifications over the machine state)
+    fn var_type(&self, fname: &str, name: &str) -> Option<VType>;
+}
+
+/// Signature of a checked definition
+struct DefineSig {
+    param_typs: Vec<VType>,
+    /// Type of the body (`bool` for predicates)
+    typ: VType,
+}
+
+/// Type checker for the specification AST. All the type errors of the specifications
+/// are reported together so that the later passes can assume well-typed specifications
+pub struct SpecTypeChecker<'a, E: TypeEnv> {
+    env: &'a E,
+    /// Function of the checked specification (empty for the machine specifications)
+    fname: String,
+    /// Location and description of the checked specification for the error messages
+    context: String,
+    /// Quantified variables (and parameters of a definition) in scope with the innermost last
+    bound_vars: Vec<(String, VType)>,
+    /// Definitions that are checked, and the names of all the definitions
+    defines: HashMap<String, DefineSig>,
+    define_names: HashSet<String>,
+    /// Types of the declared ghost variables
+    ghosts: HashMap<String, VType>,
+    /// Kind of the checked expression if it is assigned in the model (ghost code or
+    /// a tracked value) for the error messages
+    model_code: Option<&'static str>,
+    errors: Vec<String>,
+}
+
+impl<'a, E: TypeEnv> SpecTypeChecker<'a, E> {
+    pub fn new(env: &'a E) -> Self {
+        SpecTypeChecker {
+            env,
+            fname: String::new(),
+            context: String::new(),
+            bound_vars: vec![],
+            defines: HashMap::new(),
+            define_names: HashSet::new(),
+            ghosts: HashMap::new(),
+            model_code: None,
+            errors: vec![],
+        }
+    }
+
+    /// Checks all the specifications of `spec_file`
+    pub fn check_spec_file(mut self, spec_file: &sl_ast::SpecFile) -> Result<(), utils::Error> {
+        self.define_names = spec_file.defines.iter().map(|d| d.name.to_string()).collect();
+        for ghost in &spec_file.ghosts {
+            self.check_ghost(ghost);
+        }
+        // A definition can only call the definitions before it
+        for define in &spec_file.defines {
+            self.check_define(define);
+        }
+        for func_spec in &spec_file.func_specs {
+            let desc = format!("in the specification of {}", func_spec.fname);
+            let mut tracked = HashSet::new();
+            for spec in &func_spec.specs {
+                match spec {
+                    sl_ast::Spec::Requires(bexpr, loc) | sl_ast::Spec::Ensures(bexpr, loc) => {
+                        self.enter(&func_spec.fname, loc.as_ref(), &desc);
+                        self.check_bexpr(bexpr);
+                    }
+                    sl_ast::Spec::Track(name, vexpr, loc) => {
+                        self.enter(&func_spec.fname, loc.as_ref(), &desc);
+                        if !tracked.insert(name) {
+                            self.error(format!("tracked value \"{}\" is already declared", name));
+                        }
+                        self.model_code = Some("tracked values");
+                        self.check_vexpr(vexpr);
+                        self.model_code = None;
+                    }
+                    sl_ast::Spec::Update(name, vexpr, _, loc) => {
+                        // The updates are executed in the blocks of the function, where
+                        // the formal arguments are not in scope
+                        self.enter("", loc.as_ref(), &desc);
+                        self.check_update(name, vexpr);
+                    }
+                    sl_ast::Spec::Modifies(_) => (),
+                }
+            }
+        }
+        for machine_spec in &spec_file.machine_specs {
+            match machine_spec {
+                sl_ast::MachineSpec::Init(bexpr, loc) => {
+                    self.enter("", loc.as_ref(), "in init");
+                    self.check_bexpr(bexpr);
+                }
+                sl_ast::MachineSpec::Invariant(name, bexpr, loc) => {
+                    self.enter("", loc.as_ref(), &format!("in invariant {}", name));
+                    self.check_bexpr(bexpr);
+                }
+            }
+        }
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(utils::Error::SpecTypeError(self.errors.join("\n")))
+        }
+    }
+
+    /// Starts checking a specification of the function `fname`
+    fn enter(&mut self, fname: &str, loc: Option<&sl_ast::SpecLoc>, desc: &str) {
+        self.fname = fname.to_string();
+        self.context = match loc {
+            Some(loc) => format!("{}: {}", loc, desc),
+            None => desc.to_string(),
+        };
+        self.bound_vars.clear();
+    }
+
+    fn check_define(&mut self, define: &sl_ast::Define) {
+        self.enter("", define.loc.as_ref(), &format!("in define {}", define.name));
+        if self.defines.contains_key(&define.name) {
+            self.error(format!("\"{}\" is already defined", define.name));
+            return;
+        }
+        for (i, (param, typ)) in define.params.iter().enumerate() {
+            if define.params[..i].iter().any(|(p, _)| p == param) {
+                self.error(format!("duplicate parameter \"{}\"", param));
+            }
+            self.bound_vars.push((param.to_string(), typ.clone()));
+        }
+        let typ = match &define.body {
+            sl_ast::DefineBody::Pred(bexpr) => {
+                self.check_bexpr(bexpr);
+                VType::Bool
+            }
+            sl_ast::DefineBody::Value(vexpr, typ) => {
+                match self.check_vexpr(vexpr) {
+                    Some(body_typ) if body_typ != *typ => self.error(format!(
+                        "body of type {} for a definition of type {}",
+                        body_typ, typ
+                    )),
+                    _ => (),
+                }
+                typ.clone()
+            }
+        };
+        let sig = DefineSig {
+            param_typs: define.params.iter().map(|(_, typ)| typ.clone()).collect(),
+            typ,
+        };
+        self.defines.insert(define.name.to_string(), sig);
+    }
+
+    fn check_ghost(&mut self, ghost: &sl_ast::Ghost) {
+        self.enter("", ghost.loc.as_ref(), &format!("in ghost {}", ghost.name));
+        if self.ghosts.contains_key(&ghost.name) {
+            self.error(format!("ghost variable \"{}\" is already declared", ghost.name));
+            return;
+        }
+        if self.env.var_type("", &ghost.name).is_some() {
+            self.error(format!(
+                "ghost variable \"{}\" shadows a variable with the same name",
+                ghost.name
+            ));
+        }
+        if let Some(init) = &ghost.init {
+            self.model_code = Some("ghost code");
+            match self.check_vexpr(init) {
+                Some(typ) if typ != ghost.typ => self.error(format!(
+                    "initial value of type {} for a ghost variable of type {}",
+                    typ, ghost.typ
+                )),
+                _ => (),
+            }
+            self.model_code = None;
+        }
+        self.ghosts.insert(ghost.name.to_string(), ghost.typ.clone());
+    }
+
+    fn check_update(&mut self, name: &str, vexpr: &VExpr) {
+        self.model_code = Some("ghost code");
+        let typ = self.check_vexpr(vexpr);
+        self.model_code = None;
+        match (self.ghosts.get(name).cloned(), typ) {
+            (None, _) => self.error(format!("\"{}\" is not a ghost variable", name)),
+            (Some(ghost_typ), Some(typ)) if typ != ghost_typ => self.error(format!(
+                "value of type {} for the ghost variable {} of type {}",
+                typ, name, ghost_typ
+            )),
+            _ => (),
+        }
+    }
+
+    fn error(&mut self, msg: String) {
+        self.errors.push(format!("{}: {}", self.context, msg));
+    }
+
+    fn check_bexpr(&mut self, bexpr: &BExpr) {
+        match bexpr {
+            BExpr::Bool(_) => (),
+            BExpr::BOpApp(bop, bexprs) => match bop {
+                BoolOp::Forall(var, typ) | BoolOp::Exists(var, typ) => {
+                    // The rewriting passes do not scope the quantified variables
+                    let name = var.get_ident_name();
+                    let shadows = self.bound_vars.iter().any(|(v, _)| v == name)
+                        || self.env.var_type(&self.fname, name).is_some()
+                        || self.ghosts.contains_key(name);
+                    if shadows {
+                        self.error(format!(
+                            "quantified variable \"{}\" shadows a variable with the same name",
+                            name
+                        ));
+                    }
+                    self.bound_vars.push((name.to_string(), typ.clone()));
+                    for bexpr in bexprs {
+                        self.check_bexpr(bexpr);
+                    }
+                    self.bound_vars.pop();
+                }
+                _ => {
+                    for bexpr in bexprs {
+                        self.check_bexpr(bexpr);
+                    }
+                }
+            },
+            BExpr::PredApp(name, vexprs) => {
+                let typ = match &name[..] {
+                    "old" | "sext" | "uext" => self.check_funcapp(name, vexprs),
+                    _ => self.check_call(name, vexprs),
+                };
+                match typ {
+                    Some(VType::Bool) | None => (),
+                    Some(_) => self.error(format!("\"{}\" is not a predicate", name)),
+                }
+            }
+            BExpr::COpApp(cop, vexprs) => {
+                let typs = self.check_vexprs(vexprs);
+                if let Some([t1, t2]) = typs.as_deref() {
+                    let sym = cop_symbol(cop);
+                    match cop {
+                        CompOp::Equal | CompOp::Nequal => {
+                            self.same_types(sym, t1, t2, |_| true, "");
+                        }
+                        CompOp::Gtu | CompOp::Ltu | CompOp::Geu | CompOp::Leu => {
+                            self.same_types(sym, t1, t2, is_bv, "bit-vector");
+                        }
+                        _ => {
+                            self.same_types(sym, t1, t2, is_numeric, "bit-vector or integer");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the type of `vexpr` or None if it is ill-typed or of unknown type
+    fn check_vexpr(&mut self, vexpr: &VExpr) -> Option<VType> {
+        match vexpr {
+            VExpr::Bv { value, typ } => {
+                if let VType::Bv(width) = typ {
+                    if !bv_lit_fits(*value, *width) {
+                        self.error(format!("literal {}bv{} does not fit in {} bits", value, width, width));
+                    }
+                }
+                Some(typ.clone())
+            }
+            VExpr::Int(_, _) => Some(VType::Int),
+            VExpr::Bool(_, _) => Some(VType::Bool),
+            VExpr::Ident(name, _) => {
+                let bound_typ = self
+                    .bound_vars
+                    .iter()
+                    .rev()
+                    .find(|(v, _)| v == name)
+                    .map(|(_, typ)| typ.clone());
+                let typ = bound_typ
+                    .or_else(|| self.env.var_type(&self.fname, name))
+                    .or_else(|| self.ghosts.get(name).cloned());
+                match typ {
+                    Some(VType::Unknown) => None,
+                    Some(typ) => Some(typ),
+                    None => {
+                        self.error(format!("unknown identifier \"{}\"", name));
+                        None
+                    }
+                }
+            }
+            VExpr::OpApp(op, vexprs, _) => self.check_opapp(op, vexprs),
+            VExpr::FuncApp(fname, vexprs, _) => self.check_funcapp(fname, vexprs),
+        }
+    }
+
+    /// Checks all of `vexprs` and returns their types if they are all known
+    fn check_vexprs(&mut self, vexprs: &[VExpr]) -> Option<Vec<VType>> {
+        let typs = vexprs
+            .iter()
+            .map(|vexpr| self.check_vexpr(vexpr))
+            .collect::<Vec<_>>();
+        typs.into_iter().collect()
+    }
+
+    fn check_opapp(&mut self, op: &ValueOp, vexprs: &[VExpr]) -> Option<VType> {
+        // The model has no division
+        if let (Some(code), ValueOp::Div) = (self.model_code, op) {
+            self.error(format!("\"{}\" is not supported in {}", vop_symbol(op), code));
+            return None;
+        }
+        if let ValueOp::GetField = op {
+            // The field is not a variable
+            let typ = self.check_vexpr(&vexprs[0])?;
+            let field = vexprs[1].get_ident_name();
+            return match &typ {
+                VType::Struct { id, fields, .. } => match fields.get(field) {
+                    Some(field_typ) => Some(*field_typ.clone()),
+                    None => {
+                        self.error(format!("struct {} has no field \"{}\"", id, field));
+                        None
+                    }
+                },
+                _ => {
+                    self.error(format!(
+                        "cannot select the field \"{}\" of a value of type {}",
+                        field, typ
+                    ));
+                    None
+                }
+            };
+        }
+        let typs = self.check_vexprs(vexprs)?;
+        let sym = vop_symbol(op);
+        match op {
+            ValueOp::Add | ValueOp::Sub | ValueOp::Mul | ValueOp::Div => {
+                self.same_types(&sym, &typs[0], &typs[1], is_numeric, "bit-vector or integer")
+            }
+            ValueOp::BvXor
+            | ValueOp::BvOr
+            | ValueOp::BvAnd
+            | ValueOp::RightShift
+            | ValueOp::URightShift
+            | ValueOp::LeftShift => self.same_types(&sym, &typs[0], &typs[1], is_bv, "bit-vector"),
+            ValueOp::Concat => match (&typs[0], &typs[1]) {
+                (VType::Bv(w1), VType::Bv(w2)) => Some(VType::Bv(w1 + w2)),
+                (t1, t2) => {
+                    self.error(format!(
+                        "\"++\" expects bit-vector operands but found {} and {}",
+                        t1, t2
+                    ));
+                    None
+                }
+            },
+            ValueOp::Slice { lo, hi } => match &typs[0] {
+                VType::Bv(width) if lo <= hi && hi < width => Some(VType::Bv(hi - lo + 1)),
+                typ => {
+                    self.error(format!("slice [{}:{}] is out of range for type {}", hi, lo, typ));
+                    None
+                }
+            },
+            ValueOp::ArrayIndex => match &typs[0] {
+                VType::Array { in_type, out_type } => {
+                    if **in_type != typs[1] {
+                        self.error(format!(
+                            "index of type {} for an array of type {}; expected {}",
+                            typs[1], typs[0], in_type
+                        ));
+                    }
+                    Some(*out_type.clone())
+                }
+                typ => {
+                    self.error(format!("cannot index a value of type {}", typ));
+                    None
+                }
+            },
+            ValueOp::Deref => match &typs[0] {
+                VType::Bv(8) | VType::Bv(16) | VType::Bv(32) | VType::Bv(64) => Some(typs[0].clone()),
+                typ => {
+                    self.error(format!("cannot dereference a value of type {}", typ));
+                    None
+                }
+            },
+            ValueOp::GetField => unreachable!(),
+        }
+    }
+
+    /// Checks the call of the definition `name` and returns the type of its body
+    fn check_call(&mut self, name: &str, vexprs: &[VExpr]) -> Option<VType> {
+        let typs = self.check_vexprs(vexprs);
+        let sig = match self.defines.get(name) {
+            Some(sig) => sig,
+            None => {
+                if self.define_names.contains(name) {
+                    self.error(format!("\"{}\" is used before its definition", name));
+                } else {
+                    self.error(format!("unknown function \"{}\"", name));
+                }
+                return None;
+            }
+        };
+        let mut errors = vec![];
+        if sig.param_typs.len() != vexprs.len() {
+            errors.push(format!(
+                "{} expects {} argument(s) but found {}",
+                name,
+                sig.param_typs.len(),
+                vexprs.len()
+            ));
+        } else if let Some(typs) = typs {
+            for (i, (typ, param_typ)) in typs.iter().zip(sig.param_typs.iter()).enumerate() {
+                if typ != param_typ {
+                    errors.push(format!(
+                        "argument {} of {} has type {}; expected {}",
+                        i + 1,
+                        name,
+                        typ,
+                        param_typ
+                    ));
+                }
+            }
+        }
+        let typ = sig.typ.clone();
+        let ok = errors.is_empty();
+        errors.into_iter().for_each(|msg| self.error(msg));
+        if ok {
+            Some(typ)
+        } else {
+            None
+        }
+    }
+
+    fn check_funcapp(&mut self, fname: &str, vexprs: &[VExpr]) -> Option<VType> {
+        if let (Some(code), "old") = (self.model_code, fname) {
+            self.error(format!("old is not supported in {}", code));
+            return None;
+        }
+        let arity = match fname {
+            "old" => 1,
+            "sext" | "uext" => 2,
+            _ => {
+                return match self.check_call(fname, vexprs) {
+                    Some(VType::Bool) => {
+                        self.error(format!("predicate {} is used as a value", fname));
+                        None
+                    }
+                    typ => typ,
+                }
+            }
+        };
+        if vexprs.len() != arity {
+            self.error(format!(
+                "{} expects {} argument(s) but found {}",
+                fname,
+                arity,
+                vexprs.len()
+            ));
+            return None;
+        }
+        let typs = self.check_vexprs(vexprs)?;
+        match fname {
+            "old" => Some(typs[0].clone()),
+            _ => match (&vexprs[0], &typs[1]) {
+                (VExpr::Int(ext, _), VType::Bv(width)) if *ext >= 0 => {
+                    Some(VType::Bv(width + *ext as u16))
+                }
+                (VExpr::Int(ext, _), typ) if *ext >= 0 => {
+                    self.error(format!("{} expects a bit-vector but found {}", fname, typ));
+                    None
+                }
+                _ => {
+                    self.error(format!(
+                        "the number of bits of {} should be a non-negative integer literal",
+                        fname
+                    ));
+                    None
+                }
+            },
+        }
+    }
+
+    /// Returns the type of the operands of `sym` if they are the same and satisfy `pred`
+    fn same_types(
+        &mut self,
+        sym: &str,
+        t1: &VType,
+        t2: &VType,
+        pred: fn(&VType) -> bool,
+        expected: &str,
+    ) -> Option<VType> {
+        if t1 != t2 {
+            self.error(format!("mismatched types {} and {} for \"{}\"", t1, t2, sym));
+            None
+        } else if !pred(t1) {
+            self.error(format!("\"{}\" expects {} operands but found {}", sym, expected, t1));
+            None
+        } else {
+            Some(t1.clone())
+        }
+    }
+}
+
+fn is_bv(typ: &VType) -> bool {
+    matches!(typ, VType::Bv(_))
+}
+
+fn is_numeric(typ: &VType) -> bool {
+    matches!(typ, VType::Bv(_) | VType::Int)
+}
+
+/// Returns true if `value` is representable with `width` bits (negated literals
+/// are stored sign extended)
+fn bv_lit_fits(value: u64, width: u16) -> bool {
+    match width {
+        0 => false,
+        w if w >= 64 => true,
+        w => value >> w == 0 || (0 > value as i64 && value as i64 >= -(1i64 << (w - 1))),
+    }
+}
+
+fn cop_symbol(cop: &CompOp) -> &'static str {
+    match cop {
+        CompOp::Equal => "==",
+        CompOp::Nequal => "!=",
+        CompOp::Gt => ">",
+        CompOp::Lt => "<",
+        CompOp::Gtu => ">_u",
+        CompOp::Ltu => "<_u",
+        CompOp::Geq => ">=",
+        CompOp::Leq => "<=",
+        CompOp::Geu => ">=_u",
+        CompOp::Leu => "<=_u",
+    }
+}
+
+fn vop_symbol(op: &ValueOp) -> String {
+    match op {
+        ValueOp::Add => "+".to_string(),
+        ValueOp::Sub => "-".to_string(),
+        ValueOp::Div => "/".to_string(),
+        ValueOp::Mul => "*".to_string(),
+        ValueOp::BvXor => "^".to_string(),
+        ValueOp::BvOr => "|".to_string(),
+        ValueOp::BvAnd => "&".to_string(),
+        ValueOp::RightShift => ">>".to_string(),
+        ValueOp::URightShift => ">>>".to_string(),
+        ValueOp::LeftShift => "<<".to_string(),
+        ValueOp::ArrayIndex => "[]".to_string(),
+        ValueOp::GetField => ".".to_string(),
+        ValueOp::Slice { lo, hi } => format!("[{}:{}]", hi, lo),
+        ValueOp::Concat => "++".to_string(),
+        ValueOp::Deref => "*".to_string(),
+    }
+}
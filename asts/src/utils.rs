@@ -1,5 +1,52 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use serde::{Serialize, Serializer};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
     // Specification parser errors
     SpecParseError(String),
+    // Specification type errors
+    SpecTypeError(String),
+    // Serialized IR errors
+    IRFormatError(String),
+    // Textual IR parser errors
+    IRParseError(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::SpecParseError(msg)
+            | Error::SpecTypeError(msg)
+            | Error::IRFormatError(msg)
+            | Error::IRParseError(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+/// Serializes the set in sorted order so that the output is deterministic
+pub fn ordered_set<T, S>(set: &HashSet<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Ord + Serialize,
+    S: Serializer,
+{
+    let mut sorted = set.iter().collect::<Vec<_>>();
+    sorted.sort();
+    sorted.serialize(serializer)
+}
+
+/// Serializes the map in key order so that the output is deterministic
+pub fn ordered_map<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Ord + Serialize,
+    V: Serialize,
+    S: Serializer,
+{
+    let mut sorted = map.iter().collect::<Vec<_>>();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+    serializer.collect_map(sorted)
 }
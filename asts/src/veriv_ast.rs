@@ -4,17 +4,22 @@ use std::{
     fmt,
     cell::RefCell,
     hash::Hash,
+    rc::Rc,
 };
 
 use crate::spec_lang::sl_ast;
 
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+
 // =======================================================
 /// # VERI-V IR AST
 
 // =======================================================
 /// ## AST Types
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum Type {
     Unknown,
     Bool,
@@ -116,7 +121,7 @@ impl fmt::Display for Type {
 // =======================================================
 /// ## AST Expressions
 
-#[derive(Hash, PartialEq, Eq, Clone)]
+#[derive(Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum Expr {
     Literal(Literal, Type),
     Var(Var, Type),
@@ -276,7 +281,7 @@ impl fmt::Display for Expr {
 }
 
 /// Literals
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum Literal {
     Bv { val: u64, width: u64 },
     Bool { val: bool },
@@ -303,7 +308,7 @@ impl fmt::Display for Literal {
 }
 
 /// Variable
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct Var {
     pub name: String,
     pub typ: Type,
@@ -328,7 +333,7 @@ impl fmt::Display for Var {
 }
 
 // Operator application
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct OpApp {
     pub op: Op,
     pub operands: Vec<Expr>,
@@ -362,7 +367,7 @@ impl fmt::Display for OpApp {
 }
 
 /// Operators
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum Op {
     Comp(CompOp),
     Bv(BVOp),
@@ -372,7 +377,7 @@ pub enum Op {
 }
 
 /// Comparison operators
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum CompOp {
     Equality,
     Inequality,
@@ -387,7 +392,7 @@ pub enum CompOp {
 }
 
 /// BV operators
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum BVOp {
     Add,
     Sub,
@@ -405,7 +410,7 @@ pub enum BVOp {
 }
 
 /// Boolean operators
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum BoolOp {
     Conj, // and: &&
     Disj, // or: ||
@@ -415,7 +420,7 @@ pub enum BoolOp {
 }
 
 /// Function application
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct FuncApp {
     pub func_name: String,
     pub operands: Vec<Expr>,
@@ -436,6 +441,8 @@ impl fmt::Display for FuncApp {
 pub trait ASTRewriter<C> {
     fn rewrite_stmt(stmt: Stmt, _ctx: &RefCell<C>) -> Stmt { stmt }
     fn rewrite_stmt_assume(stmt: Stmt, _ctx: &RefCell<C>) -> Stmt { stmt }
+    fn rewrite_stmt_assert(stmt: Stmt, _ctx: &RefCell<C>) -> Stmt { stmt }
+    fn rewrite_stmt_havoc(stmt: Stmt, _ctx: &RefCell<C>) -> Stmt { stmt }
     fn rewrite_funccall(fc: FuncCall, _ctx: &RefCell<C>) -> FuncCall { fc }
     fn rewrite_assign(a: Assign, _ctx: &RefCell<C>) -> Assign { a }
     fn rewrite_ite(ite: IfThenElse, _ctx: &RefCell<C>) -> IfThenElse { ite }
@@ -454,6 +461,8 @@ pub trait ASTRewriter<C> {
     fn visit_stmt(stmt: Stmt, ctx: &RefCell<C>) -> Stmt {
         let rw_stmt = match stmt {
             Stmt::Assume(_) => Self::visit_stmt_assume(stmt, ctx),
+            Stmt::Assert(_) => Self::visit_stmt_assert(stmt, ctx),
+            Stmt::Havoc(_) => Self::visit_stmt_havoc(stmt, ctx),
             Stmt::FuncCall(_) => Self::visit_stmt_funccall(stmt, ctx),
             Stmt::Assign(_) => Self::visit_stmt_assign(stmt, ctx),
             Stmt::IfThenElse(_) => Self::visit_stmt_ifthenelse(stmt, ctx),
@@ -469,6 +478,20 @@ pub trait ASTRewriter<C> {
         };
         Self::rewrite_stmt_assume(rw_stmt, ctx)
     }
+    fn visit_stmt_assert(stmt: Stmt, ctx: &RefCell<C>) -> Stmt {
+        let rw_stmt = match stmt {
+            Stmt::Assert(e) => Stmt::Assert(Self::visit_expr(e, ctx)),
+            _ => panic!("Implementation error; Expected assert statement."),
+        };
+        Self::rewrite_stmt_assert(rw_stmt, ctx)
+    }
+    fn visit_stmt_havoc(stmt: Stmt, ctx: &RefCell<C>) -> Stmt {
+        let rw_stmt = match stmt {
+            Stmt::Havoc(v) => Stmt::Havoc(Rc::new(Self::visit_var((*v).clone(), ctx))),
+            _ => panic!("Implementation error; Expected havoc statement."),
+        };
+        Self::rewrite_stmt_havoc(rw_stmt, ctx)
+    }
     fn visit_expr(expr: Expr, ctx: &RefCell<C>) -> Expr {
         let rw_expr = match expr {
             Expr::Literal(_, _) => Self::visit_expr_lit(expr, ctx),
@@ -599,9 +622,11 @@ pub trait ASTRewriter<C> {
 // =======================================================
 /// ## AST Statements
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Stmt {
     Assume(Expr),
+    Assert(Expr),
+    Havoc(Rc<Var>),
     FuncCall(FuncCall),
     Assign(Assign),
     IfThenElse(IfThenElse),
@@ -640,10 +665,13 @@ impl Stmt {
     pub fn assign(lhs: Vec<Expr>, rhs: Vec<Expr>) -> Self {
         Stmt::Assign(Assign { lhs, rhs })
     }
+    pub fn havoc(var: Var) -> Self {
+        Stmt::Havoc(Rc::new(var))
+    }
 }
 
 /// Function call statement
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FuncCall {
     pub func_name: String,
     pub lhs: Vec<Expr>,
@@ -651,14 +679,14 @@ pub struct FuncCall {
 }
 
 /// Assign statement
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Assign {
     pub lhs: Vec<Expr>,
     pub rhs: Vec<Expr>,
 }
 
 /// If then else statement
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct IfThenElse {
     pub cond: Expr,
     pub then_stmt: Box<Stmt>,
@@ -668,7 +696,7 @@ pub struct IfThenElse {
 // =======================================================
 /// ## (Software) Procedure Model
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FuncModel {
     pub sig: FuncSig,
     pub body: Stmt,
@@ -709,7 +737,7 @@ impl FuncModel {
 }
 
 /// Function signature
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FuncSig {
     pub name: String,
     pub entry_addr: u64,
@@ -718,6 +746,7 @@ pub struct FuncSig {
     pub requires: Vec<sl_ast::Spec>,
     pub ensures: Vec<sl_ast::Spec>,
     pub tracked: Vec<sl_ast::Spec>,
+    #[serde(serialize_with = "utils::ordered_set")]
     pub mod_set: HashSet<String>,
 }
 
@@ -750,14 +779,28 @@ impl FuncSig {
     }
 }
 
+// =======================================================
+/// ## Transition System
+/// Whole-machine transition system; each step executes the block at `pc`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TransitionSystem {
+    /// Call of the inline procedure that executes one step
+    pub step: FuncCall,
+    /// Initial-state assumptions and invariants over the machine state
+    pub specs: Vec<sl_ast::MachineSpec>,
+}
+
 // =======================================================
 /// ## Verification Model
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Model {
     pub name: String,
+    #[serde(serialize_with = "utils::ordered_set")]
     pub vars: HashSet<Var>,
     pub func_models: Vec<FuncModel>,
+    /// Step relation of the whole machine (None for procedure models)
+    pub transition: Option<TransitionSystem>,
 }
 
 impl Model {
@@ -766,6 +809,7 @@ impl Model {
             name: String::from(name),
             vars: HashSet::new(),
             func_models: vec![],
+            transition: None,
         }
     }
     pub fn add_func_model(&mut self, fm: FuncModel) {
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+use crate::veriv_ast::Model;
+
+/// Name of the serialized IR format
+pub const FORMAT_NAME: &str = "veriv-ir";
+/// Version of the serialized IR format; bump whenever the IR types change
+pub const FORMAT_VERSION: u64 = 4;
+
+// =====================================================================================
+/// # Versioned JSON encoding of the VERI-V IR
+
+#[derive(Serialize)]
+struct ModelFileRef<'a> {
+    format: &'a str,
+    version: u64,
+    model: &'a Model,
+}
+
+#[derive(Deserialize)]
+struct ModelFileHeader {
+    format: String,
+    version: u64,
+    model: serde_json::Value,
+}
+
+/// Returns the model serialized as versioned JSON
+pub fn model_to_json(model: &Model) -> String {
+    let file = ModelFileRef {
+        format: FORMAT_NAME,
+        version: FORMAT_VERSION,
+        model,
+    };
+    serde_json::to_string_pretty(&file).expect("Unable to serialize the model.")
+}
+
+/// Rebuilds a model from versioned JSON produced by `model_to_json`
+pub fn model_from_json(json: &str) -> Result<Model, utils::Error> {
+    let file: ModelFileHeader = serde_json::from_str(json)
+        .map_err(|e| utils::Error::IRFormatError(format!("{}", e)))?;
+    if file.format != FORMAT_NAME {
+        return Err(utils::Error::IRFormatError(format!(
+            "Expected format \"{}\" but found \"{}\".",
+            FORMAT_NAME, file.format
+        )));
+    }
+    if file.version != FORMAT_VERSION {
+        return Err(utils::Error::IRFormatError(format!(
+            "Unsupported format version {} (expected {}).",
+            file.version, FORMAT_VERSION
+        )));
+    }
+    serde_json::from_value(file.model).map_err(|e| utils::Error::IRFormatError(format!("{}", e)))
+}
@@ -0,0 +1,737 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+};
+
+use lalrpop_util::ParseError;
+
+use crate::spec_lang::sl_ast::{self, BExpr, VExpr, VType, ValueOp};
+use crate::utils;
+use crate::veriv_ast::*;
+use crate::veriv_lang::ModelParser;
+
+// =====================================================================================
+// # Textual VERI-V IR
+//
+// The printer only writes a type annotation `(e : T)` where the type of `e` differs
+// from the one the parser infers, so printing and parsing a model gives back the same
+// model, except that the branches of if statements are always blocks and comments
+// with several lines become one comment per line. The clauses that come from a spec
+// file end with their location `@ "file":line:col`, so the reports of the obligations
+// of a loaded model still name the clauses.
+
+/// Returns the textual representation of the model
+pub fn model_to_text(model: &Model) -> String {
+    let mut scope = Scope::default();
+    let mut vars = model.vars.iter().collect::<Vec<_>>();
+    vars.sort();
+    let var_decls = vars
+        .iter()
+        .map(|v| {
+            scope.declare_var(&v.name, &v.typ);
+            format!("var {}: {};\n", v.name, type_to_text(&v.typ))
+        })
+        .collect::<String>();
+    let func_models = model
+        .func_models
+        .iter()
+        .map(|fm| format!("\n{}\n", func_model_to_text(fm, &mut scope)))
+        .collect::<String>();
+    let transition = model
+        .transition
+        .as_ref()
+        .map_or(String::new(), |ts| format!("\n{}\n", transition_to_text(ts, &mut scope)));
+    format!("model {};\n\n{}{}{}", model.name, var_decls, func_models, transition)
+}
+
+/// Rebuilds a model from its textual representation
+pub fn model_from_text(text: &str) -> Result<Model, utils::Error> {
+    let scope = RefCell::new(Scope::default());
+    ModelParser::new().parse(&scope, text).map_err(|e| {
+        let (loc, msg) = match e {
+            ParseError::InvalidToken { location } => (location, "invalid token".to_string()),
+            ParseError::UnrecognizedEOF { location, expected } => (
+                location,
+                format!("unexpected end of file; expected one of {}", expected_to_text(&expected)),
+            ),
+            ParseError::UnrecognizedToken { token, expected } => (
+                token.0,
+                format!(
+                    "unexpected token \"{}\"; expected one of {}",
+                    (token.1).1,
+                    expected_to_text(&expected)
+                ),
+            ),
+            ParseError::ExtraToken { token } => {
+                (token.0, format!("extra token \"{}\"", (token.1).1))
+            }
+            ParseError::User { error } => (error.0, error.1.to_string()),
+        };
+        let (line, col) = line_col(text, loc);
+        utils::Error::IRParseError(format!("{}:{}: {}", line, col, msg))
+    })
+}
+
+/// Returns the expected terminals with readable names for the regular expressions
+fn expected_to_text(expected: &[String]) -> String {
+    expected
+        .iter()
+        .map(|t| match &t[..] {
+            r##"r#"//[^\n\r]*"#"## => "comment",
+            r##"r#"[0-9]+bv[0-9]+"#"## => "bit-vector literal",
+            r##"r#"0x[0-9a-fA-F]+"#"## => "address",
+            r##"r#"[0-9]+"#"## => "number",
+            r##"r#"bv[0-9]+"#"## => "bit-vector type",
+            r##"r#"[A-Za-z_][A-Za-z0-9_.]*"#"## => "identifier",
+            r###"r#"\"[^\"\\n\\r]*\""#"### => "string",
+            t => t,
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns the 1-based line and column of the byte offset `loc` in `text`
+fn line_col(text: &str, loc: usize) -> (usize, usize) {
+    let before = &text[..loc.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let col = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, col)
+}
+
+// =====================================================================================
+// ## Scope
+
+/// Types of the identifiers that are visible while printing or parsing
+#[derive(Default)]
+pub struct Scope {
+    /// Model variables
+    vars: HashMap<String, Type>,
+    /// Arguments of the current procedure
+    args: HashMap<String, Type>,
+    /// Quantified specification variables (innermost last)
+    bound: Vec<(String, VType)>,
+}
+
+impl Scope {
+    pub(crate) fn declare_var(&mut self, name: &str, typ: &Type) {
+        self.vars.insert(name.to_string(), typ.clone());
+    }
+    pub(crate) fn enter_proc(&mut self, arg_decls: &[Expr]) {
+        self.args = arg_decls
+            .iter()
+            .map(|arg| (arg.get_var_name(), arg.typ().clone()))
+            .collect();
+    }
+    pub(crate) fn bind(&mut self, name: &str, typ: &VType) {
+        self.bound.push((name.to_string(), typ.clone()));
+    }
+    pub(crate) fn unbind(&mut self) {
+        self.bound.pop();
+    }
+    /// Returns the type of the IR variable `name`
+    pub(crate) fn var_type(&self, name: &str) -> Type {
+        self.args
+            .get(name)
+            .or_else(|| self.vars.get(name))
+            .cloned()
+            .unwrap_or(Type::Unknown)
+    }
+    /// Returns the type of the specification identifier `name`
+    pub(crate) fn spec_type(&self, name: &str) -> VType {
+        match self.bound.iter().rev().find(|(x, _)| x == name) {
+            Some((_, typ)) => typ.clone(),
+            None => match self.var_type(name) {
+                Type::Array { in_typs, .. } if in_typs.is_empty() => VType::Unknown,
+                typ => VType::from_ast_type(&typ),
+            },
+        }
+    }
+}
+
+// =====================================================================================
+// ## Type inference shared by the printer and the parser
+
+/// Returns the type of the operator application `op(operands)`
+fn op_type(op: &Op, operands: &[Expr]) -> Type {
+    match op {
+        Op::Comp(_) | Op::Bool(_) => Type::Bool,
+        Op::Bv(_) => operands.first().map_or(Type::Unknown, |e| e.typ().clone()),
+        Op::ArrayIndex => match operands.first().map(|e| e.typ()) {
+            Some(Type::Array { out_typ, .. }) => *out_typ.clone(),
+            _ => Type::Unknown,
+        },
+        Op::GetField(f) => match operands.first().map(|e| e.typ()) {
+            Some(Type::Struct { fields, .. }) => fields.get(f).map_or(Type::Unknown, |t| *t.clone()),
+            _ => Type::Unknown,
+        },
+    }
+}
+
+/// Returns the type of the specification operator application `op(operands)`
+fn value_op_type(op: &ValueOp, operands: &[VExpr]) -> VType {
+    let typ = |i: usize| operands.get(i).map_or(VType::Unknown, |e| e.typ().clone());
+    match op {
+        ValueOp::ArrayIndex => match typ(0) {
+            VType::Array { out_type, .. } => *out_type,
+            _ => VType::Unknown,
+        },
+        ValueOp::Slice { lo, hi } if hi >= lo => VType::Bv(hi - lo),
+        ValueOp::Slice { .. } => VType::Unknown,
+        ValueOp::GetField => match (typ(0), operands.get(1)) {
+            (VType::Struct { fields, .. }, Some(VExpr::Ident(f, _))) => {
+                fields.get(f).map_or(VType::Unknown, |t| *t.clone())
+            }
+            _ => VType::Unknown,
+        },
+        ValueOp::Concat => match (typ(0), typ(1)) {
+            (VType::Bv(w0), VType::Bv(w1)) => VType::Bv(w0 + w1),
+            _ => VType::Unknown,
+        },
+        ValueOp::RightShift | ValueOp::URightShift | ValueOp::LeftShift => typ(1),
+        _ => typ(0),
+    }
+}
+
+/// Returns the type of the specification function application `func(operands)`
+fn func_app_vtype(func: &str, operands: &[VExpr]) -> VType {
+    match (func, operands) {
+        ("old", [e]) | ("value", [e]) => e.typ().clone(),
+        ("sext", [w, e]) | ("uext", [w, e]) => match (w.get_lit_value(), e.typ()) {
+            (Some(w), VType::Bv(ew)) => VType::Bv(ew + w as u16),
+            _ => VType::Unknown,
+        },
+        _ => VType::Unknown,
+    }
+}
+
+/// Returns the type of the field `f` of the specification struct `vexpr`
+fn field_vtype(vexpr: &VExpr, f: &str) -> VType {
+    match vexpr.typ() {
+        VType::Struct { fields, .. } => fields.get(f).map_or(VType::Unknown, |t| *t.clone()),
+        _ => VType::Unknown,
+    }
+}
+
+// =====================================================================================
+// ## Parser helpers
+
+pub(crate) fn op_app(op: Op, operands: Vec<Expr>) -> Expr {
+    let typ = op_type(&op, &operands);
+    Expr::OpApp(OpApp { op, operands }, typ)
+}
+
+pub(crate) fn vexpr_op_app(op: ValueOp, operands: Vec<VExpr>) -> VExpr {
+    let typ = value_op_type(&op, &operands);
+    VExpr::OpApp(op, operands, typ)
+}
+
+pub(crate) fn vexpr_func_app(func: String, operands: Vec<VExpr>) -> VExpr {
+    let typ = func_app_vtype(&func, &operands);
+    VExpr::FuncApp(func, operands, typ)
+}
+
+pub(crate) fn vexpr_field(vexpr: VExpr, f: String) -> VExpr {
+    let field = VExpr::Ident(f.clone(), field_vtype(&vexpr, &f));
+    vexpr_op_app(ValueOp::GetField, vec![vexpr, field])
+}
+
+/// Replaces the type of `expr` with the annotated type `typ`
+pub(crate) fn with_type(expr: Expr, typ: Type) -> Expr {
+    match expr {
+        Expr::Literal(lit, _) => Expr::Literal(lit, typ),
+        Expr::Var(var, _) => Expr::var(&var.name, typ),
+        Expr::OpApp(opapp, _) => Expr::OpApp(opapp, typ),
+        Expr::FuncApp(fapp, _) => Expr::FuncApp(fapp, typ),
+    }
+}
+
+/// Replaces the type of `vexpr` with the annotated type `typ`
+pub(crate) fn with_vtype(vexpr: VExpr, typ: VType) -> VExpr {
+    match vexpr {
+        VExpr::Bv { value, typ: _ } => VExpr::Bv { value, typ },
+        VExpr::Int(i, _) => VExpr::Int(i, typ),
+        VExpr::Bool(b, _) => VExpr::Bool(b, typ),
+        VExpr::Ident(name, _) => VExpr::Ident(name, typ),
+        VExpr::OpApp(op, operands, _) => VExpr::OpApp(op, operands, typ),
+        VExpr::FuncApp(func, operands, _) => VExpr::FuncApp(func, operands, typ),
+    }
+}
+
+/// Splits `(e1 op e2 op ...)` into the operator and its operands; the operators must be the same
+pub(crate) fn op_chain<O, E>(
+    first: E,
+    rest: Vec<(O, E)>,
+    same: impl Fn(&O, &O) -> bool,
+) -> Result<(O, Vec<E>), &'static str> {
+    let mut operands = vec![first];
+    let mut op: Option<O> = None;
+    for (next_op, operand) in rest {
+        if op.as_ref().is_some_and(|op| !same(op, &next_op)) {
+            return Err("different operators in one application; add parentheses");
+        }
+        op = Some(next_op);
+        operands.push(operand);
+    }
+    Ok((op.expect("Expected at least one operator."), operands))
+}
+
+/// Returns the value and width of the bit-vector literal `lit` (e.g. 5bv64)
+pub(crate) fn bv_lit_parts(lit: &str) -> Option<(u64, u64)> {
+    let mut parts = lit.split("bv");
+    let value = parts.next()?.parse::<u64>().ok()?;
+    let width = parts.next()?.parse::<u64>().ok()?;
+    Some((value, width))
+}
+
+/// Returns the text of the comment token `c` without the leading "// "
+pub(crate) fn comment_text(c: &str) -> String {
+    let c = &c[2..];
+    c.strip_prefix(' ').unwrap_or(c).to_string()
+}
+
+pub(crate) fn func_model(mut sig: FuncSig, specs: Vec<sl_ast::Spec>, body: Stmt, inline: bool) -> FuncModel {
+    for spec in specs {
+        match spec {
+            sl_ast::Spec::Requires(..) => sig.requires.push(spec),
+            sl_ast::Spec::Ensures(..) => sig.ensures.push(spec),
+            sl_ast::Spec::Track(..) => sig.tracked.push(spec),
+            sl_ast::Spec::Modifies(mod_set) => sig.mod_set.extend(mod_set),
+            sl_ast::Spec::Update(..) => panic!("Implementation error; ghost updates are statements of the model."),
+        }
+    }
+    FuncModel { sig, body, inline }
+}
+
+// =====================================================================================
+// ## Printer
+
+fn func_model_to_text(fm: &FuncModel, scope: &mut Scope) -> String {
+    let sig = &fm.sig;
+    scope.enter_proc(&sig.arg_decls);
+    let arg_decls = sig
+        .arg_decls
+        .iter()
+        .map(|arg| format!("{}: {}", arg.get_var_name(), type_to_text(arg.typ())))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let ret_decl = sig
+        .ret_decl
+        .as_ref()
+        .map_or("".to_string(), |typ| format!(" returns {}", type_to_text(typ)));
+    let mut clauses = sig
+        .requires
+        .iter()
+        .chain(sig.ensures.iter())
+        .chain(sig.tracked.iter())
+        .map(|spec| spec_to_text(spec, scope))
+        .collect::<Vec<_>>();
+    if !sig.mod_set.is_empty() {
+        let mut mod_set = sig.mod_set.iter().cloned().collect::<Vec<_>>();
+        mod_set.sort();
+        clauses.push(format!("modifies {};", mod_set.join(", ")));
+    }
+    format!(
+        "{}procedure {}({}){} @ {:#x}\n{}{}",
+        if fm.inline { "inline " } else { "" },
+        sig.name,
+        arg_decls,
+        ret_decl,
+        sig.entry_addr,
+        clauses.iter().map(|c| format!("    {}\n", c)).collect::<String>(),
+        block_to_text(&fm.body, scope)
+    )
+}
+
+fn transition_to_text(ts: &TransitionSystem, scope: &mut Scope) -> String {
+    scope.enter_proc(&[]);
+    let specs = ts
+        .specs
+        .iter()
+        .map(|spec| match spec {
+            sl_ast::MachineSpec::Init(e, loc) => {
+                format!("    init {}{};\n", bexpr_to_text(e, scope), loc_to_text(loc))
+            }
+            sl_ast::MachineSpec::Invariant(name, e, loc) => format!(
+                "    invariant {}: {}{};\n",
+                name,
+                bexpr_to_text(e, scope),
+                loc_to_text(loc)
+            ),
+        })
+        .collect::<String>();
+    format!(
+        "transition {}({}) {{\n{}}}",
+        ts.step.func_name,
+        exprs_to_text(&ts.step.operands, scope),
+        specs
+    )
+}
+
+fn type_to_text(typ: &Type) -> String {
+    match typ {
+        Type::Unknown => "unknown".to_string(),
+        Type::Bool => "bool".to_string(),
+        Type::Int => "int".to_string(),
+        Type::Bv { w } => format!("bv{}", w),
+        Type::Array { in_typs, out_typ } => format!(
+            "[{}]{}",
+            in_typs.iter().map(|t| type_to_text(t)).collect::<Vec<_>>().join(", "),
+            type_to_text(out_typ)
+        ),
+        Type::Struct { id, fields, w } => format!(
+            "struct {}({}) {{ {} }}",
+            id,
+            w,
+            fields
+                .iter()
+                .map(|(f, t)| format!("{}: {}", f, type_to_text(t)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+fn vtype_to_text(typ: &VType) -> String {
+    match typ {
+        VType::Unknown => "unknown".to_string(),
+        VType::Bool => "bool".to_string(),
+        VType::Int => "int".to_string(),
+        VType::Bv(w) => format!("bv{}", w),
+        VType::Array { in_type, out_type } => {
+            format!("[{}]{}", vtype_to_text(in_type), vtype_to_text(out_type))
+        }
+        VType::Struct { id, fields, size } => {
+            let mut fields = fields.iter().collect::<Vec<_>>();
+            fields.sort_by(|a, b| a.0.cmp(b.0));
+            format!(
+                "struct {}({}) {{ {} }}",
+                id,
+                size,
+                fields
+                    .iter()
+                    .map(|(f, t)| format!("{}: {}", f, vtype_to_text(t)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+    }
+}
+
+// Statements
+
+/// Returns the statement as a block, wrapping it if it is not one
+fn block_to_text(stmt: &Stmt, scope: &Scope) -> String {
+    let stmts = match stmt {
+        Stmt::Block(stmts) => stmts.iter().map(|s| stmt_to_text(s, scope)).collect::<Vec<_>>(),
+        _ => vec![stmt_to_text(stmt, scope)],
+    };
+    let body = stmts
+        .join("\n")
+        .lines()
+        .map(|line| format!("    {}\n", line))
+        .collect::<String>();
+    format!("{{\n{}}}", body)
+}
+
+fn stmt_to_text(stmt: &Stmt, scope: &Scope) -> String {
+    match stmt {
+        Stmt::Assume(e) => format!("assume {};", expr_to_text(e, scope)),
+        Stmt::Assert(e) => format!("assert {};", expr_to_text(e, scope)),
+        Stmt::Havoc(v) => {
+            if v.typ == scope.var_type(&v.name) {
+                format!("havoc {};", v.name)
+            } else {
+                format!("havoc {} : {};", v.name, type_to_text(&v.typ))
+            }
+        }
+        Stmt::FuncCall(fc) => {
+            let lhs = if fc.lhs.is_empty() {
+                "".to_string()
+            } else {
+                format!("{} := ", exprs_to_text(&fc.lhs, scope))
+            };
+            format!("call {}{}({});", lhs, fc.func_name, exprs_to_text(&fc.operands, scope))
+        }
+        Stmt::Assign(a) => format!(
+            "{} := {};",
+            exprs_to_text(&a.lhs, scope),
+            exprs_to_text(&a.rhs, scope)
+        ),
+        Stmt::IfThenElse(ite) => {
+            let els = ite
+                .else_stmt
+                .as_ref()
+                .map_or("".to_string(), |s| format!(" else {}", block_to_text(s, scope)));
+            format!(
+                "if {} {}{}",
+                expr_to_text(&ite.cond, scope),
+                block_to_text(&ite.then_stmt, scope),
+                els
+            )
+        }
+        Stmt::Block(_) => block_to_text(stmt, scope),
+        Stmt::Comment(c) if c.is_empty() => "//".to_string(),
+        Stmt::Comment(c) => c
+            .lines()
+            .map(|line| format!("// {}", line))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+// Expressions
+
+fn exprs_to_text(exprs: &[Expr], scope: &Scope) -> String {
+    exprs
+        .iter()
+        .map(|e| expr_to_text(e, scope))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn expr_to_text(expr: &Expr, scope: &Scope) -> String {
+    let (text, inferred) = match expr {
+        Expr::Literal(lit, _) => {
+            let typ = match lit {
+                Literal::Bv { val: _, width } => Type::Bv { w: *width },
+                Literal::Bool { .. } => Type::Bool,
+                Literal::Int { .. } => Type::Int,
+            };
+            (lit.to_string(), typ)
+        }
+        Expr::Var(v, _) => (v.name.clone(), scope.var_type(&v.name)),
+        Expr::OpApp(opapp, _) => (
+            opapp_to_text(opapp, scope),
+            op_type(&opapp.op, &opapp.operands),
+        ),
+        Expr::FuncApp(fapp, _) => (
+            format!("{}({})", fapp.func_name, exprs_to_text(&fapp.operands, scope)),
+            Type::Unknown,
+        ),
+    };
+    if *expr.typ() == inferred {
+        text
+    } else {
+        format!("({} : {})", text, type_to_text(expr.typ()))
+    }
+}
+
+fn opapp_to_text(opapp: &OpApp, scope: &Scope) -> String {
+    let operands = &opapp.operands;
+    let symbol = match &opapp.op {
+        Op::Bool(BoolOp::Neg) => return format!("(!{})", expr_to_text(&operands[0], scope)),
+        Op::Bv(BVOp::SignExt) => return format!("sext({})", exprs_to_text(operands, scope)),
+        Op::Bv(BVOp::ZeroExt) => return format!("zext({})", exprs_to_text(operands, scope)),
+        Op::Bv(BVOp::Slice { l, r }) => {
+            return format!("{}[{}:{}]", expr_to_text(&operands[0], scope), l, r)
+        }
+        Op::ArrayIndex => {
+            return format!(
+                "{}[{}]",
+                expr_to_text(&operands[0], scope),
+                expr_to_text(&operands[1], scope)
+            )
+        }
+        Op::GetField(f) => {
+            // Identifiers may contain dots, so the struct is always parenthesized
+            return format!("({}).{}", expr_to_text(&operands[0], scope), f);
+        }
+        Op::Comp(cop) => match cop {
+            CompOp::Equality => "==",
+            CompOp::Inequality => "!=",
+            CompOp::Lt => "<",
+            CompOp::Le => "<=",
+            CompOp::Gt => ">",
+            CompOp::Ge => ">=",
+            CompOp::Ltu => "<_u",
+            CompOp::Leu => "<=_u",
+            CompOp::Gtu => ">_u",
+            CompOp::Geu => ">=_u",
+        },
+        Op::Bool(bop) => match bop {
+            BoolOp::Conj => "&&",
+            BoolOp::Disj => "||",
+            BoolOp::Iff => "<==>",
+            BoolOp::Impl => "==>",
+            BoolOp::Neg => unreachable!(),
+        },
+        Op::Bv(bvop) => match bvop {
+            BVOp::Add => "+",
+            BVOp::Sub => "-",
+            BVOp::Mul => "*",
+            BVOp::And => "&",
+            BVOp::Or => "|",
+            BVOp::Xor => "^",
+            BVOp::LeftShift => "<<",
+            BVOp::RightShift => ">>>",
+            BVOp::ARightShift => ">>",
+            BVOp::Concat => "++",
+            BVOp::SignExt | BVOp::ZeroExt | BVOp::Slice { .. } => unreachable!(),
+        },
+    };
+    format!(
+        "({})",
+        operands
+            .iter()
+            .map(|e| expr_to_text(e, scope))
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", symbol))
+    )
+}
+
+// Specifications
+
+fn spec_to_text(spec: &sl_ast::Spec, scope: &mut Scope) -> String {
+    match spec {
+        sl_ast::Spec::Requires(e, loc) => {
+            format!("requires {}{};", bexpr_to_text(e, scope), loc_to_text(loc))
+        }
+        sl_ast::Spec::Ensures(e, loc) => {
+            format!("ensures {}{};", bexpr_to_text(e, scope), loc_to_text(loc))
+        }
+        sl_ast::Spec::Modifies(mod_set) => {
+            let mut mod_set = mod_set.iter().cloned().collect::<Vec<_>>();
+            mod_set.sort();
+            format!("modifies {};", mod_set.join(", "))
+        }
+        sl_ast::Spec::Track(name, e, loc) => {
+            format!("track [{}] {}{};", name, vexpr_to_text(e, scope), loc_to_text(loc))
+        }
+        sl_ast::Spec::Update(..) => panic!("Implementation error; ghost updates are statements of the model."),
+    }
+}
+
+/// Returns the location suffix of a clause from a spec file
+fn loc_to_text(loc: &Option<sl_ast::SpecLoc>) -> String {
+    loc.as_ref().map_or(String::new(), |loc| {
+        format!(" @ \"{}\":{}:{}", loc.file, loc.line, loc.col)
+    })
+}
+
+fn bexpr_to_text(bexpr: &BExpr, scope: &mut Scope) -> String {
+    let symbol = match bexpr {
+        BExpr::Bool(b) => return b.to_string(),
+        BExpr::BOpApp(bop, operands) => match bop {
+            sl_ast::BoolOp::Neg => return format!("(!{})", bexpr_to_text(&operands[0], scope)),
+            sl_ast::BoolOp::Forall(v, typ) | sl_ast::BoolOp::Exists(v, typ) => {
+                let quantifier = match bop {
+                    sl_ast::BoolOp::Forall(..) => "forall",
+                    _ => "exists",
+                };
+                let x = v.get_ident_name();
+                scope.bind(x, typ);
+                let body = bexpr_to_text(&operands[0], scope);
+                scope.unbind();
+                return format!("({} ({}: {}) :: {})", quantifier, x, vtype_to_text(typ), body);
+            }
+            sl_ast::BoolOp::Conj => "&&",
+            sl_ast::BoolOp::Disj => "||",
+            sl_ast::BoolOp::Implies => "==>",
+        },
+        BExpr::COpApp(cop, _) => match cop {
+            sl_ast::CompOp::Equal => "==",
+            sl_ast::CompOp::Nequal => "!=",
+            sl_ast::CompOp::Gt => ">",
+            sl_ast::CompOp::Lt => "<",
+            sl_ast::CompOp::Gtu => ">_u",
+            sl_ast::CompOp::Ltu => "<_u",
+            sl_ast::CompOp::Geq => ">=",
+            sl_ast::CompOp::Leq => "<=",
+            sl_ast::CompOp::Geu => ">=_u",
+            sl_ast::CompOp::Leu => "<=_u",
+        },
+        BExpr::PredApp(name, _) => {
+            panic!("Implementation error; predicate {} should be expanded by the spec passes.", name)
+        }
+    };
+    let operands = match bexpr {
+        BExpr::BOpApp(_, operands) => operands
+            .iter()
+            .map(|e| bexpr_to_text(e, scope))
+            .collect::<Vec<_>>(),
+        BExpr::COpApp(_, operands) => operands
+            .iter()
+            .map(|e| vexpr_to_text(e, scope))
+            .collect::<Vec<_>>(),
+        BExpr::Bool(_) | BExpr::PredApp(_, _) => unreachable!(),
+    };
+    format!("({})", operands.join(&format!(" {} ", symbol)))
+}
+
+fn vexpr_to_text(vexpr: &VExpr, scope: &Scope) -> String {
+    let (text, inferred) = match vexpr {
+        VExpr::Bv { value, typ } => {
+            let w = match typ {
+                VType::Bv(w) => *w,
+                _ => 64,
+            };
+            (format!("{}bv{}", value, w), VType::Bv(w))
+        }
+        VExpr::Int(i, _) => (i.to_string(), VType::Int),
+        VExpr::Bool(b, _) => ((if *b { "$tt" } else { "$ff" }).to_string(), VType::Bool),
+        VExpr::Ident(name, _) => (name.clone(), scope.spec_type(name)),
+        VExpr::OpApp(op, operands, _) => (
+            value_opapp_to_text(op, operands, scope),
+            value_op_type(op, operands),
+        ),
+        VExpr::FuncApp(func, operands, _) => (
+            format!(
+                "{}({})",
+                func,
+                operands
+                    .iter()
+                    .map(|e| vexpr_to_text(e, scope))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            func_app_vtype(func, operands),
+        ),
+    };
+    if *vexpr.typ() == inferred {
+        text
+    } else {
+        format!("({} : {})", text, vtype_to_text(vexpr.typ()))
+    }
+}
+
+fn value_opapp_to_text(op: &ValueOp, operands: &[VExpr], scope: &Scope) -> String {
+    let symbol = match op {
+        ValueOp::ArrayIndex => {
+            return format!(
+                "{}[{}]",
+                vexpr_to_text(&operands[0], scope),
+                vexpr_to_text(&operands[1], scope)
+            )
+        }
+        ValueOp::GetField => {
+            return format!(
+                "({}).{}",
+                vexpr_to_text(&operands[0], scope),
+                operands[1].get_ident_name()
+            )
+        }
+        ValueOp::Slice { lo, hi } => {
+            return format!("{}[{}:{}]", vexpr_to_text(&operands[0], scope), hi, lo)
+        }
+        ValueOp::Deref => return format!("(*{})", vexpr_to_text(&operands[0], scope)),
+        ValueOp::Add => "+",
+        ValueOp::Sub => "-",
+        ValueOp::Div => "/",
+        ValueOp::Mul => "*",
+        ValueOp::BvXor => "^",
+        ValueOp::BvOr => "|",
+        ValueOp::BvAnd => "&",
+        ValueOp::RightShift => ">>",
+        ValueOp::URightShift => ">>>",
+        ValueOp::LeftShift => "<<",
+        ValueOp::Concat => "++",
+    };
+    format!(
+        "({})",
+        operands
+            .iter()
+            .map(|e| vexpr_to_text(e, scope))
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", symbol))
+    )
+}
@@ -0,0 +1,93 @@
+//! Reports of the lexer and syntax errors in spec files.
+
+extern crate asts;
+
+use asts::spec_lang::sl_parser::SpecParser;
+use asts::utils::Error;
+
+fn parse_errors(input: &str) -> String {
+    match SpecParser::new().parse(input, "test.rvspecs") {
+        Err(Error::SpecParseError(msg)) => msg,
+        Err(e) => panic!("Expected a spec parse error but found {:?}.", e),
+        Ok(_) => panic!("Expected a spec parse error."),
+    }
+}
+
+#[test]
+fn valid_specs() {
+    let input = "fun foo {
+    requires a0 >_u 0bv64;
+    ensures a0 == old(a0);
+}
+invariant pc_aligned: (pc & 3bv64) == 0bv64;
+";
+    let spec_file = SpecParser::new().parse(input, "test.rvspecs").unwrap();
+    assert_eq!(spec_file.func_specs[0].specs.len(), 2);
+    assert_eq!(spec_file.machine_specs.len(), 1);
+}
+
+#[test]
+fn syntax_error_with_snippet() {
+    let msg = parse_errors("fun foo {\n    requires a0 >_u ;\n}\n");
+    assert_eq!(
+        msg.lines().collect::<Vec<_>>(),
+        vec![
+            msg.lines().next().unwrap(),
+            " 2 |     requires a0 >_u ;",
+            "   |                     ^",
+        ]
+    );
+    assert!(msg.starts_with("test.rvspecs:2:21: unexpected \";\"; expected one of "));
+    assert!(msg.lines().next().unwrap().contains("identifier"));
+}
+
+#[test]
+fn all_errors_are_reported() {
+    let input = "fun foo {
+    requires a0 > ;
+    ensures a0 == 1bv64;
+    ensures a0 # 2bv64;
+}
+fun {
+    requires true;
+}
+fun bar {
+    requires a1 _ 0bv64;
+}
+";
+    let msg = parse_errors(input);
+    let locs = msg
+        .lines()
+        .filter(|line| line.starts_with("test.rvspecs:"))
+        .map(|line| line.split(": ").next().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        locs,
+        vec![
+            "test.rvspecs:2:19",
+            "test.rvspecs:4:16",
+            "test.rvspecs:6:5",
+            "test.rvspecs:10:17",
+        ]
+    );
+    assert!(msg.contains("unrecognized token \"#\""));
+    assert!(msg.contains("expected \"_u\""));
+}
+
+#[test]
+fn invalid_literal() {
+    let msg = parse_errors("fun foo {\n    requires a0 == 1bv99999;\n}\n");
+    assert!(msg.starts_with("test.rvspecs:2:20: invalid literal \"1bv99999\""));
+}
+
+#[test]
+fn value_instead_of_boolean() {
+    let msg = parse_errors("fun foo {\n    requires a0 + 1bv64;\n}\n");
+    assert!(msg.starts_with("test.rvspecs:2:14: expected a comparison or a predicate call"));
+}
+
+#[test]
+fn ghost_maps_are_not_supported() {
+    let msg = parse_errors("ghost owner: [bv64]bv64;\n");
+    assert!(msg.starts_with("test.rvspecs:1:14: unexpected \"[\"; expected bit-vector type"));
+}
@@ -0,0 +1,111 @@
+//! Spec files that include other spec files.
+
+extern crate asts;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use asts::spec_lang::sl_parser::SpecParser;
+use asts::utils::Error;
+
+/// Writes the spec files `files` (paths relative to the directory and contents) to
+/// a new temporary directory named `name` and returns the directory
+fn write_spec_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("riscverifier_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for (path, contents) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+fn load_errors(dir: &Path, path: &str) -> Vec<String> {
+    let path = dir.join(path).display().to_string();
+    match SpecParser::new().process_spec_files(&vec![&path[..]]) {
+        Err(Error::SpecParseError(msg)) => msg
+            .lines()
+            .map(|l| l.replace(&format!("{}/", dir.display()), ""))
+            .collect(),
+        Err(e) => panic!("Expected a spec parse error but found {:?}.", e),
+        Ok(_) => panic!("Expected a spec parse error."),
+    }
+}
+
+#[test]
+fn included_files_are_loaded_once() {
+    let dir = write_spec_files(
+        "include",
+        &[
+            (
+                "main.rvspecs",
+                "include \"lib/common.rvspecs\";\ninclude \"lib/list.rvspecs\";\nfun main {\n    requires small(a0);\n}\n",
+            ),
+            (
+                "lib/common.rvspecs",
+                "define small(x: bv64): bool = x <_u 16bv64;\n",
+            ),
+            (
+                "lib/list.rvspecs",
+                "include \"common.rvspecs\";\nfun push {\n    requires small(a1);\n}\n",
+            ),
+        ],
+    );
+    let path = dir.join("main.rvspecs").display().to_string();
+    let spec_file = SpecParser::new().process_spec_files(&vec![&path[..]]).unwrap();
+    assert_eq!(spec_file.defines.len(), 1);
+    let fnames = spec_file
+        .func_specs
+        .iter()
+        .map(|func_spec| &func_spec.fname[..])
+        .collect::<Vec<_>>();
+    assert_eq!(fnames, vec!["push", "main"]);
+}
+
+#[test]
+fn include_cycles() {
+    let dir = write_spec_files(
+        "include_cycle",
+        &[
+            ("a.rvspecs", "include \"b.rvspecs\";\n"),
+            ("b.rvspecs", "\ninclude \"a.rvspecs\";\n"),
+        ],
+    );
+    assert_eq!(
+        load_errors(&dir, "a.rvspecs"),
+        vec!["b.rvspecs:2:1: include cycle a.rvspecs -> b.rvspecs -> a.rvspecs"]
+    );
+}
+
+#[test]
+fn missing_included_file() {
+    let dir = write_spec_files("include_missing", &[("a.rvspecs", "include \"b.rvspecs\";\n")]);
+    let errors = load_errors(&dir, "a.rvspecs");
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("a.rvspecs:1:1: b.rvspecs: unable to read the spec file: "));
+}
+
+#[test]
+fn duplicate_function_specifications() {
+    let dir = write_spec_files(
+        "include_duplicate",
+        &[
+            ("a.rvspecs", "include \"b.rvspecs\";\nfun foo {\n    requires true;\n}\n"),
+            ("b.rvspecs", "fun foo {\n    ensures true;\n}\n"),
+        ],
+    );
+    assert_eq!(
+        load_errors(&dir, "a.rvspecs"),
+        vec!["a.rvspecs:2:1: duplicate specification of foo; the first one is at b.rvspecs:1:1"]
+    );
+}
+
+#[test]
+fn unterminated_string() {
+    let dir = write_spec_files("include_string", &[("a.rvspecs", "include \"b.rvspecs;\n")]);
+    let errors = load_errors(&dir, "a.rvspecs");
+    assert!(errors[0].starts_with("a.rvspecs:1:9: unterminated string"));
+}
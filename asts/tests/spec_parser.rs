@@ -0,0 +1,61 @@
+//! Parses small spec files and checks the declarations.
+
+extern crate asts;
+
+use asts::spec_lang::{sl_ast::SpecFile, sl_parser::SpecParser};
+
+fn parse(input: &str) -> SpecFile {
+    SpecParser::new().parse(input, "test.rvspecs").unwrap()
+}
+
+#[test]
+fn machine_keywords_as_names() {
+    let input = "fun init {
+    requires init == invariant;
+}
+init init == 0bv64;
+invariant invariant: invariant == 0bv64;
+";
+    let spec_file = parse(input);
+    assert_eq!(spec_file.func_specs[0].fname, "init");
+    assert_eq!(spec_file.machine_specs.len(), 2);
+}
+
+#[test]
+fn definition_keywords_as_names() {
+    let input = "define bool(define: bv64): bool = define == 0bv64;
+fun define {
+    requires bool(bool);
+}
+";
+    let spec_file = parse(input);
+    assert_eq!(spec_file.defines[0].name, "bool");
+    assert_eq!(spec_file.defines[0].params[0].0, "define");
+    assert_eq!(spec_file.func_specs[0].fname, "define");
+}
+
+#[test]
+fn include_as_name() {
+    let input = "fun include {
+    requires include != 0bv64;
+}
+";
+    let spec_file = parse(input);
+    assert_eq!(spec_file.func_specs[0].fname, "include");
+}
+
+#[test]
+fn ghost_keywords_as_names() {
+    let input = "ghost update: bv64 = 0bv64;
+ghost ghost: bv64;
+fun update {
+    modifies ghost, update;
+    update update = update + ghost;
+}
+";
+    let spec_file = parse(input);
+    assert_eq!(spec_file.ghosts[0].name, "update");
+    assert_eq!(spec_file.ghosts[1].name, "ghost");
+    assert_eq!(spec_file.func_specs[0].fname, "update");
+    assert_eq!(spec_file.func_specs[0].specs.len(), 2);
+}
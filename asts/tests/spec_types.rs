@@ -0,0 +1,271 @@
+//! Type errors in the specifications.
+
+extern crate asts;
+
+use std::collections::HashMap;
+
+use asts::spec_lang::{
+    sl_ast::VType,
+    sl_parser::SpecParser,
+    sl_type_checker::{SpecTypeChecker, TypeEnv},
+};
+use asts::utils::Error;
+
+/// Registers and `pc` of a 64-bit machine, the argument `n` of `foo` and the
+/// globals `table` (an array of `node`s) and `head` (a `node`)
+struct TestEnv;
+impl TypeEnv for TestEnv {
+    fn var_type(&self, fname: &str, name: &str) -> Option<VType> {
+        let node = || {
+            let mut fields = HashMap::new();
+            fields.insert("next".to_string(), Box::new(VType::Bv(64)));
+            fields.insert("len".to_string(), Box::new(VType::Bv(32)));
+            VType::Struct {
+                id: "node".to_string(),
+                fields,
+                size: 128,
+            }
+        };
+        match name {
+            "a0" | "a1" | "ra" | "sp" | "pc" => Some(VType::Bv(64)),
+            "returned" => Some(VType::Bv(1)),
+            "n" if fname == "foo" => Some(VType::Bv(64)),
+            "head" => Some(node()),
+            "table" => Some(VType::Array {
+                in_type: Box::new(VType::Bv(64)),
+                out_type: Box::new(node()),
+            }),
+            _ => None,
+        }
+    }
+}
+
+fn check(input: &str) -> Result<(), Error> {
+    let spec_file = SpecParser::new().parse(input, "test.rvspecs").unwrap();
+    SpecTypeChecker::new(&TestEnv).check_spec_file(&spec_file)
+}
+
+fn type_errors(input: &str) -> Vec<String> {
+    match check(input) {
+        Err(Error::SpecTypeError(msg)) => msg.lines().map(|l| l.to_string()).collect(),
+        Err(e) => panic!("Expected a spec type error but found {:?}.", e),
+        Ok(_) => panic!("Expected a spec type error."),
+    }
+}
+
+#[test]
+fn well_typed_specs() {
+    let input = "fun foo {
+    requires n >_u 0bv64 && head.len == 1bv32;
+    ensures pc == old(ra)[63:1] ++ 0bv1;
+    ensures sext(32, head.len) == table[a0].next;
+    ensures forall (i: bv32) :: uext(32, i) != n;
+    track [len] head.len;
+}
+invariant sp_aligned: (sp & 15bv64) == 0bv64 && returned == 0bv1;
+";
+    assert_eq!(check(input), Ok(()));
+}
+
+#[test]
+fn bitvector_widths() {
+    let errors = type_errors("fun foo {
+    requires a0 + 1bv32 == 0bv64;
+    ensures a0[63:32] == a1[31:0] ++ 0bv1;
+    ensures a0[64:0] == a0;
+    ensures a0 == 256bv8 ++ 0bv56;
+}
+");
+    assert_eq!(
+        errors,
+        vec![
+            "test.rvspecs:2:5: in the specification of foo: mismatched types bv64 and bv32 for \"+\"",
+            "test.rvspecs:3:5: in the specification of foo: mismatched types bv32 and bv33 for \"==\"",
+            "test.rvspecs:4:5: in the specification of foo: slice [64:0] is out of range for type bv64",
+            "test.rvspecs:5:5: in the specification of foo: literal 256bv8 does not fit in 8 bits",
+        ]
+    );
+}
+
+#[test]
+fn unknown_identifiers_are_reported_with_their_function() {
+    let errors = type_errors("fun foo {
+    requires n == 0bv64;
+}
+fun bar {
+    requires n == 0bv64;
+}
+init x == 0bv64;
+");
+    assert_eq!(
+        errors,
+        vec![
+            "test.rvspecs:5:5: in the specification of bar: unknown identifier \"n\"",
+            "test.rvspecs:7:1: in init: unknown identifier \"x\"",
+        ]
+    );
+}
+
+#[test]
+fn struct_fields_and_array_elements() {
+    let errors = type_errors("fun foo {
+    requires head.size == 0bv64;
+    requires n.next == 0bv64;
+    requires table[0bv32].next == 0bv64;
+    requires a0[a1] == 0bv64;
+    ensures table[a0].len == 0bv64;
+}
+");
+    assert_eq!(
+        errors,
+        vec![
+            "test.rvspecs:2:5: in the specification of foo: struct node has no field \"size\"",
+            "test.rvspecs:3:5: in the specification of foo: cannot select the field \"next\" of a value of type bv64",
+            "test.rvspecs:4:5: in the specification of foo: index of type bv32 for an array of type [bv64]struct node; expected bv64",
+            "test.rvspecs:5:5: in the specification of foo: cannot index a value of type bv64",
+            "test.rvspecs:6:5: in the specification of foo: mismatched types bv32 and bv64 for \"==\"",
+        ]
+    );
+}
+
+#[test]
+fn quantified_variables() {
+    let errors = type_errors("fun foo {
+    ensures (forall (i: bv64) :: i != n) && i == 0bv64;
+    ensures exists (a0: bv64) :: a0 == n;
+    ensures forall (i: bv32) :: i == n;
+}
+");
+    assert_eq!(
+        errors,
+        vec![
+            "test.rvspecs:2:5: in the specification of foo: unknown identifier \"i\"",
+            "test.rvspecs:3:5: in the specification of foo: quantified variable \"a0\" shadows a variable with the same name",
+            "test.rvspecs:4:5: in the specification of foo: mismatched types bv32 and bv64 for \"==\"",
+        ]
+    );
+}
+
+#[test]
+fn well_typed_definitions() {
+    let input = "define small(x: bv64): bool = x <_u 16bv64;
+define twice(x: bv64): bv64 = x + x;
+define all_small(): bool = forall (i: bv64) :: (small(twice(i)) ==> small(i));
+fun foo {
+    requires small(n) && (small(a0)) ==> twice(n) == a1;
+    ensures all_small();
+}
+";
+    assert_eq!(check(input), Ok(()));
+}
+
+#[test]
+fn quantified_arguments_of_definitions() {
+    // The quantified variables of the definitions are renamed when they are expanded
+    let input = "define r(x: bv64): bool = forall (i: bv64) :: i != x;
+define s(i: bv64): bool = r(i) && exists (j: bv64) :: r(i + j);
+fun foo {
+    requires forall (i: bv64) :: r(i);
+    ensures forall (j: bv64) :: s(j + n);
+}
+";
+    assert_eq!(check(input), Ok(()));
+}
+
+#[test]
+fn definitions() {
+    let errors = type_errors("define p(x: bv64): bool = x == q(x);
+define q(x: bv64): bv32 = x;
+define p(y: bv64): bool = true;
+define r(x: bv64): bool = forall (i: bv64) :: i != x;
+fun foo {
+    requires r(n, a0);
+    requires r(head.len);
+    requires forall (i: bv64) :: r(i);
+    requires q(n) == 0bv32 && q(r(n)) == 0bv32;
+    requires q(n);
+    ensures twice(n) == n;
+}
+");
+    assert_eq!(
+        errors,
+        vec![
+            "test.rvspecs:1:1: in define p: \"q\" is used before its definition",
+            "test.rvspecs:2:1: in define q: body of type bv64 for a definition of type bv32",
+            "test.rvspecs:3:1: in define p: \"p\" is already defined",
+            "test.rvspecs:6:5: in the specification of foo: r expects 1 argument(s) but found 2",
+            "test.rvspecs:7:5: in the specification of foo: argument 1 of r has type bv32; expected bv64",
+            "test.rvspecs:9:5: in the specification of foo: predicate r is used as a value",
+            "test.rvspecs:10:5: in the specification of foo: \"q\" is not a predicate",
+            "test.rvspecs:11:5: in the specification of foo: unknown function \"twice\"",
+        ]
+    );
+}
+
+#[test]
+fn well_typed_ghosts() {
+    let input = "ghost owner: bv64 = 0bv64;
+ghost count: bv32;
+fun foo {
+    requires owner == n;
+    update owner = a0;
+    update [0x80000010] count = count + uext(16, a1[15:0]);
+    update [0x80000014] owner = table[a1].next;
+    ensures owner == old(a0);
+    modifies owner, count;
+}
+invariant owned: owner != 0bv64 ==> count != 0bv32;
+";
+    assert_eq!(check(input), Ok(()));
+}
+
+#[test]
+fn ghosts() {
+    let errors = type_errors("ghost a0: bv64;
+ghost owner: bv64 = 1bv32;
+ghost owner: bv32;
+fun foo {
+    update pc = a0;
+    update owner = n;
+    update owner = old(a0);
+    update owner = a0 / a1;
+    update owner = a1[31:0];
+    ensures forall (owner: bv64) :: owner == a0;
+}
+");
+    assert_eq!(
+        errors,
+        vec![
+            "test.rvspecs:1:1: in ghost a0: ghost variable \"a0\" shadows a variable with the same name",
+            "test.rvspecs:2:1: in ghost owner: initial value of type bv32 for a ghost variable of type bv64",
+            "test.rvspecs:3:1: in ghost owner: ghost variable \"owner\" is already declared",
+            "test.rvspecs:5:5: in the specification of foo: \"pc\" is not a ghost variable",
+            "test.rvspecs:6:5: in the specification of foo: unknown identifier \"n\"",
+            "test.rvspecs:7:5: in the specification of foo: old is not supported in ghost code",
+            "test.rvspecs:8:5: in the specification of foo: \"/\" is not supported in ghost code",
+            "test.rvspecs:9:5: in the specification of foo: value of type bv32 for the ghost variable owner of type bv64",
+            "test.rvspecs:10:5: in the specification of foo: quantified variable \"owner\" shadows a variable with the same name",
+        ]
+    );
+}
+
+#[test]
+fn tracked_values() {
+    let errors = type_errors("ghost count: bv32;
+fun foo {
+    track [len] head.len;
+    track [count] count + 1bv32;
+    track [len] n;
+    track [prev] old(a0);
+    track [half] a0 / 2bv64;
+}
+");
+    assert_eq!(
+        errors,
+        vec![
+            "test.rvspecs:5:5: in the specification of foo: tracked value \"len\" is already declared",
+            "test.rvspecs:6:5: in the specification of foo: old is not supported in tracked values",
+            "test.rvspecs:7:5: in the specification of foo: \"/\" is not supported in tracked values",
+        ]
+    );
+}
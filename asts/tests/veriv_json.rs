@@ -0,0 +1,161 @@
+//! Serialization of the VERI-V IR as versioned JSON.
+
+extern crate asts;
+
+use std::collections::HashSet;
+
+use asts::spec_lang::sl_ast::{self, BExpr, VExpr, VType};
+use asts::utils::Error;
+use asts::veriv_ast::*;
+use asts::veriv_json::{model_from_json, model_to_json, FORMAT_NAME, FORMAT_VERSION};
+
+/// Returns a model of `put(dst)`, which stores the lower word of a0 at dst if a0 is not zero
+fn model() -> Model {
+    let bv64 = Type::Bv { w: 64 };
+    let mem_d = Expr::var(
+        "mem_d",
+        Type::Array {
+            in_typs: vec![Box::new(bv64.clone())],
+            out_typ: Box::new(bv64.clone()),
+        },
+    );
+    let a0 = Expr::var("a0", bv64.clone());
+    let dst = Expr::var("dst", bv64.clone());
+    let store = Stmt::assign(
+        vec![Expr::op_app(
+            Op::ArrayIndex,
+            vec![mem_d.clone(), dst.clone()],
+        )],
+        vec![Expr::op_app(
+            Op::Bv(BVOp::Concat),
+            vec![
+                Expr::op_app(Op::Bv(BVOp::Slice { l: 31, r: 0 }), vec![a0.clone()]),
+                Expr::bv_lit(0, 32),
+            ],
+        )],
+    );
+    let body = Stmt::Block(vec![
+        Box::new(Stmt::Comment("sd a0, dst".to_string())),
+        Box::new(Stmt::if_then_else(
+            Expr::op_app(
+                Op::Comp(CompOp::Inequality),
+                vec![a0.clone(), Expr::bv_lit(0, 64)],
+            ),
+            Box::new(Stmt::Block(vec![Box::new(store)])),
+            None,
+        )),
+        Box::new(Stmt::func_call(
+            "flush".to_string(),
+            vec![a0.clone()],
+            vec![dst.clone()],
+        )),
+        Box::new(Stmt::Assert(Expr::op_app(
+            Op::Comp(CompOp::Equality),
+            vec![a0, Expr::bv_lit(0, 64)],
+        ))),
+    ]);
+    let requires = sl_ast::Spec::Requires(
+        BExpr::COpApp(
+            sl_ast::CompOp::Nequal,
+            vec![
+                VExpr::Ident("dst".to_string(), VType::Bv(64)),
+                VExpr::Bv {
+                    value: 0,
+                    typ: VType::Bv(64),
+                },
+            ],
+        ),
+        None,
+    );
+    let mod_set = vec!["a0".to_string(), "mem_d".to_string()]
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let mut model = Model::new("test");
+    model.add_var(Var {
+        name: "a0".to_string(),
+        typ: bv64.clone(),
+    });
+    model.add_var(Var {
+        name: "mem_d".to_string(),
+        typ: mem_d.typ().clone(),
+    });
+    model.add_func_model(FuncModel::new(
+        "put",
+        0x80000010,
+        vec![dst],
+        Some(bv64),
+        Some(vec![requires]),
+        None,
+        None,
+        Some(mod_set),
+        body,
+        false,
+    ));
+    model
+}
+
+/// Returns the JSON of the model with the header field `field` replaced by `value`
+fn with_header(field: &str, value: serde_json::Value) -> String {
+    let mut json: serde_json::Value = serde_json::from_str(&model_to_json(&model())).unwrap();
+    json[field] = value;
+    json.to_string()
+}
+
+fn format_error(json: &str) -> String {
+    match model_from_json(json) {
+        Err(Error::IRFormatError(msg)) => msg,
+        Err(e) => panic!("Expected an IR format error but found {:?}.", e),
+        Ok(_) => panic!("Expected an IR format error."),
+    }
+}
+
+#[test]
+fn round_trip() {
+    let json = model_to_json(&model());
+    let rebuilt = model_from_json(&json).unwrap();
+    assert_eq!(rebuilt.name, "test");
+    assert_eq!(rebuilt.vars.len(), 2);
+    let fm = &rebuilt.func_models[0];
+    assert_eq!(fm.sig.name, "put");
+    assert_eq!(fm.sig.entry_addr, 0x80000010);
+    assert_eq!(fm.sig.requires.len(), 1);
+    assert_eq!(fm.body.get_expect_block().len(), 4);
+    // The encoding is deterministic, so the rebuilt model serializes to the same JSON
+    assert_eq!(model_to_json(&rebuilt), json);
+}
+
+#[test]
+fn header() {
+    let json: serde_json::Value = serde_json::from_str(&model_to_json(&model())).unwrap();
+    assert_eq!(json["format"], FORMAT_NAME);
+    assert_eq!(json["version"], FORMAT_VERSION);
+}
+
+#[test]
+fn wrong_format() {
+    let json = with_header("format", "veriv-text".into());
+    assert_eq!(
+        format_error(&json),
+        "Expected format \"veriv-ir\" but found \"veriv-text\"."
+    );
+}
+
+#[test]
+fn wrong_version() {
+    let json = with_header("version", (FORMAT_VERSION + 1).into());
+    assert_eq!(
+        format_error(&json),
+        format!(
+            "Unsupported format version {} (expected {}).",
+            FORMAT_VERSION + 1,
+            FORMAT_VERSION
+        )
+    );
+}
+
+#[test]
+fn malformed_model() {
+    let json = with_header("model", serde_json::json!({ "name": "test" }));
+    assert!(format_error(&json).starts_with("missing field `vars`"));
+    assert!(format_error("{\"format\": \"veriv-ir\"}").starts_with("missing field `version`"));
+}
@@ -0,0 +1,136 @@
+//! Printing and parsing of the textual VERI-V IR.
+
+extern crate asts;
+
+use asts::utils::Error;
+use asts::veriv_text::{model_from_text, model_to_text};
+
+/// A model with if statements, array stores, slices, concatenations, calls, specifications
+/// with and without spec locations, and a transition system
+const MODEL: &str = r#"model test;
+
+var a0: bv64;
+var a1: bv64;
+var mem_d: [bv64]bv64;
+var mem_w: [bv64]bv32;
+var node: struct node(128) { len: bv32, next: bv64 };
+var pc: bv64;
+var returned: bv1;
+
+inline procedure bb_0x80000000() @ 0x80000000
+    modifies a1, mem_d, mem_w, pc;
+{
+    {
+        // ld a1, a0, 0bv64
+        a1 := mem_d[(a0 + 0bv64)];
+        pc := (pc + 4bv64);
+    }
+    {
+        // sw a0, 8bv64, a1
+        mem_w[(a0 + 8bv64)] := a1[31:0];
+        mem_d[(a0 + 16bv64)] := (a1[31:0] ++ (node).len);
+        pc := (pc + 4bv64);
+    }
+    if (a1 == 0bv64) {
+        pc := 2147483664bv64;
+    } else {
+        havoc a1;
+        assume (sext(a1[7:0], 56) != 0bv64);
+    }
+}
+
+procedure put(dst: bv64, val: bv64) returns bv64 @ 0x80000010
+    requires (dst == a0) @ "specs/put.spec":3:5;
+    requires (forall (i: bv64) :: ((i <_u 8bv64) ==> (mem_d[(dst + i)] == 0bv64)));
+    ensures (mem_d[dst][31:0] == (val[15:0] ++ 0bv16)) @ "specs/put.spec":5:5;
+    track [stored] mem_d[dst];
+    modifies a0, mem_d;
+{
+    call a0 := put_impl(dst, (tmp : bv64));
+    assert (returned == 1bv1);
+}
+
+transition machine_step(a0, a1) {
+    init (pc == 2147483648bv64);
+    invariant aligned: ((pc & 3bv64) == 0bv64) @ "specs/machine.spec":2:5;
+}
+"#;
+
+fn parse_error(text: &str) -> String {
+    match model_from_text(text) {
+        Err(Error::IRParseError(msg)) => msg,
+        Err(e) => panic!("Expected an IR parse error but found {:?}.", e),
+        Ok(_) => panic!("Expected an IR parse error."),
+    }
+}
+
+#[test]
+fn print_parse_print() {
+    let model = model_from_text(MODEL).unwrap();
+    assert_eq!(model_to_text(&model), MODEL);
+}
+
+#[test]
+fn redundant_annotations_are_dropped() {
+    let text = MODEL.replace("(tmp : bv64)", "(val : bv64)");
+    let model = model_from_text(&text).unwrap();
+    assert!(model_to_text(&model).contains("call a0 := put_impl(dst, val);"));
+}
+
+#[test]
+fn syntax_error_location() {
+    let text = MODEL.replace("pc := (pc + 4bv64);", "pc := (pc + );");
+    let msg = parse_error(&text);
+    assert!(msg.starts_with("17:21: unexpected token \")\"; expected one of "));
+    assert!(msg.contains("bit-vector literal") && msg.contains("identifier"));
+}
+
+#[test]
+fn unexpected_end_of_file() {
+    let msg = parse_error("model test;\n\nvar a0: bv64");
+    assert!(msg.starts_with("3:13: unexpected end of file; expected one of "));
+}
+
+#[test]
+fn mixed_operators_need_parentheses() {
+    let text = MODEL.replace("(a1 == 0bv64)", "(a1 + a0 - 0bv64)");
+    assert_eq!(
+        parse_error(&text),
+        "25:9: different operators in one application; add parentheses"
+    );
+}
+
+#[test]
+fn literal_out_of_range() {
+    let text = MODEL.replace("0x80000010", "0x180000000000000000");
+    assert_eq!(parse_error(&text), "33:52: address is too large");
+}
+
+#[test]
+fn spec_locations_are_kept() {
+    let model = model_from_text(MODEL).unwrap();
+    let locs = model.func_models[1]
+        .sig
+        .requires
+        .iter()
+        .chain(model.func_models[1].sig.ensures.iter())
+        .map(|spec| spec.loc().map(|loc| loc.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        locs,
+        vec![
+            Some("specs/put.spec:3:5".to_string()),
+            None,
+            Some("specs/put.spec:5:5".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn spec_location_needs_a_file() {
+    let text = MODEL.replace("\"specs/put.spec\":3:5", "3:5");
+    assert_eq!(
+        parse_error(&text),
+        "34:28: unexpected token \"3\"; expected one of string"
+    );
+}
@@ -91,16 +91,21 @@ impl CDwarfInterface {
             "DW_TAG_array_type" => {
                 let out_type_index = dwarf_object.get_attr("DW_AT_type")?.get_expect_num_val();
                 let out_typ = Self::_get_type(out_type_index, comp_unit, typ_map)?;
-                let index_type_index = dwarf_object
-                    .get_child_named("DW_TAG_subrange_type")?
-                    .get_attr("DW_AT_type")?
-                    .get_expect_num_val();
+                let subrange = dwarf_object.get_child_named("DW_TAG_subrange_type")?;
+                let index_type_index = subrange.get_attr("DW_AT_type")?.get_expect_num_val();
                 let in_typ = Self::_get_type(index_type_index, comp_unit, typ_map)?;
                 let bytes = *comp_unit.get_attr("pointer_size")?.get_expect_num_val();
+                // Number of elements from the count or upper bound (if constant)
+                let len = match (subrange.get_attr("DW_AT_count"), subrange.get_attr("DW_AT_upper_bound")) {
+                    (Ok(DwarfAttributeValue::NumericAttr(count)), _) => Some(*count),
+                    (_, Ok(DwarfAttributeValue::NumericAttr(ub))) => Some(ub + 1),
+                    _ => None,
+                };
                 Rc::new(DwarfTypeDefn::Array {
                     in_typ,
                     out_typ,
                     bytes,
+                    len,
                 })
             }
             "DW_TAG_structure_type" => {
@@ -1,6 +1,6 @@
 use object::Object;
 
-use std::{borrow, collections::BTreeMap, collections::HashMap, fs, marker::PhantomData, rc::Rc};
+use std::{borrow, collections::BTreeMap, collections::HashMap, fmt, fs, marker::PhantomData, rc::Rc};
 
 use crate::utils;
 
@@ -53,14 +53,12 @@ pub trait DwarfInterface: std::fmt::Debug {
         Ok(Self::process_dwarf_file_object(xlen, &object, endian)?)
     }
 
-    /// Converts an object file into a vector of DwarfObjects
-    fn process_dwarf_file_object(
-        xlen: &u64,
-        object: &object::File,
-        endian: gimli::RunTimeEndian,
-    ) -> Result<Vec<DwarfObject>, gimli::Error> {
+    /// Loads the DWARF sections of an object file
+    fn load_dwarf_sections<'data>(
+        object: &object::File<'data>,
+    ) -> Result<gimli::Dwarf<borrow::Cow<'data, [u8]>>, gimli::Error> {
         // Load a section and return as `Cow<[u8]>`.
-        let load_section = |id: gimli::SectionId| -> Result<borrow::Cow<[u8]>, gimli::Error> {
+        let load_section = |id: gimli::SectionId| -> Result<borrow::Cow<'data, [u8]>, gimli::Error> {
             Ok(object
                 .section_data_by_name(id.name())
                 .unwrap_or(borrow::Cow::Borrowed(&[][..])))
@@ -69,7 +67,16 @@ pub trait DwarfInterface: std::fmt::Debug {
         // so always return an empty slice.
         let load_section_sup = |_| Ok(borrow::Cow::Borrowed(&[][..]));
         // Load all of the sections.
-        let dwarf_cow = gimli::Dwarf::load(&load_section, &load_section_sup)?;
+        gimli::Dwarf::load(&load_section, &load_section_sup)
+    }
+
+    /// Converts an object file into a vector of DwarfObjects
+    fn process_dwarf_file_object(
+        xlen: &u64,
+        object: &object::File,
+        endian: gimli::RunTimeEndian,
+    ) -> Result<Vec<DwarfObject>, gimli::Error> {
+        let dwarf_cow = Self::load_dwarf_sections(object)?;
         // Borrow a `Cow<[u8]>` to create an `EndianSlice`.
         let borrow_section: &dyn for<'a> Fn(
             &'a borrow::Cow<[u8]>,
@@ -95,6 +102,165 @@ pub trait DwarfInterface: std::fmt::Debug {
         Ok(dwarf_objects)
     }
 
+    /// Parses the line tables and inlined subroutines of the binary files in the paths
+    fn process_line_info_files(paths: &Vec<&str>) -> Result<DwarfLineInfo, gimli::Error> {
+        let mut line_info = DwarfLineInfo::new();
+        for path in paths {
+            info!("[process_line_info_files] Processing line table of {:?}.", path);
+            let file = fs::File::open(&path[..]).unwrap();
+            let mmap = unsafe { memmap::Mmap::map(&file).unwrap() };
+            let object = object::File::parse(&*mmap).unwrap();
+            let endian = if object.is_little_endian() {
+                gimli::RunTimeEndian::Little
+            } else {
+                gimli::RunTimeEndian::Big
+            };
+            let dwarf_cow = Self::load_dwarf_sections(&object)?;
+            let borrow_section: &dyn for<'a> Fn(
+                &'a borrow::Cow<[u8]>,
+            )
+                -> gimli::EndianSlice<'a, gimli::RunTimeEndian> =
+                &|section| gimli::EndianSlice::new(&*section, endian);
+            let dwarf = dwarf_cow.borrow(&borrow_section);
+            let mut iter = dwarf.units();
+            while let Some(header) = iter.next()? {
+                let unit = dwarf.unit(header)?;
+                Self::process_unit_line_info(&unit, &dwarf, &mut line_info)?;
+            }
+        }
+        Ok(line_info)
+    }
+
+    /// Adds the line table rows and inlined subroutines of the unit to `line_info`
+    fn process_unit_line_info<R: gimli::Reader<Offset = usize>>(
+        unit: &gimli::Unit<R>,
+        dwarf: &gimli::Dwarf<R>,
+        line_info: &mut DwarfLineInfo,
+    ) -> Result<(), gimli::Error> {
+        let program = match &unit.line_program {
+            Some(program) => program.clone(),
+            None => return Ok(()),
+        };
+        // Inlined subroutines and the locations they were called at
+        let mut entries_cursor = unit.entries();
+        let mut depth = 0;
+        while let Some((delta_depth, entry)) = entries_cursor.next_dfs()? {
+            depth += delta_depth;
+            if entry.tag() != gimli::DW_TAG_inlined_subroutine {
+                continue;
+            }
+            let low_pc = match entry.attr_value(gimli::DW_AT_low_pc)? {
+                Some(gimli::AttributeValue::Addr(addr)) => addr,
+                _ => continue,
+            };
+            let high_pc = match entry.attr_value(gimli::DW_AT_high_pc)? {
+                Some(gimli::AttributeValue::Addr(addr)) => addr,
+                Some(gimli::AttributeValue::Udata(size)) => low_pc + size,
+                _ => continue,
+            };
+            let func = match entry.attr_value(gimli::DW_AT_abstract_origin)? {
+                Some(gimli::AttributeValue::UnitRef(offset)) => {
+                    let mut origin_cursor = unit.entries_at_offset(offset)?;
+                    match origin_cursor.next_dfs()? {
+                        Some((_, origin)) => match origin.attr_value(gimli::DW_AT_name)? {
+                            Some(name) => Self::attr_string(name, dwarf)?,
+                            None => None,
+                        },
+                        None => None,
+                    }
+                }
+                _ => None,
+            };
+            let call_file = match entry.attr_value(gimli::DW_AT_call_file)? {
+                Some(gimli::AttributeValue::FileIndex(index))
+                | Some(gimli::AttributeValue::Udata(index)) => program.header().file(index),
+                _ => None,
+            };
+            let call_line = match entry.attr_value(gimli::DW_AT_call_line)? {
+                Some(gimli::AttributeValue::Udata(line)) => line,
+                _ => 0,
+            };
+            let call_loc = match call_file {
+                Some(file) => Some(SourceLoc {
+                    file: Self::file_entry_name(file, program.header(), dwarf)?,
+                    line: call_line,
+                    column: 0,
+                }),
+                None => None,
+            };
+            line_info.add_inlined_call(InlinedCall {
+                func: func.unwrap_or(format!("??")),
+                low_pc,
+                high_pc,
+                call_loc,
+                depth,
+            });
+        }
+        // Line table rows
+        let mut rows = program.rows();
+        while let Some((header, row)) = rows.next_row()? {
+            if row.end_sequence() {
+                line_info.add_row(row.address(), None);
+                continue;
+            }
+            let file = match row.file(header) {
+                Some(file) => Self::file_entry_name(file, header, dwarf)?,
+                None => format!("??"),
+            };
+            let column = match row.column() {
+                gimli::ColumnType::Column(column) => column,
+                gimli::ColumnType::LeftEdge => 0,
+            };
+            line_info.add_row(
+                row.address(),
+                Some(SourceLoc {
+                    file,
+                    line: row.line().unwrap_or(0),
+                    column,
+                }),
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the path of a line table file entry.
+    /// Files in the compilation directory are not prefixed with their directory.
+    fn file_entry_name<R: gimli::Reader<Offset = usize>>(
+        file: &gimli::FileEntry<R>,
+        header: &gimli::LineProgramHeader<R>,
+        dwarf: &gimli::Dwarf<R>,
+    ) -> Result<String, gimli::Error> {
+        let path = Self::attr_string(file.path_name(), dwarf)?.unwrap_or(format!("??"));
+        if file.directory_index() != 0 {
+            if let Some(dir) = file.directory(header) {
+                if let Some(dir) = Self::attr_string(dir, dwarf)? {
+                    return Ok(format!("{}/{}", dir, path));
+                }
+            }
+        }
+        Ok(path)
+    }
+
+    /// Returns the string of a string attribute value
+    fn attr_string<R: gimli::Reader<Offset = usize>>(
+        value: gimli::AttributeValue<R>,
+        dwarf: &gimli::Dwarf<R>,
+    ) -> Result<Option<String>, gimli::Error> {
+        let s = match value {
+            gimli::AttributeValue::String(s) => Some(format!("{}", s.to_string_lossy()?)),
+            gimli::AttributeValue::DebugStrRef(offset) => {
+                let s = dwarf.debug_str.get_str(offset)?;
+                Some(format!("{}", s.to_string_lossy()?))
+            }
+            gimli::AttributeValue::DebugLineStrRef(offset) => {
+                let s = dwarf.debug_line_str.get_str(offset)?;
+                Some(format!("{}", s.to_string_lossy()?))
+            }
+            _ => None,
+        };
+        Ok(s)
+    }
+
     /// Converts gimli entries_cursor to a DwarfObject.
     /// This function simplifies the gimli reader iterable
     /// into a recursive data structure DwarfObject that contains
@@ -196,6 +362,9 @@ pub trait DwarfInterface: std::fmt::Debug {
                 )))
             }
             gimli::AttributeValue::Udata(data) => Some(DwarfAttributeValue::NumericAttr(data)),
+            gimli::AttributeValue::Sdata(data) if data >= 0 => {
+                Some(DwarfAttributeValue::NumericAttr(data as u64))
+            }
             gimli::AttributeValue::UnitRef(offset) => {
                 Some(DwarfAttributeValue::NumericAttr(offset.0 as u64))
             }
@@ -381,6 +550,8 @@ pub enum DwarfTypeDefn {
         out_typ: Rc<DwarfTypeDefn>,
         /// Number of bytes used to represent the address to that array (usually just xlen)
         bytes: u64,
+        /// Number of elements if the array has a constant bound
+        len: Option<u64>,
     },
     /// Structure type
     Struct {
@@ -406,6 +577,34 @@ impl DwarfTypeDefn {
             _ => false,
         }
     }
+    /// Returns the number of bytes an object of this type occupies in memory
+    /// (None for arrays without a constant bound)
+    pub fn object_size(&self) -> Option<u64> {
+        match self {
+            DwarfTypeDefn::Primitive { bytes }
+            | DwarfTypeDefn::Struct { bytes, .. }
+            | DwarfTypeDefn::Pointer { bytes, .. } => Some(*bytes),
+            DwarfTypeDefn::Array { out_typ, len, .. } => {
+                len.and_then(|len| out_typ.object_size().map(|size| len * size))
+            }
+        }
+    }
+    /// Returns the offset of the field `field_name` of the struct `struct_id` in this type
+    /// or the types of its fields and array elements
+    pub fn field_offset(&self, struct_id: &str, field_name: &str) -> Option<u64> {
+        match self {
+            DwarfTypeDefn::Struct { id, fields, .. } => match fields.get(field_name) {
+                Some(field) if id == struct_id => Some(field.loc),
+                _ => fields
+                    .values()
+                    .find_map(|field| field.typ.field_offset(struct_id, field_name)),
+            },
+            DwarfTypeDefn::Array { in_typ, out_typ, .. } => in_typ
+                .field_offset(struct_id, field_name)
+                .or_else(|| out_typ.field_offset(struct_id, field_name)),
+            _ => None,
+        }
+    }
 }
 
 /// Field of a structure type
@@ -419,6 +618,100 @@ pub struct StructField {
     pub loc: u64,
 }
 
+/// Location in the source code from the DWARF line table
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceLoc {
+    /// Path of the source file
+    pub file: String,
+    /// Line number (0 if unknown)
+    pub line: u64,
+    /// Column number (0 if unknown)
+    pub column: u64,
+}
+impl fmt::Display for SourceLoc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+/// Address range of an inlined function call
+#[derive(Debug, Clone)]
+pub struct InlinedCall {
+    /// Name of the inlined function
+    pub func: String,
+    /// First address of the inlined code
+    pub low_pc: u64,
+    /// First address after the inlined code
+    pub high_pc: u64,
+    /// Location of the call that was inlined
+    pub call_loc: Option<SourceLoc>,
+    /// Depth of the inlined subroutine entry (deeper calls are nested in shallower ones)
+    pub depth: isize,
+}
+
+/// Line table and inlined call information from the `.debug_line`
+/// and `.debug_info` sections
+#[derive(Debug, Default)]
+pub struct DwarfLineInfo {
+    /// Line table rows by address; `None` marks the end of a sequence
+    rows: BTreeMap<u64, Option<SourceLoc>>,
+    /// Inlined function calls
+    inlined_calls: Vec<InlinedCall>,
+}
+impl DwarfLineInfo {
+    pub fn new() -> Self {
+        DwarfLineInfo {
+            rows: BTreeMap::new(),
+            inlined_calls: vec![],
+        }
+    }
+    /// Adds a line table row starting at `addr`
+    pub fn add_row(&mut self, addr: u64, loc: Option<SourceLoc>) {
+        // The end of a sequence does not override the start of the next one
+        if loc.is_some() || !self.rows.contains_key(&addr) {
+            self.rows.insert(addr, loc);
+        }
+    }
+    /// Adds an inlined function call
+    pub fn add_inlined_call(&mut self, call: InlinedCall) {
+        self.inlined_calls.push(call);
+    }
+    /// Returns the source location of the instruction at `addr`
+    pub fn source_loc(&self, addr: u64) -> Option<&SourceLoc> {
+        self.rows
+            .range(..=addr)
+            .next_back()
+            .and_then(|(_, loc)| loc.as_ref())
+    }
+    /// Returns the inlined calls containing `addr` from the innermost to the outermost call
+    pub fn inlined_calls(&self, addr: u64) -> Vec<&InlinedCall> {
+        let mut calls = self
+            .inlined_calls
+            .iter()
+            .filter(|call| call.low_pc <= addr && addr < call.high_pc)
+            .collect::<Vec<_>>();
+        calls.sort_by(|a, b| b.depth.cmp(&a.depth));
+        calls
+    }
+    /// Returns `file:line` of the instruction at `addr` followed by its inlined call context
+    pub fn describe(&self, addr: u64) -> Option<String> {
+        let loc = self.source_loc(addr)?;
+        let calls = self
+            .inlined_calls(addr)
+            .iter()
+            .map(|call| match &call.call_loc {
+                Some(call_loc) => format!("{} inlined at {}", call.func, call_loc),
+                None => format!("{} inlined", call.func),
+            })
+            .collect::<Vec<_>>();
+        if calls.len() > 0 {
+            Some(format!("{} [{}]", loc, calls.join("; ")))
+        } else {
+            Some(format!("{}", loc))
+        }
+    }
+}
+
 /// Stores the relevant debugging information for automatically
 /// translating specifications
 #[derive(Debug)]
@@ -431,8 +724,60 @@ pub struct DwarfCtx {
     global_vars: Vec<DwarfVar>,
     /// A type map computed from the arguments of the function signatures and global variables
     typ_map: HashMap<String, Rc<DwarfTypeDefn>>,
+    /// Source locations of the instructions
+    line_info: DwarfLineInfo,
 }
 impl DwarfCtx {
+    /// Creates the context from already processed debugging information
+    pub fn new(
+        xlen: u64,
+        func_sigs: HashMap<String, DwarfFuncSig>,
+        global_vars: Vec<DwarfVar>,
+        line_info: DwarfLineInfo,
+    ) -> Self {
+        let typ_map = Self::compute_typ_map(&func_sigs, &global_vars);
+        DwarfCtx {
+            xlen,
+            func_sigs,
+            global_vars,
+            typ_map,
+            line_info,
+        }
+    }
+    /// Returns a type map of the global variables, the function arguments and the
+    /// return values of the function signatures
+    pub fn compute_typ_map(
+        func_sigs: &HashMap<String, DwarfFuncSig>,
+        global_vars: &Vec<DwarfVar>,
+    ) -> HashMap<String, Rc<DwarfTypeDefn>> {
+        let mut typ_map = HashMap::new();
+        // Add globals to type map
+        for v in global_vars {
+            typ_map.insert(v.name.clone(), Rc::clone(&v.typ_defn));
+        }
+        // Add function arguments and return "variable" to type map
+        for (fun_name, fs) in func_sigs {
+            for arg in &fs.args {
+                typ_map.insert(
+                    format!("{}${}", fun_name, arg.name),
+                    Rc::clone(&arg.typ_defn),
+                );
+            }
+            if let Some(ret_type) = &fs.ret_type {
+                // FIXME: remove magic string $ret
+                typ_map.insert(format!("{}$$ret", fun_name), Rc::clone(ret_type));
+            }
+        }
+        typ_map
+    }
+    /// Returns the line table information
+    pub fn line_info(&self) -> &DwarfLineInfo {
+        &self.line_info
+    }
+    /// Returns the source location (and inlined call context) of the instruction at `addr`
+    pub fn source_loc_str(&self, addr: u64) -> Option<String> {
+        self.line_info.describe(addr)
+    }
     /// Returns the DwarfVar of the given global variable named `name`
     pub fn global_var(&self, name: &str) -> Result<&DwarfVar, utils::Error> {
         self.global_vars
@@ -487,6 +832,18 @@ impl DwarfCtx {
         let typ = &self.global_var(name)?.typ_defn;
         Ok(Rc::clone(typ))
     }
+    /// Returns the offset of the field `field_name` of the struct `struct_id` from the types
+    /// of the global variables and function signatures
+    pub fn field_offset(&self, struct_id: &str, field_name: &str) -> Option<u64> {
+        let sig_typs = self.func_sigs.values().flat_map(|fsig| {
+            fsig.args.iter().map(|arg| &arg.typ_defn).chain(fsig.ret_type.iter())
+        });
+        self.global_vars
+            .iter()
+            .map(|var| &var.typ_defn)
+            .chain(sig_typs)
+            .find_map(|typ| typ.field_offset(struct_id, field_name))
+    }
 }
 
 // =========================================================================================
@@ -529,42 +886,12 @@ where
             .map(|comp_unit| I::process_global_vars(comp_unit))
             .flatten()
             .collect();
-        let typ_map = Self::compute_typ_map(&func_sigs, &global_vars);
-        let ctx = DwarfCtx {
-            xlen: *xlen,
-            func_sigs,
-            global_vars,
-            typ_map,
-        };
+        let line_info = I::process_line_info_files(binary_paths)?;
         Ok(DwarfReader {
-            ctx,
+            ctx: DwarfCtx::new(*xlen, func_sigs, global_vars, line_info),
             _phantom_data: PhantomData,
         })
     }
-    pub fn compute_typ_map(
-        func_sigs: &HashMap<String, DwarfFuncSig>,
-        global_vars: &Vec<DwarfVar>,
-    ) -> HashMap<String, Rc<DwarfTypeDefn>> {
-        let mut typ_map = HashMap::new();
-        // Add globals to type map
-        for v in global_vars {
-            typ_map.insert(v.name.clone(), Rc::clone(&v.typ_defn));
-        }
-        // Add function arguments and return "variable" to type map
-        for (fun_name, fs) in func_sigs {
-            for arg in &fs.args {
-                typ_map.insert(
-                    format!("{}${}", fun_name, arg.name),
-                    Rc::clone(&arg.typ_defn),
-                );
-            }
-            if let Some(ret_type) = &fs.ret_type {
-                // FIXME: remove magic string $ret
-                typ_map.insert(format!("{}$$ret", fun_name), Rc::clone(ret_type));
-            }
-        }
-        typ_map
-    }
     pub fn ctx(&self) -> &DwarfCtx {
         &self.ctx
     }
@@ -0,0 +1,89 @@
+//! Looks up source locations in small hand-built line tables.
+
+extern crate dwarf_ctx;
+
+use dwarf_ctx::dwarfreader::{DwarfLineInfo, InlinedCall, SourceLoc};
+
+fn loc(file: &str, line: u64) -> Option<SourceLoc> {
+    Some(SourceLoc {
+        file: file.to_string(),
+        line,
+        column: 0,
+    })
+}
+
+/// Rows of `a.c` at 0x100..0x110 and `b.c` at 0x110..0x118, followed by the
+/// end of the sequence
+fn line_info() -> DwarfLineInfo {
+    let mut line_info = DwarfLineInfo::new();
+    line_info.add_row(0x100, loc("a.c", 3));
+    line_info.add_row(0x108, loc("a.c", 4));
+    line_info.add_row(0x110, loc("b.c", 10));
+    line_info.add_row(0x118, None);
+    line_info
+}
+
+#[test]
+fn rows_cover_addresses_up_to_the_next_row() {
+    let line_info = line_info();
+    assert_eq!(line_info.source_loc(0x100), loc("a.c", 3).as_ref());
+    assert_eq!(line_info.source_loc(0x104), loc("a.c", 3).as_ref());
+    assert_eq!(line_info.source_loc(0x108), loc("a.c", 4).as_ref());
+    assert_eq!(line_info.source_loc(0x114), loc("b.c", 10).as_ref());
+}
+
+#[test]
+fn no_location_outside_of_a_sequence() {
+    let line_info = line_info();
+    assert_eq!(line_info.source_loc(0xfc), None);
+    assert_eq!(line_info.source_loc(0x118), None);
+    assert_eq!(line_info.source_loc(0x200), None);
+    assert_eq!(line_info.describe(0x118), None);
+}
+
+#[test]
+fn end_of_sequence_does_not_override_the_next_sequence() {
+    let mut line_info = line_info();
+    line_info.add_row(0x118, loc("c.c", 1));
+    line_info.add_row(0x120, None);
+    // The end of the previous sequence is added after the start of the next one
+    line_info.add_row(0x118, None);
+    assert_eq!(line_info.source_loc(0x11c), loc("c.c", 1).as_ref());
+    assert_eq!(line_info.source_loc(0x120), None);
+}
+
+#[test]
+fn describe_without_inlined_calls() {
+    let line_info = line_info();
+    assert_eq!(line_info.describe(0x10c), Some("a.c:4".to_string()));
+}
+
+#[test]
+fn describe_nested_inlined_calls() {
+    let mut line_info = line_info();
+    line_info.add_inlined_call(InlinedCall {
+        func: "outer".to_string(),
+        low_pc: 0x104,
+        high_pc: 0x114,
+        call_loc: loc("a.c", 2),
+        depth: 1,
+    });
+    line_info.add_inlined_call(InlinedCall {
+        func: "inner".to_string(),
+        low_pc: 0x110,
+        high_pc: 0x114,
+        call_loc: None,
+        depth: 2,
+    });
+    assert_eq!(line_info.describe(0x100), Some("a.c:3".to_string()));
+    assert_eq!(
+        line_info.describe(0x108),
+        Some("a.c:4 [outer inlined at a.c:2]".to_string())
+    );
+    assert_eq!(
+        line_info.describe(0x110),
+        Some("b.c:10 [inner inlined; outer inlined at a.c:2]".to_string())
+    );
+    // `high_pc` is the first address after the inlined code
+    assert_eq!(line_info.describe(0x114), Some("b.c:10".to_string()));
+}
@@ -143,6 +143,28 @@ pub fn bv_type(width: u64) -> Type {
     Type::Bv { w: width }
 }
 
+/// Registers that a callee may clobber according to the calling convention
+pub fn caller_saved_reg_vars(xlen: u64) -> Vec<Var> {
+    CALLER_SAVED_REGS
+        .iter()
+        .map(|name| Var {
+            name: name.to_string(),
+            typ: bv_type(xlen),
+        })
+        .collect()
+}
+
+/// Registers referred to by the calling-convention contracts
+pub fn abi_reg_vars(xlen: u64) -> Vec<Var> {
+    std::iter::once(RA)
+        .chain(abi_preserved_regs())
+        .map(|name| Var {
+            name: name.to_string(),
+            typ: bv_type(xlen),
+        })
+        .collect()
+}
+
 /// A set of all the state variables
 pub fn sys_state_vars(xlen: u64) -> HashSet<Var> {
     let mut vec_var = HashSet::new();
@@ -156,6 +178,45 @@ pub fn sys_state_vars(xlen: u64) -> HashSet<Var> {
     vec_var
 }
 
+// ========================================================================
+/// # Procedure entry
+
+/// Returns the preconditions implied by the procedure entry with their descriptions:
+/// the arguments are the argument registers, the pc is the entry address
+/// and the procedure has not returned
+pub fn entry_requires(fm: &FuncModel, xlen: u64) -> Vec<(String, Expr)> {
+    let mut requires = fm
+        .sig
+        .arg_decls
+        .iter()
+        .enumerate()
+        .map(|(i, arg)| {
+            (
+                format!("requires {} == a{}", arg.get_var_name(), i),
+                Expr::op_app(
+                    Op::Comp(CompOp::Equality),
+                    vec![arg.clone(), Expr::var(&format!("a{}", i), bv_type(xlen))],
+                ),
+            )
+        })
+        .collect::<Vec<_>>();
+    requires.push((
+        format!("requires {} == {:#x}", PC_VAR, fm.sig.entry_addr),
+        Expr::op_app(
+            Op::Comp(CompOp::Equality),
+            vec![pc_expr(xlen), Expr::bv_lit(fm.sig.entry_addr, xlen)],
+        ),
+    ));
+    requires.push((
+        format!("requires {} == 0", RETURNED_FLAG),
+        Expr::op_app(
+            Op::Comp(CompOp::Equality),
+            vec![Expr::var(RETURNED_FLAG, returned_type()), Expr::bv_lit(0, 1)],
+        ),
+    ));
+    requires
+}
+
 // ========================================================================
 /// # RISC-V Instruction Semantics
 
@@ -186,6 +247,17 @@ pub fn unimplemented_inst(op: &str, xlen: u64) -> Stmt {
     Stmt::Block(stmts.iter().map(|x| Box::new(x.clone())).collect())
 }
 
+/// Returns a block that havocs the registers a callee may clobber
+/// (including the return value in a0)
+pub fn havoc_caller_saved_regs(xlen: u64) -> Stmt {
+    let mut stmts = vec![];
+    stmts.push(Stmt::Comment(format!("caller-saved registers are clobbered by the callee")));
+    for var in caller_saved_reg_vars(xlen) {
+        stmts.push(Stmt::havoc(var));
+    }
+    Stmt::Block(stmts.into_iter().map(Box::new).collect())
+}
+
 /// A helper to add nbv64 to the given expression
 pub fn add_const(expr: Expr, n: u64, xlen: u64) -> Expr {
     Expr::op_app(Op::Bv(BVOp::Add), vec![expr, Expr::bv_lit(n, xlen)])
@@ -470,7 +542,13 @@ pub fn srlw_inst(rd: Expr, rs1: Expr, rs2: Expr, xlen: u64) -> Stmt {
                 Op::Bv(BVOp::RightShift),
                 vec![
                     Expr::op_app(Op::Bv(BVOp::Slice { l: 31, r: 0 }), vec![rs1]),
-                    Expr::op_app(Op::Bv(BVOp::And), vec![rs2, Expr::bv_lit(31, xlen)]),
+                    Expr::op_app(
+                        Op::Bv(BVOp::Slice { l: 31, r: 0 }),
+                        vec![Expr::op_app(
+                            Op::Bv(BVOp::And),
+                            vec![rs2, Expr::bv_lit(31, xlen)],
+                        )],
+                    ),
                 ],
             ),
             Expr::int_lit(32),
@@ -495,7 +573,13 @@ pub fn sraw_inst(rd: Expr, rs1: Expr, rs2: Expr, xlen: u64) -> Stmt {
                 Op::Bv(BVOp::ARightShift),
                 vec![
                     Expr::op_app(Op::Bv(BVOp::Slice { l: 31, r: 0 }), vec![rs1]),
-                    Expr::op_app(Op::Bv(BVOp::And), vec![rs2, Expr::bv_lit(31, xlen)]),
+                    Expr::op_app(
+                        Op::Bv(BVOp::Slice { l: 31, r: 0 }),
+                        vec![Expr::op_app(
+                            Op::Bv(BVOp::And),
+                            vec![rs2, Expr::bv_lit(31, xlen)],
+                        )],
+                    ),
                 ],
             ),
             Expr::int_lit(32),
@@ -513,14 +597,6 @@ pub fn jalr_inst(rd: Expr, rs1: Expr, imm: Expr, xlen: u64) -> Stmt {
         "jalr {}, {}, {}",
         rd, rs1, imm
     )));
-    // rd := pc + 4bv64
-    stmts.push(Stmt::assign(
-        vec![rd],
-        vec![Expr::op_app(
-            Op::Bv(BVOp::Add),
-            vec![pc_expr(xlen), Expr::bv_lit(4, xlen)],
-        )],
-    ));
     let target = Expr::op_app(
         Op::Bv(BVOp::Concat),
         vec![
@@ -531,8 +607,18 @@ pub fn jalr_inst(rd: Expr, rs1: Expr, imm: Expr, xlen: u64) -> Stmt {
             Expr::bv_lit(0, 1),
         ],
     );
-    // pc := (rs1 + imm)[63:1] ++ 0bv1
-    stmts.push(pc_jump(target, xlen));
+    // rd, pc := pc + 4bv64, (rs1 + imm)[63:1] ++ 0bv1
+    // Both are assigned simultaneously since rd may be the same register as rs1
+    stmts.push(Stmt::assign(
+        vec![rd, pc_expr(xlen)],
+        vec![
+            Expr::op_app(
+                Op::Bv(BVOp::Add),
+                vec![pc_expr(xlen), Expr::bv_lit(4, xlen)],
+            ),
+            target,
+        ],
+    ));
     // returned := 1bv1 (true)
     stmts.push(Stmt::assign(
         vec![Expr::var(RETURNED_FLAG, bv_type(1))],
@@ -593,7 +679,6 @@ pub fn lw_inst(rd: Expr, rs1: Expr, imm: Expr, xlen: u64) -> Stmt {
 }
 
 /// lbu
-/// FIXME
 pub fn lbu_inst(rd: Expr, rs1: Expr, imm: Expr, xlen: u64) -> Stmt {
     let mut stmts = vec![];
     stmts.push(Stmt::Comment(format!("lbu {}, {}, {}", rd, rs1, imm)));
@@ -611,7 +696,6 @@ pub fn lbu_inst(rd: Expr, rs1: Expr, imm: Expr, xlen: u64) -> Stmt {
 }
 
 /// lhu
-/// FIXME
 pub fn lhu_inst(rd: Expr, rs1: Expr, imm: Expr, xlen: u64) -> Stmt {
     let mut stmts = vec![];
     stmts.push(Stmt::Comment(format!("lhu {}, {}, {}", rd, rs1, imm)));
@@ -629,7 +713,6 @@ pub fn lhu_inst(rd: Expr, rs1: Expr, imm: Expr, xlen: u64) -> Stmt {
 }
 
 /// addi
-/// FIXME
 pub fn addi_inst(rd: Expr, rs1: Expr, imm: Expr, xlen: u64) -> Stmt {
     let mut stmts = vec![];
     stmts.push(Stmt::Comment(format!(
@@ -683,7 +766,6 @@ pub fn sltiu_inst(rd: Expr, rs1: Expr, imm: Expr, xlen: u64) -> Stmt {
 }
 
 /// xori
-/// FIXME
 pub fn xori_inst(rd: Expr, rs1: Expr, rs2: Expr, xlen: u64) -> Stmt {
     let mut stmts = vec![];
     stmts.push(Stmt::Comment(format!(
@@ -699,7 +781,6 @@ pub fn xori_inst(rd: Expr, rs1: Expr, rs2: Expr, xlen: u64) -> Stmt {
 }
 
 /// ori
-/// FIXME
 pub fn ori_inst(rd: Expr, rs1: Expr, rs2: Expr, xlen: u64) -> Stmt {
     let mut stmts = vec![];
     stmts.push(Stmt::Comment(format!("ori {}, {}, {}", rd, rs1, rs2)));
@@ -712,7 +793,6 @@ pub fn ori_inst(rd: Expr, rs1: Expr, rs2: Expr, xlen: u64) -> Stmt {
 }
 
 /// andi
-/// FIXME
 pub fn andi_inst(rd: Expr, rs1: Expr, rs2: Expr, xlen: u64) -> Stmt {
     let mut stmts = vec![];
     stmts.push(Stmt::Comment(format!(
@@ -1127,13 +1207,21 @@ pub fn lui_inst(rd: Expr, imm: Expr, xlen: u64) -> Stmt {
 pub fn auipc_inst(rd: Expr, imm: Expr, xlen: u64) -> Stmt {
     let mut stmts = vec![];
     stmts.push(Stmt::Comment(format!("auipc {}, {}", rd, imm)));
-    let ret = Expr::op_app(
-        Op::Bv(BVOp::Add),
+    // The upper immediate is sign extended from bit 31
+    let offset = Expr::op_app(
+        Op::Bv(BVOp::SignExt),
         vec![
-            pc_expr(xlen),
-            Expr::op_app(Op::Bv(BVOp::LeftShift), vec![imm, Expr::bv_lit(12, xlen)]),
+            Expr::op_app(
+                Op::Bv(BVOp::Slice { l: 31, r: 0 }),
+                vec![Expr::op_app(
+                    Op::Bv(BVOp::LeftShift),
+                    vec![imm, Expr::bv_lit(12, xlen)],
+                )],
+            ),
+            Expr::int_lit(32),
         ],
     );
+    let ret = Expr::op_app(Op::Bv(BVOp::Add), vec![pc_expr(xlen), offset]);
     stmts.push(Stmt::assign(vec![rd], vec![ret]));
     stmts.push(update_pc(xlen));
     Stmt::Block(stmts.iter().map(|x| Box::new(x.clone())).collect())
@@ -0,0 +1,706 @@
+//! Randomized differential tests for the rv_model instruction semantics.
+//!
+//! Each test case generates an instruction with random operands and a random
+//! initial state, evaluates the IR returned by the `system_model::*_inst`
+//! function with a small concrete interpreter and compares the resulting
+//! state with an independent reference evaluator of the RISC-V semantics.
+//! The reference uses the same state abstraction as the model, i.e. the
+//! byte, half, word and double memories are separate arrays.
+//!
+//! Set `RV_MODEL_DIFF_SEED` and `RV_MODEL_DIFF_ITERS` to change the seed and
+//! the number of test cases per instruction.
+
+use std::collections::BTreeMap;
+
+use asts::veriv_ast::*;
+use rv_model::system_model;
+use utils::constants::*;
+
+const XLEN: u64 = 64;
+
+/// Registers used as operands; `zero` exercises the zero register substitution
+const REGS: [&str; 4] = ["a0", "a1", "a2", "zero"];
+
+// =========================================================================================
+/// # Test state
+
+#[derive(Debug, Clone, PartialEq)]
+struct State {
+    pc: u64,
+    returned: u64,
+    regs: BTreeMap<String, u64>,
+    /// Memory values by array name and address (missing entries are 0)
+    mem: BTreeMap<(String, u64), u64>,
+}
+
+impl State {
+    fn reg(&self, name: &str) -> u64 {
+        *self.regs.get(name).unwrap_or(&0)
+    }
+
+    fn set_reg(&mut self, name: &str, val: u64) {
+        self.regs.insert(name.to_string(), val);
+    }
+
+    fn load(&self, mem: &str, addr: u64) -> u64 {
+        *self.mem.get(&(mem.to_string(), addr)).unwrap_or(&0)
+    }
+
+    fn store(&mut self, mem: &str, addr: u64, val: u64) {
+        self.mem.insert((mem.to_string(), addr), val);
+    }
+
+    /// Returns the state without the zero register and zero valued memory
+    fn normalized(&self) -> State {
+        let mut s = self.clone();
+        s.regs.remove("zero");
+        s.mem.retain(|_, v| *v != 0);
+        s
+    }
+}
+
+// =========================================================================================
+/// # Random generation
+
+/// Xorshift pseudo-random number generator
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a random register value biased towards edge cases
+    fn value(&mut self) -> u64 {
+        const EDGES: [u64; 10] = [
+            0,
+            1,
+            u64::MAX,
+            0x7fff_ffff,
+            0x8000_0000,
+            0xffff_ffff,
+            0xffff_ffff_8000_0000,
+            0x7fff_ffff_ffff_ffff,
+            0x8000_0000_0000_0000,
+            0xff,
+        ];
+        match self.next() % 4 {
+            0 => EDGES[(self.next() % EDGES.len() as u64) as usize],
+            1 => self.next() & 0xffff,
+            _ => self.next(),
+        }
+    }
+
+    /// Returns a random signed immediate of the given bit width
+    fn simm(&mut self, bits: u64) -> i64 {
+        let v = self.next() & ((1 << bits) - 1);
+        ((v << (64 - bits)) as i64) >> (64 - bits)
+    }
+
+    fn reg(&mut self) -> &'static str {
+        REGS[(self.next() % REGS.len() as u64) as usize]
+    }
+}
+
+// =========================================================================================
+/// # Test instructions
+
+#[derive(Debug, Clone)]
+struct TestInst {
+    op: &'static str,
+    rd: &'static str,
+    rs1: &'static str,
+    rs2: &'static str,
+    /// Immediate operand as printed by the disassembler; branch and jump
+    /// immediates are absolute target addresses
+    imm: i64,
+}
+
+/// Kinds of operands an instruction takes
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    /// rd, rs1, rs2
+    R,
+    /// rd, rs1, 12 bit immediate
+    I,
+    /// rd, rs1, shift amount below the given bound
+    Shift(u64),
+    /// rd, offset(rs1)
+    Load,
+    /// rs2, offset(rs1)
+    Store,
+    /// rs1, rs2, target
+    Branch,
+    /// rd, 20 bit immediate
+    U,
+    /// rd, target
+    Jal,
+    /// rd, offset(rs1)
+    Jalr,
+}
+
+const INSTS: [(&str, Format); 50] = [
+    ("add", Format::R),
+    ("sub", Format::R),
+    ("mul", Format::R),
+    ("sll", Format::R),
+    ("slt", Format::R),
+    ("sltu", Format::R),
+    ("xor", Format::R),
+    ("srl", Format::R),
+    ("sra", Format::R),
+    ("or", Format::R),
+    ("and", Format::R),
+    ("addw", Format::R),
+    ("subw", Format::R),
+    ("sllw", Format::R),
+    ("srlw", Format::R),
+    ("sraw", Format::R),
+    ("jalr", Format::Jalr),
+    ("lb", Format::Load),
+    ("lh", Format::Load),
+    ("lw", Format::Load),
+    ("lbu", Format::Load),
+    ("lhu", Format::Load),
+    ("lwu", Format::Load),
+    ("ld", Format::Load),
+    ("addi", Format::I),
+    ("slti", Format::I),
+    ("sltiu", Format::I),
+    ("xori", Format::I),
+    ("ori", Format::I),
+    ("andi", Format::I),
+    ("addiw", Format::I),
+    ("slli", Format::Shift(64)),
+    ("srli", Format::Shift(64)),
+    ("srai", Format::Shift(64)),
+    ("slliw", Format::Shift(32)),
+    ("srliw", Format::Shift(32)),
+    ("sraiw", Format::Shift(32)),
+    ("sb", Format::Store),
+    ("sh", Format::Store),
+    ("sw", Format::Store),
+    ("sd", Format::Store),
+    ("beq", Format::Branch),
+    ("bne", Format::Branch),
+    ("blt", Format::Branch),
+    ("bge", Format::Branch),
+    ("bltu", Format::Branch),
+    ("bgeu", Format::Branch),
+    ("lui", Format::U),
+    ("auipc", Format::U),
+    ("jal", Format::Jal),
+];
+
+/// Generates a random instruction and initial state
+fn gen_case(rng: &mut Rng, op: &'static str, fmt: Format) -> (TestInst, State) {
+    let mut state = State {
+        pc: 0x8000_0000 + 4 * (rng.next() & 0xfffff),
+        returned: 0,
+        regs: BTreeMap::new(),
+        mem: BTreeMap::new(),
+    };
+    for reg in REGS.iter().filter(|r| **r != "zero") {
+        state.set_reg(reg, rng.value());
+    }
+    let mut inst = TestInst {
+        op,
+        rd: rng.reg(),
+        rs1: rng.reg(),
+        rs2: rng.reg(),
+        imm: 0,
+    };
+    inst.imm = match fmt {
+        Format::R => 0,
+        Format::I | Format::Load | Format::Store | Format::Jalr => rng.simm(12),
+        Format::Shift(bound) => (rng.next() % bound) as i64,
+        Format::Branch => state.pc.wrapping_add(rng.simm(13) as u64 & !1) as i64,
+        Format::Jal => state.pc.wrapping_add(rng.simm(21) as u64 & !1) as i64,
+        Format::U => (rng.next() & 0xfffff) as i64,
+    };
+    // Initialize the memory accessed by loads
+    if fmt == Format::Load {
+        let addr = read_reg(&state, inst.rs1).wrapping_add(inst.imm as u64);
+        state.store(MEM_VAR_B, addr, rng.value() & 0xff);
+        state.store(MEM_VAR_H, addr, rng.value() & 0xffff);
+        state.store(MEM_VAR_W, addr, rng.value() & 0xffff_ffff);
+        state.store(MEM_VAR_D, addr, rng.value());
+    }
+    (inst, state)
+}
+
+/// Returns the IR of the instruction using the operand conventions of the translator
+fn model_stmt(inst: &TestInst) -> Stmt {
+    let reg = |name: &str| match name {
+        "zero" => Expr::bv_lit(0, XLEN),
+        _ => Expr::var(name, system_model::bv_type(XLEN)),
+    };
+    let rd = Expr::var(inst.rd, system_model::bv_type(XLEN));
+    let rs1 = reg(inst.rs1);
+    let rs2 = reg(inst.rs2);
+    let imm = Expr::bv_lit(inst.imm as u64, XLEN);
+    match inst.op {
+        "add" => system_model::add_inst(rd, rs1, rs2, XLEN),
+        "sub" => system_model::sub_inst(rd, rs1, rs2, XLEN),
+        "mul" => system_model::mul_inst(rd, rs1, rs2, XLEN),
+        "sll" => system_model::sll_inst(rd, rs1, rs2, XLEN),
+        "slt" => system_model::slt_inst(rd, rs1, rs2, XLEN),
+        "sltu" => system_model::sltu_inst(rd, rs1, rs2, XLEN),
+        "xor" => system_model::xor_inst(rd, rs1, rs2, XLEN),
+        "srl" => system_model::srl_inst(rd, rs1, rs2, XLEN),
+        "sra" => system_model::sra_inst(rd, rs1, rs2, XLEN),
+        "or" => system_model::or_inst(rd, rs1, rs2, XLEN),
+        "and" => system_model::and_inst(rd, rs1, rs2, XLEN),
+        "addw" => system_model::addw_inst(rd, rs1, rs2, XLEN),
+        "subw" => system_model::subw_inst(rd, rs1, rs2, XLEN),
+        "sllw" => system_model::sllw_inst(rd, rs1, rs2, XLEN),
+        "srlw" => system_model::srlw_inst(rd, rs1, rs2, XLEN),
+        "sraw" => system_model::sraw_inst(rd, rs1, rs2, XLEN),
+        "jalr" => system_model::jalr_inst(rd, rs1, imm, XLEN),
+        "lb" => system_model::lb_inst(rd, rs1, imm, XLEN),
+        "lh" => system_model::lh_inst(rd, rs1, imm, XLEN),
+        "lw" => system_model::lw_inst(rd, rs1, imm, XLEN),
+        "lbu" => system_model::lbu_inst(rd, rs1, imm, XLEN),
+        "lhu" => system_model::lhu_inst(rd, rs1, imm, XLEN),
+        "lwu" => system_model::lwu_inst(rd, rs1, imm, XLEN),
+        "ld" => system_model::ld_inst(rd, rs1, imm, XLEN),
+        "addi" => system_model::addi_inst(rd, rs1, imm, XLEN),
+        "slti" => system_model::slti_inst(rd, rs1, imm, XLEN),
+        "sltiu" => system_model::sltiu_inst(rd, rs1, imm, XLEN),
+        "xori" => system_model::xori_inst(rd, rs1, imm, XLEN),
+        "ori" => system_model::ori_inst(rd, rs1, imm, XLEN),
+        "andi" => system_model::andi_inst(rd, rs1, imm, XLEN),
+        "addiw" => system_model::addiw_inst(rd, rs1, imm, XLEN),
+        "slli" => system_model::slli_inst(rd, rs1, imm, XLEN),
+        "srli" => system_model::srli_inst(rd, rs1, imm, XLEN),
+        "srai" => system_model::srai_inst(rd, rs1, imm, XLEN),
+        "slliw" => system_model::slliw_inst(rd, rs1, imm, XLEN),
+        "srliw" => system_model::srliw_inst(rd, rs1, imm, XLEN),
+        "sraiw" => system_model::sraiw_inst(rd, rs1, imm, XLEN),
+        "sb" => system_model::sb_inst(rs1, imm, rs2, XLEN),
+        "sh" => system_model::sh_inst(rs1, imm, rs2, XLEN),
+        "sw" => system_model::sw_inst(rs1, imm, rs2, XLEN),
+        "sd" => system_model::sd_inst(rs1, imm, rs2, XLEN),
+        "beq" => system_model::beq_inst(rs1, rs2, imm, XLEN),
+        "bne" => system_model::bne_inst(rs1, rs2, imm, XLEN),
+        "blt" => system_model::blt_inst(rs1, rs2, imm, XLEN),
+        "bge" => system_model::bge_inst(rs1, rs2, imm, XLEN),
+        "bltu" => system_model::bltu_inst(rs1, rs2, imm, XLEN),
+        "bgeu" => system_model::bgeu_inst(rs1, rs2, imm, XLEN),
+        "lui" => system_model::lui_inst(rd, imm, XLEN),
+        "auipc" => system_model::auipc_inst(rd, imm, XLEN),
+        "jal" => system_model::jal_inst(rd, imm, XLEN),
+        op => panic!("No model for instruction {}.", op),
+    }
+}
+
+// =========================================================================================
+/// # Reference evaluator
+
+fn read_reg(s: &State, name: &str) -> u64 {
+    match name {
+        "zero" => 0,
+        _ => s.reg(name),
+    }
+}
+
+fn sext(val: u64, width: u64) -> u64 {
+    (((val << (64 - width)) as i64) >> (64 - width)) as u64
+}
+
+/// Returns the state after executing the instruction according to the ISA manual
+fn reference(inst: &TestInst, s: &State) -> State {
+    let mut n = s.clone();
+    let a = read_reg(s, inst.rs1);
+    let b = read_reg(s, inst.rs2);
+    let imm = inst.imm as u64;
+    let addr = a.wrapping_add(imm);
+    let next = s.pc.wrapping_add(4);
+    n.pc = next;
+    let rd_val = match inst.op {
+        "add" => Some(a.wrapping_add(b)),
+        "sub" => Some(a.wrapping_sub(b)),
+        "mul" => Some(a.wrapping_mul(b)),
+        "sll" => Some(a << (b & 63)),
+        "slt" => Some(((a as i64) < (b as i64)) as u64),
+        "sltu" => Some((a < b) as u64),
+        "xor" => Some(a ^ b),
+        "srl" => Some(a >> (b & 63)),
+        "sra" => Some(((a as i64) >> (b & 63)) as u64),
+        "or" => Some(a | b),
+        "and" => Some(a & b),
+        "addw" => Some(sext(a.wrapping_add(b), 32)),
+        "subw" => Some(sext(a.wrapping_sub(b), 32)),
+        "sllw" => Some(sext(((a as u32) << (b & 31)) as u64, 32)),
+        "srlw" => Some(sext(((a as u32) >> (b & 31)) as u64, 32)),
+        "sraw" => Some(((a as i32) >> (b & 31)) as i64 as u64),
+        "jalr" => {
+            n.pc = addr & !1;
+            n.returned = 1;
+            Some(next)
+        }
+        "lb" => Some(sext(s.load(MEM_VAR_B, addr), 8)),
+        "lh" => Some(sext(s.load(MEM_VAR_H, addr), 16)),
+        "lw" => Some(sext(s.load(MEM_VAR_W, addr), 32)),
+        "lbu" => Some(s.load(MEM_VAR_B, addr)),
+        "lhu" => Some(s.load(MEM_VAR_H, addr)),
+        "lwu" => Some(s.load(MEM_VAR_W, addr)),
+        "ld" => Some(s.load(MEM_VAR_D, addr)),
+        "addi" => Some(a.wrapping_add(imm)),
+        "slti" => Some(((a as i64) < (imm as i64)) as u64),
+        "sltiu" => Some((a < imm) as u64),
+        "xori" => Some(a ^ imm),
+        "ori" => Some(a | imm),
+        "andi" => Some(a & imm),
+        "addiw" => Some(sext(a.wrapping_add(imm), 32)),
+        "slli" => Some(a << imm),
+        "srli" => Some(a >> imm),
+        "srai" => Some(((a as i64) >> imm) as u64),
+        "slliw" => Some(sext(((a as u32) << imm) as u64, 32)),
+        "srliw" => Some(sext(((a as u32) >> imm) as u64, 32)),
+        "sraiw" => Some(((a as i32) >> imm) as i64 as u64),
+        "sb" | "sh" | "sw" | "sd" => {
+            let (mem, mask) = match inst.op {
+                "sb" => (MEM_VAR_B, 0xff),
+                "sh" => (MEM_VAR_H, 0xffff),
+                "sw" => (MEM_VAR_W, 0xffff_ffff),
+                _ => (MEM_VAR_D, u64::MAX),
+            };
+            n.store(mem, addr, b & mask);
+            None
+        }
+        "beq" | "bne" | "blt" | "bge" | "bltu" | "bgeu" => {
+            let taken = match inst.op {
+                "beq" => a == b,
+                "bne" => a != b,
+                "blt" => (a as i64) < (b as i64),
+                "bge" => (a as i64) >= (b as i64),
+                "bltu" => a < b,
+                _ => a >= b,
+            };
+            if taken {
+                n.pc = imm;
+            }
+            None
+        }
+        "lui" => Some(sext(imm << 12, 32)),
+        "auipc" => Some(s.pc.wrapping_add(sext(imm << 12, 32))),
+        "jal" => {
+            n.pc = imm;
+            Some(next)
+        }
+        op => panic!("No reference semantics for instruction {}.", op),
+    };
+    if let Some(val) = rd_val {
+        if inst.rd != "zero" {
+            n.set_reg(inst.rd, val);
+        }
+    }
+    n
+}
+
+// =========================================================================================
+/// # IR interpreter
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Val {
+    Bv(u64, u64),
+    Int(u64),
+    Bool(bool),
+}
+
+fn mask(width: u64) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+fn expect_bv(v: Val) -> Result<(u64, u64), String> {
+    match v {
+        Val::Bv(val, w) => Ok((val, w)),
+        _ => Err(format!("expected a bitvector but found {:?}", v)),
+    }
+}
+
+fn expect_bool(v: Val) -> Result<bool, String> {
+    match v {
+        Val::Bool(b) => Ok(b),
+        _ => Err(format!("expected a boolean but found {:?}", v)),
+    }
+}
+
+/// Returns the bitvector operands of a binary operator of equal widths
+fn same_width_bvs(op: &Op, vals: &[Val]) -> Result<(u64, u64, u64), String> {
+    let (a, wa) = expect_bv(vals[0])?;
+    let (b, wb) = expect_bv(vals[1])?;
+    if wa != wb {
+        return Err(format!(
+            "operands of {:?} have different widths bv{} and bv{}",
+            op, wa, wb
+        ));
+    }
+    Ok((a, b, wa))
+}
+
+/// Returns the width of the array elements
+fn array_out_width(array: &Expr) -> Result<u64, String> {
+    match array.typ() {
+        Type::Array { out_typ, .. } => Ok(out_typ.get_expect_bv_width()),
+        t => Err(format!("cannot index into non-array type {}", t)),
+    }
+}
+
+fn eval(e: &Expr, s: &State) -> Result<Val, String> {
+    match e {
+        Expr::Literal(Literal::Bv { val, width }, _) => {
+            if val & !mask(*width) != 0 {
+                return Err(format!("literal {} does not fit in bv{}", val, width));
+            }
+            Ok(Val::Bv(*val, *width))
+        }
+        Expr::Literal(Literal::Int { val }, _) => Ok(Val::Int(*val)),
+        Expr::Literal(Literal::Bool { val }, _) => Ok(Val::Bool(*val)),
+        Expr::Var(v, _) => match &v.name[..] {
+            PC_VAR => Ok(Val::Bv(s.pc, XLEN)),
+            RETURNED_FLAG => Ok(Val::Bv(s.returned, 1)),
+            name => Ok(Val::Bv(s.reg(name), XLEN)),
+        },
+        Expr::OpApp(opapp, _) => {
+            let op = &opapp.op;
+            if let Op::ArrayIndex = op {
+                let array = &opapp.operands[0];
+                let (addr, w) = expect_bv(eval(&opapp.operands[1], s)?)?;
+                if w != XLEN {
+                    return Err(format!("array index has width bv{}", w));
+                }
+                let width = array_out_width(array)?;
+                return Ok(Val::Bv(s.load(&array.get_var_name()[..], addr), width));
+            }
+            let vals = opapp
+                .operands
+                .iter()
+                .map(|operand| eval(operand, s))
+                .collect::<Result<Vec<_>, _>>()?;
+            eval_op(op, &vals)
+        }
+        Expr::FuncApp(fapp, _) => Err(format!("unsupported function {}", fapp.func_name)),
+    }
+}
+
+fn eval_op(op: &Op, vals: &[Val]) -> Result<Val, String> {
+    match op {
+        Op::Bv(bvop) => match bvop {
+            BVOp::Add | BVOp::Sub | BVOp::Mul | BVOp::And | BVOp::Or | BVOp::Xor => {
+                let (a, b, w) = same_width_bvs(op, vals)?;
+                let res = match bvop {
+                    BVOp::Add => a.wrapping_add(b),
+                    BVOp::Sub => a.wrapping_sub(b),
+                    BVOp::Mul => a.wrapping_mul(b),
+                    BVOp::And => a & b,
+                    BVOp::Or => a | b,
+                    _ => a ^ b,
+                };
+                Ok(Val::Bv(res & mask(w), w))
+            }
+            BVOp::LeftShift | BVOp::RightShift | BVOp::ARightShift => {
+                let (a, b, w) = same_width_bvs(op, vals)?;
+                let res = match bvop {
+                    BVOp::LeftShift if b < w => a << b,
+                    BVOp::RightShift if b < w => a >> b,
+                    BVOp::ARightShift => ((sext(a, w) as i64) >> b.min(63)) as u64,
+                    _ => 0,
+                };
+                Ok(Val::Bv(res & mask(w), w))
+            }
+            BVOp::SignExt | BVOp::ZeroExt => {
+                let (a, w) = expect_bv(vals[0])?;
+                let n = match vals[1] {
+                    Val::Int(n) => n,
+                    v => return Err(format!("extension amount {:?} is not an integer", v)),
+                };
+                if w + n > 64 {
+                    return Err(format!("extending bv{} by {} exceeds 64 bits", w, n));
+                }
+                let res = match bvop {
+                    BVOp::SignExt => sext(a, w),
+                    _ => a,
+                };
+                Ok(Val::Bv(res & mask(w + n), w + n))
+            }
+            BVOp::Concat => {
+                let (a, wa) = expect_bv(vals[0])?;
+                let (b, wb) = expect_bv(vals[1])?;
+                if wa + wb > 64 {
+                    return Err(format!("concatenating bv{} and bv{} exceeds 64 bits", wa, wb));
+                }
+                Ok(Val::Bv((a << wb) | b, wa + wb))
+            }
+            BVOp::Slice { l, r } => {
+                let (a, w) = expect_bv(vals[0])?;
+                if l < r || *l >= w {
+                    return Err(format!("invalid slice [{}:{}] of bv{}", l, r, w));
+                }
+                Ok(Val::Bv((a >> r) & mask(l - r + 1), l - r + 1))
+            }
+        },
+        Op::Comp(cop) => {
+            if let CompOp::Equality | CompOp::Inequality = cop {
+                if let (Val::Bool(a), Val::Bool(b)) = (vals[0], vals[1]) {
+                    return Ok(Val::Bool((a == b) == (*cop == CompOp::Equality)));
+                }
+            }
+            let (a, b, w) = same_width_bvs(op, vals)?;
+            let (sa, sb) = (sext(a, w) as i64, sext(b, w) as i64);
+            Ok(Val::Bool(match cop {
+                CompOp::Equality => a == b,
+                CompOp::Inequality => a != b,
+                CompOp::Lt => sa < sb,
+                CompOp::Le => sa <= sb,
+                CompOp::Gt => sa > sb,
+                CompOp::Ge => sa >= sb,
+                CompOp::Ltu => a < b,
+                CompOp::Leu => a <= b,
+                CompOp::Gtu => a > b,
+                CompOp::Geu => a >= b,
+            }))
+        }
+        Op::Bool(bop) => {
+            let bs = vals
+                .iter()
+                .map(|v| expect_bool(*v))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Val::Bool(match bop {
+                BoolOp::Conj => bs.iter().all(|b| *b),
+                BoolOp::Disj => bs.iter().any(|b| *b),
+                BoolOp::Iff => bs[0] == bs[1],
+                BoolOp::Impl => !bs[0] || bs[1],
+                BoolOp::Neg => !bs[0],
+            }))
+        }
+        Op::ArrayIndex | Op::GetField(_) => Err(format!("unsupported operator {:?}", op)),
+    }
+}
+
+fn exec(stmt: &Stmt, s: &mut State) -> Result<(), String> {
+    match stmt {
+        Stmt::Assume(e) => {
+            if !expect_bool(eval(e, s)?)? {
+                return Err(format!("assumption {} does not hold", e));
+            }
+        }
+        Stmt::Assign(assign) => {
+            // Evaluate the right hand sides before updating the state
+            let vals = assign
+                .rhs
+                .iter()
+                .map(|e| eval(e, s))
+                .collect::<Result<Vec<_>, _>>()?;
+            for (lhs, val) in assign.lhs.iter().zip(vals) {
+                let (val, w) = expect_bv(val)?;
+                match lhs {
+                    Expr::Var(v, _) => {
+                        let expected = if v.name == RETURNED_FLAG { 1 } else { XLEN };
+                        if w != expected {
+                            return Err(format!("assigning bv{} to {}", w, v.name));
+                        }
+                        match &v.name[..] {
+                            PC_VAR => s.pc = val,
+                            RETURNED_FLAG => s.returned = val,
+                            name => s.set_reg(name, val),
+                        }
+                    }
+                    Expr::OpApp(opapp, _) if opapp.op == Op::ArrayIndex => {
+                        let array = &opapp.operands[0];
+                        let width = array_out_width(array)?;
+                        if w != width {
+                            return Err(format!("storing bv{} into {}", w, array));
+                        }
+                        let (addr, _) = expect_bv(eval(&opapp.operands[1], s)?)?;
+                        s.store(&array.get_var_name()[..], addr, val);
+                    }
+                    _ => return Err(format!("unsupported assignment to {}", lhs)),
+                }
+            }
+        }
+        Stmt::IfThenElse(ite) => {
+            if expect_bool(eval(&ite.cond, s)?)? {
+                exec(&ite.then_stmt, s)?;
+            } else if let Some(else_stmt) = &ite.else_stmt {
+                exec(else_stmt, s)?;
+            }
+        }
+        Stmt::Block(stmts) => {
+            for stmt in stmts {
+                exec(stmt, s)?;
+            }
+        }
+        Stmt::Assert(e) => {
+            if !expect_bool(eval(e, s)?)? {
+                return Err(format!("assertion {} does not hold", e));
+            }
+        }
+        Stmt::Havoc(v) => return Err(format!("unsupported havoc of {}", v.name)),
+        Stmt::Comment(_) => (),
+        Stmt::FuncCall(fc) => return Err(format!("unsupported call to {}", fc.func_name)),
+    }
+    Ok(())
+}
+
+// =========================================================================================
+/// # Differential tests
+
+/// Runs `iters` random test cases of the instruction and returns the disagreements
+fn check_inst(rng: &mut Rng, op: &'static str, fmt: Format, iters: u64) -> Vec<String> {
+    let mut failures = vec![];
+    for _ in 0..iters {
+        let (inst, init) = gen_case(rng, op, fmt);
+        let expected = reference(&inst, &init).normalized();
+        let mut actual = init.clone();
+        let res = exec(&model_stmt(&inst), &mut actual);
+        let msg = match res {
+            Err(e) => Some(format!("IR evaluation failed: {}", e)),
+            Ok(_) if actual.normalized() != expected => Some(format!(
+                "expected {:?}\n    but the model produced {:?}",
+                expected,
+                actual.normalized()
+            )),
+            _ => None,
+        };
+        if let Some(msg) = msg {
+            failures.push(format!("{:?} from {:?}\n    {}", inst, init, msg));
+            // One counterexample per instruction is enough
+            break;
+        }
+    }
+    failures
+}
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(default)
+}
+
+#[test]
+fn differential_instruction_semantics() {
+    let seed = env_u64("RV_MODEL_DIFF_SEED", 0x5eed_1234_abcd_0001);
+    let iters = env_u64("RV_MODEL_DIFF_ITERS", 500);
+    let mut rng = Rng(seed.max(1));
+    let mut failures = vec![];
+    for (op, fmt) in INSTS.iter() {
+        failures.append(&mut check_inst(&mut rng, op, *fmt, iters));
+    }
+    assert!(
+        failures.is_empty(),
+        "{} instruction(s) disagree with the reference semantics (seed {:#x}):\n{}",
+        failures.len(),
+        seed,
+        failures.join("\n")
+    );
+}
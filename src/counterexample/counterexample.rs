@@ -0,0 +1,341 @@
+//! Parser for UCLID5 counterexample traces.
+//! The values in the trace are mapped back to the disassembled instructions,
+//! basic block procedures, registers and DWARF global variables of the binary.
+
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    rc::Rc,
+};
+
+use dwarf_ctx::dwarfreader::DwarfCtx;
+
+use utils::{constants, helpers};
+
+use crate::{
+    datastructures::cfg, disassembler::disassembler::AssemblyLine,
+    disassembler::disassembler::Inst, simulator::simulator::SimState,
+};
+
+// ========================================================================================
+// # Counterexample trace
+
+/// Value of a variable in a counterexample frame
+#[derive(Debug, Clone, PartialEq)]
+pub enum CexValue {
+    /// Bit vector value and its width
+    Bv(u64, u64),
+    Int(i64),
+    Bool(bool),
+    /// Values that are not parsed (e.g. arrays)
+    Other(String),
+}
+impl CexValue {
+    /// Parses a value printed by UCLID5 or the underlying SMT solver
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim();
+        let bv_re = Regex::new(r"^(-?\d+)bv(\d+)$").unwrap();
+        let smt_bv_re = Regex::new(r"^\(_\s+bv(\d+)\s+(\d+)\)$").unwrap();
+        if let Some(caps) = bv_re.captures(s) {
+            let w = helpers::dec_str_to_u64(&caps[2]).unwrap_or(64);
+            if let Ok(v) = helpers::dec_str_to_u64(&caps[1]) {
+                return CexValue::Bv(v, w);
+            }
+            if let Ok(v) = helpers::dec_str_to_i64(&caps[1]) {
+                return CexValue::Bv(v as u64 & Self::mask(w), w);
+            }
+        } else if let Some(caps) = smt_bv_re.captures(s) {
+            if let (Ok(v), Ok(w)) = (
+                helpers::dec_str_to_u64(&caps[1]),
+                helpers::dec_str_to_u64(&caps[2]),
+            ) {
+                return CexValue::Bv(v, w);
+            }
+        } else if let Some(hex) = s.strip_prefix("#x") {
+            if let Ok(v) = helpers::hex_str_to_u64(hex) {
+                return CexValue::Bv(v, hex.len() as u64 * 4);
+            }
+        } else if let Some(bin) = s.strip_prefix("#b") {
+            if let Ok(v) = u64::from_str_radix(bin, 2) {
+                return CexValue::Bv(v, bin.len() as u64);
+            }
+        } else if s == "true" || s == "false" {
+            return CexValue::Bool(s == "true");
+        } else if let Ok(i) = helpers::dec_str_to_i64(s) {
+            return CexValue::Int(i);
+        }
+        CexValue::Other(s.to_string())
+    }
+
+    /// Returns the value as an unsigned integer if it is a bit vector or integer
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            CexValue::Bv(v, _) => Some(*v),
+            CexValue::Int(i) => Some(*i as u64),
+            _ => None,
+        }
+    }
+
+    fn mask(w: u64) -> u64 {
+        if w >= 64 {
+            u64::MAX
+        } else {
+            (1 << w) - 1
+        }
+    }
+}
+impl fmt::Display for CexValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CexValue::Bv(v, w) => write!(f, "{:#x} (bv{})", v, w),
+            CexValue::Int(i) => write!(f, "{}", i),
+            CexValue::Bool(b) => write!(f, "{}", b),
+            CexValue::Other(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Values of the variables at one step of a counterexample
+#[derive(Debug, Clone)]
+pub struct CexFrame {
+    /// Step number printed by UCLID5
+    pub step: u64,
+    /// Values by variable name
+    pub vals: BTreeMap<String, CexValue>,
+}
+
+/// A counterexample to one property
+#[derive(Debug, Clone)]
+pub struct Counterexample {
+    /// Description of the failed property (e.g. `assertion @ model.ucl, line 120`)
+    pub property: String,
+    /// Frames in the order they are printed
+    pub frames: Vec<CexFrame>,
+}
+impl Counterexample {
+    /// Parses all counterexamples in the output of UCLID5's `print_cex`
+    ///
+    /// # Example
+    ///
+    /// CEX for assertion @ main.ucl, line 120
+    /// =================================
+    /// Step #0
+    ///   pc : 2147483648bv64
+    ///   a0 : 0bv64
+    /// =================================
+    pub fn parse(text: &str) -> Vec<Counterexample> {
+        let step_re = Regex::new(r"^Step\s*#(\d+)").unwrap();
+        let val_re = Regex::new(r"^([\w.\[\]]+)\s*:\s*(.+)$").unwrap();
+        let mut cexs: Vec<Counterexample> = vec![];
+        for line in text.lines() {
+            let line = line.trim();
+            if let Some(property) = line.strip_prefix("CEX for") {
+                cexs.push(Counterexample {
+                    property: property.trim().to_string(),
+                    frames: vec![],
+                });
+            } else if let Some(caps) = step_re.captures(line) {
+                // Traces without a header belong to an unnamed property
+                if cexs.is_empty() {
+                    cexs.push(Counterexample {
+                        property: "?".to_string(),
+                        frames: vec![],
+                    });
+                }
+                let step = helpers::dec_str_to_u64(&caps[1]).unwrap_or(0);
+                cexs.last_mut().unwrap().frames.push(CexFrame {
+                    step,
+                    vals: BTreeMap::new(),
+                });
+            } else if let Some(caps) = val_re.captures(line) {
+                if let Some(frame) = cexs.last_mut().and_then(|cex| cex.frames.last_mut()) {
+                    frame.vals.insert(caps[1].to_string(), CexValue::parse(&caps[2]));
+                }
+            }
+        }
+        cexs
+    }
+
+    /// Parses the counterexamples in the file at `path`
+    pub fn parse_file(path: &str) -> Vec<Counterexample> {
+        let content = fs::read_to_string(path)
+            .unwrap_or_else(|_| panic!("Unable to read counterexample file {}.", path));
+        Self::parse(&content)
+    }
+}
+
+// ========================================================================================
+// # Counterexample mapping
+
+/// Maps the values of a counterexample to the binary
+pub struct CexMapper<'a> {
+    /// Disassembled lines by address
+    lines: HashMap<u64, Rc<AssemblyLine>>,
+    /// Entry addresses of the basic blocks
+    bb_entries: BTreeMap<u64, Rc<cfg::BasicBlock<AssemblyLine>>>,
+    /// DWARF debugging information
+    dwarf_ctx: &'a DwarfCtx,
+    /// Lines of the generated model used to describe the failed assertions
+    model_lines: Vec<String>,
+}
+
+impl<'a> CexMapper<'a> {
+    pub fn new(
+        als: &[Rc<AssemblyLine>],
+        bbs: &HashMap<u64, Rc<cfg::BasicBlock<AssemblyLine>>>,
+        dwarf_ctx: &'a DwarfCtx,
+    ) -> Self {
+        CexMapper {
+            lines: als.iter().map(|al| (*al.addr(), Rc::clone(al))).collect(),
+            bb_entries: bbs.iter().map(|(addr, bb)| (*addr, Rc::clone(bb))).collect(),
+            dwarf_ctx,
+            model_lines: vec![],
+        }
+    }
+
+    /// Reads the generated model that the counterexamples refer to
+    pub fn set_model_file(&mut self, path: &str) {
+        let content = fs::read_to_string(path)
+            .unwrap_or_else(|_| panic!("Unable to read model file {}.", path));
+        self.model_lines = content.lines().map(|line| line.to_string()).collect();
+    }
+
+    /// Returns the comment preceding the failed property in the model
+    /// (e.g. the instruction of a memory-safety assertion)
+    pub fn describe_property(&self, property: &str) -> Option<String> {
+        let line_re = Regex::new(r"line (\d+)").unwrap();
+        let line_num = helpers::dec_str_to_u64(&line_re.captures(property)?[1]).ok()? as usize;
+        self.model_lines
+            .iter()
+            .take(line_num.saturating_sub(1))
+            .rev()
+            .map(|line| line.trim())
+            .find(|line| !line.is_empty() && !line.starts_with("assert"))
+            .and_then(|line| line.strip_prefix("//"))
+            .map(|comment| comment.trim().to_string())
+    }
+
+    /// Returns a readable execution path of the counterexample.
+    /// Each step shows the instruction at the pc followed by the variables that changed.
+    pub fn describe(&self, cex: &Counterexample) -> String {
+        let mut lines = vec![format!("// Counterexample for {}", cex.property)];
+        if let Some(desc) = self.describe_property(&cex.property) {
+            lines.push(format!("// Failed check: {}", desc));
+        }
+        let mut prev: Option<&CexFrame> = None;
+        for frame in &cex.frames {
+            lines.push(format!("Step #{}", frame.step));
+            if let Some(pc) = frame.vals.get(constants::PC_VAR).and_then(|v| v.as_u64()) {
+                lines.push(format!("  pc = {}", self.describe_pc(pc)));
+            }
+            for (name, val) in &frame.vals {
+                if name == constants::PC_VAR {
+                    continue;
+                }
+                if let Some(prev) = prev {
+                    if prev.vals.get(name) == Some(val) {
+                        continue;
+                    }
+                }
+                lines.push(format!("  {} = {}", self.describe_var(name), val));
+            }
+            prev = Some(frame);
+        }
+        format!("{}\n", lines.join("\n"))
+    }
+
+    /// Returns the instruction, basic block procedure and source location at `pc`
+    pub fn describe_pc(&self, pc: u64) -> String {
+        let mut desc = format!("{:#x}", pc);
+        if let Some(al) = self.lines.get(&pc) {
+            let ops = al
+                .ops()
+                .iter()
+                .map(|op| op.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            desc = format!(
+                "{} <{}+{:#x}>: {} {}",
+                desc,
+                al.function_name(),
+                al.offset(),
+                al.op(),
+                ops
+            );
+        }
+        if let Some((entry, _)) = self
+            .bb_entries
+            .range(..=pc)
+            .next_back()
+            .filter(|(_, bb)| bb.exit().address() >= pc)
+        {
+            desc = format!("{} [bb_{:#x?}]", desc, entry);
+        }
+        if let Some(loc) = self.dwarf_ctx.source_loc_str(pc) {
+            desc = format!("{} @ {}", desc, loc);
+        }
+        desc
+    }
+
+    /// Returns the variable name annotated with the global variable it abstracts
+    pub fn describe_var(&self, name: &str) -> String {
+        match helpers::abs_access_addr(name).and_then(|addr| self.global_at(addr)) {
+            Some(global) => format!("{} ({})", name, global),
+            None => name.to_string(),
+        }
+    }
+
+    /// Returns the initial state of the counterexample in the simulator state file format
+    pub fn initial_state(&self, cex: &Counterexample) -> String {
+        let mut lines = vec![format!("// Initial state of the counterexample for {}", cex.property)];
+        let frame = match cex.frames.first() {
+            Some(frame) => frame,
+            None => return format!("{}\n", lines.join("\n")),
+        };
+        for (name, val) in &frame.vals {
+            let v = match val {
+                CexValue::Bv(v, _) => *v,
+                _ => continue,
+            };
+            if let Some(addr) = helpers::abs_access_addr(name) {
+                let suffix = match val {
+                    CexValue::Bv(_, 8) => "b",
+                    CexValue::Bv(_, 16) => "h",
+                    CexValue::Bv(_, 32) => "w",
+                    _ => "d",
+                };
+                if let Some(global) = self.global_at(addr) {
+                    lines.push(format!("// {}", global));
+                }
+                lines.push(format!("mem_{}[{:#x}] = {:#x}", suffix, addr, v));
+            } else if name == constants::PC_VAR || Self::is_reg(name) {
+                lines.push(format!("{} = {:#x}", name, v));
+            }
+        }
+        format!("{}\n", lines.join("\n"))
+    }
+
+    /// Returns the global variable (and offset into it) at `addr`
+    fn global_at(&self, addr: u64) -> Option<String> {
+        self.dwarf_ctx
+            .global_vars()
+            .iter()
+            .find(|var| {
+                let bytes = var.typ_defn.object_size().unwrap_or(1).max(1);
+                var.memory_addr <= addr && addr < var.memory_addr + bytes
+            })
+            .map(|var| {
+                if var.memory_addr == addr {
+                    var.name.clone()
+                } else {
+                    format!("{}+{:#x}", var.name, addr - var.memory_addr)
+                }
+            })
+    }
+
+    /// Returns true if `name` is a general purpose register
+    fn is_reg(name: &str) -> bool {
+        SimState::reg_index(name).is_some()
+    }
+}
@@ -267,6 +267,11 @@ impl AssemblyLine {
         &self.addr
     }
 
+    /// Returns the offset of this line from the entry of the function
+    pub fn offset(&self) -> &u64 {
+        &self.offset
+    }
+
     /// Return the arguments of the assembly line
     pub fn ops(&self) -> &Vec<InstOperand> {
         &self.ops
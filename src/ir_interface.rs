@@ -1,8 +1,15 @@
-use std::{collections::HashSet, fmt, rc::Rc};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt,
+    io::{self, Write},
+    rc::Rc,
+};
 
 use asts::{spec_lang::sl_ast, veriv_ast as ast};
 
-use dwarf_ctx::dwarfreader::DwarfCtx;
+use dwarf_ctx::dwarfreader::{DwarfCtx, DwarfTypeDefn};
+
+use crate::solver::SolverConfig;
 
 // =====================================================================================================
 /// # IR Interface
@@ -41,25 +48,152 @@ pub trait IRInterface: fmt::Debug {
     fn array_index_to_string(arr: &ast::Expr, index: &ast::Expr, xlen: &u64) -> String;
     fn get_field_to_string(struct_: &ast::Expr, field: &String, xlen: &u64) -> String;
     /// Statements to string
-    fn stmt_to_string(stmt: &ast::Stmt, xlen: &u64) -> String;
+    /// Single line statements are converted to strings; compound statements, procedures and
+    /// the model are written line by line to an `IndentWriter`
+    fn write_stmt(out: &mut IndentWriter, stmt: &ast::Stmt, xlen: &u64) -> io::Result<()> {
+        match stmt {
+            ast::Stmt::Assume(expr) => out.line(&Self::assume_to_string(expr, xlen)),
+            ast::Stmt::Assert(expr) => out.line(&Self::assert_to_string(expr, xlen)),
+            ast::Stmt::Havoc(var) => out.line(&Self::havoc_to_string(var)),
+            ast::Stmt::FuncCall(fc) => out.line(&Self::func_call_to_string(fc, xlen)),
+            ast::Stmt::Assign(assign) => out.line(&Self::assign_to_string(assign, xlen)),
+            ast::Stmt::IfThenElse(ite) => Self::write_ite(out, ite, xlen),
+            ast::Stmt::Block(stmt_vec) => Self::write_block(out, stmt_vec, xlen),
+            ast::Stmt::Comment(comment) => out.line(&Self::comment_to_string(comment)),
+        }
+    }
     fn skip_to_string() -> String;
     fn assert_to_string(expr: &ast::Expr, xlen: &u64) -> String;
     fn assume_to_string(expr: &ast::Expr, xlen: &u64) -> String;
     fn havoc_to_string(var: &Rc<ast::Var>) -> String;
     fn func_call_to_string(func_call: &ast::FuncCall, xlen: &u64) -> String;
     fn assign_to_string(assign: &ast::Assign, xlen: &u64) -> String;
-    fn ite_to_string(ite: &ast::IfThenElse, xlen: &u64) -> String;
-    fn block_to_string(blk: &Vec<Box<ast::Stmt>>, xlen: &u64) -> String;
     fn comment_to_string(comment: &String) -> String;
-    fn func_model_to_string(fm: &ast::FuncModel, dwarf_ctx: &DwarfCtx, xlen: &u64) -> String;
-    // IR to model string
-    fn model_to_string(
+    fn write_ite(out: &mut IndentWriter, ite: &ast::IfThenElse, xlen: &u64) -> io::Result<()>;
+    fn write_block(out: &mut IndentWriter, blk: &[Box<ast::Stmt>], xlen: &u64) -> io::Result<()>;
+    fn write_func_model(
+        out: &mut IndentWriter,
+        fm: &ast::FuncModel,
+        dwarf_ctx: &DwarfCtx,
+        xlen: &u64,
+    ) -> io::Result<()>;
+    // IR to model; the model is written to `out` as it is generated
+    fn write_model(
+        out: &mut dyn Write,
         xlen: &u64,
         model: &ast::Model,
         dwarf_ctx: &DwarfCtx,
         ignored_funcs: &HashSet<&str>,
         verify_funcs: &Vec<&str>,
-    ) -> String;
+        solver_config: &SolverConfig,
+    ) -> io::Result<()>;
+}
+
+// =================================================================================================
+// # Model helpers shared by the interfaces
+
+/// Returns the procedures to verify; the functions in `verify_funcs` or else
+/// all the functions with a signature that are not ignored
+pub fn verified_funcs<'m>(
+    model: &'m ast::Model,
+    dwarf_ctx: &DwarfCtx,
+    ignored_funcs: &HashSet<&str>,
+    verify_funcs: &[&str],
+) -> Vec<&'m ast::FuncModel> {
+    if !verify_funcs.is_empty() {
+        verify_funcs
+            .iter()
+            .filter_map(|f_name| {
+                let fm = model.func_models.iter().find(|fm| fm.sig.name == *f_name);
+                if fm.is_none() {
+                    warn!("Unable to find the procedure {} to verify.", f_name);
+                }
+                fm
+            })
+            .collect()
+    } else {
+        model
+            .func_models
+            .iter()
+            .filter(|fm| dwarf_ctx.func_sig(&fm.sig.name).is_ok())
+            .filter(|fm| !ignored_funcs.contains(&fm.sig.name[..]))
+            .collect()
+    }
+}
+
+/// Returns the types of the global variables, function arguments and return values
+fn dwarf_types(dwarf_ctx: &DwarfCtx) -> Vec<&DwarfTypeDefn> {
+    let mut typs = dwarf_ctx
+        .global_vars()
+        .iter()
+        .map(|var| &*var.typ_defn)
+        .collect::<Vec<_>>();
+    for func_sig in dwarf_ctx.func_sigs().values() {
+        typs.extend(func_sig.args.iter().map(|var| &*var.typ_defn));
+        typs.extend(func_sig.ret_type.as_deref());
+    }
+    typs
+}
+
+/// Returns the sizes (in bytes) of the elements that can be indexed in the types of
+/// the global variables and function signatures; the interfaces define an array index
+/// function `index_by_N` for each size `N`
+pub fn array_index_sizes(dwarf_ctx: &DwarfCtx) -> BTreeSet<u64> {
+    fn add_sizes(typ: &DwarfTypeDefn, sizes: &mut BTreeSet<u64>) {
+        match typ {
+            DwarfTypeDefn::Primitive { bytes } => {
+                sizes.insert(*bytes);
+            }
+            DwarfTypeDefn::Array { in_typ, out_typ, .. } => {
+                add_sizes(in_typ, sizes);
+                add_sizes(out_typ, sizes);
+            }
+            DwarfTypeDefn::Struct { fields, bytes, .. } => {
+                for field in fields.values() {
+                    add_sizes(&field.typ, sizes);
+                }
+                sizes.insert(*bytes);
+            }
+            DwarfTypeDefn::Pointer { value_typ, .. } => add_sizes(value_typ, sizes),
+        }
+    }
+    let mut sizes = BTreeSet::new();
+    for typ in dwarf_types(dwarf_ctx) {
+        add_sizes(typ, &mut sizes);
+    }
+    // Types without a size cannot be indexed
+    sizes.remove(&0);
+    sizes
+}
+
+/// Returns the (struct id, field name, offset) of the struct fields in the types of the
+/// global variables and function signatures; the interfaces define a get field function
+/// `<struct id>_<field name>` for each field
+pub fn struct_fields(dwarf_ctx: &DwarfCtx) -> BTreeSet<(String, String, u64)> {
+    fn add_fields(typ: &DwarfTypeDefn, fields: &mut BTreeSet<(String, String, u64)>) {
+        match typ {
+            DwarfTypeDefn::Struct {
+                id,
+                fields: struct_fields,
+                ..
+            } => {
+                for (field_name, field) in struct_fields {
+                    add_fields(&field.typ, fields);
+                    fields.insert((id.clone(), field_name.clone(), field.loc));
+                }
+            }
+            DwarfTypeDefn::Array { in_typ, out_typ, .. } => {
+                add_fields(in_typ, fields);
+                add_fields(out_typ, fields);
+            }
+            _ => (),
+        }
+    }
+    let mut fields = BTreeSet::new();
+    for typ in dwarf_types(dwarf_ctx) {
+        add_fields(typ, &mut fields);
+    }
+    fields
 }
 
 // =================================================================================================
@@ -73,6 +207,9 @@ pub trait SpecLangASTInterface: fmt::Debug {
             sl_ast::BExpr::Bool(b) => Self::bexpr_bool_to_string(b),
             sl_ast::BExpr::BOpApp(bop, exprs) => Self::bexpr_bopapp_to_string(bop, exprs),
             sl_ast::BExpr::COpApp(cop, exprs) => Self::bexpr_copapp_to_string(cop, exprs),
+            sl_ast::BExpr::PredApp(name, _) => {
+                panic!("Implementation error; predicate {} should be expanded by the spec passes.", name)
+            }
         }
     }
     fn bexpr_bool_to_string(b: &bool) -> String;
@@ -103,3 +240,53 @@ pub trait SpecLangASTInterface: fmt::Debug {
     // Spec statement to string
     fn spec_to_string(spec: &sl_ast::Spec) -> String;
 }
+
+// =================================================================================================
+/// # Indentation-aware writer
+/// Writes the lines of the translated model at the current indentation level, so that
+/// nested statements are written once instead of being re-indented at every nesting level
+pub struct IndentWriter<'w> {
+    out: &'w mut dyn Write,
+    /// Number of spaces before each line
+    indent: usize,
+}
+
+impl<'w> IndentWriter<'w> {
+    /// Number of spaces added by each indentation level
+    const INDENT_WIDTH: usize = 4;
+
+    pub fn new(out: &'w mut dyn Write) -> Self {
+        IndentWriter { out, indent: 0 }
+    }
+
+    /// Increases the indentation of the following lines
+    pub fn indent(&mut self) {
+        self.indent += Self::INDENT_WIDTH;
+    }
+
+    /// Decreases the indentation of the following lines
+    pub fn dedent(&mut self) {
+        self.indent -= Self::INDENT_WIDTH;
+    }
+
+    /// Writes each line of `text` on its own indented line.
+    /// An empty `text` writes nothing and empty lines are not indented.
+    pub fn line(&mut self, text: &str) -> io::Result<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        for line in text.split('\n') {
+            if line.is_empty() {
+                self.out.write_all(b"\n")?;
+            } else {
+                writeln!(self.out, "{:indent$}{}", "", line, indent = self.indent)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes an empty line
+    pub fn blank(&mut self) -> io::Result<()> {
+        self.out.write_all(b"\n")
+    }
+}
@@ -3,13 +3,14 @@ extern crate log;
 extern crate env_logger;
 
 extern crate clap;
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches};
 
 extern crate pest;
 #[macro_use]
 extern crate pest_derive;
 
 extern crate asts;
+use asts::{veriv_json, veriv_text};
 extern crate dwarf_ctx;
 extern crate rv_model;
 extern crate utils;
@@ -20,10 +21,14 @@ pub mod disassembler;
 use disassembler::disassembler::Disassembler;
 
 pub mod translator;
-use translator::Translator;
+use translator::{Translator, TranslatorOptions};
 
 pub mod verification_interfaces;
-use verification_interfaces::uclidinterface::Uclid5Interface;
+use verification_interfaces::{
+    boogieinterface::BoogieInterface, btor2interface::Btor2Interface,
+    smtlibinterface::SmtLib2Interface,
+    uclidinterface::Uclid5Interface,
+};
 
 pub mod datastructures;
 use datastructures::cfg::BasicBlock;
@@ -36,14 +41,28 @@ use vectre_program_generator::VectreProgramGenerator;
 
 pub mod ir_interface;
 
+pub mod simulator;
+use simulator::simulator::Simulator;
+
+pub mod counterexample;
+use counterexample::counterexample::{CexMapper, Counterexample};
+
+pub mod stack_analysis;
+use stack_analysis::StackAnalyzer;
+
+pub mod solver;
+use solver::{CheckResult, SolverConfig, SolverProfile, UclidRunner, VerifyCommand};
+
 // pub mod utils;
 
 use std::{
     cell::RefCell,
     collections::{HashMap, HashSet},
-    fs::File,
-    io::prelude::*,
+    fs::{self, File},
+    io::{prelude::*, BufWriter},
+    process,
     rc::Rc,
+    time::Duration,
 };
 
 use dwarf_ctx::{
@@ -51,7 +70,12 @@ use dwarf_ctx::{
     dwarfreader::{DwarfCtx, DwarfReader, DwarfTypeDefn},
 };
 
-use asts::spec_lang::{sl_ast, sl_ast::ASTRewriter, sl_parser};
+use asts::spec_lang::{
+    sl_ast,
+    sl_ast::ASTRewriter,
+    sl_parser,
+    sl_type_checker::{SpecTypeChecker, TypeEnv},
+};
 
 use rv_model::system_model;
 
@@ -63,6 +87,16 @@ use utils::{constants, helpers};
 /// Process the commands given to the tool
 pub fn process_commands() {
     let matches = cl_options().get_matches();
+    // --check runs UCLID5 on the generated model
+    if let Some(backend) = matches.value_of("backend").filter(|b| *b != "uclid") {
+        if matches.is_present("check") {
+            clap::Error::with_description(
+                &format!("--check runs UCLID5 and does not support the {} backend", backend),
+                clap::ErrorKind::ArgumentConflict,
+            )
+            .exit();
+        }
+    }
     let xlen = helpers::dec_str_to_u64(matches.value_of("xlen").unwrap_or("64"))
         .expect("[main] Unable to parse numberic xlen.");
     if xlen != 64 {
@@ -91,7 +125,7 @@ pub fn process_commands() {
     let spec_files = matches
         .value_of("spec")
         .map_or(vec![], |lst| lst.split(",").collect::<Vec<&str>>());
-    let specs_map = process_specs(&spec_files, &dwarf_reader.ctx());
+    let (specs_map, machine_specs, ghosts) = process_specs(&spec_files, dwarf_reader.ctx());
     // Get ignored functions
     let ignored_funcs = matches
         .value_of("ignore-funcs")
@@ -104,6 +138,40 @@ pub fn process_commands() {
         .map_or(vec![], |lst| lst.split(",").collect::<Vec<&str>>());
     // Flag for ignoring and inlining functions
     let ignore_specs = matches.is_present("ignore-specs");
+    // Flag for failing on incomplete modifies clauses of ignored functions
+    let strict_mod_sets = matches.is_present("strict-modifies");
+    // Memory-safety instrumentation and the user-declared regions
+    let mem_safety_regions = if matches.is_present("mem-safety") {
+        Some(matches.value_of("mem-regions").map_or(vec![], |lst| {
+            lst.split(",").map(|region| parse_mem_region(region)).collect::<Vec<_>>()
+        }))
+    } else {
+        None
+    };
+    // Stack region that sp must stay within
+    let stack_region = matches.value_of("stack-region").map(|region| {
+        let (base, size) = parse_mem_region(region);
+        if base.checked_add(size).is_none() {
+            panic!("[main] Stack region {} exceeds the address space.", region);
+        }
+        (base, size)
+    });
+    // Calling-convention contracts and the functions exempted from them
+    let abi_exemptions = if matches.is_present("abi-contracts") {
+        Some(matches.value_of("abi-exempt").map_or(HashMap::new(), |lst| parse_abi_exemptions(lst)))
+    } else {
+        None
+    };
+    // Solver profile, timeouts and command of the control block
+    let solver_config = parse_solver_config(&matches);
+    // Whole-machine transition system instead of a procedure per function
+    let transition_system = matches.is_present("transition-system");
+    if transition_system && matches!(matches.value_of("backend"), Some(b) if b != "uclid") {
+        panic!("[main] The transition system is only supported by the uclid backend.");
+    }
+    if !transition_system && !machine_specs.is_empty() {
+        warn!("The init and invariant declarations are only checked with --transition-system.");
+    }
 
     // Print all the vectre programs
     if let Some(vectre_output_file) = matches.value_of("vectre_programs") {
@@ -123,6 +191,101 @@ pub fn process_commands() {
         return;
     }
 
+    // Run the function in the simulator and print the trace
+    if let Some(sim_func) = matches.value_of("simulate") {
+        let max_steps = matches
+            .value_of("sim_steps")
+            .map_or(simulator::simulator::DEFAULT_MAX_STEPS, |n| {
+                helpers::dec_str_to_u64(n).expect("[main] Unable to parse numeric sim_steps.")
+            });
+        let mut sim = Simulator::new(&als, max_steps);
+        sim.set_source_locs(dwarf_reader.ctx());
+        sim.load_binaries(&binary_paths);
+        if let Some(state_file) = matches.value_of("sim_state") {
+            sim.load_state_file(state_file);
+        }
+        let trace_str = sim.run(sim_func).to_string();
+        if let Some(output_file) = matches.value_of("output") {
+            let res = File::create(output_file)
+                .ok()
+                .unwrap()
+                .write_all(trace_str.as_bytes());
+            match res {
+                Ok(_) => info!("Successfully wrote simulation trace to {}", output_file),
+                Err(_) => panic!("Unable to write simulation trace to {}", output_file),
+            }
+        } else {
+            print!("{}", trace_str);
+        }
+        return;
+    }
+
+    // Print the stack usage of the functions and their callees
+    if let Some(output_file) = matches.value_of("stack-usage") {
+        let mut analyzer = StackAnalyzer::new(&bbs, dwarf_reader.ctx());
+        let roots = if func_names.len() > 0 {
+            func_names.iter().map(|f| f.to_string()).collect::<Vec<_>>()
+        } else {
+            Translator::<Uclid5Interface>::create_label_to_addr_map(&bbs)
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let funcs = analyzer.reachable_funcs(&roots);
+        let report_str = analyzer.report(&funcs);
+        let res = File::create(output_file)
+            .ok()
+            .unwrap()
+            .write_all(report_str.as_bytes());
+        match res {
+            Ok(_) => info!("Successfully wrote stack usage to {}", output_file),
+            Err(_) => panic!("Unable to write stack usage to {}", output_file),
+        }
+        return;
+    }
+
+    // Map the counterexamples in the UCLID5 output back to the binary
+    if let Some(cex_file) = matches.value_of("cex") {
+        let cexs = Counterexample::parse_file(cex_file);
+        if cexs.is_empty() {
+            warn!("No counterexamples found in {}.", cex_file);
+        }
+        let mut mapper = CexMapper::new(&als, &bbs, dwarf_reader.ctx());
+        if let Some(model_file) = matches.value_of("cex_model") {
+            mapper.set_model_file(model_file);
+        }
+        let path_str = cexs
+            .iter()
+            .map(|cex| mapper.describe(cex))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if let Some(output_file) = matches.value_of("output") {
+            let res = File::create(output_file)
+                .ok()
+                .unwrap()
+                .write_all(path_str.as_bytes());
+            match res {
+                Ok(_) => info!("Successfully wrote counterexample path to {}", output_file),
+                Err(_) => panic!("Unable to write counterexample path to {}", output_file),
+            }
+        } else {
+            print!("{}", path_str);
+        }
+        // Write the initial state of the first counterexample for the simulator
+        if let Some(state_file) = matches.value_of("cex_state") {
+            let state_str = cexs.first().map_or(String::new(), |cex| mapper.initial_state(cex));
+            let res = File::create(state_file)
+                .ok()
+                .unwrap()
+                .write_all(state_str.as_bytes());
+            match res {
+                Ok(_) => info!("Successfully wrote counterexample state to {}", state_file),
+                Err(_) => panic!("Unable to write counterexample state to {}", state_file),
+            }
+        }
+        return;
+    }
+
     // Print all specification template
     if let Some(output_file) = matches.value_of("spec_template") {
         let funcs: HashSet<String> = dwarf_reader.ctx().func_sigs().keys().cloned().collect();
@@ -142,30 +305,102 @@ pub fn process_commands() {
     }
 
     // Translate and write to output file
+    let options = TranslatorOptions {
+        ghosts: &ghosts,
+        ignore_specs,
+        strict_mod_sets,
+        mem_safety_regions,
+        stack_region,
+        abi_exemptions,
+        solver_config: solver_config.clone(),
+    };
     let mut translator: Translator<Uclid5Interface> = Translator::new(
-        xlen,
         &module_name,
         &bbs,
         &ignored_funcs,
         &verify_funcs,
         dwarf_reader.ctx(),
         &specs_map,
-        ignore_specs,
+        options,
     );
-    for func_name in func_names {
-        translator.gen_func_model(&func_name);
+    if let Some(json_file) = matches.value_of("load-json") {
+        // Reuse a previously saved translation instead of translating again
+        let json = fs::read_to_string(json_file)
+            .unwrap_or_else(|_| panic!("Unable to read model from {}", json_file));
+        let model = veriv_json::model_from_json(&json)
+            .unwrap_or_else(|e| panic!("Unable to load model from {}: {:?}", json_file, e));
+        translator.set_model(model);
+    } else if let Some(ir_file) = matches.value_of("load-ir") {
+        // Use a (possibly hand-edited) textual IR instead of translating
+        let text = fs::read_to_string(ir_file)
+            .unwrap_or_else(|_| panic!("Unable to read IR from {}", ir_file));
+        let model = veriv_text::model_from_text(&text)
+            .unwrap_or_else(|e| panic!("Unable to parse IR from {}: {:?}", ir_file, e));
+        translator.set_model(model);
+    } else {
+        for func_name in &func_names {
+            translator.gen_func_model(func_name);
+        }
+        if transition_system {
+            translator.gen_transition_system(&func_names, &machine_specs);
+        }
     }
-    // Print model to file
-    let model_str = translator.print_model();
-    if let Some(output_file) = matches.value_of("output") {
-        let res = File::create(output_file)
+    if let Some(ir_file) = matches.value_of("emit-ir") {
+        let res = File::create(ir_file)
             .ok()
             .unwrap()
-            .write_all(model_str.as_bytes());
+            .write_all(veriv_text::model_to_text(translator.model()).as_bytes());
+        match res {
+            Ok(_) => info!("Successfully wrote IR to {}", ir_file),
+            Err(_) => panic!("Unable to write IR to {}", ir_file),
+        }
+    }
+    if let Some(json_file) = matches.value_of("save-json") {
+        let res = File::create(json_file)
+            .ok()
+            .unwrap()
+            .write_all(veriv_json::model_to_json(translator.model()).as_bytes());
+        match res {
+            Ok(_) => info!("Successfully wrote IR to {}", json_file),
+            Err(_) => panic!("Unable to write IR to {}", json_file),
+        }
+    }
+    // Write model to file as it is generated
+    if let Some(output_file) = matches.value_of("output") {
+        let mut out = BufWriter::new(File::create(output_file).ok().unwrap());
+        let res = match matches.value_of("backend") {
+            Some("smtlib") => translator.write_model_with::<SmtLib2Interface>(&mut out),
+            Some("boogie") => translator.write_model_with::<BoogieInterface>(&mut out),
+            Some("btor2") => translator.write_model_with::<Btor2Interface>(&mut out),
+            _ => translator.write_model(&mut out),
+        }
+        .and_then(|_| out.flush());
         match res {
             Ok(_) => info!("Successfully wrote model to {}", output_file),
             Err(_) => panic!("Unable to write model to {}", output_file),
         }
+        // Run UCLID5 on the written model and exit with the verification result
+        if matches.is_present("check") {
+            let uclid_args = matches.values_of("uclid-arg").map_or_else(
+                || solver_config.uclid_args(),
+                |args| args.map(|arg| arg.to_string()).collect(),
+            );
+            let timeout = matches.value_of("check-timeout").map(|secs| {
+                Duration::from_secs(
+                    helpers::dec_str_to_u64(secs)
+                        .unwrap_or_else(|_| panic!("[main] Invalid check timeout {}.", secs)),
+                )
+            });
+            let uclid = matches.value_of("uclid").unwrap_or("uclid");
+            let runner = UclidRunner::new(uclid, uclid_args, timeout);
+            let results = match runner.check(output_file) {
+                Ok(results) => results,
+                Err(e) => panic!("[main] Unable to run {} on {}: {}", uclid, output_file, e),
+            };
+            print!("{}", CheckResult::report(&results));
+            translator.clear();
+            process::exit(CheckResult::exit_code(&results));
+        }
     }
     translator.clear();
     return;
@@ -237,6 +472,39 @@ fn cl_options<'t, 's>() -> App<'t, 's> {
                 .long("xlen")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("backend")
+                .help("Verification language of the generated model: uclid (default), smtlib, boogie or btor2.")
+                .long("backend")
+                .takes_value(true)
+                .possible_values(&["uclid", "smtlib", "boogie", "btor2"]),
+        )
+        .arg(
+            Arg::with_name("save-json")
+                .help("Save the translated VERI-V IR as versioned JSON to the given file.")
+                .long("save-json")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("load-json")
+                .help("Load the VERI-V IR from a JSON file produced by --save-json instead of translating.")
+                .long("load-json")
+                .takes_value(true)
+                .conflicts_with("save-json"),
+        )
+        .arg(
+            Arg::with_name("emit-ir")
+                .help("Write the VERI-V IR in its textual form to the given file.")
+                .long("emit-ir")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("load-ir")
+                .help("Load the VERI-V IR from a textual IR file instead of translating.")
+                .long("load-ir")
+                .takes_value(true)
+                .conflicts_with("load-json"),
+        )
         .arg(
             Arg::with_name("ignore-funcs")
                 .help("Comma separated list of functions to ignore. E.g. \"foo,bar\"")
@@ -257,12 +525,237 @@ fn cl_options<'t, 's>() -> App<'t, 's> {
                 .long("ignore-specs")
                 .takes_value(false),
         )
+        .arg(
+            Arg::with_name("strict-modifies")
+                .help("Fail if the modifies clause of an ignored function is missing a variable it writes.")
+                .long("strict-modifies")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("mem-safety")
+                .help("Assert that every load and store is aligned and inside a permitted memory region.")
+                .long("mem-safety")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("mem-regions")
+                .help("List of additional memory regions (base:size) that loads and stores may access.")
+                .long("mem-regions")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("stack-region")
+                .help("Stack region (base:size) that sp must stay within; adds stack requires and assertions to the model.")
+                .long("stack-region")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("abi-contracts")
+                .help("Add calling-convention postconditions (return to old(ra), sp, gp, tp and s0-s11 preserved) to every function.")
+                .long("abi-contracts")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("abi-exempt")
+                .help("Comma separated list of functions exempted from the calling-convention contracts. Use func:clause:... (clauses: ra, sp, gp, tp, s0-s11) to exempt only some clauses.")
+                .long("abi-exempt")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("stack-usage")
+                .help("Write the frame size and worst-case stack depth of the functions (and their callees) to the file.")
+                .long("stack-usage")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("simulate")
+                .help("Run the function in the built-in simulator and print its trace.")
+                .long("simulate")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("sim_state")
+                .help("Specify the initial register and memory state file for the simulator.")
+                .long("sim_state")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("sim_steps")
+                .help("Specify the maximum number of instructions to simulate.")
+                .long("sim_steps")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("cex")
+                .help("Map the counterexamples in the UCLID5 output file to the binary and print the execution path.")
+                .long("cex")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("cex_model")
+                .help("Specify the generated model the counterexamples refer to, to describe the failed assertions.")
+                .long("cex_model")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("cex_state")
+                .help("Write the initial state of the counterexample to a simulator state file.")
+                .long("cex_state")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("solver")
+                .help("Solver profile whose options are set in the control block and whose command is used by --check.")
+                .long("solver")
+                .possible_values(&["z3", "cvc4", "cvc5", "boolector"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("solver-config")
+                .help("Solver configuration file (solver, solver_cmd, option, timeout and command entries). The other solver options override it.")
+                .long("solver-config")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("solver-timeout")
+                .help("Comma separated list of solver timeouts in seconds, either for every function (secs) or for a function (func:secs).")
+                .long("solver-timeout")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("split-obligations")
+                .help("Check each ensures clause and each callee requires clause at each call site separately; --check reports the result of each clause.")
+                .long("split-obligations")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("uclid-command")
+                .help("Command that checks the model in the control block: verify (each function), induction[:K] (the invariants of the transition system by K-induction) or bmc:K (the module for K steps).")
+                .long("uclid-command")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("transition-system")
+                .help("Generate a whole-machine transition system whose next block executes the basic block at pc, starting at the entry of one of the functions. The init and invariant declarations of the spec files are checked by induction (default) or bmc.")
+                .long("transition-system")
+                .requires("function")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("check")
+                .help("Run UCLID5 on the generated model and report the result of each verified function. Exits with 0 if all passed, 1 if one failed and 2 if one is undetermined or timed out.")
+                .long("check")
+                .requires("output")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("uclid")
+                .help("Path of the UCLID5 binary used by --check (default: uclid).")
+                .long("uclid")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("uclid-arg")
+                .help("Additional argument passed to UCLID5 by --check (e.g. --uclid-arg=-s --uclid-arg=\"cvc4 --incremental\"). Can be repeated.")
+                .long("uclid-arg")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::with_name("check-timeout")
+                .help("Time limit in seconds of the UCLID5 run of --check.")
+                .long("check-timeout")
+                .takes_value(true),
+        )
+}
+
+/// Parses a memory region of the form `base:size` (decimal or 0x prefixed hexadecimal)
+fn parse_mem_region(region: &str) -> (u64, u64) {
+    let parse = |s: &str| {
+        let s = s.trim();
+        let res = if let Some(hex) = s.strip_prefix("0x") {
+            helpers::hex_str_to_u64(hex)
+        } else {
+            helpers::dec_str_to_u64(s)
+        };
+        res.expect(&format!("[main] Unable to parse memory region {}.", region))
+    };
+    let parts = region.split(":").collect::<Vec<_>>();
+    if parts.len() != 2 {
+        panic!("[main] Memory region {} should be of the form base:size.", region);
+    }
+    (parse(parts[0]), parse(parts[1]))
+}
+
+/// Parses a list of calling-convention exemptions of the form `func` (all clauses) or
+/// `func:clause:...` into a map from function names to the exempted clauses
+pub fn parse_abi_exemptions(lst: &str) -> HashMap<String, HashSet<String>> {
+    let all_clauses = std::iter::once(constants::RA)
+        .chain(constants::abi_preserved_regs())
+        .map(|clause| clause.to_string())
+        .collect::<HashSet<_>>();
+    let mut exemptions: HashMap<String, HashSet<String>> = HashMap::new();
+    for exemption in lst.split(",") {
+        let mut parts = exemption.trim().split(":");
+        let func_name = parts.next().unwrap().to_string();
+        if func_name.is_empty() {
+            panic!("[main] Missing function name in calling-convention exemption {:?}.", exemption);
+        }
+        let clauses = parts.map(|clause| clause.to_string()).collect::<HashSet<_>>();
+        if let Some(clause) = clauses.iter().find(|clause| !all_clauses.contains(*clause)) {
+            panic!("[main] Unknown calling-convention clause {} in {}.", clause, exemption);
+        }
+        let clauses = if clauses.is_empty() { all_clauses.clone() } else { clauses };
+        exemptions.entry(func_name).or_default().extend(clauses);
+    }
+    exemptions
+}
+
+/// Returns the solver configuration of the solver configuration file overridden by the
+/// `--solver`, `--solver-timeout`, `--split-obligations` and `--uclid-command` options
+fn parse_solver_config(matches: &ArgMatches) -> SolverConfig {
+    let mut config = SolverConfig::default();
+    if let Some(config_file) = matches.value_of("solver-config") {
+        if let Err(e) = config.load_file(config_file) {
+            eprintln!("[main] {}", e);
+            process::exit(1);
+        }
+    }
+    if let Some(name) = matches.value_of("solver") {
+        let profile = SolverProfile::from_name(name)
+            .unwrap_or_else(|| panic!("[main] Unknown solver profile {}.", name));
+        config.set_profile(profile);
+    }
+    if let Some(lst) = matches.value_of("solver-timeout") {
+        for timeout in lst.split(",") {
+            let mut parts = timeout.trim().rsplitn(2, ":");
+            let secs = parts.next().unwrap();
+            let secs = helpers::dec_str_to_u64(secs)
+                .unwrap_or_else(|_| panic!("[main] Invalid solver timeout {}.", timeout));
+            config.set_timeout(parts.next(), secs);
+        }
+    }
+    if matches.is_present("split-obligations") {
+        config.set_split_obligations(true);
+    }
+    if config.split_obligations() && matches!(matches.value_of("backend"), Some(b) if b != "uclid") {
+        panic!("[main] Split obligations are only supported by the uclid backend.");
+    }
+    if let Some(command) = matches.value_of("uclid-command") {
+        let command = VerifyCommand::parse(command)
+            .unwrap_or_else(|| panic!("[main] Unknown UCLID5 command {}.", command));
+        config.set_command(command);
+    }
+    config
 }
 
 // ====================================================================================================
 /// # Specifications
 
-/// Returns a map from function names to a vector of specifications
+/// Returns a map from function names to a vector of specifications, the
+/// specifications over the machine state and the ghost variables
 ///
 /// # Arguments
 ///
@@ -272,46 +765,113 @@ fn cl_options<'t, 's>() -> App<'t, 's> {
 pub fn process_specs(
     spec_files: &Vec<&str>,
     dwarf_ctx: &DwarfCtx,
-) -> HashMap<String, Vec<sl_ast::Spec>> {
+) -> (
+    HashMap<String, Vec<sl_ast::Spec>>,
+    Vec<sl_ast::MachineSpec>,
+    Vec<sl_ast::Ghost>,
+) {
     // Parse specifications
     let spec_parser = sl_parser::SpecParser::new();
-    let fun_specs_vec = spec_parser
+    let spec_file = spec_parser
         .process_spec_files(spec_files)
-        .expect("Could not read spec.");
+        .unwrap_or_else(|e| panic!("[main] Unable to parse the specifications.\n{}", e));
+
+    // Report the type errors before the rewriting passes rely on the types
+    SpecTypeChecker::new(&SpecTypeEnv(dwarf_ctx))
+        .check_spec_file(&spec_file)
+        .unwrap_or_else(|e| panic!("[main] Type errors in the specifications.\n{}", e));
+    let ghost_typs = spec_file
+        .ghosts
+        .iter()
+        .map(|ghost| (ghost.name.to_string(), ghost.typ.clone()))
+        .collect::<HashMap<_, _>>();
+    let defines = expand_defines(spec_file.defines, dwarf_ctx, &ghost_typs);
+    let specs_ctx = SpecsCtx {
+        dwarf_ctx,
+        defines: &defines,
+        ghost_typs: &ghost_typs,
+    };
 
     // Run a set of passes over each individual specification expression
     let mut ret = HashMap::new();
-    for fun_spec in fun_specs_vec {
+    for fun_spec in spec_file.func_specs {
         let fname = fun_spec.fname.to_string();
         let rw_specs = fun_spec
             .specs
             .into_iter()
             .map(|spec| match spec {
-                sl_ast::Spec::Requires(bexpr) => {
-                    sl_ast::Spec::Requires(sl_bexpr_rewrite_passes(bexpr, dwarf_ctx, &fname[..]))
-                }
-                sl_ast::Spec::Ensures(bexpr) => {
-                    sl_ast::Spec::Ensures(sl_bexpr_rewrite_passes(bexpr, dwarf_ctx, &fname[..]))
-                }
+                sl_ast::Spec::Requires(bexpr, loc) => sl_ast::Spec::Requires(
+                    sl_bexpr_rewrite_passes(bexpr, &specs_ctx, &fname[..]),
+                    loc,
+                ),
+                sl_ast::Spec::Ensures(bexpr, loc) => sl_ast::Spec::Ensures(
+                    sl_bexpr_rewrite_passes(bexpr, &specs_ctx, &fname[..]),
+                    loc,
+                ),
+                sl_ast::Spec::Track(name, vexpr, loc) => sl_ast::Spec::Track(
+                    name,
+                    sl_vexpr_rewrite_passes(vexpr, &specs_ctx, &fname[..]),
+                    loc,
+                ),
+                // The formal arguments are not in scope of the updates
+                sl_ast::Spec::Update(name, vexpr, addr, loc) => sl_ast::Spec::Update(
+                    name,
+                    sl_vexpr_rewrite_passes(vexpr, &specs_ctx, ""),
+                    addr,
+                    loc,
+                ),
                 _ => spec,
             })
             .collect::<Vec<_>>();
         ret.insert(fname, rw_specs);
     }
-    ret
+    // The machine specifications are not in the scope of a function
+    let machine_specs = spec_file
+        .machine_specs
+        .into_iter()
+        .map(|spec| match spec {
+            sl_ast::MachineSpec::Init(bexpr, loc) => sl_ast::MachineSpec::Init(
+                sl_bexpr_rewrite_passes(bexpr, &specs_ctx, ""),
+                loc,
+            ),
+            sl_ast::MachineSpec::Invariant(name, bexpr, loc) => sl_ast::MachineSpec::Invariant(
+                name,
+                sl_bexpr_rewrite_passes(bexpr, &specs_ctx, ""),
+                loc,
+            ),
+        })
+        .collect::<Vec<_>>();
+    let ghosts = spec_file
+        .ghosts
+        .into_iter()
+        .map(|mut ghost| {
+            ghost.init = ghost.init.map(|init| sl_vexpr_rewrite_passes(init, &specs_ctx, ""));
+            ghost
+        })
+        .collect::<Vec<_>>();
+    (ret, machine_specs, ghosts)
+}
+
+/// Context of the specification passes
+struct SpecsCtx<'a> {
+    dwarf_ctx: &'a DwarfCtx,
+    defines: &'a HashMap<String, sl_ast::Define>,
+    /// Types of the ghost variables
+    ghost_typs: &'a HashMap<String, sl_ast::VType>,
 }
 
 /// Iterates over all spec AST passes
-fn sl_bexpr_rewrite_passes(
-    bexpr: sl_ast::BExpr,
-    dwarf_ctx: &DwarfCtx,
-    fname: &str,
-) -> sl_ast::BExpr {
+fn sl_bexpr_rewrite_passes(bexpr: sl_ast::BExpr, specs_ctx: &SpecsCtx, fname: &str) -> sl_ast::BExpr {
+    let dwarf_ctx = specs_ctx.dwarf_ctx;
+    // Expand the calls of the definitions
+    let mut rw_bexpr = ExpandDefines::visit_bexpr(bexpr, &RefCell::new(specs_ctx.defines));
+
     // Type inference pass. Before the initial pass, we expect the specficiation
-    // AST to have Unknown types for all VExpr.
-    let mut rw_bexpr = VExprTypeInference::visit_bexpr(
-        bexpr,
-        &RefCell::new((dwarf_ctx, fname, &mut HashMap::new())),
+    // AST to have Unknown types for all VExpr. The ghost variables are typed like
+    // the quantified variables
+    rw_bexpr = VExprTypeInference::visit_bexpr(
+        rw_bexpr,
+        &RefCell::new((dwarf_ctx, fname, &mut specs_ctx.ghost_typs.clone())),
     );
 
     // Rewrite all quantified variable names. Identifiers that are global variables are
@@ -325,24 +885,312 @@ fn sl_bexpr_rewrite_passes(
     rw_bexpr
 }
 
+/// Iterates over all spec AST passes of a value expression
+fn sl_vexpr_rewrite_passes(vexpr: sl_ast::VExpr, specs_ctx: &SpecsCtx, fname: &str) -> sl_ast::VExpr {
+    let dwarf_ctx = specs_ctx.dwarf_ctx;
+    let mut rw_vexpr = ExpandDefines::visit_vexpr(vexpr, &RefCell::new(specs_ctx.defines));
+    rw_vexpr = VExprTypeInference::visit_vexpr(
+        rw_vexpr,
+        &RefCell::new((dwarf_ctx, fname, &mut specs_ctx.ghost_typs.clone())),
+    );
+    rw_vexpr = RenameGlobals::visit_vexpr(rw_vexpr, &RefCell::new(dwarf_ctx));
+    ConstantFolder::visit_vexpr(rw_vexpr, &RefCell::new(dwarf_ctx))
+}
+
+/// Returns the definitions by name with the calls in their bodies expanded and the global
+/// variables in their bodies resolved in the global scope
+fn expand_defines(
+    defines: Vec<sl_ast::Define>,
+    dwarf_ctx: &DwarfCtx,
+    ghost_typs: &HashMap<String, sl_ast::VType>,
+) -> HashMap<String, sl_ast::Define> {
+    // Definitions only call the definitions before them
+    let mut expanded = HashMap::new();
+    for mut define in defines {
+        let ctx = RefCell::new(&expanded);
+        // The parameters and ghost variables shadow the global variables
+        let locals = define
+            .params
+            .iter()
+            .map(|(param, _)| param.to_string())
+            .chain(ghost_typs.keys().cloned())
+            .collect::<HashSet<_>>();
+        let scope_ctx = RefCell::new((dwarf_ctx, locals));
+        define.body = match define.body {
+            sl_ast::DefineBody::Pred(bexpr) => {
+                let bexpr = ScopeGlobals::visit_bexpr(bexpr, &scope_ctx);
+                sl_ast::DefineBody::Pred(ExpandDefines::visit_bexpr(bexpr, &ctx))
+            }
+            sl_ast::DefineBody::Value(vexpr, typ) => {
+                let vexpr = ScopeGlobals::visit_vexpr(vexpr, &scope_ctx);
+                sl_ast::DefineBody::Value(ExpandDefines::visit_vexpr(vexpr, &ctx), typ)
+            }
+        };
+        expanded.insert(define.name.to_string(), define);
+    }
+    expanded
+}
+
 // ====================================================================================================
 /// ## Specification transformation passes
 
+/// AST pass that replaces the calls of the definitions with their bodies
+struct ExpandDefines;
+impl ExpandDefines {
+    /// Returns the map from the parameters of `define` to the arguments `args`
+    fn bind_params(define: &sl_ast::Define, args: Vec<sl_ast::VExpr>) -> HashMap<String, sl_ast::VExpr> {
+        define
+            .params
+            .iter()
+            .map(|(param, _)| param.to_string())
+            .zip(args)
+            .collect()
+    }
+}
+impl sl_ast::ASTRewriter<&HashMap<String, sl_ast::Define>> for ExpandDefines {
+    fn rewrite_bexpr_predapp(
+        predapp: sl_ast::BExpr,
+        context: &RefCell<&HashMap<String, sl_ast::Define>>,
+    ) -> sl_ast::BExpr {
+        match predapp {
+            sl_ast::BExpr::PredApp(name, args) => {
+                let define = context.borrow().get(&name).cloned();
+                match define {
+                    Some(define) => match &define.body {
+                        sl_ast::DefineBody::Pred(body) => SubstituteParams::visit_bexpr(
+                            body.clone(),
+                            &RefCell::new(Self::bind_params(&define, args)),
+                        ),
+                        _ => panic!("Implementation error; {} is not a predicate.", name),
+                    },
+                    None => panic!("Implementation error; predicate {} is not defined.", name),
+                }
+            }
+            _ => panic!("Implementation error; expected `BExpr::PredApp`."),
+        }
+    }
+
+    fn rewrite_vexpr_funcapp(
+        funcapp: sl_ast::VExpr,
+        context: &RefCell<&HashMap<String, sl_ast::Define>>,
+    ) -> sl_ast::VExpr {
+        match funcapp {
+            sl_ast::VExpr::FuncApp(name, args, typ) => {
+                let define = context.borrow().get(&name).cloned();
+                match define {
+                    Some(define) => match &define.body {
+                        sl_ast::DefineBody::Value(body, _) => SubstituteParams::visit_vexpr(
+                            body.clone(),
+                            &RefCell::new(Self::bind_params(&define, args)),
+                        ),
+                        _ => panic!("Implementation error; {} is not a value.", name),
+                    },
+                    // Built-in function
+                    None => sl_ast::VExpr::FuncApp(name, args, typ),
+                }
+            }
+            _ => panic!("Implementation error; expected `VExpr::FuncApp`."),
+        }
+    }
+}
+
+/// Prefix of the identifiers that refer to a global variable regardless of the scope
+/// of the function
+const GLOBAL_SCOPE_PREFIX: &str = "::";
+
+/// AST pass that prefixes the identifiers of a definition body that refer to global
+/// variables with `GLOBAL_SCOPE_PREFIX`, so that the formal arguments of the function
+/// that calls the definition do not capture them. The context holds the names of the
+/// parameters, ghost variables and bound variables in scope.
+struct ScopeGlobals;
+impl<'a> sl_ast::ASTRewriter<(&'a DwarfCtx, HashSet<String>)> for ScopeGlobals {
+    fn visit_bexpr_bopapp(
+        bopapp: sl_ast::BExpr,
+        context: &RefCell<(&'a DwarfCtx, HashSet<String>)>,
+    ) -> sl_ast::BExpr {
+        match bopapp {
+            sl_ast::BExpr::BOpApp(
+                bop @ (sl_ast::BoolOp::Forall(..) | sl_ast::BoolOp::Exists(..)),
+                body,
+            ) => {
+                // The bound variable shadows the global variable of the same name
+                let mut scope = context.borrow().clone();
+                if let sl_ast::BoolOp::Forall(var, _) | sl_ast::BoolOp::Exists(var, _) = &bop {
+                    scope.1.insert(var.get_ident_name().to_string());
+                }
+                let rw_body = Self::visit_bexprs(body, &RefCell::new(scope));
+                Self::rewrite_bexpr_bopapp(sl_ast::BExpr::BOpApp(bop, rw_body), context)
+            }
+            sl_ast::BExpr::BOpApp(bop, exprs) => {
+                let rw_bexprs = Self::visit_bexprs(exprs, context);
+                Self::rewrite_bexpr_bopapp(sl_ast::BExpr::BOpApp(bop, rw_bexprs), context)
+            }
+            _ => panic!("Implementation error; expected `BExpr::BOpApp`."),
+        }
+    }
+
+    /// Struct fields are not variables
+    fn visit_vexpr_opapp(
+        opapp: sl_ast::VExpr,
+        context: &RefCell<(&'a DwarfCtx, HashSet<String>)>,
+    ) -> sl_ast::VExpr {
+        match opapp {
+            sl_ast::VExpr::OpApp(sl_ast::ValueOp::GetField, mut exprs, typ) => {
+                let field = exprs.pop().expect("Expected a struct field.");
+                let rw_struct = Self::visit_vexpr(exprs.pop().expect("Expected a struct."), context);
+                sl_ast::VExpr::OpApp(sl_ast::ValueOp::GetField, vec![rw_struct, field], typ)
+            }
+            sl_ast::VExpr::OpApp(op, exprs, typ) => {
+                sl_ast::VExpr::OpApp(op, Self::visit_vexprs(exprs, context), typ)
+            }
+            _ => panic!("Implementation error; expected `VExpr::OpApp`."),
+        }
+    }
+
+    fn rewrite_vexpr_ident(
+        ident: sl_ast::VExpr,
+        context: &RefCell<(&'a DwarfCtx, HashSet<String>)>,
+    ) -> sl_ast::VExpr {
+        let (dwarf_ctx, locals) = &*context.borrow();
+        match ident {
+            sl_ast::VExpr::Ident(name, typ)
+                if !locals.contains(&name)
+                    && spec_var_type(dwarf_ctx, "", &name).is_none()
+                    && dwarf_ctx.global_var(&name).is_ok() =>
+            {
+                sl_ast::VExpr::Ident(format!("{}{}", GLOBAL_SCOPE_PREFIX, name), typ)
+            }
+            _ => ident,
+        }
+    }
+}
+
+/// AST pass that replaces the parameters of a definition with the arguments of a call.
+/// Parameters are not replaced inside the quantifiers that bind a variable of the same
+/// name, and bound variables that clash with an identifier of an argument are renamed.
+struct SubstituteParams;
+impl sl_ast::ASTRewriter<HashMap<String, sl_ast::VExpr>> for SubstituteParams {
+    fn visit_bexpr_bopapp(
+        bopapp: sl_ast::BExpr,
+        context: &RefCell<HashMap<String, sl_ast::VExpr>>,
+    ) -> sl_ast::BExpr {
+        let (var, typ, is_forall, body) = match bopapp {
+            sl_ast::BExpr::BOpApp(sl_ast::BoolOp::Forall(var, typ), body) => (var, typ, true, body),
+            sl_ast::BExpr::BOpApp(sl_ast::BoolOp::Exists(var, typ), body) => (var, typ, false, body),
+            sl_ast::BExpr::BOpApp(bop, exprs) => {
+                let rw_bexprs = Self::visit_bexprs(exprs, context);
+                return Self::rewrite_bexpr_bopapp(sl_ast::BExpr::BOpApp(bop, rw_bexprs), context);
+            }
+            _ => panic!("Implementation error; expected `BExpr::BOpApp`."),
+        };
+        // The bound variable shadows the parameter of the same name
+        let name = var.get_ident_name().to_string();
+        let mut subst = context.borrow().clone();
+        subst.remove(&name);
+        // Rename the bound variable if an argument refers to a variable of the same name
+        let arg_idents = CollectIdents::vexprs(subst.values());
+        let var = if arg_idents.contains(&name) {
+            let body_idents = body
+                .iter()
+                .flat_map(CollectIdents::bexpr)
+                .collect::<HashSet<_>>();
+            let fresh = (1..)
+                .map(|i| format!("{}__{}", name, i))
+                .find(|fresh| !arg_idents.contains(fresh) && !body_idents.contains(fresh))
+                .unwrap();
+            let fresh_var = sl_ast::VExpr::Ident(fresh, var.typ().clone());
+            subst.insert(name, fresh_var.clone());
+            fresh_var
+        } else {
+            var
+        };
+        let rw_body = Self::visit_bexprs(body, &RefCell::new(subst));
+        let bop = if is_forall {
+            sl_ast::BoolOp::Forall(var, typ)
+        } else {
+            sl_ast::BoolOp::Exists(var, typ)
+        };
+        Self::rewrite_bexpr_bopapp(sl_ast::BExpr::BOpApp(bop, rw_body), context)
+    }
+
+    /// Struct fields are not parameters
+    fn visit_vexpr_opapp(
+        opapp: sl_ast::VExpr,
+        context: &RefCell<HashMap<String, sl_ast::VExpr>>,
+    ) -> sl_ast::VExpr {
+        match opapp {
+            sl_ast::VExpr::OpApp(sl_ast::ValueOp::GetField, mut exprs, typ) => {
+                let field = exprs.pop().expect("Expected a struct field.");
+                let rw_struct = Self::visit_vexpr(exprs.pop().expect("Expected a struct."), context);
+                sl_ast::VExpr::OpApp(sl_ast::ValueOp::GetField, vec![rw_struct, field], typ)
+            }
+            sl_ast::VExpr::OpApp(op, exprs, typ) => {
+                sl_ast::VExpr::OpApp(op, Self::visit_vexprs(exprs, context), typ)
+            }
+            _ => panic!("Implementation error; expected `VExpr::OpApp`."),
+        }
+    }
+
+    fn rewrite_vexpr_ident(
+        ident: sl_ast::VExpr,
+        context: &RefCell<HashMap<String, sl_ast::VExpr>>,
+    ) -> sl_ast::VExpr {
+        match context.borrow().get(ident.get_ident_name()) {
+            Some(arg) => arg.clone(),
+            None => ident,
+        }
+    }
+}
+
+/// AST pass that collects the names of the identifiers of an expression
+struct CollectIdents;
+impl CollectIdents {
+    /// Returns the names of the identifiers in `vexprs`
+    fn vexprs<'a>(vexprs: impl Iterator<Item = &'a sl_ast::VExpr>) -> HashSet<String> {
+        let idents = RefCell::new(HashSet::new());
+        for vexpr in vexprs {
+            Self::visit_vexpr(vexpr.clone(), &idents);
+        }
+        idents.into_inner()
+    }
+
+    /// Returns the names of the identifiers in `bexpr`, including the bound variables
+    fn bexpr(bexpr: &sl_ast::BExpr) -> HashSet<String> {
+        let idents = RefCell::new(HashSet::new());
+        Self::visit_bexpr(bexpr.clone(), &idents);
+        idents.into_inner()
+    }
+}
+impl sl_ast::ASTRewriter<HashSet<String>> for CollectIdents {
+    fn rewrite_bexpr_boolop(bop: sl_ast::BoolOp, context: &RefCell<HashSet<String>>) -> sl_ast::BoolOp {
+        if let sl_ast::BoolOp::Forall(var, _) | sl_ast::BoolOp::Exists(var, _) = &bop {
+            context.borrow_mut().insert(var.get_ident_name().to_string());
+        }
+        bop
+    }
+
+    fn rewrite_vexpr_ident(ident: sl_ast::VExpr, context: &RefCell<HashSet<String>>) -> sl_ast::VExpr {
+        context.borrow_mut().insert(ident.get_ident_name().to_string());
+        ident
+    }
+}
+
 /// AST pass that renames the identifiers for global variables from
 /// Identifiers `name` to FuncApp `global_var_name()`.
 struct RenameGlobals;
 impl sl_ast::ASTRewriter<&DwarfCtx> for RenameGlobals {
+    /// Only the identifiers resolved to global variables by the type inference are renamed
     fn rewrite_vexpr_ident(ident: sl_ast::VExpr, context: &RefCell<&DwarfCtx>) -> sl_ast::VExpr {
-        if is_global(&ident, &*context.borrow()) {
-            match ident {
-                sl_ast::VExpr::Ident(name, typ) => {
-                    let global_addr = context.borrow().global_var(&name).expect("Could not find global variable").memory_addr;
-                    sl_ast::VExpr::Bv { value: global_addr, typ }
-                }
-                _ => panic!("Expected identifier."),
+        match ident {
+            sl_ast::VExpr::Ident(name, typ) if name.starts_with(GLOBAL_SCOPE_PREFIX) => {
+                let global_addr = context
+                    .borrow()
+                    .global_var(&name[GLOBAL_SCOPE_PREFIX.len()..])
+                    .expect("Could not find global variable")
+                    .memory_addr;
+                sl_ast::VExpr::Bv { value: global_addr, typ }
             }
-        } else {
-            ident
+            _ => ident,
         }
     }
 }
@@ -385,53 +1233,26 @@ impl sl_ast::ASTRewriter<(&DwarfCtx, &str, &mut HashMap<String, sl_ast::VType>)>
         let fname = borrowed_ctx.1;
         let typ_map = &borrowed_ctx.2;
 
-        // Initialize a type option to store the identifier type
-        let mut typ_opt;
-        // Identifier name
-        let var_id = vexpr.get_ident_name();
-
-        // Check if it's a system variable
-        let xlen = dwarf_ctx.xlen;
-        typ_opt = match &var_id[..] {
-            constants::PC_VAR => {
-                Some(sl_ast::VType::from_ast_type(&system_model::pc_type(xlen)))
-            }
-            constants::RETURNED_FLAG => {
-                Some(sl_ast::VType::from_ast_type(&system_model::returned_type()))
-            }
-            constants::PRIV_VAR => {
-                Some(sl_ast::VType::from_ast_type(&system_model::priv_type()))
-            }
-            constants::MEM_VAR_B => Some(sl_ast::VType::from_ast_type(
-                &system_model::mem_b_type(xlen),
-            )),
-            constants::MEM_VAR_H => Some(sl_ast::VType::from_ast_type(
-                &system_model::mem_h_type(xlen),
-            )),
-            constants::MEM_VAR_W => Some(sl_ast::VType::from_ast_type(
-                &system_model::mem_w_type(xlen),
-            )),
-            constants::MEM_VAR_D => Some(sl_ast::VType::from_ast_type(
-                &system_model::mem_d_type(xlen),
-            )),
-            // TODO: Should include all registers, not just these
-            constants::A0 | constants::SP | constants::RA => {
-                Some(sl_ast::VType::from_ast_type(&system_model::bv_type(xlen)))
-            }
-            _ => None,
+        // Identifier name; the global variables of the definition bodies are not looked
+        // up in the scope of the function
+        let name = vexpr.get_ident_name();
+        let (var_id, global_scope) = match name.strip_prefix(GLOBAL_SCOPE_PREFIX) {
+            Some(var_id) => (var_id, true),
+            None => (name, false),
         };
 
-        // Check if it's a formal argument
-        let func_sig_opt = dwarf_ctx.func_sigs().get(&fname[..]);
-        if let Some(func_sig) = func_sig_opt {
-            let formal_arg_opt = func_sig.args.iter().find(|dv| dv.name == var_id);
-            if let Some(fa) = formal_arg_opt {
-                typ_opt = Some(from_dwarf_type(&fa.typ_defn));
-            }
-        }
+        // Check if it's a system variable, register or formal argument
+        let mut typ_opt = if global_scope {
+            None
+        } else {
+            spec_var_type(dwarf_ctx, fname, var_id)
+        };
 
         // Bound variable; find in type map `typ_map`
         typ_opt = typ_opt.or_else(|| {
+            if global_scope {
+                return None;
+            }
             typ_map
                 .get(&var_id[..])
                 .map_or(None, |typ| Some(typ.clone()))
@@ -459,7 +1280,11 @@ impl sl_ast::ASTRewriter<(&DwarfCtx, &str, &mut HashMap<String, sl_ast::VType>)>
             "Unable to find variable {} in DWARF info.",
             &var_id
         );
-        let global_addr = sl_ast::VExpr::Ident(format!("{}", var_id), typ_opt.clone().unwrap());
+        // Globals keep the prefix so that `RenameGlobals` does not rename shadowing formals
+        let global_addr = sl_ast::VExpr::Ident(
+            format!("{}{}", GLOBAL_SCOPE_PREFIX, var_id),
+            typ_opt.clone().unwrap(),
+        );
         if is_global {
             match &typ_opt.clone().unwrap() {
                 sl_ast::VType::Bv(_) => {
@@ -473,8 +1298,7 @@ impl sl_ast::ASTRewriter<(&DwarfCtx, &str, &mut HashMap<String, sl_ast::VType>)>
                 _ => global_addr,
             }
         } else {
-            // FIXME: formal arguments all have xlen bit width (for now)
-            sl_ast::VExpr::Ident(var_id.to_string(), sl_ast::VType::Bv(dwarf_ctx.xlen as u16))
+            sl_ast::VExpr::Ident(var_id.to_string(), typ_opt.unwrap())
         }
     }
 
@@ -626,6 +1450,48 @@ impl sl_ast::ASTRewriter<&DwarfCtx> for ConstantFolder {
     }
 }
 
+/// Types of the variables in the specifications from the system model and the DWARF
+/// information; the specification type checker runs against these
+struct SpecTypeEnv<'a>(&'a DwarfCtx);
+impl TypeEnv for SpecTypeEnv<'_> {
+    fn var_type(&self, fname: &str, name: &str) -> Option<sl_ast::VType> {
+        spec_var_type(self.0, fname, name).or_else(|| {
+            self.0
+                .global_var_type(name)
+                .ok()
+                .map(|dtd| from_dwarf_type(&dtd))
+        })
+    }
+}
+
+/// Returns the type of the system variable, register or formal argument of `fname`
+/// named `var_id`. Formal arguments shadow the registers
+fn spec_var_type(dwarf_ctx: &DwarfCtx, fname: &str, var_id: &str) -> Option<sl_ast::VType> {
+    let xlen = dwarf_ctx.xlen;
+    // FIXME: formal arguments all have xlen bit width (for now)
+    let is_formal = matches!(
+        dwarf_ctx.func_sigs().get(fname),
+        Some(func_sig) if func_sig.args.iter().any(|dv| dv.name == var_id)
+    );
+    let is_reg = constants::CALLER_SAVED_REGS
+        .iter()
+        .cloned()
+        .chain(constants::abi_preserved_regs())
+        .any(|reg| reg == var_id);
+    let typ = match var_id {
+        _ if is_formal || is_reg => system_model::bv_type(xlen),
+        constants::PC_VAR => system_model::pc_type(xlen),
+        constants::RETURNED_FLAG => system_model::returned_type(),
+        constants::PRIV_VAR => system_model::priv_type(),
+        constants::MEM_VAR_B => system_model::mem_b_type(xlen),
+        constants::MEM_VAR_H => system_model::mem_h_type(xlen),
+        constants::MEM_VAR_W => system_model::mem_w_type(xlen),
+        constants::MEM_VAR_D => system_model::mem_d_type(xlen),
+        _ => return None,
+    };
+    Some(sl_ast::VType::from_ast_type(&typ))
+}
+
 // ================================================================================
 /// # DWARF Helpers
 
@@ -641,6 +1507,7 @@ fn from_dwarf_type(dtd: &DwarfTypeDefn) -> sl_ast::VType {
             in_typ,
             out_typ,
             bytes: _,
+            len: _,
         } => sl_ast::VType::Array {
             in_type: Box::new(from_dwarf_type(in_typ)),
             out_type: Box::new(from_dwarf_type(out_typ)),
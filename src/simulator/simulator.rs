@@ -0,0 +1,741 @@
+//! A small RV64IMA instruction set simulator.
+//! The simulator decodes the raw instruction words loaded from the
+//! ELF segments and executes them with its own semantics. It does not
+//! use the rv_model instruction semantics, so that it can be used as a
+//! second opinion when debugging specifications and counterexamples.
+
+use object::{Object, ObjectSegment};
+use regex::Regex;
+use std::{collections::HashMap, fmt, fs, rc::Rc};
+
+use crate::disassembler::disassembler::{AssemblyLine, InstOperand};
+
+use dwarf_ctx::dwarfreader::DwarfCtx;
+
+use utils::helpers;
+
+// ========================================================================================
+// # Simulator constants
+
+/// ABI names of the general purpose registers indexed by register number
+pub const REG_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+/// Return address used when the initial state does not set `ra`
+pub const RETURN_SENTINEL: u64 = 0xffff_ffff_ffff_fff0;
+
+/// Default maximum number of instructions to execute
+pub const DEFAULT_MAX_STEPS: u64 = 100000;
+
+// ========================================================================================
+/// # Machine state
+
+#[derive(Debug, Clone)]
+pub struct SimState {
+    /// Program counter
+    pc: u64,
+    /// General purpose registers
+    regs: [u64; 32],
+    /// Byte addressable memory (unwritten bytes read as 0)
+    mem: HashMap<u64, u8>,
+    /// Control and status registers
+    csrs: HashMap<u64, u64>,
+    /// Reservation set of the last LR instruction
+    reservation: Option<u64>,
+}
+
+impl Default for SimState {
+    fn default() -> Self {
+        SimState::new()
+    }
+}
+
+impl SimState {
+    /// Creates an empty machine state
+    pub fn new() -> Self {
+        SimState {
+            pc: 0,
+            regs: [0; 32],
+            mem: HashMap::new(),
+            csrs: HashMap::new(),
+            reservation: None,
+        }
+    }
+
+    /// Returns the register number of the register name
+    pub fn reg_index(name: &str) -> Option<usize> {
+        if name == "fp" {
+            return Some(8);
+        }
+        if let Some(index) = REG_NAMES.iter().position(|n| *n == name) {
+            return Some(index);
+        }
+        name.strip_prefix("x")
+            .and_then(|n| n.parse::<usize>().ok())
+            .filter(|n| *n < 32)
+    }
+
+    /// Returns the value of register number `index`
+    pub fn reg(&self, index: usize) -> u64 {
+        self.regs[index]
+    }
+
+    /// Sets register number `index`; writes to zero are ignored
+    pub fn set_reg(&mut self, index: usize, value: u64) {
+        if index != 0 {
+            self.regs[index] = value;
+        }
+    }
+
+    /// Returns the program counter
+    pub fn pc(&self) -> u64 {
+        self.pc
+    }
+
+    /// Reads `bytes` bytes starting at `addr` in little endian order
+    pub fn load(&self, addr: u64, bytes: u64) -> u64 {
+        let mut value = 0;
+        for i in 0..bytes {
+            let byte = *self.mem.get(&addr.wrapping_add(i)).unwrap_or(&0) as u64;
+            value |= byte << (8 * i);
+        }
+        value
+    }
+
+    /// Writes the lower `bytes` bytes of `value` starting at `addr` in little endian order
+    pub fn store(&mut self, addr: u64, bytes: u64, value: u64) {
+        for i in 0..bytes {
+            self.mem
+                .insert(addr.wrapping_add(i), (value >> (8 * i)) as u8);
+        }
+    }
+}
+
+// ========================================================================================
+// # Simulation trace
+
+/// Reason the simulation stopped
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    /// The function returned to its caller
+    Returned,
+    /// The maximum number of steps was reached
+    StepLimit(u64),
+    /// The instruction could not be executed
+    Trap(String),
+}
+
+/// An executed instruction and its effects on the machine state
+#[derive(Debug, Clone)]
+pub struct SimStep {
+    /// Address of the instruction
+    pub addr: u64,
+    /// Function the instruction resides in
+    pub func: String,
+    /// Offset of the instruction from the entry of the function
+    pub offset: u64,
+    /// Disassembled instruction
+    pub inst: String,
+    /// Source location of the instruction
+    pub loc: Option<String>,
+    /// Register, memory and control flow effects
+    pub effects: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimTrace {
+    /// Executed instructions in order
+    pub steps: Vec<SimStep>,
+    /// Final machine state
+    pub state: SimState,
+    /// Reason the simulation stopped
+    pub stop: StopReason,
+}
+
+impl fmt::Display for SimStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut inst = format!(
+            "{:#x} <{}+{:#x}>: {}",
+            self.addr, self.func, self.offset, self.inst
+        );
+        if let Some(loc) = &self.loc {
+            inst = format!("{} @ {}", inst, loc);
+        }
+        if !self.effects.is_empty() {
+            write!(f, "{:<60} ; {}", inst, self.effects.join(", "))
+        } else {
+            write!(f, "{}", inst)
+        }
+    }
+}
+
+impl fmt::Display for SimTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for step in &self.steps {
+            writeln!(f, "{}", step)?;
+        }
+        match &self.stop {
+            StopReason::Returned => writeln!(f, "// Returned after {} steps.", self.steps.len())?,
+            StopReason::StepLimit(n) => writeln!(f, "// Stopped after reaching {} steps.", n)?,
+            StopReason::Trap(msg) => writeln!(f, "// Stopped at {:#x}: {}", self.state.pc, msg)?,
+        }
+        writeln!(f, "// Final register state:")?;
+        for (index, name) in REG_NAMES.iter().enumerate().skip(1) {
+            writeln!(f, "//   {} = {:#x}", name, self.state.regs[index])?;
+        }
+        Ok(())
+    }
+}
+
+// ========================================================================================
+// # Simulator
+
+pub struct Simulator {
+    /// Machine state
+    state: SimState,
+    /// Disassembled lines by address used to annotate the trace
+    lines: HashMap<u64, Rc<AssemblyLine>>,
+    /// Source locations by address used to annotate the trace
+    source_locs: HashMap<u64, String>,
+    /// Maximum number of instructions to execute
+    max_steps: u64,
+}
+
+impl Simulator {
+    /// Creates a simulator with the disassembled lines of the binaries
+    pub fn new(als: &[Rc<AssemblyLine>], max_steps: u64) -> Self {
+        Simulator {
+            state: SimState::new(),
+            lines: als.iter().map(|al| (*al.addr(), Rc::clone(al))).collect(),
+            source_locs: HashMap::new(),
+            max_steps,
+        }
+    }
+
+    /// Annotates the trace with the source locations from the DWARF line tables
+    pub fn set_source_locs(&mut self, dwarf_ctx: &DwarfCtx) {
+        self.source_locs = self
+            .lines
+            .keys()
+            .filter_map(|addr| dwarf_ctx.source_loc_str(*addr).map(|loc| (*addr, loc)))
+            .collect();
+    }
+
+    /// Returns the machine state
+    pub fn state(&self) -> &SimState {
+        &self.state
+    }
+
+    /// Returns a mutable reference to the machine state
+    pub fn state_mut(&mut self) -> &mut SimState {
+        &mut self.state
+    }
+
+    /// Loads the segments of the ELF binaries into memory
+    pub fn load_binaries(&mut self, paths: &Vec<&str>) {
+        for path in paths {
+            let file = fs::File::open(path).unwrap_or_else(|_| panic!("Unable to open binary {}.", path));
+            let mmap = unsafe { memmap::Mmap::map(&file).unwrap() };
+            let object = object::File::parse(&mmap)
+                .unwrap_or_else(|_| panic!("Unable to parse binary {}.", path));
+            for segment in object.segments() {
+                let addr = segment.address();
+                for (i, byte) in segment.data().iter().enumerate() {
+                    self.state.mem.insert(addr + i as u64, *byte);
+                }
+                info!(
+                    "[simulator] Loaded segment at {:#x} ({} bytes) from {}.",
+                    addr,
+                    segment.data().len(),
+                    path
+                );
+            }
+        }
+    }
+
+    /// Initializes registers and memory from a state file.
+    /// Each line is either `<reg> = <value>` or `mem_<b|h|w|d>[<addr>] = <value>`
+    /// where values are decimal or `0x` prefixed hexadecimal numbers.
+    /// Lines starting with `//` are ignored.
+    pub fn load_state_file(&mut self, path: &str) {
+        let content = fs::read_to_string(path)
+            .unwrap_or_else(|_| panic!("Unable to read state file {}.", path));
+        let reg_re = Regex::new(r"^(\w+)\s*=\s*(\S+)$").unwrap();
+        let mem_re = Regex::new(r"^mem_([bhwd])\[\s*(\S+)\s*\]\s*=\s*(\S+)$").unwrap();
+        for (line_num, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            if let Some(caps) = mem_re.captures(line) {
+                let bytes = match &caps[1] {
+                    "b" => 1,
+                    "h" => 2,
+                    "w" => 4,
+                    _ => 8,
+                };
+                let addr = Self::parse_value(&caps[2], path, line_num);
+                let value = Self::parse_value(&caps[3], path, line_num);
+                self.state.store(addr, bytes, value);
+            } else if let Some(caps) = reg_re.captures(line) {
+                let value = Self::parse_value(&caps[2], path, line_num);
+                match &caps[1] {
+                    "pc" => self.state.pc = value,
+                    reg => {
+                        let index = SimState::reg_index(reg).unwrap_or_else(|| {
+                            panic!(
+                                "[simulator] Unknown register {} at {}:{}.",
+                                reg,
+                                path,
+                                line_num + 1
+                            )
+                        });
+                        self.state.set_reg(index, value);
+                    }
+                }
+            } else {
+                panic!(
+                    "[simulator] Unable to parse state at {}:{}: {}",
+                    path,
+                    line_num + 1,
+                    line
+                );
+            }
+        }
+    }
+
+    /// Parses a decimal or hexadecimal value from the state file
+    fn parse_value(s: &str, path: &str, line_num: usize) -> u64 {
+        let res = if let Some(hex) = s.strip_prefix("0x") {
+            helpers::hex_str_to_u64(hex)
+        } else if s.starts_with("-") {
+            helpers::dec_str_to_i64(s).map(|v| v as u64)
+        } else {
+            helpers::dec_str_to_u64(s)
+        };
+        res.unwrap_or_else(|_| {
+            panic!(
+                "[simulator] Invalid value {} at {}:{}.",
+                s,
+                path,
+                line_num + 1
+            )
+        })
+    }
+
+    /// Returns the entry address of the function
+    pub fn func_entry(&self, func_name: &str) -> Option<u64> {
+        self.lines
+            .values()
+            .find(|al| al.is_label_entry() && al.function_name() == func_name)
+            .map(|al| *al.addr())
+    }
+
+    /// Runs the function from its entry until it returns
+    pub fn run(&mut self, func_name: &str) -> SimTrace {
+        let entry = self
+            .func_entry(func_name)
+            .unwrap_or_else(|| panic!("[simulator] Unable to find function {}.", func_name));
+        let ra = SimState::reg_index("ra").unwrap();
+        if self.state.reg(ra) == 0 {
+            self.state.set_reg(ra, RETURN_SENTINEL);
+        }
+        let ret_addr = self.state.reg(ra);
+        self.state.pc = entry;
+        let mut steps = vec![];
+        let stop = loop {
+            if self.state.pc == ret_addr {
+                break StopReason::Returned;
+            }
+            if steps.len() as u64 >= self.max_steps {
+                break StopReason::StepLimit(self.max_steps);
+            }
+            let addr = self.state.pc;
+            let (func, offset, inst) = self.annotate(addr);
+            match self.step() {
+                Ok(effects) => steps.push(SimStep {
+                    addr,
+                    func,
+                    offset,
+                    inst,
+                    loc: self.source_locs.get(&addr).cloned(),
+                    effects,
+                }),
+                Err(msg) => break StopReason::Trap(msg),
+            }
+        };
+        SimTrace {
+            steps,
+            state: self.state.clone(),
+            stop,
+        }
+    }
+
+    /// Returns the function name, offset and disassembly of the instruction at the address
+    fn annotate(&self, addr: u64) -> (String, u64, String) {
+        match self.lines.get(&addr) {
+            Some(al) => {
+                let ops = al
+                    .ops()
+                    .iter()
+                    .map(|op| match op {
+                        InstOperand::Register(reg, Some(offset)) => format!("{}({})", offset, reg),
+                        _ => op.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                (
+                    al.function_name().to_string(),
+                    *al.offset(),
+                    format!("{} {}", al.op(), ops),
+                )
+            }
+            None => (
+                "?".to_string(),
+                0,
+                format!(".word {:#010x}", self.state.load(addr, 4)),
+            ),
+        }
+    }
+
+    /// Returns the name of the function at the address for call annotations
+    fn func_at(&self, addr: u64) -> String {
+        self.lines
+            .get(&addr)
+            .map_or(format!("{:#x}", addr), |al| al.function_name().to_string())
+    }
+
+    // =============================================================================
+    // Instruction execution
+
+    /// Executes the instruction at the program counter and returns its effects
+    pub fn step(&mut self) -> Result<Vec<String>, String> {
+        let pc = self.state.pc;
+        let w = self.state.load(pc, 4) as u32;
+        let opcode = w & 0x7f;
+        let rd = ((w >> 7) & 0x1f) as usize;
+        let f3 = (w >> 12) & 0x7;
+        let rs1 = ((w >> 15) & 0x1f) as usize;
+        let rs2 = ((w >> 20) & 0x1f) as usize;
+        let f7 = w >> 25;
+        let a = self.state.reg(rs1);
+        let b = self.state.reg(rs2);
+        let illegal = || Err(format!("illegal instruction {:#010x}", w));
+        let mut effects = vec![];
+        let mut next_pc = pc.wrapping_add(4);
+        // Value written to rd, if any
+        let mut rd_val = None;
+        match opcode {
+            // LUI
+            0x37 => rd_val = Some(imm_u(w)),
+            // AUIPC
+            0x17 => rd_val = Some(pc.wrapping_add(imm_u(w))),
+            // JAL
+            0x6f => {
+                rd_val = Some(next_pc);
+                next_pc = pc.wrapping_add(imm_j(w));
+            }
+            // JALR
+            0x67 if f3 == 0 => {
+                rd_val = Some(next_pc);
+                next_pc = a.wrapping_add(imm_i(w)) & !1;
+            }
+            // Branches
+            0x63 => {
+                let taken = match f3 {
+                    0 => a == b,
+                    1 => a != b,
+                    4 => (a as i64) < (b as i64),
+                    5 => (a as i64) >= (b as i64),
+                    6 => a < b,
+                    7 => a >= b,
+                    _ => return illegal(),
+                };
+                if taken {
+                    next_pc = pc.wrapping_add(imm_b(w));
+                    effects.push("taken".to_string());
+                }
+            }
+            // Loads
+            0x03 => {
+                let addr = a.wrapping_add(imm_i(w));
+                let value = match f3 {
+                    0 => sext(self.state.load(addr, 1), 8),
+                    1 => sext(self.state.load(addr, 2), 16),
+                    2 => sext(self.state.load(addr, 4), 32),
+                    3 => self.state.load(addr, 8),
+                    4 => self.state.load(addr, 1),
+                    5 => self.state.load(addr, 2),
+                    6 => self.state.load(addr, 4),
+                    _ => return illegal(),
+                };
+                rd_val = Some(value);
+            }
+            // Stores
+            0x23 => {
+                let addr = a.wrapping_add(imm_s(w));
+                let (bytes, mem) = match f3 {
+                    0 => (1, "mem_b"),
+                    1 => (2, "mem_h"),
+                    2 => (4, "mem_w"),
+                    3 => (8, "mem_d"),
+                    _ => return illegal(),
+                };
+                self.state.store(addr, bytes, b);
+                effects.push(format!(
+                    "{}[{:#x}] <- {:#x}",
+                    mem,
+                    addr,
+                    self.state.load(addr, bytes)
+                ));
+            }
+            // Integer register-immediate instructions
+            0x13 => {
+                let imm = imm_i(w);
+                let shamt = (w >> 20) & 0x3f;
+                rd_val = Some(match f3 {
+                    0 => a.wrapping_add(imm),
+                    1 if f7 >> 1 == 0 => a << shamt,
+                    2 => ((a as i64) < (imm as i64)) as u64,
+                    3 => (a < imm) as u64,
+                    4 => a ^ imm,
+                    5 if f7 >> 1 == 0 => a >> shamt,
+                    5 if f7 >> 1 == 0x10 => ((a as i64) >> shamt) as u64,
+                    6 => a | imm,
+                    7 => a & imm,
+                    _ => return illegal(),
+                });
+            }
+            // 32 bit integer register-immediate instructions
+            0x1b => {
+                let shamt = (w >> 20) & 0x1f;
+                rd_val = Some(match (f3, f7) {
+                    (0, _) => sext(a.wrapping_add(imm_i(w)), 32),
+                    (1, 0x00) => sext(a << shamt, 32),
+                    (5, 0x00) => sext(((a as u32) >> shamt) as u64, 32),
+                    (5, 0x20) => ((a as i32) >> shamt) as i64 as u64,
+                    _ => return illegal(),
+                });
+            }
+            // Integer register-register instructions
+            0x33 => {
+                rd_val = Some(match (f7, f3) {
+                    (0x00, 0) => a.wrapping_add(b),
+                    (0x20, 0) => a.wrapping_sub(b),
+                    (0x00, 1) => a << (b & 0x3f),
+                    (0x00, 2) => ((a as i64) < (b as i64)) as u64,
+                    (0x00, 3) => (a < b) as u64,
+                    (0x00, 4) => a ^ b,
+                    (0x00, 5) => a >> (b & 0x3f),
+                    (0x20, 5) => ((a as i64) >> (b & 0x3f)) as u64,
+                    (0x00, 6) => a | b,
+                    (0x00, 7) => a & b,
+                    (0x01, 0) => a.wrapping_mul(b),
+                    (0x01, 1) => (((a as i64 as i128) * (b as i64 as i128)) >> 64) as u64,
+                    (0x01, 2) => (((a as i64 as i128) * (b as i128)) >> 64) as u64,
+                    (0x01, 3) => (((a as u128) * (b as u128)) >> 64) as u64,
+                    (0x01, 4) => {
+                        if b == 0 {
+                            u64::MAX
+                        } else {
+                            (a as i64).wrapping_div(b as i64) as u64
+                        }
+                    }
+                    (0x01, 5) => a.checked_div(b).unwrap_or(u64::MAX),
+                    (0x01, 6) => {
+                        if b == 0 {
+                            a
+                        } else {
+                            (a as i64).wrapping_rem(b as i64) as u64
+                        }
+                    }
+                    (0x01, 7) => a.checked_rem(b).unwrap_or(a),
+                    _ => return illegal(),
+                });
+            }
+            // 32 bit integer register-register instructions
+            0x3b => {
+                let (a32, b32) = (a as u32, b as u32);
+                rd_val = Some(match (f7, f3) {
+                    (0x00, 0) => sext(a32.wrapping_add(b32) as u64, 32),
+                    (0x20, 0) => sext(a32.wrapping_sub(b32) as u64, 32),
+                    (0x00, 1) => sext((a32 << (b32 & 0x1f)) as u64, 32),
+                    (0x00, 5) => sext((a32 >> (b32 & 0x1f)) as u64, 32),
+                    (0x20, 5) => ((a32 as i32) >> (b32 & 0x1f)) as i64 as u64,
+                    (0x01, 0) => sext(a32.wrapping_mul(b32) as u64, 32),
+                    (0x01, 4) => {
+                        if b32 == 0 {
+                            u64::MAX
+                        } else {
+                            (a32 as i32).wrapping_div(b32 as i32) as i64 as u64
+                        }
+                    }
+                    (0x01, 5) => a32
+                        .checked_div(b32)
+                        .map_or(u64::MAX, |q| sext(q as u64, 32)),
+                    (0x01, 6) => {
+                        if b32 == 0 {
+                            sext(a32 as u64, 32)
+                        } else {
+                            (a32 as i32).wrapping_rem(b32 as i32) as i64 as u64
+                        }
+                    }
+                    (0x01, 7) => sext(a32.checked_rem(b32).unwrap_or(a32) as u64, 32),
+                    _ => return illegal(),
+                });
+            }
+            // Fences have no effect on a single hart
+            0x0f => (),
+            // System instructions
+            0x73 => match f3 {
+                0 if w == 0x0000_0073 => return Err("ecall".to_string()),
+                0 if w == 0x0010_0073 => return Err("ebreak".to_string()),
+                0 if w == 0x3020_0073 => return Err("mret".to_string()),
+                // SFENCE.VMA
+                0 if f7 == 0x09 => (),
+                1 | 2 | 3 | 5 | 6 | 7 => {
+                    let csr = (w >> 20) as u64;
+                    let old = *self.state.csrs.get(&csr).unwrap_or(&0);
+                    let src = if f3 >= 5 { rs1 as u64 } else { a };
+                    let new = match f3 & 0x3 {
+                        1 => Some(src),
+                        2 if rs1 != 0 => Some(old | src),
+                        3 if rs1 != 0 => Some(old & !src),
+                        _ => None,
+                    };
+                    if let Some(new) = new {
+                        self.state.csrs.insert(csr, new);
+                        effects.push(format!("csr[{:#x}] <- {:#x}", csr, new));
+                    }
+                    rd_val = Some(old);
+                }
+                _ => return illegal(),
+            },
+            // Atomic memory operations
+            0x2f if f3 == 2 || f3 == 3 => {
+                let bytes = if f3 == 2 { 4 } else { 8 };
+                let width = bytes * 8;
+                if !a.is_multiple_of(bytes) {
+                    return Err(format!("misaligned atomic access at {:#x}", a));
+                }
+                let old = sext(self.state.load(a, bytes), width);
+                let funct5 = w >> 27;
+                let new = match funct5 {
+                    // LR
+                    0x02 => {
+                        self.state.reservation = Some(a);
+                        rd_val = Some(old);
+                        None
+                    }
+                    // SC
+                    0x03 => {
+                        let success = self.state.reservation == Some(a);
+                        self.state.reservation = None;
+                        rd_val = Some(if success { 0 } else { 1 });
+                        if success {
+                            Some(b)
+                        } else {
+                            None
+                        }
+                    }
+                    _ => {
+                        rd_val = Some(old);
+                        let (so, sb) = (sext(old, width) as i64, sext(b, width) as i64);
+                        let (uo, ub) = (zext(old, width), zext(b, width));
+                        Some(match funct5 {
+                            0x01 => b,
+                            0x00 => old.wrapping_add(b),
+                            0x04 => old ^ b,
+                            0x0c => old & b,
+                            0x08 => old | b,
+                            0x10 => so.min(sb) as u64,
+                            0x14 => so.max(sb) as u64,
+                            0x18 => uo.min(ub),
+                            0x1c => uo.max(ub),
+                            _ => return illegal(),
+                        })
+                    }
+                };
+                if let Some(new) = new {
+                    self.state.store(a, bytes, new);
+                    let mem = if bytes == 4 { "mem_w" } else { "mem_d" };
+                    effects.push(format!(
+                        "{}[{:#x}] <- {:#x}",
+                        mem,
+                        a,
+                        self.state.load(a, bytes)
+                    ));
+                }
+            }
+            _ => return illegal(),
+        }
+        if let Some(value) = rd_val {
+            if rd != 0 {
+                self.state.set_reg(rd, value);
+                effects.push(format!("{} <- {:#x}", REG_NAMES[rd], value));
+            }
+        }
+        // Annotate calls and returns
+        if opcode == 0x6f || opcode == 0x67 {
+            if rd == 1 {
+                effects.push(format!("call {}", self.func_at(next_pc)));
+            } else if rd == 0 && opcode == 0x67 && rs1 == 1 {
+                effects.push(format!("return to {}", self.func_at(next_pc)));
+            }
+        }
+        self.state.pc = next_pc;
+        Ok(effects)
+    }
+}
+
+// ========================================================================================
+// # Decoding helpers
+
+/// Sign extends the lower `width` bits of the value
+fn sext(value: u64, width: u64) -> u64 {
+    let shift = 64 - width;
+    (((value << shift) as i64) >> shift) as u64
+}
+
+/// Zero extends the lower `width` bits of the value
+fn zext(value: u64, width: u64) -> u64 {
+    if width == 64 {
+        value
+    } else {
+        value & ((1 << width) - 1)
+    }
+}
+
+/// Returns the sign extended I-type immediate
+fn imm_i(w: u32) -> u64 {
+    ((w as i32) >> 20) as i64 as u64
+}
+
+/// Returns the sign extended S-type immediate
+fn imm_s(w: u32) -> u64 {
+    ((((w as i32) >> 25) << 5) | ((w >> 7) & 0x1f) as i32) as i64 as u64
+}
+
+/// Returns the sign extended B-type immediate
+fn imm_b(w: u32) -> u64 {
+    ((((w as i32) >> 31) << 12)
+        | (((w >> 7) & 0x1) << 11) as i32
+        | (((w >> 25) & 0x3f) << 5) as i32
+        | (((w >> 8) & 0xf) << 1) as i32) as i64 as u64
+}
+
+/// Returns the sign extended U-type immediate
+fn imm_u(w: u32) -> u64 {
+    (w & 0xffff_f000) as i32 as i64 as u64
+}
+
+/// Returns the sign extended J-type immediate
+fn imm_j(w: u32) -> u64 {
+    ((((w as i32) >> 31) << 20)
+        | (w & 0x000f_f000) as i32
+        | (((w >> 20) & 0x1) << 11) as i32
+        | (((w >> 21) & 0x3ff) << 1) as i32) as i64 as u64
+}
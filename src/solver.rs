@@ -0,0 +1,710 @@
+//! Runs UCLID5 on a generated model and parses the output of its `print_results` command
+//! into a result for each `verify(f)` command of the control block.
+
+use regex::Regex;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    io::{self, Read},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+// ========================================================================================
+/// # Proof obligations
+/// Prefix of the comment that describes the obligation checked on the next line of the model
+pub const OBLIGATION_PREFIX: &str = "obligation: ";
+
+/// Returns the name of the macro for the `index`th requires clause of the function
+pub fn requires_macro_name(func_name: &str, index: usize) -> String {
+    format!("{}_requires_{}", func_name.replace(".", "_"), index)
+}
+
+// ========================================================================================
+/// # Verification results
+/// Outcome of a `verify(f)` command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// All the assertions of the procedure hold
+    Passed,
+    /// An assertion of the procedure does not hold
+    Failed,
+    /// The solver could not decide an assertion, or its result could not be read
+    Undetermined,
+    /// The solver did not finish within the time limit
+    Timeout,
+}
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckStatus::Passed => write!(f, "passed"),
+            CheckStatus::Failed => write!(f, "failed"),
+            CheckStatus::Undetermined => write!(f, "undetermined"),
+            CheckStatus::Timeout => write!(f, "timeout"),
+        }
+    }
+}
+
+/// Result of the `verify(f)` command of a function
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckResult {
+    /// Name of the verified function
+    pub func_name: String,
+    pub status: CheckStatus,
+    /// Failed and undetermined properties (e.g. `[Step #1] assertion @ model.ucl, line 120`)
+    pub properties: Vec<String>,
+    /// Results of the proof obligations of the function when they are checked separately
+    pub obligations: Vec<(String, CheckStatus)>,
+}
+impl CheckResult {
+    /// Returns the exit code for the results: 0 if all passed, 1 if a function failed
+    /// and 2 if a function is undetermined or timed out
+    pub fn exit_code(results: &[CheckResult]) -> i32 {
+        if results.iter().any(|res| res.status == CheckStatus::Failed) {
+            1
+        } else if results.iter().all(|res| res.status == CheckStatus::Passed) {
+            0
+        } else {
+            2
+        }
+    }
+
+    /// Returns a line per function followed by its obligations and its other failed and
+    /// undetermined properties
+    pub fn report(results: &[CheckResult]) -> String {
+        let mut lines = vec![];
+        for res in results {
+            lines.push(format!("{}: {}", res.func_name, res.status));
+            for (obligation, status) in &res.obligations {
+                lines.push(format!("    {}: {}", obligation, status));
+            }
+            for property in &res.properties {
+                lines.push(format!("    {}", property));
+            }
+        }
+        format!("{}\n", lines.join("\n"))
+    }
+
+    /// Parses the output of UCLID5 for the generated model `model_text`
+    ///
+    /// # Example
+    ///
+    /// 3 assertions passed.
+    /// 1 assertions failed.
+    /// 0 assertions indeterminate.
+    ///   FAILED -> [Step #1] assertion @ model.ucl, line 120
+    ///
+    /// Each failed (`FAILED`) and undetermined (`UNDEF`) property is attributed to the
+    /// `verify` command whose label it mentions or else, by its line number, to the verified
+    /// procedure that contains it or inlines the procedure that contains it. A property at
+    /// the line following an obligation comment (see `OBLIGATION_PREFIX`) is the result of
+    /// that obligation.
+    /// The functions are undetermined if there are no results or a property can not be attributed.
+    pub fn parse(output: &str, model_text: &str) -> Vec<CheckResult> {
+        let outline = ModelOutline::new(model_text);
+        let mut results = outline.results(CheckStatus::Passed);
+        let summary_re = Regex::new(r"^\d+ assertions (passed|failed|indeterminate)\.").unwrap();
+        let property_re = Regex::new(r"^(FAILED|UNDEF)\s*->\s*(.*)$").unwrap();
+        let mut has_summary = false;
+        let mut unattributed = vec![];
+        for line in output.lines() {
+            let line = line.trim();
+            if summary_re.is_match(line) {
+                has_summary = true;
+            } else if let Some(caps) = property_re.captures(line) {
+                let status = if &caps[1] == "FAILED" {
+                    CheckStatus::Failed
+                } else {
+                    CheckStatus::Undetermined
+                };
+                let property = caps[2].to_string();
+                let owners = outline.owners(&property);
+                // The results are printed again after each check command
+                if owners.is_empty() {
+                    if !unattributed.contains(&property) {
+                        warn!("Unable to find the verified function of the property {}.", property);
+                        unattributed.push(property);
+                    }
+                    continue;
+                }
+                let obligation = outline.obligation(&property);
+                for res in results.iter_mut().filter(|res| owners.contains(&res.func_name)) {
+                    if res.status != CheckStatus::Failed {
+                        res.status = status;
+                    }
+                    let obligation = obligation.and_then(|desc| {
+                        res.obligations.iter_mut().find(|(obligation, _)| obligation == desc)
+                    });
+                    match obligation {
+                        Some((_, obligation_status)) => {
+                            if *obligation_status != CheckStatus::Failed {
+                                *obligation_status = status;
+                            }
+                        }
+                        None => {
+                            if !res.properties.contains(&property) {
+                                res.properties.push(property.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if !has_summary {
+            warn!("No verification results found in the UCLID5 output:\n{}", output);
+            unattributed.push("no results in the UCLID5 output".to_string());
+        }
+        // Functions and obligations can not be reported as passed if a property may belong to them
+        if !unattributed.is_empty() {
+            for res in results.iter_mut() {
+                for (_, status) in res.obligations.iter_mut() {
+                    if *status == CheckStatus::Passed {
+                        *status = CheckStatus::Undetermined;
+                    }
+                }
+                if res.status == CheckStatus::Passed {
+                    res.status = CheckStatus::Undetermined;
+                    res.properties = unattributed.clone();
+                }
+            }
+        }
+        results
+    }
+}
+
+// ========================================================================================
+/// # Generated model outline
+/// Procedures and `verify` commands of a generated UCLID5 model
+struct ModelOutline {
+    /// Labels and function names of the `verify` commands in order.
+    /// A `bmc(k)` or `induction` command is named after itself and checks the whole module
+    verified: Vec<(String, String)>,
+    /// First line (1-indexed) and name of each procedure in order
+    proc_lines: Vec<(usize, String)>,
+    /// Line of the control block; the procedures end before it
+    control_line: usize,
+    /// Verified functions of each procedure that they contain or inline
+    owners: HashMap<String, HashSet<String>>,
+    /// Procedures that call each procedure
+    callers: HashMap<String, HashSet<String>>,
+    /// Procedure of each line of a requires clause
+    requires_lines: HashMap<usize, String>,
+    /// Line, description and procedure of each obligation
+    obligations: Vec<(usize, String, String)>,
+    /// Line number of a property
+    line_re: Regex,
+    /// Words of a property that may be labels of the verify commands
+    word_re: Regex,
+}
+
+impl ModelOutline {
+    fn new(model_text: &str) -> Self {
+        let proc_re = Regex::new(r"^procedure\s+(\[inline\]\s+)?([\w.]+)\s*\(").unwrap();
+        let call_re = Regex::new(r"^call\s*\(.*\)\s*=\s*([\w.]+)\s*\(").unwrap();
+        let verify_re =
+            Regex::new(r"^(\w+)\s*=\s*(verify\(([\w.]+)\)|bmc\(\d+\)|induction(\(\d+\))?);").unwrap();
+        let mut verified = vec![];
+        let mut proc_lines = vec![];
+        let mut control_line = usize::MAX;
+        let mut inlined = HashSet::new();
+        let mut callees: HashMap<String, Vec<String>> = HashMap::new();
+        let mut callers: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut requires_lines = HashMap::new();
+        let mut obligations = vec![];
+        let mut in_header = false;
+        let mut obligation = None;
+        for (i, line) in model_text.lines().enumerate() {
+            let line = line.trim();
+            let proc_name = proc_lines.last().map(|(_, proc_name): &(usize, String)| proc_name.clone());
+            // An obligation comment describes the next statement or clause
+            if !line.is_empty() && !line.starts_with("//") {
+                if let (Some(desc), Some(proc_name)) = (obligation.take(), proc_name.clone()) {
+                    obligations.push((i + 1, desc, proc_name));
+                }
+            }
+            if let Some(desc) = line.strip_prefix("//").and_then(|c| c.trim().strip_prefix(OBLIGATION_PREFIX)) {
+                obligation = Some(desc.to_string());
+            } else if let Some(caps) = proc_re.captures(line) {
+                // Calls refer to the procedures with dots replaced by underscores
+                let name = caps[2].replace(".", "_");
+                if caps.get(1).is_some() {
+                    inlined.insert(name.clone());
+                }
+                proc_lines.push((i + 1, name));
+                in_header = true;
+            } else if in_header && line.starts_with("requires") {
+                if let Some(proc_name) = proc_name {
+                    requires_lines.insert(i + 1, proc_name);
+                }
+            } else if line == "{" {
+                in_header = false;
+            } else if let Some(caps) = call_re.captures(line) {
+                if let Some(caller) = proc_name {
+                    let callee = caps[1].replace(".", "_");
+                    callers.entry(callee.clone()).or_default().insert(caller.clone());
+                    callees.entry(caller).or_default().push(callee);
+                }
+            } else if let Some(caps) = verify_re.captures(line) {
+                let name = caps.get(3).map_or(&caps[2], |func_name| func_name.as_str());
+                verified.push((caps[1].to_string(), name.to_string()));
+            } else if line.starts_with("control {") {
+                control_line = i + 1;
+            }
+        }
+        // A procedure belongs to the verified functions that inline it
+        let mut owners: HashMap<String, HashSet<String>> = HashMap::new();
+        for (_, func_name) in &verified {
+            let mut stack = vec![func_name.replace(".", "_")];
+            let mut visited = HashSet::new();
+            while let Some(proc_name) = stack.pop() {
+                if !visited.insert(proc_name.clone()) {
+                    continue;
+                }
+                owners
+                    .entry(proc_name.clone())
+                    .or_default()
+                    .insert(func_name.clone());
+                for callee in callees.get(&proc_name).into_iter().flatten() {
+                    if inlined.contains(callee) {
+                        stack.push(callee.clone());
+                    }
+                }
+            }
+        }
+        ModelOutline {
+            verified,
+            proc_lines,
+            control_line,
+            owners,
+            callers,
+            requires_lines,
+            obligations,
+            line_re: Regex::new(r"line (\d+)").unwrap(),
+            word_re: Regex::new(r"[\w.]+").unwrap(),
+        }
+    }
+
+    /// Returns a result with the status for each verified function and its obligations
+    fn results(&self, status: CheckStatus) -> Vec<CheckResult> {
+        self.verified
+            .iter()
+            .map(|(_, func_name)| CheckResult {
+                func_name: func_name.clone(),
+                status,
+                properties: vec![],
+                obligations: self
+                    .obligations
+                    .iter()
+                    .filter(|(_, _, proc_name)| {
+                        matches!(self.owners.get(proc_name), Some(owners) if owners.contains(func_name))
+                    })
+                    .map(|(_, desc, _)| (desc.clone(), status))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Returns the line of the model that the property refers to
+    fn property_line(&self, property: &str) -> Option<usize> {
+        self.line_re
+            .captures(property)
+            .and_then(|caps| caps[1].parse::<usize>().ok())
+    }
+
+    /// Returns the description of the obligation that the property is the result of
+    fn obligation(&self, property: &str) -> Option<&str> {
+        let line = self.property_line(property)?;
+        self.obligations
+            .iter()
+            .find(|(obligation_line, _, _)| *obligation_line == line)
+            .map(|(_, desc, _)| desc.as_str())
+    }
+
+    /// Returns the verified functions that the property belongs to
+    fn owners(&self, property: &str) -> HashSet<String> {
+        // The label of the verify command
+        let labelled = self
+            .word_re
+            .find_iter(property)
+            .filter_map(|word| self.verified.iter().find(|(label, _)| label == word.as_str()))
+            .map(|(_, func_name)| func_name.clone())
+            .collect::<HashSet<_>>();
+        if !labelled.is_empty() {
+            return labelled;
+        }
+        // The procedure at the line of the property
+        let owners = self.property_line(property)
+            .filter(|line| *line < self.control_line)
+            .and_then(|line| match self.requires_lines.get(&line) {
+                // A requires clause is checked at the call sites
+                Some(proc_name) => Some(
+                    self.callers
+                        .get(proc_name)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|caller| self.owners.get(caller))
+                        .flatten()
+                        .cloned()
+                        .collect(),
+                ),
+                None => self
+                    .proc_lines
+                    .iter()
+                    .take_while(|(start, _)| *start <= line)
+                    .last()
+                    .and_then(|(_, proc_name)| self.owners.get(proc_name))
+                    .cloned(),
+            })
+            .unwrap_or_default();
+        // Otherwise the property is in the transition system checked by bmc or induction
+        if owners.is_empty() {
+            return self
+                .verified
+                .iter()
+                .filter(|(_, name)| Self::is_module_command(name))
+                .map(|(_, name)| name.clone())
+                .collect();
+        }
+        owners
+    }
+
+    /// Returns true if the verify command named `name` checks the whole module
+    fn is_module_command(name: &str) -> bool {
+        name.starts_with("bmc(") || name.starts_with("induction(") || name == "induction"
+    }
+}
+
+// ========================================================================================
+/// # UCLID5 runner
+/// Runs a UCLID5 binary on generated models
+#[derive(Debug, Clone)]
+pub struct UclidRunner {
+    /// Path of the UCLID5 binary
+    binary: String,
+    /// Arguments before the model file (e.g. `-s` and the solver command)
+    args: Vec<String>,
+    /// Time limit of a run
+    timeout: Option<Duration>,
+}
+
+impl UclidRunner {
+    pub fn new(binary: &str, args: Vec<String>, timeout: Option<Duration>) -> Self {
+        UclidRunner {
+            binary: binary.to_string(),
+            args,
+            timeout,
+        }
+    }
+
+    /// Runs UCLID5 on the model file and returns the result of each `verify` command
+    pub fn check(&self, model_file: &str) -> io::Result<Vec<CheckResult>> {
+        let model_text = fs::read_to_string(model_file)?;
+        match self.run(model_file)? {
+            Some(output) => Ok(CheckResult::parse(&output, &model_text)),
+            None => Ok(ModelOutline::new(&model_text).results(CheckStatus::Timeout)),
+        }
+    }
+
+    /// Returns the output (stdout followed by stderr) of UCLID5 or None if it timed out
+    fn run(&self, model_file: &str) -> io::Result<Option<String>> {
+        let mut child = Command::new(&self.binary)
+            .args(&self.args)
+            .arg(model_file)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // Read the output in the background so that UCLID5 does not block on a full pipe
+        let stdout = Self::read_in_background(child.stdout.take());
+        let stderr = Self::read_in_background(child.stderr.take());
+        let start = Instant::now();
+        while child.try_wait()?.is_none() {
+            if matches!(self.timeout, Some(timeout) if start.elapsed() >= timeout) {
+                child.kill()?;
+                child.wait()?;
+                // The readers are not joined; the solver started by UCLID5 may keep the pipes open
+                return Ok(None);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        Ok(Some(format!("{}{}", stdout, stderr)))
+    }
+
+    fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<String> {
+        thread::spawn(move || {
+            let mut output = String::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_string(&mut output);
+            }
+            output
+        })
+    }
+}
+
+// ========================================================================================
+/// # Solver configuration
+/// Solvers that UCLID5 can run the generated models with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolverProfile {
+    Z3,
+    Cvc4,
+    Cvc5,
+    Boolector,
+}
+
+impl SolverProfile {
+    /// Returns the profile with the name (z3, cvc4, cvc5 or boolector)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "z3" => Some(SolverProfile::Z3),
+            "cvc4" => Some(SolverProfile::Cvc4),
+            "cvc5" => Some(SolverProfile::Cvc5),
+            "boolector" => Some(SolverProfile::Boolector),
+            _ => None,
+        }
+    }
+
+    /// Returns the `set_solver_option` options (name and UCLID5 literal) of the profile
+    fn options(&self) -> Vec<(String, String)> {
+        let options: &[(&str, &str)] = match self {
+            SolverProfile::Z3 => &[
+                (":mbqi", "false"),
+                (":case_split", "0"),
+                (":relevancy", "0"),
+                (":blast_full", "true"),
+            ],
+            SolverProfile::Cvc4 => &[(":bitblast", "\"lazy\"")],
+            SolverProfile::Cvc5 => &[(":bv-solver", "\"bitblast\"")],
+            SolverProfile::Boolector => &[],
+        };
+        options
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Returns the option that limits the time (in milliseconds) of each query
+    fn timeout_option(&self) -> Option<&'static str> {
+        match self {
+            SolverProfile::Z3 => Some(":timeout"),
+            SolverProfile::Cvc4 | SolverProfile::Cvc5 => Some(":tlimit-per"),
+            SolverProfile::Boolector => None,
+        }
+    }
+
+    /// Returns the external solver command passed to UCLID5 with `-s` (Z3 is built in)
+    fn command(&self) -> Option<&'static str> {
+        match self {
+            SolverProfile::Z3 => None,
+            SolverProfile::Cvc4 => Some("cvc4 --incremental --lang smt2 --force-logic=ALL"),
+            SolverProfile::Cvc5 => Some("cvc5 --incremental --lang smt2 --force-logic=ALL"),
+            SolverProfile::Boolector => Some("boolector --incremental --smt2"),
+        }
+    }
+}
+
+/// Command that checks the model in the control block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyCommand {
+    /// `verify(f)` for each verified function
+    Verify,
+    /// `bmc(k)` of the module's transition system with the number of steps
+    Bmc(u64),
+    /// `induction(k)` of the invariants of the module's transition system with the depth
+    Induction(u64),
+}
+
+impl VerifyCommand {
+    /// Parses `verify`, `bmc:K` or `induction[:K]` (also `bmc K` and `induction K`)
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if s == "verify" {
+            return Some(VerifyCommand::Verify);
+        }
+        if s == "induction" {
+            return Some(VerifyCommand::Induction(1));
+        }
+        let steps = |steps: &str| {
+            steps
+                .trim_start_matches(|c: char| c == ':' || c.is_whitespace())
+                .parse::<u64>()
+                .ok()
+        };
+        if let Some(depth) = s.strip_prefix("induction") {
+            return steps(depth).filter(|k| *k > 0).map(VerifyCommand::Induction);
+        }
+        s.strip_prefix("bmc").and_then(steps).map(VerifyCommand::Bmc)
+    }
+}
+
+/// Solver profile, options, timeouts and command of the generated UCLID5 control block
+#[derive(Debug, Clone)]
+pub struct SolverConfig {
+    profile: SolverProfile,
+    /// Options that are added to or replace the options of the profile
+    options: Vec<(String, String)>,
+    /// External solver command that replaces the command of the profile
+    solver_cmd: Option<String>,
+    /// Timeout in seconds of the functions without their own timeout
+    default_timeout: Option<u64>,
+    /// Timeouts in seconds of functions
+    func_timeouts: HashMap<String, u64>,
+    command: VerifyCommand,
+    /// Checks each ensures clause and each callee requires at each call site separately
+    split_obligations: bool,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            profile: SolverProfile::Z3,
+            options: vec![],
+            solver_cmd: None,
+            default_timeout: None,
+            func_timeouts: HashMap::new(),
+            command: VerifyCommand::Verify,
+            split_obligations: false,
+        }
+    }
+}
+
+impl SolverConfig {
+    /// Reads a solver configuration file. Each line is one of
+    ///
+    /// solver = cvc4
+    /// solver_cmd = cvc4 --incremental --lang smt2 --force-logic=ALL
+    /// option :tlimit-per = 1000
+    /// timeout = 60
+    /// timeout pmp_set = 600
+    /// command = bmc 10
+    /// split_obligations = true
+    ///
+    /// where timeouts are in seconds. Lines starting with `//` are ignored.
+    /// Returns an error if the file cannot be read or has an invalid line.
+    pub fn load_file(&mut self, path: &str) -> Result<(), String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read solver configuration file {}: {}", path, e))?;
+        let entry_re = Regex::new(r"^(\w+)(\s+[^=\s]+)?\s*=\s*(.+)$").unwrap();
+        for (line_num, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            if self.load_entry(&entry_re, line).is_none() {
+                return Err(format!(
+                    "Unable to parse solver configuration at {}:{}: {}",
+                    path,
+                    line_num + 1,
+                    line
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the entry of a configuration file line, returns None if it is invalid
+    fn load_entry(&mut self, entry_re: &Regex, line: &str) -> Option<()> {
+        let caps = entry_re.captures(line)?;
+        let arg = caps.get(2).map(|arg| arg.as_str().trim());
+        let value = caps[3].trim();
+        match (&caps[1], arg) {
+            ("solver", None) => self.profile = SolverProfile::from_name(value)?,
+            ("solver_cmd", None) => self.solver_cmd = Some(value.to_string()),
+            ("option", Some(name)) => self.set_option(name, value),
+            ("timeout", func_name) => self.set_timeout(func_name, value.parse::<u64>().ok()?),
+            ("command", None) => self.command = VerifyCommand::parse(value)?,
+            ("split_obligations", None) => self.split_obligations = value.parse::<bool>().ok()?,
+            _ => return None,
+        }
+        Some(())
+    }
+
+    pub fn set_profile(&mut self, profile: SolverProfile) {
+        self.profile = profile;
+    }
+
+    pub fn set_command(&mut self, command: VerifyCommand) {
+        self.command = command;
+    }
+
+    pub fn command(&self) -> VerifyCommand {
+        self.command
+    }
+
+    pub fn set_split_obligations(&mut self, split_obligations: bool) {
+        self.split_obligations = split_obligations;
+    }
+
+    pub fn split_obligations(&self) -> bool {
+        self.split_obligations
+    }
+
+    /// Adds or replaces an option; values other than booleans and integers are quoted
+    pub fn set_option(&mut self, name: &str, value: &str) {
+        let literal = value == "true"
+            || value == "false"
+            || value.parse::<i64>().is_ok()
+            || value.starts_with('"');
+        let value = if literal {
+            value.to_string()
+        } else {
+            format!("\"{}\"", value)
+        };
+        self.options.retain(|(n, _)| n != name);
+        self.options.push((name.to_string(), value));
+    }
+
+    /// Sets the timeout in seconds of the function, or the default timeout if None
+    pub fn set_timeout(&mut self, func_name: Option<&str>, secs: u64) {
+        match func_name {
+            Some(func_name) => {
+                self.func_timeouts.insert(func_name.to_string(), secs);
+            }
+            None => self.default_timeout = Some(secs),
+        }
+    }
+
+    /// Returns the options of the profile followed by the options that are added
+    pub fn solver_options(&self) -> Vec<(String, String)> {
+        let mut options = self
+            .profile
+            .options()
+            .into_iter()
+            .filter(|(name, _)| !self.options.iter().any(|(n, _)| n == name))
+            .collect::<Vec<_>>();
+        options.extend(self.options.iter().cloned());
+        options
+    }
+
+    /// Returns the option name and timeout in milliseconds (0 is no limit) of each function,
+    /// or None if there are no timeouts
+    pub fn timeout_option(&self, func_name: &str) -> Option<(&'static str, u64)> {
+        if self.default_timeout.is_none() && self.func_timeouts.is_empty() {
+            return None;
+        }
+        let option = match self.profile.timeout_option() {
+            Some(option) => option,
+            None => {
+                warn!("The {:?} profile does not support timeouts; they are ignored.", self.profile);
+                return None;
+            }
+        };
+        let secs = self
+            .func_timeouts
+            .get(func_name)
+            .cloned()
+            .or(self.default_timeout)
+            .unwrap_or(0);
+        Some((option, secs * 1000))
+    }
+
+    /// Returns the UCLID5 arguments that select the solver
+    pub fn uclid_args(&self) -> Vec<String> {
+        match self.solver_cmd.as_deref().or_else(|| self.profile.command()) {
+            Some(cmd) => vec!["-s".to_string(), cmd.to_string()],
+            None => vec![],
+        }
+    }
+}
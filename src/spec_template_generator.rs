@@ -55,6 +55,7 @@ impl SpecTemplateGenerator {
                 in_typ: _,
                 out_typ: _,
                 bytes,
+                len: _,
             } => format!("bv{}", bytes * constants::BYTE_SIZE),
             DwarfTypeDefn::Struct {
                 id: _,
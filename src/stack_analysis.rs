@@ -0,0 +1,302 @@
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use dwarf_ctx::dwarfreader::DwarfCtx;
+
+use utils::constants;
+
+use crate::{
+    datastructures::cfg,
+    disassembler::disassembler::{AssemblyLine, Inst},
+};
+
+// ========================================================================================
+// # Stack usage analysis
+
+/// Stack frame of a function computed from the `sp` adjustments in its CFG
+#[derive(Debug, Clone)]
+pub struct FrameInfo {
+    /// Name of the function
+    pub func: String,
+    /// Largest number of bytes allocated below the `sp` at the entry of the function
+    pub frame_size: u64,
+    /// Callees and the number of bytes allocated by this function when they are called
+    pub calls: Vec<(String, u64)>,
+    /// Addresses of `sp` adjustments that cannot be bounded
+    pub dynamic_adjustments: Vec<u64>,
+    /// Addresses of indirect calls
+    pub indirect_calls: Vec<u64>,
+}
+
+/// Worst-case stack depth of a call tree
+#[derive(Debug, Clone)]
+pub struct StackDepth {
+    /// Worst-case number of bytes used by the call tree (a lower bound if it is unbounded)
+    pub bytes: u64,
+    /// Functions on the deepest call path
+    pub path: Vec<String>,
+    /// Reasons the depth cannot be bounded (empty if the depth is bounded)
+    pub unbounded: Vec<String>,
+}
+impl StackDepth {
+    /// Returns true iff the depth is bounded
+    pub fn is_bounded(&self) -> bool {
+        self.unbounded.is_empty()
+    }
+}
+
+/// Computes the frame sizes of functions and the stack depth of their call trees
+pub struct StackAnalyzer<'a> {
+    /// Basic blocks of the binary by entry address
+    bbs: &'a HashMap<u64, Rc<cfg::BasicBlock<AssemblyLine>>>,
+    /// DWARF debugging information used to report source locations
+    dwarf_ctx: &'a DwarfCtx,
+    /// Function entry addresses by name
+    func_entries: HashMap<String, u64>,
+    /// Memoized frames
+    frames: HashMap<String, Rc<FrameInfo>>,
+    /// Memoized stack depths
+    depths: HashMap<String, StackDepth>,
+}
+
+impl<'a> StackAnalyzer<'a> {
+    pub fn new(
+        bbs: &'a HashMap<u64, Rc<cfg::BasicBlock<AssemblyLine>>>,
+        dwarf_ctx: &'a DwarfCtx,
+    ) -> Self {
+        let func_entries = bbs
+            .values()
+            .filter(|bb| bb.entry().is_label_entry())
+            .map(|bb| (bb.entry().function_name().to_string(), bb.entry().address()))
+            .collect();
+        StackAnalyzer {
+            bbs,
+            dwarf_ctx,
+            func_entries,
+            frames: HashMap::new(),
+            depths: HashMap::new(),
+        }
+    }
+
+    /// Returns the stack frame of the function
+    pub fn frame_info(&mut self, func_name: &str) -> Rc<FrameInfo> {
+        if let Some(frame) = self.frames.get(func_name) {
+            return Rc::clone(frame);
+        }
+        let frame = Rc::new(self.compute_frame_info(func_name));
+        self.frames.insert(func_name.to_string(), Rc::clone(&frame));
+        frame
+    }
+
+    /// Returns the worst-case stack depth of the call tree rooted at the function
+    pub fn stack_depth(&mut self, func_name: &str) -> StackDepth {
+        self.compute_stack_depth(func_name, &mut vec![])
+    }
+
+    /// Returns the names of the given functions and all the functions they call
+    pub fn reachable_funcs(&mut self, func_names: &[String]) -> Vec<String> {
+        let mut reachable = vec![];
+        let mut seen = HashSet::new();
+        let mut worklist = func_names.to_vec();
+        while let Some(func_name) = worklist.pop() {
+            if !seen.insert(func_name.clone()) {
+                continue;
+            }
+            for (callee, _) in &self.frame_info(&func_name).calls {
+                worklist.push(callee.clone());
+            }
+            reachable.push(func_name);
+        }
+        reachable.sort();
+        reachable
+    }
+
+    /// Returns a report of the frame size and worst-case stack depth of each function
+    pub fn report(&mut self, func_names: &Vec<String>) -> String {
+        let mut report = format!(
+            "// Stack usage in bytes\n// {:<32} {:>8} {:>8}  deepest call path\n",
+            "function", "frame", "depth"
+        );
+        for func_name in func_names {
+            if !self.func_entries.contains_key(func_name) {
+                report = format!("{}// Unable to find function {}.\n", report, func_name);
+                continue;
+            }
+            let frame = self.frame_info(func_name);
+            let depth = self.stack_depth(func_name);
+            let depth_str = if depth.is_bounded() {
+                format!("{}", depth.bytes)
+            } else {
+                format!(">={}", depth.bytes)
+            };
+            report = format!(
+                "{}{:<35} {:>8} {:>8}  {}\n",
+                report,
+                func_name,
+                frame.frame_size,
+                depth_str,
+                depth.path.join(" -> ")
+            );
+            for reason in &depth.unbounded {
+                report = format!("{}    warning: {}\n", report, reason);
+            }
+        }
+        report
+    }
+
+    /// Computes the frame of the function by propagating the `sp` offset from
+    /// the entry of the function through its CFG
+    fn compute_frame_info(&self, func_name: &str) -> FrameInfo {
+        let mut frame = FrameInfo {
+            func: func_name.to_string(),
+            frame_size: 0,
+            calls: vec![],
+            dynamic_adjustments: vec![],
+            indirect_calls: vec![],
+        };
+        let entry = match self.func_entries.get(func_name) {
+            Some(entry) => *entry,
+            None => return frame,
+        };
+        let func_cfg = cfg::Cfg::new(entry, self.bbs);
+        // Offsets of sp and s0 (if known) from the sp at the entry of the function
+        let mut bb_offsets: HashMap<u64, (i64, Option<i64>)> = HashMap::new();
+        let mut worklist = vec![(entry, 0, None)];
+        while let Some((bb_addr, sp_entry_off, s0_entry_off)) = worklist.pop() {
+            if let Some((prev_sp, _)) = bb_offsets.get(&bb_addr) {
+                if *prev_sp != sp_entry_off && !frame.dynamic_adjustments.contains(&bb_addr) {
+                    // Paths with different stack offsets merge at this basic block
+                    frame.dynamic_adjustments.push(bb_addr);
+                }
+                continue;
+            }
+            bb_offsets.insert(bb_addr, (sp_entry_off, s0_entry_off));
+            let cfg_node = match func_cfg.nodes().get(&bb_addr) {
+                Some(cfg_node) => cfg_node,
+                None => continue,
+            };
+            let mut sp_off = sp_entry_off;
+            let mut s0_off = s0_entry_off;
+            for al in cfg_node.into_iter() {
+                let rd = al.rd().map(|rd| rd.get_reg_name());
+                let rs1 = al.rs1().map(|rs1| rs1.get_reg_name());
+                let imm = al.imm().map(|imm| imm.get_imm_val());
+                match (al.op(), rd.as_deref(), rs1.as_deref(), imm) {
+                    // Stack allocation and deallocation
+                    ("addi", Some(constants::SP), Some(constants::SP), Some(imm)) => sp_off += imm,
+                    // Restore from the frame pointer
+                    ("addi", Some(constants::SP), Some("s0"), Some(imm)) => match s0_off {
+                        Some(s0_off) => sp_off = s0_off + imm,
+                        None => frame.dynamic_adjustments.push(al.address()),
+                    },
+                    // Frame pointer setup
+                    ("addi", Some("s0"), Some(constants::SP), Some(imm)) => {
+                        s0_off = Some(sp_off + imm)
+                    }
+                    (_, Some(constants::SP), _, _) => frame.dynamic_adjustments.push(al.address()),
+                    (_, Some("s0"), _, _) => s0_off = None,
+                    _ => (),
+                }
+                frame.frame_size = frame.frame_size.max((-sp_off).max(0) as u64);
+                // Calls to other functions
+                if al.op() == constants::JAL {
+                    let target = al.imm().unwrap().get_imm_val() as u64;
+                    if let Some(callee) = self.func_at(target) {
+                        frame.calls.push((callee, (-sp_off).max(0) as u64));
+                    }
+                } else if al.op() == constants::JALR && rd.as_deref() == Some(constants::RA) {
+                    frame.indirect_calls.push(al.address());
+                }
+            }
+            // Successors within the function
+            for succ in cfg_node.exit().successors() {
+                let is_callee_entry = self
+                    .bbs
+                    .get(&succ)
+                    .is_none_or(|bb| bb.entry().is_label_entry() && bb.entry().function_name() != func_name);
+                if succ == entry || is_callee_entry {
+                    continue;
+                }
+                worklist.push((succ, sp_off, s0_off));
+            }
+        }
+        frame
+    }
+
+    /// Computes the stack depth of the call tree rooted at the function.
+    /// `call_stack` contains the functions on the current call path to detect recursion.
+    fn compute_stack_depth(&mut self, func_name: &str, call_stack: &mut Vec<String>) -> StackDepth {
+        if let Some(depth) = self.depths.get(func_name) {
+            return depth.clone();
+        }
+        let frame = self.frame_info(func_name);
+        let mut depth = StackDepth {
+            bytes: frame.frame_size,
+            path: vec![func_name.to_string()],
+            unbounded: vec![],
+        };
+        for addr in &frame.dynamic_adjustments {
+            depth.unbounded.push(format!(
+                "unbounded sp adjustment in {} at {}",
+                func_name,
+                self.addr_str(*addr)
+            ));
+        }
+        for addr in &frame.indirect_calls {
+            depth.unbounded.push(format!(
+                "indirect call in {} at {}",
+                func_name,
+                self.addr_str(*addr)
+            ));
+        }
+        call_stack.push(func_name.to_string());
+        let mut recursive = false;
+        for (callee, offset) in &frame.calls {
+            if call_stack.contains(callee) {
+                recursive = true;
+                depth.unbounded.push(format!(
+                    "recursive call {} -> {}",
+                    call_stack.join(" -> "),
+                    callee
+                ));
+                continue;
+            }
+            let callee_depth = self.compute_stack_depth(callee, call_stack);
+            for reason in callee_depth.unbounded {
+                if !depth.unbounded.contains(&reason) {
+                    depth.unbounded.push(reason);
+                }
+            }
+            if offset + callee_depth.bytes > depth.bytes {
+                depth.bytes = offset + callee_depth.bytes;
+                depth.path = vec![func_name.to_string()];
+                depth.path.extend(callee_depth.path);
+            }
+        }
+        call_stack.pop();
+        // Depths inside a recursive cycle depend on the call path
+        if !recursive {
+            self.depths.insert(func_name.to_string(), depth.clone());
+        }
+        depth
+    }
+
+    /// Returns the name of the function with the entry address
+    fn func_at(&self, addr: u64) -> Option<String> {
+        self.bbs
+            .get(&addr)
+            .map(|bb| bb.entry())
+            .filter(|entry| entry.is_label_entry())
+            .map(|entry| entry.function_name().to_string())
+    }
+
+    /// Returns the address followed by its source location if it is known
+    fn addr_str(&self, addr: u64) -> String {
+        match self.dwarf_ctx.source_loc_str(addr) {
+            Some(loc) => format!("{:#x} ({})", addr, loc),
+            None => format!("{:#x}", addr),
+        }
+    }
+}
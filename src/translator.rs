@@ -2,6 +2,7 @@ use std::{
     boxed::Box,
     collections::HashSet,
     collections::{BTreeMap, HashMap},
+    io::{self, Write},
     marker::PhantomData,
     rc::Rc,
     cell::RefCell,
@@ -20,11 +21,32 @@ use utils::{constants, helpers};
 use crate::{
     datastructures::cfg, disassembler::disassembler, disassembler::disassembler::Inst,
     ir_interface::IRInterface,
+    solver::{requires_macro_name, SolverConfig, OBLIGATION_PREFIX},
+    stack_analysis::StackAnalyzer,
 };
 
 // ================================================================================
 /// # VERI-V Translator
 
+/// Options of the translation that are not derived from the binary
+#[derive(Default)]
+pub struct TranslatorOptions<'t> {
+    /// Ghost variables declared in the specifications
+    pub ghosts: &'t [sl_ast::Ghost],
+    /// Ignore the function pre and post conditions and inline all functions
+    pub ignore_specs: bool,
+    /// Report variables missing from the `modifies` clause of an ignored function as errors
+    pub strict_mod_sets: bool,
+    /// Memory regions (base address, size in bytes) that loads and stores may access
+    pub mem_safety_regions: Option<Vec<(u64, u64)>>,
+    /// Stack region (base address, size in bytes) that `sp` must stay within
+    pub stack_region: Option<(u64, u64)>,
+    /// Calling-convention contract exemptions by function name; `None` disables the contracts
+    pub abi_exemptions: Option<HashMap<String, HashSet<String>>>,
+    /// Solver profile, timeouts and command of the generated control block
+    pub solver_config: SolverConfig,
+}
+
 /// Instruction level translator from RISC-V to verification language IR
 pub struct Translator<'t, I>
 where
@@ -46,10 +68,28 @@ where
     dwarf_ctx: &'t DwarfCtx,
     /// Map of specs from function name to a list of pre/post conditions
     specs_map: &'t HashMap<String, Vec<sl_ast::Spec>>,
+    /// Ghost variables declared in the specifications
+    ghosts: &'t [sl_ast::Ghost],
     /// Flag indicating if the translator will ignore specs
     /// When true, all function pre and post conditions are ignored
     /// and functions are all inlined
     ignore_specs: bool,
+    /// Flag indicating if a `modifies` clause of an ignored function that is
+    /// missing a variable written by the function is an error instead of a warning
+    strict_mod_sets: bool,
+    /// User-declared memory regions (base address, size in bytes) that loads and stores
+    /// may access. When set, memory-safety assertions are inserted before each load and store
+    mem_safety_regions: Option<Vec<(u64, u64)>>,
+    /// Stack region (base address, size in bytes) that `sp` must stay within.
+    /// When set, functions require enough stack for their worst-case depth
+    /// and every `sp` update is asserted to stay inside the region
+    stack_region: Option<(u64, u64)>,
+    /// Calling-convention contracts. When set, every function ensures that it returns to
+    /// `old(ra)` and preserves `sp`, `gp`, `tp` and `s0`-`s11`. Maps function names to the
+    /// clauses (`ra` or a preserved register) that are not generated for them
+    abi_exemptions: Option<HashMap<String, HashSet<String>>>,
+    /// Solver profile, timeouts and command of the generated control block
+    solver_config: SolverConfig,
 
     // ====================================================================
     // Translator context
@@ -61,6 +101,10 @@ where
     generated: HashSet<u64>,
     /// Map of procedure name to thier modifies set
     mod_set_map: HashMap<String, HashSet<String>>,
+    /// Map of instruction addresses to the ghost updates executed before the instruction
+    ghost_updates: HashMap<u64, Vec<Stmt>>,
+    /// Stack frame and depth analysis
+    stack_analyzer: RefCell<StackAnalyzer<'t>>,
 
     // =====================================================================
     // Phantom data
@@ -73,18 +117,40 @@ where
 {
     /// Translator constructor
     pub fn new(
-        xlen: u64,
         module_name: &'t str,
         bbs: &'t HashMap<u64, Rc<cfg::BasicBlock<disassembler::AssemblyLine>>>,
         ignored_funcs: &'t HashSet<&'t str>,
         verify_funcs: &'t Vec<&'t str>,
         dwarf_ctx: &'t DwarfCtx,
         specs_map: &'t HashMap<String, Vec<sl_ast::Spec>>,
-        ignore_specs: bool,
+        options: TranslatorOptions<'t>,
     ) -> Self {
+        let xlen = dwarf_ctx.xlen;
+        let TranslatorOptions {
+            ghosts,
+            ignore_specs,
+            strict_mod_sets,
+            mem_safety_regions,
+            stack_region,
+            abi_exemptions,
+            solver_config,
+        } = options;
         // Initialize the VERI-V model
         let mut model = Model::new(module_name);
         model.add_vars(&system_model::sys_state_vars(xlen));
+        // Registers referred to by the calling-convention contracts
+        if abi_exemptions.is_some() {
+            model.add_vars(&system_model::abi_reg_vars(xlen).into_iter().collect());
+        }
+        for ghost in ghosts {
+            model.add_var(Var {
+                name: ghost.name.clone(),
+                typ: ghost.typ.to_ast_type(),
+            });
+        }
+        let labels_to_addr = Translator::<I>::create_label_to_addr_map(bbs);
+        let ghost_updates =
+            Translator::<I>::ghost_updates(xlen, bbs, dwarf_ctx, specs_map, ghosts, &labels_to_addr);
 
         // Create a translator
         Translator {
@@ -96,12 +162,20 @@ where
             verify_funcs: verify_funcs,
             dwarf_ctx: dwarf_ctx,
             specs_map: specs_map,
+            ghosts,
             ignore_specs: ignore_specs,
+            strict_mod_sets: strict_mod_sets,
+            mem_safety_regions: mem_safety_regions,
+            stack_region: stack_region,
+            abi_exemptions: abi_exemptions,
+            solver_config,
             // Context
-            labels_to_addr: Translator::<I>::create_label_to_addr_map(bbs),
+            labels_to_addr,
             cfg_memo: HashMap::new(),
             generated: HashSet::new(),
             mod_set_map: HashMap::new(),
+            ghost_updates,
+            stack_analyzer: RefCell::new(StackAnalyzer::new(bbs, dwarf_ctx)),
             _phantom_i: PhantomData,
         }
     }
@@ -130,17 +204,192 @@ where
         label_to_addr
     }
 
+    /// Returns a map of instruction addresses to the ghost updates executed before the
+    /// instruction. The updates without an address are executed at the entry of their function
+    fn ghost_updates(
+        xlen: u64,
+        bbs: &HashMap<u64, Rc<cfg::BasicBlock<disassembler::AssemblyLine>>>,
+        dwarf_ctx: &DwarfCtx,
+        specs_map: &HashMap<String, Vec<sl_ast::Spec>>,
+        ghosts: &[sl_ast::Ghost],
+        labels_to_addr: &HashMap<String, u64>,
+    ) -> HashMap<u64, Vec<Stmt>> {
+        let mut ghost_updates: HashMap<u64, Vec<Stmt>> = HashMap::new();
+        for (func_name, specs) in specs_map {
+            for spec in specs {
+                if let sl_ast::Spec::Update(name, vexpr, addr, _) = spec {
+                    let addr = match addr.or_else(|| labels_to_addr.get(func_name).copied()) {
+                        Some(addr) => addr,
+                        // The function is not in the binaries
+                        None => continue,
+                    };
+                    let is_inst = bbs.values().any(|bb| {
+                        bb.insts()
+                            .iter()
+                            .any(|al| al.address() == addr && al.function_name() == func_name)
+                    });
+                    if !is_inst {
+                        panic!(
+                            "[main] The update of the ghost variable {} is at {:#x}, which is not an instruction of {}.",
+                            name, addr, func_name
+                        );
+                    }
+                    let ghost = ghosts
+                        .iter()
+                        .find(|ghost| ghost.name == *name)
+                        .unwrap_or_else(|| panic!("Implementation error; {} is not a ghost variable.", name));
+                    let lhs = Expr::var(name, ghost.typ.to_ast_type());
+                    let rhs = Self::spec_to_ir_expr(vexpr, xlen, dwarf_ctx, &HashMap::new());
+                    ghost_updates
+                        .entry(addr)
+                        .or_default()
+                        .push(Stmt::assign(vec![lhs], vec![rhs]));
+                }
+            }
+        }
+        ghost_updates
+    }
+
+    /// Returns the VERI-V IR expression of a ghost update or tracked value with the formal
+    /// arguments replaced by `args`. Struct fields are addresses like in the specifications
+    fn spec_to_ir_expr(
+        vexpr: &sl_ast::VExpr,
+        xlen: u64,
+        dwarf_ctx: &DwarfCtx,
+        args: &HashMap<String, Expr>,
+    ) -> Expr {
+        let to_ir = |vexpr: &sl_ast::VExpr| Self::spec_to_ir_expr(vexpr, xlen, dwarf_ctx, args);
+        match vexpr {
+            sl_ast::VExpr::Bv { value, typ } => match typ {
+                sl_ast::VType::Bv(width) => Expr::bv_lit(*value, *width as u64),
+                // Address of a global variable
+                _ => Expr::bv_lit(*value, xlen),
+            },
+            sl_ast::VExpr::Int(i, _) => Expr::int_lit(*i as u64),
+            sl_ast::VExpr::Bool(b, _) => Expr::bool_lit(*b),
+            sl_ast::VExpr::Ident(name, typ) => match args.get(name) {
+                Some(arg) => arg.clone(),
+                None => Expr::var(name, Self::spec_ir_type(typ, xlen)),
+            },
+            sl_ast::VExpr::OpApp(op, vexprs, typ) => {
+                let bvop = match op {
+                    sl_ast::ValueOp::Add => BVOp::Add,
+                    sl_ast::ValueOp::Sub => BVOp::Sub,
+                    sl_ast::ValueOp::Mul => BVOp::Mul,
+                    sl_ast::ValueOp::BvAnd => BVOp::And,
+                    sl_ast::ValueOp::BvOr => BVOp::Or,
+                    sl_ast::ValueOp::BvXor => BVOp::Xor,
+                    sl_ast::ValueOp::LeftShift => BVOp::LeftShift,
+                    sl_ast::ValueOp::RightShift => BVOp::ARightShift,
+                    sl_ast::ValueOp::URightShift => BVOp::RightShift,
+                    sl_ast::ValueOp::Concat => BVOp::Concat,
+                    sl_ast::ValueOp::Slice { lo, hi } => BVOp::Slice {
+                        l: *hi as u64,
+                        r: *lo as u64,
+                    },
+                    sl_ast::ValueOp::Deref => {
+                        let addr = match &vexprs[0] {
+                            // Address of a global variable
+                            sl_ast::VExpr::Bv { value, .. } => Expr::bv_lit(*value, xlen),
+                            vexpr => to_ir(vexpr),
+                        };
+                        return match typ.get_bv_width() {
+                            8 => system_model::load_byte(addr, xlen),
+                            16 => system_model::load_half(addr, xlen),
+                            32 => system_model::load_word(addr, xlen),
+                            64 => system_model::load_double(addr, xlen),
+                            w => panic!("Implementation error; cannot dereference {} bits.", w),
+                        };
+                    }
+                    sl_ast::ValueOp::ArrayIndex => {
+                        // Address of the element
+                        let base = to_ir(&vexprs[0]);
+                        let index = to_ir(&vexprs[1]);
+                        let bytes = vexprs[0].typ().get_array_out_type_size() / constants::BYTE_SIZE;
+                        let offset = Expr::op_app(Op::Bv(BVOp::Mul), vec![index, Expr::bv_lit(bytes, xlen)]);
+                        return Expr::op_app(Op::Bv(BVOp::Add), vec![base, offset]);
+                    }
+                    sl_ast::ValueOp::GetField => {
+                        // Address of the field
+                        let struct_id = match vexprs[0].typ() {
+                            sl_ast::VType::Struct { id, .. } => id,
+                            _ => panic!("Expected struct type for {:?}.", vexprs[0]),
+                        };
+                        let field = vexprs[1].get_ident_name();
+                        let offset = dwarf_ctx.field_offset(struct_id, field).unwrap_or_else(|| {
+                            panic!("Unable to find the offset of {}.{}.", struct_id, field)
+                        });
+                        let base = to_ir(&vexprs[0]);
+                        return Expr::op_app(Op::Bv(BVOp::Add), vec![base, Expr::bv_lit(offset, xlen)]);
+                    }
+                    sl_ast::ValueOp::Div => {
+                        panic!("Implementation error; {:?} is not supported in the model.", op)
+                    }
+                };
+                let operands = vexprs.iter().map(to_ir).collect();
+                Expr::OpApp(OpApp { op: Op::Bv(bvop), operands }, Self::spec_ir_type(typ, xlen))
+            }
+            sl_ast::VExpr::FuncApp(fname, vexprs, typ) => {
+                let bvop = match &fname[..] {
+                    "sext" => BVOp::SignExt,
+                    "uext" => BVOp::ZeroExt,
+                    _ => panic!("Implementation error; {} is not supported in the model.", fname),
+                };
+                let operands = vec![to_ir(&vexprs[1]), to_ir(&vexprs[0])];
+                Expr::OpApp(OpApp { op: Op::Bv(bvop), operands }, Self::spec_ir_type(typ, xlen))
+            }
+        }
+    }
+
+    /// Returns the VERI-V IR type of a specification value. Structs and arrays are addresses
+    fn spec_ir_type(typ: &sl_ast::VType, xlen: u64) -> Type {
+        match typ {
+            sl_ast::VType::Struct { .. } | sl_ast::VType::Array { .. } => system_model::bv_type(xlen),
+            _ => typ.to_ast_type(),
+        }
+    }
+
+    /// Returns the verification model
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    /// Replaces the verification model, e.g. with one loaded from a cache
+    pub fn set_model(&mut self, model: Model) {
+        self.model = model;
+    }
+
     // =============================================================================
     // Helper functions
 
     /// Returns the string representation of the model
     pub fn print_model(&self) -> String {
-        I::model_to_string(
+        self.print_model_with::<I>()
+    }
+
+    /// Returns the string representation of the model in the verification language of `J`
+    pub fn print_model_with<J: IRInterface>(&self) -> String {
+        let mut buf = vec![];
+        self.write_model_with::<J>(&mut buf)
+            .expect("Unable to write the model to a buffer.");
+        String::from_utf8(buf).expect("The model should be valid UTF-8.")
+    }
+
+    /// Writes the model to `out` as it is generated
+    pub fn write_model(&self, out: &mut dyn Write) -> io::Result<()> {
+        self.write_model_with::<I>(out)
+    }
+
+    /// Writes the model in the verification language of `J` to `out` as it is generated
+    pub fn write_model_with<J: IRInterface>(&self, out: &mut dyn Write) -> io::Result<()> {
+        J::write_model(
+            out,
             &self.xlen,
             &self.model,
             &self.dwarf_ctx,
             &self.ignored_funcs,
             &self.verify_funcs,
+            &self.solver_config,
         )
     }
 
@@ -154,6 +403,7 @@ where
                 in_typ,
                 out_typ,
                 bytes: _,
+                len: _,
             } => Type::Array {
                 in_typs: vec![Box::new(Self::to_ir_type(in_typ))],
                 out_typ: Box::new(Self::to_ir_type(out_typ)),
@@ -190,7 +440,58 @@ where
                 Expr::var(&var_name, system_model::bv_type(self.xlen))
             })
             .collect();
-        let mod_set = self.mod_set_from_spec_map(func_name);
+        let entry_addr = *self
+            .func_entry_addr(func_name)
+            .expect(&format!("Unable to find {}'s entry address.", func_name));
+        // The stub records the tracked values at its entry and exit, executes the ghost
+        // updates at its entry and clobbers the caller-saved registers (including the return value)
+        let mut body = system_model::havoc_caller_saved_regs(self.xlen);
+        let entry_obs = self.track_observations(func_name, &HashMap::new(), "entry");
+        let exit_obs = self.track_observations(func_name, &HashMap::new(), "exit");
+        let updates = self.ghost_updates.get(&entry_addr).cloned().unwrap_or_default();
+        if !entry_obs.is_empty() || !updates.is_empty() {
+            let stmts = entry_obs
+                .into_iter()
+                .chain(updates)
+                .chain(vec![body])
+                .chain(exit_obs)
+                .map(Box::new)
+                .collect::<Vec<_>>();
+            body = Stmt::Block(stmts);
+        }
+        self.model.add_vars(&Self::assigned_vars(&body));
+        let inner_updates = self.specs_map.get(func_name).is_some_and(|specs| {
+            specs.iter().any(|spec| {
+                matches!(spec, sl_ast::Spec::Update(_, _, Some(addr), _) if *addr != entry_addr)
+            })
+        });
+        if inner_updates {
+            warn!(
+                "The ghost updates inside the ignored function {} are not executed.",
+                func_name
+            );
+        }
+        let havoc_vars = system_model::caller_saved_reg_vars(self.xlen);
+        self.model.add_vars(&havoc_vars.iter().cloned().collect());
+        // Conservatively infer the modifies set from the disassembly
+        let mut mod_set = self.infer_stub_mod_set(func_name, &mut HashSet::new());
+        mod_set = mod_set.union(&self.infer_mod_set(&body)).cloned().collect();
+        // The stub returns to the caller
+        if self.abi_exemptions.is_some() {
+            mod_set.insert(constants::PC_VAR.to_string());
+        }
+        match self.mod_set_from_spec_map(func_name) {
+            Some(spec_mod_set) => {
+                self.check_spec_mod_set(func_name, &spec_mod_set, &mod_set);
+                mod_set = mod_set.union(&spec_mod_set).cloned().collect();
+            }
+            None => warn!(
+                "No modifies clause for the ignored function {}; using the inferred modifies set.",
+                func_name
+            ),
+        }
+        self.mod_set_map
+            .insert(func_name.to_string(), mod_set.clone());
         let requires = if !self.ignore_specs {
             self.requires_from_spec_map(func_name)
         } else {
@@ -201,11 +502,10 @@ where
         } else {
             None
         };
+        // Callers assume the stub follows the calling convention
+        let ensures = self.with_abi_ensures(func_name, ensures);
         let tracked = self.tracked_from_spec_map(func_name);
         let ret = None;
-        let entry_addr = *self
-            .func_entry_addr(func_name)
-            .expect(&format!("Unable to find {}'s entry address.", func_name));
         let stub_fm = FuncModel::new(
             func_name,
             entry_addr,
@@ -214,8 +514,8 @@ where
             requires,
             ensures,
             tracked,
-            mod_set,
-            Stmt::Block(vec![]),
+            Some(mod_set),
+            body,
             false,
         );
         self.model.add_func_model(stub_fm);
@@ -306,26 +606,43 @@ where
             }
         }
         // Add callee modifies set to this function's modifies set
+        // (stubs of ignored functions are added to mod_set_map when they are generated)
         for (target, _) in &callees {
             if let Some(name) = self.get_func_at(target) {
-                if !self.ignored_funcs.contains(&name[..]) {
+                // Recursive calls do not add to the modifies set
+                if name == func_name {
                     continue;
                 }
-                if self.ignored_funcs.get(&name[..]).is_some() {
-                    if let Some(ms) = self.mod_set_from_spec_map(func_name) {
-                        mod_set = mod_set.union(&ms).cloned().collect();
-                    } else {
-                        // FIXME: Warn that we haven't provided a modifies set here?
-                    }
-                } else {
-                    let callee_ms = self
-                        .mod_set_map
-                        .get(&name)
-                        .expect(&format!("Unable to find modifies set for {}.", name));
-                    mod_set = mod_set.union(callee_ms).cloned().collect();
-                }
+                let callee_ms = self
+                    .mod_set_map
+                    .get(&name)
+                    .expect(&format!("Unable to find modifies set for {}.", name));
+                mod_set = mod_set.union(callee_ms).cloned().collect();
+            }
+        }
+
+        // Pointer argument ghost variables are set by the function body
+        let mem_safety_args = self.mem_safety_args(func_name);
+        for (i, _) in &mem_safety_args {
+            let ghost_var = Var {
+                name: helpers::mem_safety_arg_name(*i),
+                typ: system_model::bv_type(self.xlen),
+            };
+            mod_set.insert(ghost_var.name.clone());
+            self.model.add_var(ghost_var);
+        }
+        // Callers cannot rely on the caller-saved registers after the call
+        if self.abi_exemptions.is_some() {
+            for var in system_model::caller_saved_reg_vars(self.xlen) {
+                mod_set.insert(var.name.clone());
+                self.model.add_var(var);
             }
         }
+        // The procedure body records the tracked values of the function and its callees
+        let body = self.cfg_to_symbolic_blk(&func_entry, &func_cfg);
+        let body_vars = Self::assigned_vars(&body);
+        mod_set.extend(body_vars.iter().map(|var| var.name.clone()));
+        self.model.add_vars(&body_vars);
 
         // ================= Create function model ============================
         // Memo current mod set
@@ -351,9 +668,12 @@ where
         } else {
             None
         };
+        let requires = match self.stack_requires(func_name) {
+            Some(stack_req) => Some(requires.unwrap_or(vec![]).into_iter().chain(vec![stack_req]).collect()),
+            None => requires,
+        };
+        let ensures = self.with_abi_ensures(func_name, ensures);
         let tracked = self.tracked_from_spec_map(func_name);
-        // Create the procedure body
-        let body = self.cfg_to_symbolic_blk(&func_entry, &func_cfg);
         // Add the function to the verification model
         self.model.add_func_model(FuncModel::new(
             func_name,
@@ -369,6 +689,143 @@ where
         ));
     }
 
+    /// Generates the whole-machine transition system from the generated models.
+    /// Each step executes the basic block at `pc` (or the stub of an ignored function
+    /// at once) and the machine starts at the entry of one of the functions `entries`
+    pub fn gen_transition_system(&mut self, entries: &[&str], machine_specs: &[sl_ast::MachineSpec]) {
+        let pc = system_model::pc_expr(self.xlen);
+        self.model.add_var(Var {
+            name: constants::RA.to_string(),
+            typ: system_model::bv_type(self.xlen),
+        });
+        // The pc and return address before the step
+        let step_pc = Expr::var(constants::STEP_PC_ARG, system_model::bv_type(self.xlen));
+        let step_ra = Expr::var(constants::STEP_RA_ARG, system_model::bv_type(self.xlen));
+        // Guarded calls by the entry address of the block
+        let mut cases = vec![];
+        let mut mod_set = HashSet::new();
+        mod_set.insert(constants::PC_VAR.to_string());
+        mod_set.insert(constants::RETURNED_FLAG.to_string());
+        // The blocks of the ignored functions are replaced by their stubs
+        let in_ignored_func = |addr: &u64| {
+            self.bbs
+                .get(addr)
+                .is_some_and(|bb| self.ignored_funcs.contains(bb.entry().function_name()))
+        };
+        for fm in &self.model.func_models {
+            let entry = fm.sig.entry_addr;
+            let stmts = if self.ignored_funcs.contains(&fm.sig.name[..]) {
+                // The stub of an ignored function takes its arguments from the argument
+                // registers and returns to the caller
+                let args = fm
+                    .sig
+                    .arg_decls
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arg)| Expr::var(&format!("a{}", i), arg.typ().clone()))
+                    .collect();
+                vec![
+                    Stmt::func_call(fm.sig.name.clone(), vec![], args),
+                    Stmt::assign(vec![pc.clone()], vec![step_ra.clone()]),
+                ]
+            } else if fm.sig.name == self.bb_proc_name(entry) && !in_ignored_func(&entry) {
+                vec![Stmt::func_call(fm.sig.name.clone(), vec![], vec![])]
+            } else {
+                continue;
+            };
+            mod_set.extend(fm.sig.mod_set.iter().cloned());
+            cases.push((entry, stmts));
+        }
+        cases.sort_by_key(|(entry, _)| *entry);
+        // The block procedures require that the caller has not returned
+        let mut body = vec![Box::new(Stmt::assign(
+            vec![Expr::var(constants::RETURNED_FLAG, system_model::bv_type(1))],
+            vec![Expr::bv_lit(0, 1)],
+        ))];
+        // The guards refer to the pc before the step so that only one block is executed.
+        // The machine stutters at addresses without a block
+        body.extend(cases.into_iter().map(|(entry, stmts)| {
+            let guard = Expr::op_app(
+                Op::Comp(CompOp::Equality),
+                vec![step_pc.clone(), Expr::bv_lit(entry, self.xlen)],
+            );
+            let then_blk_stmt = Stmt::Block(stmts.into_iter().map(Box::new).collect());
+            Box::new(Stmt::if_then_else(guard, Box::new(then_blk_stmt), None))
+        }));
+        self.model.add_func_model(FuncModel::new(
+            constants::STEP_PROC,
+            0,
+            vec![step_pc, step_ra],
+            None,
+            None,
+            None,
+            None,
+            Some(mod_set),
+            Stmt::Block(body),
+            true,
+        ));
+        // Initially, the pc is at the entry of one of the functions
+        let pc_typ = sl_ast::VType::Bv(self.xlen as u16);
+        let init_pc = entries
+            .iter()
+            .map(|func_name| {
+                let entry_addr = *self
+                    .func_entry_addr(func_name)
+                    .unwrap_or_else(|| panic!("Unable to find {}'s entry address.", func_name));
+                sl_ast::BExpr::COpApp(
+                    sl_ast::CompOp::Equal,
+                    vec![
+                        sl_ast::VExpr::Ident(constants::PC_VAR.to_string(), pc_typ.clone()),
+                        sl_ast::VExpr::Bv { value: entry_addr, typ: pc_typ.clone() },
+                    ],
+                )
+            })
+            .reduce(|lhs, rhs| sl_ast::BExpr::BOpApp(sl_ast::BoolOp::Disj, vec![lhs, rhs]))
+            .expect("The transition system needs at least one function.");
+        // The ghost variables start at their initial values
+        let init_ghosts = self.ghosts.iter().filter_map(|ghost| {
+            let init = ghost.init.clone()?;
+            let ghost_var = sl_ast::VExpr::Ident(ghost.name.clone(), ghost.typ.clone());
+            let bexpr = sl_ast::BExpr::COpApp(sl_ast::CompOp::Equal, vec![ghost_var, init]);
+            Some(sl_ast::MachineSpec::Init(bexpr, ghost.loc.clone()))
+        });
+        let specs = std::iter::once(sl_ast::MachineSpec::Init(init_pc, None))
+            .chain(init_ghosts)
+            .chain(machine_specs.iter().cloned())
+            .collect();
+        self.model.transition = Some(TransitionSystem {
+            step: FuncCall {
+                func_name: constants::STEP_PROC.to_string(),
+                lhs: vec![],
+                operands: vec![pc, Expr::var(constants::RA, system_model::bv_type(self.xlen))],
+            },
+            specs,
+        });
+    }
+
+    /// Returns the variables assigned by the statement (not by the procedures it calls)
+    fn assigned_vars(stmt: &Stmt) -> HashSet<Var> {
+        match stmt {
+            Stmt::Assign(a) => a
+                .lhs
+                .iter()
+                .filter_map(|e| match e {
+                    Expr::Var(v, _) => Some(v.clone()),
+                    _ => None,
+                })
+                .collect(),
+            Stmt::IfThenElse(ite) => {
+                let mut vars = Self::assigned_vars(&ite.then_stmt);
+                if let Some(else_stmt) = &ite.else_stmt {
+                    vars.extend(Self::assigned_vars(else_stmt));
+                }
+                vars
+            }
+            Stmt::Block(stmts) => stmts.iter().flat_map(|stmt| Self::assigned_vars(stmt)).collect(),
+            _ => HashSet::new(),
+        }
+    }
+
     /// Returns the inferred modifies set
     fn infer_mod_set(&self, stmt: &Stmt) -> HashSet<String> {
         let mut mod_set = HashSet::new();
@@ -408,6 +865,9 @@ where
                     .collect::<HashSet<String>>();
                 mod_set = mod_set.union(&lhs_mod_set).cloned().collect();
             }
+            Stmt::Havoc(v) => {
+                mod_set.insert(v.name.clone());
+            }
             Stmt::IfThenElse(ite) => {
                 let then_mod_set = self.infer_mod_set(&ite.then_stmt);
                 mod_set = mod_set.union(&then_mod_set).cloned().collect();
@@ -429,6 +889,363 @@ where
         mod_set
     }
 
+    /// Returns a conservative modifies set of the function inferred from its disassembly
+    /// and the modifies sets of its callees without translating the function body.
+    /// When the calling-convention contracts are enabled, the callee-saved registers are
+    /// left out since the contracts require the function to restore them (unless it is
+    /// exempted from them); the prologue and epilogue would otherwise add them to every
+    /// inferred set and callers would lose `sp` and the frame pointer.
+    fn infer_stub_mod_set(&mut self, func_name: &str, visiting: &mut HashSet<String>) -> HashSet<String> {
+        let mut mod_set = HashSet::new();
+        mod_set.insert(constants::PC_VAR.to_string());
+        mod_set.insert(constants::RETURNED_FLAG.to_string());
+        if !visiting.insert(func_name.to_string()) {
+            return mod_set;
+        }
+        let func_entry = *self
+            .func_entry_addr(func_name)
+            .expect(&format!("Unable to find {}'s entry address.", func_name));
+        let func_cfg = self.get_func_cfg(func_entry);
+        self.model.add_vars(&self.infer_vars(&func_cfg));
+        // Registers, CSRs and memory written by the instructions of the function
+        for (_, cfg_node) in func_cfg.nodes() {
+            for al in cfg_node.into_iter() {
+                if al.function_name() != func_name {
+                    continue;
+                }
+                mod_set = mod_set.union(&Self::inst_mod_set(&al)).cloned().collect();
+                for update in self.ghost_updates.get(&al.address()).into_iter().flatten() {
+                    mod_set = mod_set.union(&self.infer_mod_set(update)).cloned().collect();
+                }
+            }
+        }
+        // Modifies sets of the callees
+        for (target, _) in self.get_callee_addrs(func_name, &func_cfg) {
+            if let Some(name) = self.get_func_at(&target) {
+                let callee_ms = match self.mod_set_map.get(&name) {
+                    Some(callee_ms) => callee_ms.clone(),
+                    None => self.infer_stub_mod_set(&name, visiting),
+                };
+                mod_set = mod_set.union(&callee_ms).cloned().collect();
+            }
+        }
+        if let Some(exemptions) = &self.abi_exemptions {
+            let exempt = exemptions.get(func_name);
+            mod_set.retain(|v| {
+                !constants::CALLEE_SAVED_REGS.contains(&&v[..])
+                    || exempt.is_some_and(|clauses| clauses.contains(v))
+            });
+        }
+        mod_set
+    }
+
+    /// Returns the variables written by the instruction
+    fn inst_mod_set(al: &Rc<disassembler::AssemblyLine>) -> HashSet<String> {
+        let mut mod_set = HashSet::new();
+        for reg_op in [al.rd(), al.csr()].iter() {
+            if let Some(reg) = reg_op {
+                mod_set.insert(reg.get_reg_name());
+            }
+        }
+        let op = al.op();
+        let mem_var = match op {
+            "sb" => Some(constants::MEM_VAR_B),
+            "sh" => Some(constants::MEM_VAR_H),
+            "sw" => Some(constants::MEM_VAR_W),
+            "sd" => Some(constants::MEM_VAR_D),
+            _ if (op.starts_with("amo") || op.starts_with("sc.")) && op.contains(".w") => {
+                Some(constants::MEM_VAR_W)
+            }
+            _ if (op.starts_with("amo") || op.starts_with("sc.")) && op.contains(".d") => {
+                Some(constants::MEM_VAR_D)
+            }
+            _ => None,
+        };
+        if let Some(mem_var) = mem_var {
+            mod_set.insert(mem_var.to_string());
+        }
+        mod_set
+    }
+
+    /// Warns (or panics in strict mode) if the `modifies` clause of an ignored function
+    /// is missing variables that the function writes. Registers that are saved and restored
+    /// according to the calling convention, the caller-saved registers, the ghost variables
+    /// updated by the specifications and the tracked values are not reported.
+    fn check_spec_mod_set(
+        &self,
+        func_name: &str,
+        spec_mod_set: &HashSet<String>,
+        inferred_mod_set: &HashSet<String>,
+    ) {
+        let observed = ["entry", "exit"]
+            .iter()
+            .flat_map(|point| self.track_observations(func_name, &HashMap::new(), point))
+            .flat_map(|stmt| Self::assigned_vars(&stmt))
+            .map(|var| var.name)
+            .collect::<HashSet<_>>();
+        let mut missing = inferred_mod_set
+            .difference(spec_mod_set)
+            .filter(|v| {
+                *v != constants::PC_VAR
+                    && *v != constants::RETURNED_FLAG
+                    && *v != "zero"
+                    && !constants::CALLER_SAVED_REGS.contains(&&v[..])
+                    && !constants::CALLEE_SAVED_REGS.contains(&&v[..])
+                    && !self.ghosts.iter().any(|ghost| ghost.name == **v)
+                    && !observed.contains(*v)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return;
+        }
+        missing.sort();
+        let msg = format!(
+            "The modifies clause of {} is missing {} written by the function.",
+            func_name,
+            missing.join(", ")
+        );
+        if self.strict_mod_sets {
+            panic!("{}", msg);
+        } else {
+            warn!("{}", msg);
+        }
+    }
+
+    /// Returns the memory regions (base address expression, size in bytes) that the
+    /// instructions of the function may access: its stack frame and stack-passed arguments,
+    /// the DWARF global variables, the objects its pointer arguments point to and the
+    /// user-declared regions.
+    /// Returns None if memory-safety instrumentation is disabled.
+    fn mem_safety_regions(&self, func_name: &str) -> Option<Vec<(Expr, u64)>> {
+        let user_regions = self.mem_safety_regions.as_ref()?;
+        let mut regions = vec![];
+        // Stack frame allocated by the function and the arguments passed above it
+        let frame_size = self.stack_analyzer.borrow_mut().frame_info(func_name).frame_size;
+        let stack_size = frame_size + self.stack_args_size(func_name);
+        if stack_size > 0 {
+            regions.push((Expr::var(constants::SP, system_model::bv_type(self.xlen)), stack_size));
+        }
+        // Global variables
+        for var in self.dwarf_ctx.global_vars() {
+            if let Some(size) = var.typ_defn.object_size() {
+                regions.push((Expr::bv_lit(var.memory_addr, self.xlen), size));
+            }
+        }
+        // Objects pointed to by the arguments
+        for (i, size) in self.mem_safety_args(func_name) {
+            let base = Expr::var(&helpers::mem_safety_arg_name(i), system_model::bv_type(self.xlen));
+            regions.push((base, size));
+        }
+        // User-declared regions
+        for (base, size) in user_regions {
+            regions.push((Expr::bv_lit(*base, self.xlen), *size));
+        }
+        Some(regions)
+    }
+
+    /// Returns the size in bytes of the arguments of the function passed on the caller's
+    /// stack (the ones after a0-a7), assuming that each takes one `xlen`-bit slot.
+    /// Variadic arguments are not included since the DWARF signature does not list them
+    fn stack_args_size(&self, func_name: &str) -> u64 {
+        self.dwarf_ctx.func_sig(func_name).map_or(0, |fs| {
+            fs.args.len().saturating_sub(8) as u64 * (self.xlen / 8)
+        })
+    }
+
+    /// Returns the indices of the pointer arguments (passed in a0-a7) of the function
+    /// and the sizes of the objects they point to
+    fn mem_safety_args(&self, func_name: &str) -> Vec<(usize, u64)> {
+        if self.mem_safety_regions.is_none() {
+            return vec![];
+        }
+        self.dwarf_ctx
+            .func_sig(func_name)
+            .map_or(vec![], |fs| {
+                fs.args
+                    .iter()
+                    .enumerate()
+                    .take(8)
+                    .filter_map(|(i, arg)| match &*arg.typ_defn {
+                        DwarfTypeDefn::Pointer { value_typ, .. } => {
+                            value_typ.object_size().map(|size| (i, size))
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            })
+    }
+
+    /// Returns a statement that sets the pointer argument ghost variables to
+    /// the (immutable) arguments of the function procedure
+    fn mem_safety_arg_stmt(&self, func_name: &str) -> Option<Stmt> {
+        let args = self.mem_safety_args(func_name);
+        if args.is_empty() {
+            return None;
+        }
+        let func_args = self.func_args(func_name);
+        let (lhss, rhss) = args
+            .iter()
+            .map(|(i, _)| {
+                (
+                    Expr::var(&helpers::mem_safety_arg_name(*i), system_model::bv_type(self.xlen)),
+                    Expr::var(&func_args[*i].get_var_name(), system_model::bv_type(self.xlen)),
+                )
+            })
+            .unzip();
+        Some(Stmt::assign(lhss, rhss))
+    }
+
+    /// Returns the precondition that the stack region has enough space below `sp`
+    /// for the worst-case stack depth of the function, or None if the depth is unbounded.
+    /// Panics if the depth does not fit in the stack region.
+    fn stack_requires(&self, func_name: &str) -> Option<sl_ast::Spec> {
+        let (base, size) = self.stack_region?;
+        let depth = self.stack_analyzer.borrow_mut().stack_depth(func_name);
+        if !depth.is_bounded() {
+            for reason in &depth.unbounded {
+                warn!("Stack depth of {} is not bounded: {}.", func_name, reason);
+            }
+            warn!("No stack requires clause is generated for {}.", func_name);
+            return None;
+        }
+        if depth.bytes > size {
+            panic!(
+                "The stack depth of {} ({} bytes) exceeds the stack region of {} bytes.",
+                func_name, depth.bytes, size
+            );
+        }
+        let (lower_bound, upper_bound) = match (base.checked_add(depth.bytes), base.checked_add(size)) {
+            (Some(lower_bound), Some(upper_bound)) => (lower_bound, upper_bound),
+            _ => panic!("The stack region {:#x}:{:#x} exceeds the address space.", base, size),
+        };
+        let bv = |value| sl_ast::VExpr::Bv {
+            value,
+            typ: sl_ast::VType::Bv(self.xlen as u16),
+        };
+        let sp = sl_ast::VExpr::Ident(constants::SP.to_string(), sl_ast::VType::Bv(self.xlen as u16));
+        let lower = sl_ast::BExpr::COpApp(sl_ast::CompOp::Geu, vec![sp.clone(), bv(lower_bound)]);
+        let upper = sl_ast::BExpr::COpApp(sl_ast::CompOp::Leu, vec![sp, bv(upper_bound)]);
+        Some(sl_ast::Spec::Requires(
+            sl_ast::BExpr::BOpApp(sl_ast::BoolOp::Conj, vec![lower, upper]),
+            None,
+        ))
+    }
+
+    /// Returns an assertion of each requires clause of the callee at the call instruction
+    /// at `addr`, preceded by a comment with the clause's location and the call address
+    fn call_site_obligations(&self, callee: &str, args: &[Expr], addr: u64) -> Vec<Stmt> {
+        let requires = if !self.ignore_specs {
+            self.requires_from_spec_map(callee).unwrap_or_default()
+        } else {
+            vec![]
+        };
+        let mut stmts = vec![];
+        for (i, spec) in requires.iter().enumerate() {
+            let clause = spec.loc().map_or(format!("#{}", i), |loc| loc.to_string());
+            stmts.push(Stmt::Comment(format!(
+                "{}requires {} of {} at call {}",
+                OBLIGATION_PREFIX,
+                clause,
+                callee,
+                self.addr_str(addr)
+            )));
+            let check = Expr::func_app(requires_macro_name(callee, i), args.to_vec(), Type::Bool);
+            stmts.push(Stmt::Assert(check));
+        }
+        stmts
+    }
+
+    /// Returns the assignments that record the tracked values of the function `func_name`
+    /// at `point` (`entry`, `exit` or a call site) with the formal arguments replaced by `args`
+    fn track_observations(&self, func_name: &str, args: &HashMap<String, Expr>, point: &str) -> Vec<Stmt> {
+        let tracked = self.tracked_from_spec_map(func_name).unwrap_or_default();
+        tracked
+            .iter()
+            .filter_map(|spec| match spec {
+                sl_ast::Spec::Track(id, vexpr, _) => {
+                    let name = helpers::track_var_name(func_name, id, point);
+                    let lhs = Expr::var(&name, Self::spec_ir_type(vexpr.typ(), self.xlen));
+                    let rhs = Self::spec_to_ir_expr(vexpr, self.xlen, self.dwarf_ctx, args);
+                    Some(Stmt::assign(vec![lhs], vec![rhs]))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the calling-convention postconditions of the function that are not exempted:
+    /// `pc == old(ra)[xlen-1:1] ++ 0bv1` and `r == old(r)` for each preserved register `r`
+    fn abi_ensures(&self, func_name: &str) -> Vec<sl_ast::Spec> {
+        let exemptions = match &self.abi_exemptions {
+            Some(exemptions) => exemptions,
+            None => return vec![],
+        };
+        let exempt = |clause: &str| {
+            exemptions
+                .get(func_name)
+                .map_or(false, |clauses| clauses.contains(clause))
+        };
+        let typ = sl_ast::VType::Bv(self.xlen as u16);
+        let reg = |name: &str| sl_ast::VExpr::Ident(name.to_string(), typ.clone());
+        let old = |name: &str| sl_ast::VExpr::FuncApp("old".to_string(), vec![reg(name)], typ.clone());
+        let eq = |lhs, rhs| {
+            sl_ast::Spec::Ensures(sl_ast::BExpr::COpApp(sl_ast::CompOp::Equal, vec![lhs, rhs]), None)
+        };
+        let mut ensures = vec![];
+        if !exempt(constants::RA) {
+            let hi = (self.xlen - 1) as u16;
+            let ra_hi = sl_ast::VExpr::OpApp(
+                sl_ast::ValueOp::Slice { lo: 1, hi },
+                vec![old(constants::RA)],
+                sl_ast::VType::Bv(hi),
+            );
+            let ret_addr = sl_ast::VExpr::OpApp(
+                sl_ast::ValueOp::Concat,
+                vec![ra_hi, sl_ast::VExpr::Bv { value: 0, typ: sl_ast::VType::Bv(1) }],
+                typ.clone(),
+            );
+            ensures.push(eq(reg(constants::PC_VAR), ret_addr));
+        }
+        for name in constants::abi_preserved_regs() {
+            if !exempt(name) {
+                ensures.push(eq(reg(name), old(name)));
+            }
+        }
+        ensures
+    }
+
+    /// Appends the calling-convention postconditions of the function to the ensures
+    fn with_abi_ensures(&self, func_name: &str, ensures: Option<Vec<sl_ast::Spec>>) -> Option<Vec<sl_ast::Spec>> {
+        let abi_ensures = self.abi_ensures(func_name);
+        if abi_ensures.is_empty() {
+            return ensures;
+        }
+        Some(ensures.unwrap_or(vec![]).into_iter().chain(abi_ensures).collect())
+    }
+
+    /// Returns an assertion that `sp` is inside the stack region if the instruction writes `sp`
+    fn stack_bound_assert(&self, al: &Rc<disassembler::AssemblyLine>) -> Option<Stmt> {
+        let (base, size) = self.stack_region?;
+        if al.rd().map(|rd| rd.get_reg_name()) != Some(constants::SP.to_string()) {
+            return None;
+        }
+        let sp = Expr::var(constants::SP, system_model::bv_type(self.xlen));
+        let lower = Expr::op_app(Op::Comp(CompOp::Geu), vec![sp.clone(), Expr::bv_lit(base, self.xlen)]);
+        let upper = Expr::op_app(
+            Op::Comp(CompOp::Leu),
+            vec![sp, Expr::bv_lit(base + size, self.xlen)],
+        );
+        Some(Stmt::Block(vec![
+            Box::new(Stmt::Comment(format!(
+                "stack bound: {} at {}",
+                al.op(),
+                self.addr_str(al.address())
+            ))),
+            Box::new(Stmt::Assert(Expr::op_app(Op::Bool(BoolOp::Conj), vec![lower, upper]))),
+        ]))
+    }
+
     /// Returns a block statement for the CFG
     fn cfg_to_symbolic_blk(
         &self,
@@ -436,11 +1253,21 @@ where
         cfg_rc: &Rc<cfg::Cfg<disassembler::AssemblyLine>>,
     ) -> Stmt {
         let mut stmts_vec = vec![];
+        // Record the pointer arguments for the memory-safety assertions
+        let func_name = self
+            .get_func_at(func_entry_addr)
+            .expect(&format!("Could not find function entry at {}.", self.addr_str(*func_entry_addr)));
+        let mem_safety_arg_stmt = self.mem_safety_arg_stmt(&func_name);
+        if let Some(stmt) = &mem_safety_arg_stmt {
+            stmts_vec.push(Box::new(stmt.clone()));
+        }
+        let entry_obs = self.track_observations(&func_name, &HashMap::new(), "entry");
+        stmts_vec.extend(entry_obs.into_iter().map(Box::new));
         let sorted_entries = self.topo_sort(cfg_rc);
         for bb_entry in sorted_entries {
             let cfg_node = cfg_rc.nodes().get(&bb_entry).expect(&format!(
                 "Unable to find CFG node with entry address {}.",
-                bb_entry
+                self.addr_str(bb_entry)
             ));
             // Skip basic blocks that are entry addresses to functions (except for this function)
             // FIXME: This is not tested well. Check if trap_vector is properly generated.
@@ -467,13 +1294,16 @@ where
                     .get_imm_val() as u64;
                 let target_cfg_node = cfg_rc.nodes().get(&target_addr).expect(&format!(
                     "Unable to find CFG node with entry address {}.",
-                    bb_entry
+                    self.addr_str(target_addr)
                 ));
                 if target_cfg_node.entry().is_label_entry() {
                     // This is a function in the higher level code because the CFG node has an entry point
                     let f_name = self
                         .get_func_at(&target_addr)
-                        .expect(&format!("Could not find function entry at {}.", bb_entry));
+                        .expect(&format!(
+                            "Could not find function entry at {}.",
+                            self.addr_str(target_addr)
+                        ));
                     let f_args = self
                         .func_args(&f_name)
                         .iter()
@@ -484,9 +1314,25 @@ where
                     // use the return value and is only tested with functions that have single
                     // return values. hence lhss is left as an empty vector below.
                     let lhss = vec![];
+                    let mut then_stmts = vec![];
+                    // Check each requires clause of the callee before the call
+                    if self.solver_config.split_obligations() {
+                        let addr = cfg_node.exit().address();
+                        let obligations = self.call_site_obligations(&f_name, &f_args, addr);
+                        then_stmts.extend(obligations.into_iter().map(Box::new));
+                    }
+                    // Record the tracked values of the callee with the arguments of the call
+                    let args = self
+                        .func_args(&f_name)
+                        .iter()
+                        .map(|arg| arg.get_var_name())
+                        .zip(f_args.iter().cloned())
+                        .collect();
+                    let point = format!("call_{:x}", cfg_node.exit().address());
+                    let call_obs = self.track_observations(&f_name, &args, &point);
+                    then_stmts.extend(call_obs.into_iter().map(Box::new));
                     // Construct the function call
                     let f_call_stmt = Box::new(Stmt::func_call(f_name, lhss, f_args));
-                    let mut then_stmts = vec![];
                     // Add function call to then statement
                     then_stmts.push(f_call_stmt);
                     // Reset the returned variable for the caller
@@ -497,6 +1343,10 @@ where
                         )],
                         vec![Expr::bv_lit(0, 1)],
                     )));
+                    // Restore the pointer arguments of this function after the call
+                    if let Some(stmt) = &mem_safety_arg_stmt {
+                        then_stmts.push(Box::new(stmt.clone()));
+                    }
                     let then_blk_stmt = Stmt::Block(then_stmts);
                     let guarded_call = Box::new(self.guarded_call(&target_addr, then_blk_stmt));
                     stmts_vec.push(guarded_call)
@@ -510,6 +1360,8 @@ where
             )],
             vec![Expr::bv_lit(1, 1)],
         )));
+        let exit_obs = self.track_observations(&func_name, &HashMap::new(), "exit");
+        stmts_vec.extend(exit_obs.into_iter().map(Box::new));
         Stmt::Block(stmts_vec)
     }
 
@@ -580,7 +1432,7 @@ where
                         cycle
                             .iter()
                             .rev()
-                            .map(|v| format!("{:#x?}", v))
+                            .map(|v| self.addr_str(*v))
                             .collect::<Vec<String>>()
                     )
                 } else {
@@ -632,7 +1484,7 @@ where
                 self.compute_deps(ignore, cfg_rc, &target, ts, processed);
             }
         } else {
-            panic!("Unable to find basic block at {}", curr);
+            panic!("Unable to find basic block at {}", self.addr_str(*curr));
         }
     }
 
@@ -679,12 +1531,67 @@ where
         format!("bb_{:#x?}", addr)
     }
 
+    /// Returns the address followed by its source location if it is known
+    fn addr_str(&self, addr: u64) -> String {
+        match self.dwarf_ctx.source_loc_str(addr) {
+            Some(loc) => format!("{:#x} ({})", addr, loc),
+            None => format!("{:#x}", addr),
+        }
+    }
+
     /// Returns a block statement given representing the basic block
     fn cfg_node_to_block(&self, bb: &Rc<cfg::CfgNode<disassembler::AssemblyLine>>) -> Stmt {
         let mut stmt_vec = vec![];
+        // Source lines the basic block was compiled from
+        let mut locs = vec![];
+        for al in bb.into_iter() {
+            if let Some(loc) = self.dwarf_ctx.line_info().source_loc(al.address()) {
+                let loc = format!("{}", loc);
+                if !locs.contains(&loc) {
+                    locs.push(loc);
+                }
+            }
+        }
+        if locs.len() > 0 {
+            stmt_vec.push(Box::new(Stmt::Comment(format!("source: {}", locs.join(", ")))));
+        }
+        // Memory regions the basic block may access; the blocks of ignored functions are
+        // not executed and their pointer argument ghost variables are not declared
+        let entry = bb.entry();
+        let func_name = entry.function_name();
+        let mem_regions = if self.ignored_funcs.contains(func_name) {
+            None
+        } else {
+            self.mem_safety_regions(func_name)
+        };
         for al in bb.into_iter() {
+            if let Some(updates) = self.ghost_updates.get(&al.address()) {
+                stmt_vec.push(Box::new(Stmt::Comment(format!(
+                    "ghost updates at {}",
+                    self.addr_str(al.address())
+                ))));
+                stmt_vec.extend(updates.iter().cloned().map(Box::new));
+            }
             // stmt_vec.push(Box::new(self.al_to_ir(&al)));
-            stmt_vec.push(Box::new(self.al_to_ir_stmt(&al)));
+            let mut stmt = self.al_to_ir_stmt(&al);
+            if let Some(regions) = &mem_regions {
+                let desc = format!("{} at {}", al.op(), self.addr_str(al.address()));
+                stmt = MemorySafetyInstrumenter::instrument(stmt, &desc, regions, self.xlen);
+            }
+            if let Some(stack_assert) = self.stack_bound_assert(&al) {
+                stmt = Stmt::Block(vec![Box::new(stmt), Box::new(stack_assert)]);
+            }
+            // Annotate the instruction comment with its source location
+            let stmt = match (stmt, self.dwarf_ctx.source_loc_str(al.address())) {
+                (Stmt::Block(mut stmts), Some(loc)) => {
+                    if let Some(Stmt::Comment(comment)) = stmts.first().map(|stmt| &**stmt) {
+                        stmts[0] = Box::new(Stmt::Comment(format!("{} @ {}", comment, loc)));
+                    }
+                    Stmt::Block(stmts)
+                }
+                (stmt, _) => stmt,
+            };
+            stmt_vec.push(Box::new(stmt));
         }
         Stmt::Block(stmt_vec)
     }
@@ -932,7 +1839,14 @@ where
             "lui" => system_model::lui_inst(dsts[0].clone(), srcs[0].clone(), self.xlen),
             "auipc" => system_model::auipc_inst(dsts[0].clone(), srcs[0].clone(), self.xlen),
             "jal" => system_model::jal_inst(dsts[0].clone(), srcs[0].clone(), self.xlen),
-            _ => system_model::unimplemented_inst(al.op(), self.xlen),
+            _ => {
+                warn!(
+                    "Unimplemented instruction {} at {}.",
+                    al.op(),
+                    self.addr_str(al.address())
+                );
+                system_model::unimplemented_inst(al.op(), self.xlen)
+            }
         }
     }
 
@@ -944,10 +1858,10 @@ where
         let entry_bb = self
             .bbs
             .get(&addr)
-            .expect(&format!("Unable to basic block at {}.", addr));
+            .expect(&format!("Unable to basic block at {}.", self.addr_str(addr)));
         assert!(
             &entry_bb.entry().is_label_entry(),
-            "{} is not an entry address to a function.", addr
+            "{} is not an entry address to a function.", self.addr_str(addr)
         );
         let cfg = Rc::new(cfg::Cfg::new(addr, &self.bbs));
         self.cfg_memo.insert(addr, Rc::clone(&cfg));
@@ -960,11 +1874,12 @@ where
         let mut var_names = vec![];
         for (_, cfg_node) in cfg_rc.nodes() {
             for al in cfg_node.into_iter() {
-                let mut regs: [Option<&disassembler::InstOperand>; 4] =
+                let regs: [Option<&disassembler::InstOperand>; 4] =
                     [al.rd(), al.rs1(), al.rs2(), al.csr()];
-                for reg_op in regs.iter_mut() {
-                    if let Some(reg) = reg_op {
-                        var_names.push(reg.to_string());
+                for reg_op in regs.iter() {
+                    // Only the register name; the offset of a memory operand is not part of it
+                    if let Some(disassembler::InstOperand::Register(name, _)) = reg_op {
+                        var_names.push(name.clone());
                     }
                 }
             }
@@ -1182,21 +2097,28 @@ impl ConstantPropagator {
         Self::constant_fold(constified_expr)
     }
 
-    /// Updates the constant map
-    fn constant_propagate(id: String, expr: Expr, ctx: &RefCell<&mut HashMap<String, u64>>) -> Expr {
-        let folded_expr = Self::try_make_constant(expr, ctx);
+    /// Updates the constant map with the folded expression assigned to `id`
+    fn constant_propagate(id: String, folded_expr: &Expr, ctx: &RefCell<&mut HashMap<String, u64>>) {
         match folded_expr {
             Expr::Literal(_, _) => {
                 let mut context = ctx.borrow_mut();
                 context.insert(id, folded_expr.get_lit_value().unwrap());
             },
-            _ => (),
+            _ => {
+                ctx.borrow_mut().remove(&id);
+            },
         };
-        folded_expr
     }
 }
 
 impl ASTRewriter<&mut HashMap<String, u64>> for ConstantPropagator {
+    // Havocked variables are no longer constant
+    fn rewrite_stmt_havoc(stmt: Stmt, ctx: &RefCell<&mut HashMap<String, u64>>) -> Stmt {
+        if let Stmt::Havoc(v) = &stmt {
+            ctx.borrow_mut().remove(&v.name);
+        }
+        stmt
+    }
     // Ignore the ITEs (there are only one level ITEs, don't constant propagate here)
     // and conservatively clear the map
     fn visit_stmt_ifthenelse(stmt: Stmt, ctx: &RefCell<&mut HashMap<String, u64>>) -> Stmt {
@@ -1214,14 +2136,14 @@ impl ASTRewriter<&mut HashMap<String, u64>> for ConstantPropagator {
         let Assign { lhs, rhs } = a;
         let mut rw_lhss: Vec<Expr> = vec![];
         let mut rw_rhss: Vec<Expr> = vec![];
+        // Assignments are simultaneous, so the constant map is updated after all RHS are folded
+        let mut updates = vec![];
         for (l, r) in lhs.into_iter().zip(rhs) {
             let (rw_lhs, rw_rhs) = match &l {
                 // when the LHS is just a variable, constant propagate the RHS to the LHS variable
                 Expr::Var(var, _) => {
-                    let rw_r = Self::constant_propagate(var.name.to_string(), r, ctx);
-                    if !rw_r.is_lit() {
-                        ctx.borrow_mut().remove(&var.name);
-                    }
+                    let rw_r = Self::try_make_constant(r, ctx);
+                    updates.push((var.name.to_string(), rw_r.clone()));
                     (l, rw_r)
                 }
                 // when the LHS is an array access, fold both the RHS and LHS (no constant propagation)
@@ -1249,6 +2171,9 @@ impl ASTRewriter<&mut HashMap<String, u64>> for ConstantPropagator {
             rw_lhss.push(rw_lhs);
             rw_rhss.push(rw_rhs);
         }
+        for (id, folded_expr) in updates {
+            Self::constant_propagate(id, &folded_expr, ctx);
+        }
         Assign { lhs: rw_lhss, rhs: rw_rhss }
     }
 }
@@ -1290,3 +2215,95 @@ impl ASTRewriter<&mut HashSet<Var>> for DataMemoryAbstractor {
     }
 }
 
+/// Inserts memory-safety assertions before the loads and stores of an instruction
+///
+/// Each access to `mem_b`, `mem_h`, `mem_w` or `mem_d` is checked to be naturally
+/// aligned and to fall entirely inside one of the permitted memory regions.
+/// The assertions are preceded by a comment with the instruction and its address.
+struct MemorySafetyInstrumenter;
+impl MemorySafetyInstrumenter {
+    /// Returns the instruction block with the memory-safety assertions inserted after its comment
+    fn instrument(stmt: Stmt, desc: &str, regions: &Vec<(Expr, u64)>, xlen: u64) -> Stmt {
+        let mut accesses = vec![];
+        let stmt = Self::visit_stmt(stmt, &RefCell::new(&mut accesses));
+        if accesses.is_empty() {
+            return stmt;
+        }
+        let mut checks = vec![Box::new(Stmt::Comment(format!("memory safety: {}", desc)))];
+        for (addr, bytes) in accesses {
+            checks.push(Box::new(Stmt::Assert(Self::safe_access(addr, bytes, regions, xlen))));
+        }
+        match stmt {
+            Stmt::Block(stmts) => {
+                // Keep the instruction comment first
+                let split = match stmts.first().map(|stmt| &**stmt) {
+                    Some(Stmt::Comment(_)) => 1,
+                    _ => 0,
+                };
+                let mut rest = stmts;
+                let tail = rest.split_off(split);
+                rest.extend(checks);
+                rest.extend(tail);
+                Stmt::Block(rest)
+            }
+            stmt => {
+                checks.push(Box::new(stmt));
+                Stmt::Block(checks)
+            }
+        }
+    }
+
+    /// Returns the condition that an access of `bytes` bytes at `addr` is aligned and in a region
+    fn safe_access(addr: Expr, bytes: u64, regions: &Vec<(Expr, u64)>, xlen: u64) -> Expr {
+        let in_regions = regions
+            .iter()
+            .filter(|(_, size)| *size >= bytes)
+            .map(|(base, size)| {
+                // base <= addr && addr - base <= size - bytes (without overflow)
+                let lower = Expr::op_app(Op::Comp(CompOp::Geu), vec![addr.clone(), base.clone()]);
+                let offset = Expr::op_app(Op::Bv(BVOp::Sub), vec![addr.clone(), base.clone()]);
+                let upper = Expr::op_app(
+                    Op::Comp(CompOp::Leu),
+                    vec![offset, Expr::bv_lit(size - bytes, xlen)],
+                );
+                Expr::op_app(Op::Bool(BoolOp::Conj), vec![lower, upper])
+            })
+            .fold(None, |acc: Option<Expr>, cond| match acc {
+                Some(acc) => Some(Expr::op_app(Op::Bool(BoolOp::Disj), vec![acc, cond])),
+                None => Some(cond),
+            })
+            .unwrap_or(Expr::bool_lit(false));
+        if bytes <= 1 {
+            return in_regions;
+        }
+        let aligned = Expr::op_app(
+            Op::Comp(CompOp::Equality),
+            vec![
+                Expr::op_app(
+                    Op::Bv(BVOp::And),
+                    vec![addr, Expr::bv_lit(bytes - 1, xlen)],
+                ),
+                Expr::bv_lit(0, xlen),
+            ],
+        );
+        Expr::op_app(Op::Bool(BoolOp::Conj), vec![aligned, in_regions])
+    }
+}
+impl ASTRewriter<&mut Vec<(Expr, u64)>> for MemorySafetyInstrumenter {
+    /// Collects the address and width (in bytes) of every memory access
+    fn rewrite_expr(expr: Expr, ctx: &RefCell<&mut Vec<(Expr, u64)>>) -> Expr {
+        if let (Some(array), Some(index)) = (expr.get_array_expr(), expr.get_array_index()) {
+            let bytes = match &array.get_var_name()[..] {
+                constants::MEM_VAR_B => Some(1),
+                constants::MEM_VAR_H => Some(2),
+                constants::MEM_VAR_W => Some(4),
+                constants::MEM_VAR_D => Some(8),
+                _ => None,
+            };
+            if let Some(bytes) = bytes {
+                ctx.borrow_mut().push((index.clone(), bytes));
+            }
+        }
+        expr
+    }
+}
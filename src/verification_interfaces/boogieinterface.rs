@@ -0,0 +1,925 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashSet},
+    io::{self, Write},
+    rc::Rc,
+};
+
+use asts::{spec_lang::sl_ast, veriv_ast::*};
+
+use dwarf_ctx::dwarfreader::{DwarfCtx, DwarfVar};
+
+use utils::helpers;
+
+use crate::{
+    ir_interface::{self, IRInterface, IndentWriter, SpecLangASTInterface},
+    solver::SolverConfig,
+};
+
+use rv_model::system_model;
+
+use utils::constants::BYTE_SIZE;
+
+// ========================================================================================================================
+// # Boogie Interface
+
+/// Emits a Boogie program with a procedure for each function model.
+/// Boogie has no bit-vector operators other than concatenation and extraction,
+/// so the operators are emitted as `{:bvbuiltin}` functions named `$op.bvW`
+/// (and `$sext.bvW.bvV` for extensions) which are declared for the widths used.
+/// The operators are recorded as the expressions are translated and their declarations
+/// are written at the end of the program.
+#[derive(Debug)]
+pub struct BoogieInterface;
+
+impl BoogieInterface {
+    /// Returns a string of the variable declarations in the model
+    fn gen_var_defns(model: &Model) -> String {
+        let mut sorted = model.vars.iter().collect::<Vec<_>>();
+        sorted.sort();
+        let defns = sorted
+            .iter()
+            .map(|v| format!("var {};", Self::var_decl(&v.name, &v.typ)))
+            .collect::<Vec<String>>()
+            .join("\n");
+        format!("// RISC-V system state variables\n{}\n", defns)
+    }
+
+    /// Returns the functions for dereferencing 1, 2, 4, and 8 byte values
+    fn prelude(xlen: &u64) -> String {
+        let addr = Expr::var("addr", system_model::bv_type(*xlen));
+        let defns = [1, 2, 4, 8]
+            .iter()
+            .map(|bytes| {
+                let mem_typ = Type::Array {
+                    in_typs: vec![Box::new(system_model::bv_type(*xlen))],
+                    out_typ: Box::new(system_model::bv_type(bytes * BYTE_SIZE)),
+                };
+                let load = Self::array_index_to_string(&Expr::var("mem", mem_typ.clone()), &addr, xlen);
+                format!(
+                    "function deref_{}(mem: {}, addr: bv{}) returns (bv{}) {{ {} }}",
+                    bytes,
+                    Self::typ_to_string(&mem_typ),
+                    xlen,
+                    bytes * BYTE_SIZE,
+                    load
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("// Dereference helpers\n{}\n", defns.join("\n"))
+    }
+
+    /// Generate an index function for each type of array variable
+    /// that is a global variable or function argument.
+    /// These are the `index_by_N` macros of the UCLID5 model.
+    ///
+    /// # Example
+    ///
+    /// function index_by_16(base: bv64, index: bv64) returns (bv64) { $add.bv64(base, $mul.bv64(index, 16bv64)) }
+    fn gen_array_defns(dwarf_ctx: &DwarfCtx, xlen: &u64) -> String {
+        let defns = ir_interface::array_index_sizes(dwarf_ctx)
+            .iter()
+            .map(|bytes| {
+                // Returns 'base + index * bytes'
+                let offset = if *bytes == 1 {
+                    "index".to_string()
+                } else {
+                    format!("{}(index, {}bv{})", BvBuiltin::bin("mul", *xlen), bytes, xlen)
+                };
+                format!(
+                    "function index_by_{}(base: bv{}, index: bv{}) returns (bv{}) {{ {}(base, {}) }}",
+                    bytes,
+                    xlen,
+                    xlen,
+                    xlen,
+                    BvBuiltin::bin("add", *xlen),
+                    offset
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("// Array helpers\n{}\n", defns.join("\n"))
+    }
+
+    /// Return a string of get field functions for all the type definitions in the global variables
+    /// and formal arguments of functions.
+    ///
+    /// # Example
+    ///
+    /// Given the following struct definition:
+    ///
+    /// ```text
+    /// struct ctx { ..., a0: T, ... };
+    /// ```
+    ///
+    /// This function returns the following definition to simplify 'c.a0', where c is of type ctx:
+    ///
+    /// ```text
+    /// function ctx_a0(ptr: bv64) returns (bv64) { $add.bv64(ptr, 80bv64) }
+    /// ```
+    fn gen_struct_defns(dwarf_ctx: &DwarfCtx, xlen: &u64) -> String {
+        let defns = ir_interface::struct_fields(dwarf_ctx)
+            .iter()
+            .map(|(id, field_name, loc)| {
+                format!(
+                    "function {}_{}(ptr: bv{}) returns (bv{}) {{ {}(ptr, {}bv{}) }}",
+                    id,
+                    field_name,
+                    xlen,
+                    xlen,
+                    BvBuiltin::bin("add", *xlen),
+                    loc,
+                    xlen
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("// Struct helpers\n{}\n", defns.join("\n"))
+    }
+
+    /// Given the dwarf_ctx, returns a string of global variable definitions.
+    fn gen_global_defns(dwarf_ctx: &DwarfCtx, xlen: &u64) -> String {
+        let mut defns = String::from("// Global variables\n");
+        for var in dwarf_ctx.global_vars() {
+            defns = format!("{}{}\n", defns, Self::gen_global_defn(var, xlen));
+        }
+        defns
+    }
+
+    /// Given a global variable, returns a function that returns the static
+    /// memory location of the variable.
+    fn gen_global_defn(global_var: &DwarfVar, xlen: &u64) -> String {
+        format!(
+            "function {}() returns (bv{}) {{ {}bv{} }}",
+            helpers::global_var_ptr_name(&global_var.name[..]),
+            xlen,
+            global_var.memory_addr,
+            xlen
+        )
+    }
+
+    /// Returns a string of functions that return the static functions' entry addresses.
+    fn gen_global_func_defns(model: &Model, xlen: &u64) -> String {
+        let mut defns = String::from("// Global function entry addresses\n");
+        for fm in &model.func_models {
+            defns = format!(
+                "{}function {}() returns (bv{}) {{ {}bv{} }}\n",
+                defns,
+                helpers::global_func_addr_name(&fm.sig.name),
+                xlen,
+                fm.sig.entry_addr,
+                xlen
+            );
+        }
+        defns
+    }
+
+    fn specs_to_string(fm: &FuncModel, xlen: &u64) -> String {
+        let fsig = &fm.sig;
+        let mut specs = vec![];
+        // requires
+        for require in &fsig.requires {
+            let bexpr = require.get_bexpr().unwrap();
+            specs.push(format!("requires {};", Self::bexpr_to_string(bexpr)));
+        }
+        // Formal argument, pc and initial return value constraints
+        for (_, require) in system_model::entry_requires(fm, *xlen) {
+            specs.push(format!("requires {};", Self::expr_to_string(&require, xlen)));
+        }
+        // modifies
+        if !fsig.mod_set.is_empty() {
+            let mut mod_set = fsig.mod_set.iter().cloned().collect::<Vec<_>>();
+            mod_set.sort();
+            specs.push(format!("modifies {};", mod_set.join(", ")));
+        }
+        // ensures
+        for ensure in &fsig.ensures {
+            let bexpr = ensure.get_bexpr().unwrap();
+            specs.push(format!("ensures {};", Self::bexpr_to_string(bexpr)));
+        }
+        specs.join("\n")
+    }
+
+    /// Writes the procedure of the function model.
+    /// Procedures that are not verified are marked with `{:verify false}`; these
+    /// are still used through their contract or by inlining them.
+    fn write_proc(out: &mut IndentWriter, fm: &FuncModel, xlen: &u64, verify: bool) -> io::Result<()> {
+        let args = fm
+            .sig
+            .arg_decls
+            .iter()
+            .map(|arg_expr| Self::var_decl(&arg_expr.get_var_name(), arg_expr.typ()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let ret = if let Some(rd) = &fm.sig.ret_decl {
+            format!(" returns (ret: {})", Self::typ_to_string(rd))
+        } else {
+            String::new()
+        };
+        let mut attrs = String::from("");
+        if fm.inline {
+            attrs = format!("{}{{:inline 1}} ", attrs);
+        }
+        if !verify || fm.inline {
+            attrs = format!("{}{{:verify false}} ", attrs);
+        }
+        out.line(&format!("procedure {}{}({}){}", attrs, fm.sig.name, args, ret))?;
+        out.indent();
+        out.line(&Self::specs_to_string(fm, xlen))?;
+        out.dedent();
+        out.line("{")?;
+        out.indent();
+        Self::write_block(out, fm.body.get_expect_block(), xlen)?;
+        out.dedent();
+        out.line("}")
+    }
+
+    // ==================================================================================================================
+    // # Helper functions
+
+    /// Return a Boogie variable declaration.
+    ///
+    /// # Example
+    ///
+    /// Var = { name: "x".to_string(), typ: Type::Bv { bytes: 64 } } will return:
+    /// `x: bv64`
+    fn var_decl(var_name: &str, typ: &Type) -> String {
+        format!("{}: {}", var_name, Self::typ_to_string(typ))
+    }
+
+    /// Returns the width of a bit-vector expression.
+    /// The types of the bit-vector operators in the IR are the types of their first operand,
+    /// so the width is computed from the operands instead.
+    fn bv_width(expr: &Expr) -> u64 {
+        match expr {
+            Expr::OpApp(opapp, typ) => match &opapp.op {
+                Op::Bv(BVOp::SignExt) | Op::Bv(BVOp::ZeroExt) => {
+                    Self::bv_width(&opapp.operands[0]) + Self::ext_width(&opapp.operands[1])
+                }
+                Op::Bv(BVOp::Concat) => {
+                    Self::bv_width(&opapp.operands[0]) + Self::bv_width(&opapp.operands[1])
+                }
+                Op::Bv(BVOp::Slice { l, r }) => l - r + 1,
+                Op::Bv(_) => Self::bv_width(&opapp.operands[0]),
+                _ => typ.get_expect_bv_width(),
+            },
+            _ => expr.typ().get_expect_bv_width(),
+        }
+    }
+
+    /// Returns the value of a width argument of an extension
+    fn ext_width(expr: &Expr) -> u64 {
+        expr.get_lit_value()
+            .expect("The width of an extension should be a literal.")
+    }
+
+    /// Returns the width of a bit-vector specification expression if it is known
+    fn vexpr_width(expr: &sl_ast::VExpr) -> Option<u64> {
+        match expr {
+            sl_ast::VExpr::OpApp(op, exprs, typ) => match op {
+                sl_ast::ValueOp::Slice { hi, lo } => Some((hi - lo + 1) as u64),
+                sl_ast::ValueOp::Concat => Some(Self::vexpr_width(&exprs[0])? + Self::vexpr_width(&exprs[1])?),
+                sl_ast::ValueOp::RightShift
+                | sl_ast::ValueOp::URightShift
+                | sl_ast::ValueOp::LeftShift => Self::vexpr_width(&exprs[0]),
+                sl_ast::ValueOp::Add
+                | sl_ast::ValueOp::Sub
+                | sl_ast::ValueOp::Div
+                | sl_ast::ValueOp::Mul
+                | sl_ast::ValueOp::BvXor
+                | sl_ast::ValueOp::BvOr
+                | sl_ast::ValueOp::BvAnd => exprs.iter().find_map(Self::vexpr_width),
+                _ => Self::vtype_width(typ),
+            },
+            sl_ast::VExpr::FuncApp(fname, args, typ) => match &fname[..] {
+                "sext" | "uext" => Some(Self::vexpr_width(&args[1])? + Self::spec_ext_width(&args[0])),
+                "old" => Self::vexpr_width(&args[0]),
+                _ => Self::vtype_width(typ),
+            },
+            _ => Self::vtype_width(expr.typ()),
+        }
+    }
+
+    /// Returns the width of a bit-vector specification type
+    fn vtype_width(typ: &sl_ast::VType) -> Option<u64> {
+        match typ {
+            sl_ast::VType::Bv(w) => Some(*w as u64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a width argument of an extension in a specification
+    fn spec_ext_width(arg: &sl_ast::VExpr) -> u64 {
+        match arg {
+            sl_ast::VExpr::Bv { value, typ: _ } => *value,
+            sl_ast::VExpr::Int(i, _) => *i as u64,
+            _ => panic!("The width of an extension should be a literal but found {:?}.", arg),
+        }
+    }
+
+    /// Returns the width of the operands of a specification operator
+    fn expect_vexprs_width(exprs: &Vec<sl_ast::VExpr>) -> u64 {
+        exprs
+            .iter()
+            .find_map(Self::vexpr_width)
+            .unwrap_or_else(|| panic!("Unable to infer the bit-vector width of {:?}.", exprs))
+    }
+}
+
+impl IRInterface for BoogieInterface {
+    /// IR translation functions
+    fn lit_to_string(lit: &Literal) -> String {
+        match lit {
+            Literal::Bv { val, width } => {
+                let val = if *width < 64 { val & ((1 << width) - 1) } else { *val };
+                format!("{}bv{}", val, width)
+            }
+            Literal::Bool { val } => format!("{}", val),
+            Literal::Int { val } => format!("{}", val),
+        }
+    }
+
+    fn typ_to_string(typ: &Type) -> String {
+        match typ {
+            Type::Unknown => panic!("Type is unknown!"),
+            Type::Bool => "bool".to_string(),
+            Type::Int => "int".to_string(),
+            Type::Bv { w } => format!("bv{}", w),
+            Type::Array { in_typs, out_typ } => format!(
+                "[{}]{}",
+                in_typs
+                    .iter()
+                    .map(|typ| Self::typ_to_string(typ))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                Self::typ_to_string(out_typ)
+            ),
+            Type::Struct {
+                id: _,
+                fields: _,
+                w: _,
+            } => panic!("Should not need to print struct types in this model."),
+        }
+    }
+
+    fn comp_app_to_string(compop: &CompOp, exprs: &Vec<Expr>, xlen: &u64) -> String {
+        assert!(
+            exprs.len() == 2,
+            "Comparison operator should have two expressions."
+        );
+        let e1 = Self::expr_to_string(&exprs[0], xlen);
+        let e2 = Self::expr_to_string(&exprs[1], xlen);
+        let fname = match compop {
+            CompOp::Equality => return format!("({} == {})", e1, e2),
+            CompOp::Inequality => return format!("({} != {})", e1, e2),
+            CompOp::Lt => "slt",
+            CompOp::Le => "sle",
+            CompOp::Gt => "sgt",
+            CompOp::Ge => "sge",
+            CompOp::Ltu => "ult",
+            CompOp::Leu => "ule",
+            CompOp::Gtu => "ugt",
+            CompOp::Geu => "uge",
+        };
+        format!("{}({}, {})", BvBuiltin::cmp(fname, Self::bv_width(&exprs[0])), e1, e2)
+    }
+
+    fn bv_app_to_string(bvop: &BVOp, exprs: &Vec<Expr>, xlen: &u64) -> String {
+        let w = Self::bv_width(&exprs[0]);
+        let e1 = Self::expr_to_string(&exprs[0], xlen);
+        let e2 = if exprs.len() > 1 {
+            Some(Self::expr_to_string(&exprs[1], xlen))
+        } else {
+            None
+        };
+        let fname = match bvop {
+            BVOp::Add => "add",
+            BVOp::Sub => "sub",
+            BVOp::Mul => "mul",
+            BVOp::And => "and",
+            BVOp::Or => "or",
+            BVOp::Xor => "xor",
+            BVOp::LeftShift => "shl",
+            BVOp::RightShift => "lshr",
+            BVOp::ARightShift => "ashr",
+            BVOp::SignExt | BVOp::ZeroExt => {
+                let fname = if let BVOp::SignExt = bvop { "sext" } else { "zext" };
+                return match Self::ext_width(&exprs[1]) {
+                    0 => e1,
+                    n => format!("{}({})", BvBuiltin::ext(fname, w, w + n), e1),
+                };
+            }
+            BVOp::Concat => return format!("({} ++ {})", e1, e2.unwrap()),
+            // Boogie's extraction excludes the upper bound
+            BVOp::Slice { l, r } => return format!("({})[{}:{}]", e1, l + 1, r),
+        };
+        format!("{}({}, {})", BvBuiltin::bin(fname, w), e1, e2.unwrap())
+    }
+
+    fn bool_app_to_string(bop: &BoolOp, exprs: &Vec<Expr>, xlen: &u64) -> String {
+        let e1_str = Self::expr_to_string(&exprs[0], xlen);
+        let e2_str = if exprs.len() > 1 {
+            Some(Self::expr_to_string(&exprs[1], xlen))
+        } else {
+            None
+        };
+        match bop {
+            BoolOp::Conj => format!("({} && {})", e1_str, e2_str.unwrap()),
+            BoolOp::Disj => format!("({} || {})", e1_str, e2_str.unwrap()),
+            BoolOp::Iff => format!("({} <==> {})", e1_str, e2_str.unwrap()),
+            BoolOp::Impl => format!("({} ==> {})", e1_str, e2_str.unwrap()),
+            BoolOp::Neg => format!("!{}", e1_str),
+        }
+    }
+
+    fn fapp_to_string(fapp: &FuncApp, xlen: &u64) -> String {
+        format!(
+            "{}({})",
+            fapp.func_name,
+            fapp.operands
+                .iter()
+                .map(|x| Self::expr_to_string(x, xlen))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+
+    fn var_to_string(var: &Var) -> String {
+        var.name.clone()
+    }
+
+    fn array_index_to_string(arr: &Expr, index: &Expr, xlen: &u64) -> String {
+        let arr_str = Self::expr_to_string(arr, xlen);
+        let index_str = Self::expr_to_string(index, xlen);
+        format!("{}[{}]", arr_str, index_str)
+    }
+
+    fn get_field_to_string(struct_: &Expr, field: &String, xlen: &u64) -> String {
+        let struct_str = Self::expr_to_string(struct_, xlen);
+        format!("{}_{}({})", struct_.typ().get_struct_id(), field, struct_str)
+    }
+
+    /// Statements to string
+    fn skip_to_string() -> String {
+        String::new()
+    }
+
+    fn assert_to_string(expr: &Expr, xlen: &u64) -> String {
+        format!("assert {};", Self::expr_to_string(expr, xlen))
+    }
+
+    fn assume_to_string(expr: &Expr, xlen: &u64) -> String {
+        format!("assume {};", Self::expr_to_string(expr, xlen))
+    }
+
+    fn havoc_to_string(var: &Rc<Var>) -> String {
+        format!("havoc {};", Self::var_to_string(var))
+    }
+
+    fn func_call_to_string(func_call: &FuncCall, xlen: &u64) -> String {
+        let lhs = func_call
+            .lhs
+            .iter()
+            .map(|expr| Self::expr_to_string(expr, xlen))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let args = func_call
+            .operands
+            .iter()
+            .map(|expr| {
+                let expr_str = Self::expr_to_string(expr, xlen);
+                if expr_str == "zero" {
+                    format!("0bv{}", xlen)
+                } else {
+                    expr_str
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let lhs = if !lhs.is_empty() {
+            format!("{} := ", lhs)
+        } else {
+            lhs
+        };
+        format!("call {}{}({});", lhs, func_call.func_name, args)
+    }
+
+    fn assign_to_string(assign: &Assign, xlen: &u64) -> String {
+        let lhs = assign
+            .lhs
+            .iter()
+            .map(|expr| Self::expr_to_string(expr, xlen))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let rhs = assign
+            .rhs
+            .iter()
+            .map(|expr| Self::expr_to_string(expr, xlen))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{} := {};", lhs, rhs)
+    }
+
+    fn comment_to_string(string: &String) -> String {
+        format!("// {}", string.replace("\n", " "))
+    }
+
+    fn write_ite(out: &mut IndentWriter, ite: &IfThenElse, xlen: &u64) -> io::Result<()> {
+        out.line(&format!("if ({}) {{", Self::expr_to_string(&ite.cond, xlen)))?;
+        out.indent();
+        Self::write_stmt(out, &ite.then_stmt, xlen)?;
+        out.dedent();
+        if let Some(else_stmt) = &ite.else_stmt {
+            out.line("} else {")?;
+            out.indent();
+            Self::write_stmt(out, else_stmt, xlen)?;
+            out.dedent();
+        }
+        out.line("}")
+    }
+
+    /// Boogie has no block statement; the statements are listed in order
+    fn write_block(out: &mut IndentWriter, blk: &[Box<Stmt>], xlen: &u64) -> io::Result<()> {
+        for stmt in blk {
+            Self::write_stmt(out, stmt, xlen)?;
+        }
+        Ok(())
+    }
+
+    fn write_func_model(
+        out: &mut IndentWriter,
+        fm: &FuncModel,
+        _dwarf_ctx: &DwarfCtx,
+        xlen: &u64,
+    ) -> io::Result<()> {
+        Self::write_proc(out, fm, xlen, true)
+    }
+
+    fn write_model(
+        out: &mut dyn Write,
+        xlen: &u64,
+        model: &Model,
+        dwarf_ctx: &DwarfCtx,
+        ignored_funcs: &HashSet<&str>,
+        verify_funcs: &Vec<&str>,
+        _solver_config: &SolverConfig,
+    ) -> io::Result<()> {
+        // Record the bit-vector operators used in the program
+        BvBuiltin::clear();
+        let mut program = IndentWriter::new(out);
+        program.line(&format!("// Boogie model of {}", model.name))?;
+        // variables
+        program.line(&Self::gen_var_defns(model))?;
+        // definitions
+        program.line(&Self::prelude(xlen))?;
+        program.line(&Self::gen_array_defns(dwarf_ctx, xlen))?;
+        program.line(&Self::gen_struct_defns(dwarf_ctx, xlen))?;
+        program.line(&Self::gen_global_defns(dwarf_ctx, xlen))?;
+        program.line(&Self::gen_global_func_defns(model, xlen))?;
+        // procedures
+        let verified = ir_interface::verified_funcs(model, dwarf_ctx, ignored_funcs, verify_funcs)
+            .iter()
+            .map(|fm| &fm.sig.name)
+            .collect::<HashSet<_>>();
+        for fm in &model.func_models {
+            Self::write_proc(&mut program, fm, xlen, verified.contains(&fm.sig.name))?;
+            program.blank()?;
+        }
+        // Declare the bit-vector operators used in the program
+        program.line("// Bit-vector operators")?;
+        for decl in BvBuiltin::decls() {
+            program.line(&decl)?;
+        }
+        Ok(())
+    }
+}
+
+impl SpecLangASTInterface for BoogieInterface {
+    /// BExpr translation functions
+    fn bexpr_bool_to_string(b: &bool) -> String {
+        format!("{}", b)
+    }
+
+    fn bexpr_bopapp_to_string(bop: &sl_ast::BoolOp, exprs: &Vec<sl_ast::BExpr>) -> String {
+        let bop_str = Self::bopp_to_string(bop);
+        let mut exprs_iter = exprs.iter();
+        let mut ret = Self::bexpr_to_string(exprs_iter.next().unwrap());
+        // Unary prefix operators
+        match bop {
+            sl_ast::BoolOp::Neg => return format!("{}({})", bop_str, ret),
+            sl_ast::BoolOp::Forall(_, _) | sl_ast::BoolOp::Exists(_, _) => {
+                return format!("({}{})", bop_str, ret)
+            }
+            _ => (),
+        }
+        // Infix operator
+        for expr in exprs_iter {
+            let expr_str = Self::bexpr_to_string(expr);
+            ret = format!("({} {} {})", ret, bop_str, expr_str)
+        }
+        ret
+    }
+
+    fn bexpr_copapp_to_string(cop: &sl_ast::CompOp, exprs: &Vec<sl_ast::VExpr>) -> String {
+        assert!(
+            exprs.len() == 2,
+            "Invalid number of operands for comparison."
+        );
+        let cop_str = Self::cop_to_string(cop);
+        let expr_str1 = Self::vexpr_to_string(&exprs[0]);
+        let expr_str2 = Self::vexpr_to_string(&exprs[1]);
+        match (cop, exprs.iter().find_map(Self::vexpr_width)) {
+            // Integer comparisons and (in)equalities are built into Boogie
+            (sl_ast::CompOp::Equal, _) | (sl_ast::CompOp::Nequal, _) | (_, None) => {
+                format!("({} {} {})", expr_str1, cop_str, expr_str2)
+            }
+            (_, Some(w)) => format!("{}({}, {})", BvBuiltin::cmp(&cop_str, w), expr_str1, expr_str2),
+        }
+    }
+
+    fn bopp_to_string(bop: &sl_ast::BoolOp) -> String {
+        match bop {
+            sl_ast::BoolOp::Conj => "&&".to_string(),
+            sl_ast::BoolOp::Disj => "||".to_string(),
+            sl_ast::BoolOp::Neg => "!".to_string(),
+            sl_ast::BoolOp::Implies => "==>".to_string(),
+            sl_ast::BoolOp::Forall(var, typ) => format!(
+                "forall {}: {} :: ",
+                Self::vexpr_to_string(var),
+                Self::vtype_to_string(typ)
+            ),
+            sl_ast::BoolOp::Exists(var, typ) => format!(
+                "exists {}: {} :: ",
+                Self::vexpr_to_string(var),
+                Self::vtype_to_string(typ)
+            ),
+        }
+    }
+
+    /// Returns the comparison operator; the ordering operators on bit-vectors are
+    /// the names of their operator functions
+    fn cop_to_string(cop: &sl_ast::CompOp) -> String {
+        match cop {
+            sl_ast::CompOp::Equal => "==".to_string(),
+            sl_ast::CompOp::Nequal => "!=".to_string(),
+            sl_ast::CompOp::Gt => "sgt".to_string(),
+            sl_ast::CompOp::Lt => "slt".to_string(),
+            sl_ast::CompOp::Gtu => "ugt".to_string(),
+            sl_ast::CompOp::Ltu => "ult".to_string(),
+            sl_ast::CompOp::Geq => "sge".to_string(),
+            sl_ast::CompOp::Leq => "sle".to_string(),
+            sl_ast::CompOp::Geu => "uge".to_string(),
+            sl_ast::CompOp::Leu => "ule".to_string(),
+        }
+    }
+
+    /// VExpr translation functions
+    fn vexpr_bv_to_string(value: &u64, typ: &sl_ast::VType) -> String {
+        let width = match typ {
+            // normal bv literal value
+            sl_ast::VType::Bv(width) => *width as u64,
+            // array as bitvector literal
+            sl_ast::VType::Array { in_type, out_type: _ } => in_type.get_bv_width() as u64,
+            _ => panic!("Should be bv typed but is {:?}.", typ),
+        };
+        Self::lit_to_string(&Literal::Bv { val: *value, width })
+    }
+
+    fn vexpr_int_to_string(i: &i64) -> String {
+        format!("{}", i)
+    }
+
+    fn vexpr_bool_to_string(b: &bool) -> String {
+        format!("{}", b)
+    }
+
+    fn vexpr_ident_to_string(v: &String) -> String {
+        v.clone()
+    }
+
+    fn vexpr_opapp_to_string(op: &sl_ast::ValueOp, exprs: &Vec<sl_ast::VExpr>) -> String {
+        let exprs_str = exprs
+            .iter()
+            .map(Self::vexpr_to_string)
+            .collect::<Vec<_>>();
+        match op {
+            sl_ast::ValueOp::Add
+            | sl_ast::ValueOp::Sub
+            | sl_ast::ValueOp::Div
+            | sl_ast::ValueOp::Mul
+            | sl_ast::ValueOp::BvXor
+            | sl_ast::ValueOp::BvOr
+            | sl_ast::ValueOp::BvAnd => {
+                // Left associative binary operators
+                let fname = BvBuiltin::bin(&Self::valueop_to_string(op), Self::expect_vexprs_width(exprs));
+                exprs_str
+                    .iter()
+                    .skip(1)
+                    .fold(exprs_str[0].clone(), |acc, expr_str| {
+                        format!("{}({}, {})", fname, acc, expr_str)
+                    })
+            }
+            sl_ast::ValueOp::RightShift
+            | sl_ast::ValueOp::URightShift
+            | sl_ast::ValueOp::LeftShift => format!(
+                "{}({}, {})",
+                BvBuiltin::bin(&Self::valueop_to_string(op), Self::expect_vexprs_width(exprs)),
+                exprs_str[0],
+                exprs_str[1]
+            ),
+            sl_ast::ValueOp::ArrayIndex => {
+                let bytes = match &exprs[0].typ() {
+                    sl_ast::VType::Array {
+                        in_type: _,
+                        out_type,
+                    } => match &**out_type {
+                        sl_ast::VType::Bv(w) => *w as u64 / BYTE_SIZE,
+                        sl_ast::VType::Struct {
+                            id: _,
+                            fields: _,
+                            size,
+                        } => *size / BYTE_SIZE,
+                        _ => panic!("Expected BV type (op: {:#?}, exprs: {:#?}).", op, exprs),
+                    },
+                    _ => panic!("Expected array type for {:#?} but found {:#?}.", &exprs[0], &exprs[0].typ()),
+                };
+                match &exprs_str[0][..] {
+                    "mem" => format!("{}[{}]", exprs_str[0], exprs_str[1]),
+                    _ => format!("index_by_{}({}, {})", bytes, exprs_str[0], exprs_str[1]),
+                }
+            }
+            sl_ast::ValueOp::GetField => {
+                let struct_name = match &exprs[0].typ() {
+                    sl_ast::VType::Struct {
+                        id,
+                        fields: _,
+                        size: _,
+                    } => id,
+                    _ => panic!("Expected struct type."),
+                };
+                format!("{}_{}({})", struct_name, exprs_str[1], exprs_str[0])
+            }
+            sl_ast::ValueOp::Deref => {
+                let bytes = exprs[0].typ().get_bv_width() as u64 / BYTE_SIZE;
+                let mem_suffix = match bytes {
+                    1 => "b",
+                    2 => "h",
+                    4 => "w",
+                    8 => "d",
+                    _ => panic!("Cannot dereference values that are not 1, 2, 4, or 8 bytes."),
+                };
+                format!("deref_{}(mem_{}, {})", bytes, mem_suffix, exprs_str[0])
+            }
+            sl_ast::ValueOp::Concat => format!("({} ++ {})", exprs_str[0], exprs_str[1]),
+            // Boogie's extraction excludes the upper bound
+            sl_ast::ValueOp::Slice { hi, lo } => format!("({})[{}:{}]", exprs_str[0], hi + 1, lo),
+        }
+    }
+
+    fn vexpr_funcapp_to_string(fname: &String, args: &Vec<sl_ast::VExpr>) -> String {
+        match &fname[..] {
+            "sext" | "uext" => {
+                let from = Self::vexpr_width(&args[1])
+                    .unwrap_or_else(|| panic!("Unable to infer the bit-vector width of {:?}.", args[1]));
+                let to = from + Self::spec_ext_width(&args[0]);
+                let ext = if fname == "sext" { "sext" } else { "zext" };
+                format!("{}({})", BvBuiltin::ext(ext, from, to), Self::vexpr_to_string(&args[1]))
+            }
+            _ => {
+                let args_str = args
+                    .iter()
+                    .map(Self::vexpr_to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!("{}({})", fname, args_str)
+            }
+        }
+    }
+
+    /// Returns the name of the operator function
+    fn valueop_to_string(op: &sl_ast::ValueOp) -> String {
+        match op {
+            sl_ast::ValueOp::Add => String::from("add"),
+            sl_ast::ValueOp::Sub => String::from("sub"),
+            sl_ast::ValueOp::Mul => String::from("mul"),
+            sl_ast::ValueOp::BvXor => String::from("xor"),
+            sl_ast::ValueOp::BvOr => String::from("or"),
+            sl_ast::ValueOp::BvAnd => String::from("and"),
+            sl_ast::ValueOp::RightShift => String::from("ashr"),
+            sl_ast::ValueOp::URightShift => String::from("lshr"),
+            sl_ast::ValueOp::LeftShift => String::from("shl"),
+            _ => panic!("Unimplemented value op {:#?}.", op),
+        }
+    }
+
+    /// Value Type to string
+    fn vtype_to_string(typ: &sl_ast::VType) -> String {
+        match typ {
+            sl_ast::VType::Bv(width) => format!("bv{}", width),
+            sl_ast::VType::Int => "int".to_string(),
+            sl_ast::VType::Bool => "bool".to_string(),
+            _ => panic!("Unimplemented type to string translation for {:#?}.", typ),
+        }
+    }
+
+    /// Spec statement to string
+    fn spec_to_string(spec: &sl_ast::Spec) -> String {
+        match spec {
+            sl_ast::Spec::Requires(bexpr, _) => format!("requires {};", Self::bexpr_to_string(bexpr)),
+            sl_ast::Spec::Ensures(bexpr, _) => format!("ensures {};", Self::bexpr_to_string(bexpr)),
+            _ => panic!("Unimplemented."),
+        }
+    }
+}
+
+// ========================================================================================================================
+// # Bit-vector operator declarations
+
+/// A bit-vector operator function used by the program
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum BvBuiltin {
+    /// Binary operator `$op.bvW` on `bvW` values
+    Bin(String, u64),
+    /// Comparison `$op.bvW` of `bvW` values
+    Cmp(String, u64),
+    /// Extension `$op.bvW.bvV` of a `bvW` value to `bvV`
+    Ext(String, u64, u64),
+}
+
+thread_local! {
+    /// Bit-vector operators used by the expressions translated since the last `BvBuiltin::clear`
+    static BV_BUILTINS: RefCell<BTreeSet<BvBuiltin>> = const { RefCell::new(BTreeSet::new()) };
+}
+
+impl BvBuiltin {
+    /// Returns the name of the binary operator function `op` on `bvW` values and records its use
+    fn bin(op: &str, w: u64) -> String {
+        BvBuiltin::Bin(op.to_string(), w).record()
+    }
+
+    /// Returns the name of the comparison function `op` on `bvW` values and records its use
+    fn cmp(op: &str, w: u64) -> String {
+        BvBuiltin::Cmp(op.to_string(), w).record()
+    }
+
+    /// Returns the name of the extension function `op` from `bvW` to `bvV` and records its use
+    fn ext(op: &str, from: u64, to: u64) -> String {
+        BvBuiltin::Ext(op.to_string(), from, to).record()
+    }
+
+    fn record(self) -> String {
+        let name = self.name();
+        BV_BUILTINS.with(|builtins| builtins.borrow_mut().insert(self));
+        name
+    }
+
+    /// Forgets the operators used so far
+    fn clear() {
+        BV_BUILTINS.with(|builtins| builtins.borrow_mut().clear());
+    }
+
+    /// Returns the declarations of the operators used since the last `clear`
+    fn decls() -> Vec<String> {
+        BV_BUILTINS.with(|builtins| builtins.borrow().iter().map(BvBuiltin::decl).collect())
+    }
+
+    fn name(&self) -> String {
+        match self {
+            BvBuiltin::Bin(op, w) | BvBuiltin::Cmp(op, w) => format!("${}.bv{}", op, w),
+            BvBuiltin::Ext(op, from, to) => format!("${}.bv{}.bv{}", op, from, to),
+        }
+    }
+
+    fn decl(&self) -> String {
+        match self {
+            BvBuiltin::Bin(op, w) => format!(
+                "function {{:bvbuiltin \"bv{}\"}} {}(x: bv{}, y: bv{}) returns (bv{});",
+                op,
+                self.name(),
+                w,
+                w,
+                w
+            ),
+            BvBuiltin::Cmp(op, w) => format!(
+                "function {{:bvbuiltin \"bv{}\"}} {}(x: bv{}, y: bv{}) returns (bool);",
+                op,
+                self.name(),
+                w,
+                w
+            ),
+            BvBuiltin::Ext(op, from, to) => {
+                let builtin = if op == "sext" {
+                    "sign_extend"
+                } else {
+                    "zero_extend"
+                };
+                format!(
+                    "function {{:bvbuiltin \"{} {}\"}} {}(x: bv{}) returns (bv{});",
+                    builtin,
+                    to - from,
+                    self.name(),
+                    from,
+                    to
+                )
+            }
+        }
+    }
+}
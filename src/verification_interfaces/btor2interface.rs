@@ -0,0 +1,1082 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+    mem,
+    rc::Rc,
+};
+
+use asts::{spec_lang::sl_ast, veriv_ast::*};
+
+use dwarf_ctx::dwarfreader::DwarfCtx;
+
+use utils::{constants, helpers};
+
+use crate::{
+    ir_interface::{self, IRInterface, IndentWriter, SpecLangASTInterface},
+    solver::SolverConfig,
+    verification_interfaces::uclidinterface::Uclid5Interface,
+};
+
+use rv_model::system_model;
+
+use utils::constants::BYTE_SIZE;
+
+// ========================================================================================================================
+// # BTOR2 Interface
+
+/// Emits a BTOR2 transition system for the verified procedures.
+/// Each procedure is a separate set of states (prefixed by the procedure name) that
+/// executes the whole flattened procedure body in a single transition from an entry
+/// state satisfying its requires. Failed assertions are bad states before the transition
+/// and failed ensures are bad states after it.
+/// Statements and specifications are printed as comments in the UCLID5 syntax.
+#[derive(Debug)]
+pub struct Btor2Interface;
+
+impl Btor2Interface {
+    /// Returns the addresses of the global variables and function entries by macro name
+    fn gen_global_addrs(model: &Model, dwarf_ctx: &DwarfCtx) -> HashMap<String, u64> {
+        let mut addrs = HashMap::new();
+        for var in dwarf_ctx.global_vars() {
+            addrs.insert(helpers::global_var_ptr_name(&var.name), var.memory_addr);
+        }
+        for fm in &model.func_models {
+            addrs.insert(helpers::global_func_addr_name(&fm.sig.name), fm.sig.entry_addr);
+        }
+        addrs
+    }
+
+    /// Returns the offsets of the struct fields by get field macro name
+    /// for the types of the global variables and function signatures
+    fn gen_field_offsets(dwarf_ctx: &DwarfCtx) -> HashMap<String, u64> {
+        ir_interface::struct_fields(dwarf_ctx)
+            .into_iter()
+            .map(|(id, field_name, loc)| (format!("{}_{}", id, field_name), loc))
+            .collect()
+    }
+}
+
+impl IRInterface for Btor2Interface {
+    fn lit_to_string(lit: &Literal) -> String {
+        Uclid5Interface::lit_to_string(lit)
+    }
+
+    fn typ_to_string(typ: &Type) -> String {
+        Uclid5Interface::typ_to_string(typ)
+    }
+
+    fn comp_app_to_string(compop: &CompOp, exprs: &Vec<Expr>, xlen: &u64) -> String {
+        Uclid5Interface::comp_app_to_string(compop, exprs, xlen)
+    }
+
+    fn bv_app_to_string(bvop: &BVOp, exprs: &Vec<Expr>, xlen: &u64) -> String {
+        Uclid5Interface::bv_app_to_string(bvop, exprs, xlen)
+    }
+
+    fn bool_app_to_string(bop: &BoolOp, exprs: &Vec<Expr>, xlen: &u64) -> String {
+        Uclid5Interface::bool_app_to_string(bop, exprs, xlen)
+    }
+
+    fn fapp_to_string(fapp: &FuncApp, xlen: &u64) -> String {
+        Uclid5Interface::fapp_to_string(fapp, xlen)
+    }
+
+    fn var_to_string(var: &Var) -> String {
+        Uclid5Interface::var_to_string(var)
+    }
+
+    fn array_index_to_string(arr: &Expr, index: &Expr, xlen: &u64) -> String {
+        Uclid5Interface::array_index_to_string(arr, index, xlen)
+    }
+
+    fn get_field_to_string(struct_: &Expr, field: &String, xlen: &u64) -> String {
+        Uclid5Interface::get_field_to_string(struct_, field, xlen)
+    }
+
+    /// Statements to string
+    /// Statements are listed as comments; the transition system is generated by `Btor2Gen`
+    fn skip_to_string() -> String {
+        String::new()
+    }
+
+    fn assert_to_string(expr: &Expr, xlen: &u64) -> String {
+        format!("; assert {}", Self::expr_to_string(expr, xlen))
+    }
+
+    fn assume_to_string(expr: &Expr, xlen: &u64) -> String {
+        format!("; assume {}", Self::expr_to_string(expr, xlen))
+    }
+
+    fn havoc_to_string(var: &Rc<Var>) -> String {
+        format!("; havoc {}", Self::var_to_string(var))
+    }
+
+    fn func_call_to_string(func_call: &FuncCall, xlen: &u64) -> String {
+        format!("; {}", Uclid5Interface::func_call_to_string(func_call, xlen))
+    }
+
+    fn assign_to_string(assign: &Assign, xlen: &u64) -> String {
+        format!("; {}", Uclid5Interface::assign_to_string(assign, xlen))
+    }
+
+    fn comment_to_string(string: &String) -> String {
+        format!("; {}", string.replace("\n", " "))
+    }
+
+    fn write_ite(out: &mut IndentWriter, ite: &IfThenElse, xlen: &u64) -> io::Result<()> {
+        out.line(&format!("; if ({}) {{", Self::expr_to_string(&ite.cond, xlen)))?;
+        out.indent();
+        Self::write_stmt(out, &ite.then_stmt, xlen)?;
+        out.dedent();
+        if let Some(else_stmt) = &ite.else_stmt {
+            out.line("; } else {")?;
+            out.indent();
+            Self::write_stmt(out, else_stmt, xlen)?;
+            out.dedent();
+        }
+        out.line("; }")
+    }
+
+    fn write_block(out: &mut IndentWriter, blk: &[Box<Stmt>], xlen: &u64) -> io::Result<()> {
+        for stmt in blk {
+            Self::write_stmt(out, stmt, xlen)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a comment with the signature and contract of the procedure
+    fn write_func_model(
+        out: &mut IndentWriter,
+        fm: &FuncModel,
+        _dwarf_ctx: &DwarfCtx,
+        _xlen: &u64,
+    ) -> io::Result<()> {
+        let mut mod_set = fm.sig.mod_set.iter().cloned().collect::<Vec<_>>();
+        mod_set.sort();
+        let args = fm
+            .sig
+            .arg_decls
+            .iter()
+            .map(|arg| format!("{}: {}", arg.get_var_name(), Self::typ_to_string(arg.typ())))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut lines = vec![
+            format!("; ==== Procedure {} ({:#x}) ====", fm.sig.name, fm.sig.entry_addr),
+            format!("; arguments ({})", args),
+            format!("; modifies {}", mod_set.join(", ")),
+        ];
+        for spec in &fm.sig.requires {
+            lines.push(Self::spec_to_string(spec));
+        }
+        for spec in &fm.sig.ensures {
+            lines.push(Self::spec_to_string(spec));
+        }
+        for line in &lines {
+            out.line(line)?;
+        }
+        Ok(())
+    }
+
+    fn write_model(
+        out: &mut dyn Write,
+        xlen: &u64,
+        model: &Model,
+        dwarf_ctx: &DwarfCtx,
+        ignored_funcs: &HashSet<&str>,
+        verify_funcs: &Vec<&str>,
+        _solver_config: &SolverConfig,
+    ) -> io::Result<()> {
+        let mut out = IndentWriter::new(out);
+        out.line(&format!("; BTOR2 transition system for {}", model.name))?;
+        out.line("; Each procedure executes in one transition from a state that satisfies its requires.")?;
+        out.line("; The bad properties are its failed assertions (before the transition) and ensures (after the transition).")?;
+        // The nodes of each procedure are written once it has been translated
+        let mut gen = Btor2Gen::new(model, dwarf_ctx, ignored_funcs, *xlen);
+        for fm in ir_interface::verified_funcs(model, dwarf_ctx, ignored_funcs, verify_funcs) {
+            Self::write_func_model(&mut out, fm, dwarf_ctx, xlen)?;
+            gen.func(fm);
+            for line in mem::take(&mut gen.lines) {
+                out.line(&line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SpecLangASTInterface for Btor2Interface {
+    fn bexpr_bool_to_string(b: &bool) -> String {
+        Uclid5Interface::bexpr_bool_to_string(b)
+    }
+
+    fn bexpr_bopapp_to_string(bop: &sl_ast::BoolOp, exprs: &Vec<sl_ast::BExpr>) -> String {
+        Uclid5Interface::bexpr_bopapp_to_string(bop, exprs)
+    }
+
+    fn bexpr_copapp_to_string(cop: &sl_ast::CompOp, exprs: &Vec<sl_ast::VExpr>) -> String {
+        Uclid5Interface::bexpr_copapp_to_string(cop, exprs)
+    }
+
+    fn bopp_to_string(bop: &sl_ast::BoolOp) -> String {
+        Uclid5Interface::bopp_to_string(bop)
+    }
+
+    fn cop_to_string(cop: &sl_ast::CompOp) -> String {
+        Uclid5Interface::cop_to_string(cop)
+    }
+
+    fn vexpr_bv_to_string(value: &u64, typ: &sl_ast::VType) -> String {
+        Uclid5Interface::vexpr_bv_to_string(value, typ)
+    }
+
+    fn vexpr_int_to_string(i: &i64) -> String {
+        Uclid5Interface::vexpr_int_to_string(i)
+    }
+
+    fn vexpr_bool_to_string(b: &bool) -> String {
+        Uclid5Interface::vexpr_bool_to_string(b)
+    }
+
+    fn vexpr_ident_to_string(v: &String) -> String {
+        Uclid5Interface::vexpr_ident_to_string(v)
+    }
+
+    fn vexpr_opapp_to_string(op: &sl_ast::ValueOp, exprs: &Vec<sl_ast::VExpr>) -> String {
+        Uclid5Interface::vexpr_opapp_to_string(op, exprs)
+    }
+
+    fn vexpr_funcapp_to_string(fname: &String, args: &Vec<sl_ast::VExpr>) -> String {
+        Uclid5Interface::vexpr_funcapp_to_string(fname, args)
+    }
+
+    fn valueop_to_string(op: &sl_ast::ValueOp) -> String {
+        Uclid5Interface::valueop_to_string(op)
+    }
+
+    fn vtype_to_string(typ: &sl_ast::VType) -> String {
+        Uclid5Interface::vtype_to_string(typ)
+    }
+
+    /// Spec statement to string
+    fn spec_to_string(spec: &sl_ast::Spec) -> String {
+        match spec {
+            sl_ast::Spec::Requires(bexpr, _) => format!("; requires {}", Self::bexpr_to_string(bexpr)),
+            sl_ast::Spec::Ensures(bexpr, _) => format!("; ensures {}", Self::bexpr_to_string(bexpr)),
+            _ => panic!("Unimplemented."),
+        }
+    }
+}
+
+// ========================================================================================================================
+// # Transition system generation
+
+/// BTOR2 sorts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Sort {
+    /// Bit-vector of the width (booleans are 1 bit wide)
+    Bv(u64),
+    /// Array from bit-vectors of the first width to bit-vectors of the second width
+    Array(u64, u64),
+}
+
+/// BTOR2 nodes of the procedures' transition systems
+struct Btor2Gen<'a> {
+    /// Verification model that contains the callees
+    model: &'a Model,
+    /// Functions that are replaced by their contract
+    ignored_funcs: &'a HashSet<&'a str>,
+    /// Width of the registers in bits
+    xlen: u64,
+    /// Addresses of the global variables and functions by macro name
+    global_addrs: HashMap<String, u64>,
+    /// Offsets of the struct fields by macro name
+    field_offsets: HashMap<String, u64>,
+    /// Lines of the BTOR2 file
+    lines: Vec<String>,
+    /// Identifier of the next node
+    next_id: u64,
+    /// Sort nodes
+    sorts: HashMap<Sort, u64>,
+    /// Sorts of the nodes
+    node_sorts: HashMap<u64, Sort>,
+    /// Constant nodes by value and width
+    consts: HashMap<(u64, u64), u64>,
+    /// Prefix of the state and property names of the current procedure
+    prefix: String,
+    /// Types of the state variables
+    types: HashMap<String, Type>,
+    /// Nodes of the current values of the variables
+    vars: HashMap<String, u64>,
+    /// Flag that is set after the procedure has executed
+    done: u64,
+    /// Condition of the current path (branch conditions and assumptions)
+    path: u64,
+    /// Conjunction of the assertions so far; assumptions only constrain executions
+    /// in which no assertion failed
+    valid: u64,
+    /// Number of inputs and bad properties of the current procedure
+    num_inputs: u64,
+    num_bads: u64,
+    /// Procedures that are being inlined
+    inlining: Vec<String>,
+}
+
+impl<'a> Btor2Gen<'a> {
+    fn new(model: &'a Model, dwarf_ctx: &DwarfCtx, ignored_funcs: &'a HashSet<&'a str>, xlen: u64) -> Self {
+        Btor2Gen {
+            model,
+            ignored_funcs,
+            xlen,
+            global_addrs: Btor2Interface::gen_global_addrs(model, dwarf_ctx),
+            field_offsets: Btor2Interface::gen_field_offsets(dwarf_ctx),
+            lines: vec![],
+            next_id: 1,
+            sorts: HashMap::new(),
+            node_sorts: HashMap::new(),
+            consts: HashMap::new(),
+            prefix: String::from(""),
+            types: model.vars.iter().map(|var| (var.name.clone(), var.typ.clone())).collect(),
+            vars: HashMap::new(),
+            done: 0,
+            path: 0,
+            valid: 0,
+            num_inputs: 0,
+            num_bads: 0,
+            inlining: vec![],
+        }
+    }
+
+    /// Adds the transition system of the procedure
+    fn func(&mut self, fm: &FuncModel) {
+        self.prefix = fm.sig.name.clone();
+        self.num_inputs = 0;
+        self.num_bads = 0;
+        // States
+        let mut vars = self.model.vars.iter().collect::<Vec<_>>();
+        vars.sort();
+        let bit = Sort::Bv(1);
+        self.done = self.state(bit, "done");
+        let zero = self.constant(0, 1);
+        self.untyped("init", bit, vec![self.done, zero]);
+        let mut states = HashMap::new();
+        let mut olds = HashMap::new();
+        for var in &vars {
+            let sort = self.sort_of_type(&var.typ);
+            states.insert(var.name.clone(), self.state(sort, &var.name));
+            olds.insert(var.name.clone(), self.state(sort, &format!("old.{}", var.name)));
+        }
+        let mut args = vec![];
+        for arg in &fm.sig.arg_decls {
+            let sort = self.sort_of_type(arg.typ());
+            let state = self.state(sort, &format!("arg.{}", arg.get_var_name()));
+            states.insert(arg.get_var_name(), state);
+            args.push(state);
+        }
+        // Entry state
+        self.vars = states.clone();
+        self.path = self.constant(1, 1);
+        self.valid = self.path;
+        for (desc, require) in system_model::entry_requires(fm, self.xlen) {
+            self.comment(&desc);
+            let require = self.expr(&require);
+            self.assume(require);
+        }
+        for spec in &fm.sig.requires {
+            self.lines.push(Btor2Interface::spec_to_string(spec));
+            let require = self.spec(spec.get_bexpr().unwrap(), &states);
+            self.assume(require);
+        }
+        // Procedure body
+        self.stmt(&fm.body);
+        // Transition
+        self.comment("transition");
+        let one = self.constant(1, 1);
+        self.untyped("next", bit, vec![self.done, one]);
+        for var in &vars {
+            let state = states[&var.name];
+            let old = olds[&var.name];
+            let sort = self.node_sorts[&state];
+            let value = self.vars[&var.name];
+            let next = if value == state {
+                state
+            } else {
+                self.ite(self.done, state, value)
+            };
+            self.untyped("next", sort, vec![state, next]);
+            let next_old = self.ite(self.done, old, state);
+            self.untyped("next", sort, vec![old, next_old]);
+        }
+        for arg in args {
+            let sort = self.node_sorts[&arg];
+            self.untyped("next", sort, vec![arg, arg]);
+        }
+        // Postconditions over the state after the transition
+        self.vars = states;
+        for spec in &fm.sig.ensures {
+            self.lines.push(Btor2Interface::spec_to_string(spec));
+            let ensure = self.spec(spec.get_bexpr().unwrap(), &olds);
+            let failed = self.not(ensure);
+            let bad = self.and(self.done, failed);
+            self.bad(bad, "ensures");
+        }
+    }
+
+    // ==================================================================================================================
+    // ## Statements
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Assume(expr) => {
+                let cond = self.expr(expr);
+                self.assume(cond);
+            }
+            Stmt::Assert(expr) => {
+                self.lines.push(Btor2Interface::assert_to_string(expr, &self.xlen));
+                let cond = self.expr(expr);
+                self.assert(cond, "assert");
+            }
+            Stmt::Havoc(var) => {
+                let sort = self.sort_of_type(&var.typ);
+                let input = self.input(sort, &var.name);
+                self.vars.insert(var.name.clone(), input);
+            }
+            Stmt::FuncCall(fc) => self.call(fc),
+            Stmt::Assign(assign) => self.assign(assign),
+            Stmt::IfThenElse(ite) => self.if_then_else(ite),
+            Stmt::Block(stmts) => {
+                for stmt in stmts {
+                    self.stmt(stmt);
+                }
+            }
+            Stmt::Comment(comment) => self.comment(comment),
+        }
+    }
+
+    /// Constrains the executions that reach the current path without failing an assertion
+    fn assume(&mut self, cond: u64) {
+        let reached = self.and(self.path, self.valid);
+        let holds = self.implies(reached, cond);
+        let constraint = self.or(self.done, holds);
+        self.untyped("constraint", Sort::Bv(1), vec![constraint]);
+        self.path = self.and(self.path, cond);
+    }
+
+    /// Adds a bad property for the condition failing on the current path before the transition
+    fn assert(&mut self, cond: u64, kind: &str) {
+        let failed = self.not(cond);
+        let failed_on_path = self.and(self.path, failed);
+        let not_done = self.not(self.done);
+        let bad = self.and(not_done, failed_on_path);
+        self.bad(bad, kind);
+        let holds = self.implies(self.path, cond);
+        self.valid = self.and(self.valid, holds);
+    }
+
+    /// Simultaneously assigns the values to the variables and memory locations
+    fn assign(&mut self, assign: &Assign) {
+        let values = assign.rhs.iter().map(|expr| self.expr(expr)).collect::<Vec<_>>();
+        let indices = assign
+            .lhs
+            .iter()
+            .map(|lhs| lhs.get_array_index().map(|index| self.expr(index)))
+            .collect::<Vec<_>>();
+        for ((lhs, index), value) in assign.lhs.iter().zip(indices).zip(values) {
+            match (lhs.get_array_expr(), index) {
+                (Some(arr), Some(index)) => {
+                    let name = arr.get_var_name();
+                    let arr = self.var(&name);
+                    let sort = self.node_sorts[&arr];
+                    let write = self.node(sort, "write", vec![arr, index, value]);
+                    self.vars.insert(name, write);
+                }
+                _ => {
+                    self.vars.insert(lhs.get_var_name(), value);
+                }
+            }
+        }
+    }
+
+    /// Executes both branches from the current state and merges their states
+    fn if_then_else(&mut self, ite: &IfThenElse) {
+        let cond = self.expr(&ite.cond);
+        let vars = self.vars.clone();
+        let path = self.path;
+        // Then branch
+        let then_path = self.and(path, cond);
+        self.path = then_path;
+        self.stmt(&ite.then_stmt);
+        let then_vars = mem::replace(&mut self.vars, vars);
+        let then_end = self.path;
+        // Else branch
+        let not_cond = self.not(cond);
+        let else_path = self.and(path, not_cond);
+        self.path = else_path;
+        if let Some(else_stmt) = &ite.else_stmt {
+            self.stmt(else_stmt);
+        }
+        let else_end = self.path;
+        // Merge the states
+        let mut names = then_vars.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            let then_val = then_vars[&name];
+            match self.vars.get(&name).cloned() {
+                Some(else_val) if else_val != then_val => {
+                    let merged = self.ite(cond, then_val, else_val);
+                    self.vars.insert(name, merged);
+                }
+                Some(_) => (),
+                None => {
+                    self.vars.insert(name, then_val);
+                }
+            }
+        }
+        self.path = if then_end == then_path && else_end == else_path {
+            path
+        } else {
+            self.ite(cond, then_end, else_end)
+        };
+    }
+
+    /// Inlines the callee or, for ignored and recursive procedures, replaces it by its contract
+    fn call(&mut self, fc: &FuncCall) {
+        let model = self.model;
+        let callee = model
+            .func_models
+            .iter()
+            .find(|fm| fm.sig.name == fc.func_name)
+            .unwrap_or_else(|| panic!("Unable to find the procedure {}.", fc.func_name));
+        // Bind the formal arguments
+        let args = fc.operands.iter().map(|expr| self.expr(expr)).collect::<Vec<_>>();
+        let saved = callee
+            .sig
+            .arg_decls
+            .iter()
+            .map(|arg| (arg.get_var_name(), self.vars.get(&arg.get_var_name()).cloned()))
+            .collect::<Vec<_>>();
+        for (arg, value) in callee.sig.arg_decls.iter().zip(args) {
+            self.vars.insert(arg.get_var_name(), value);
+        }
+        let recursive = self.inlining.contains(&callee.sig.name);
+        if recursive && callee.inline {
+            panic!("Unable to inline the recursive procedure {}.", callee.sig.name);
+        }
+        // Check the preconditions
+        let entry = self.vars.clone();
+        if !callee.inline {
+            for spec in &callee.sig.requires {
+                let bexpr = spec.get_bexpr().unwrap();
+                self.comment(&format!(
+                    "requires {} of {}",
+                    Btor2Interface::bexpr_to_string(bexpr),
+                    callee.sig.name
+                ));
+                let require = self.spec(bexpr, &entry);
+                self.assert(require, "requires");
+            }
+        }
+        if !recursive && !self.ignored_funcs.contains(&callee.sig.name[..]) {
+            self.inlining.push(callee.sig.name.clone());
+            self.stmt(&callee.body);
+            self.inlining.pop();
+        } else {
+            if recursive {
+                warn!("Using the contract of the recursive procedure {}.", callee.sig.name);
+            }
+            // Havoc the modifies set and assume the postconditions
+            let mut mod_set = callee.sig.mod_set.iter().cloned().collect::<Vec<_>>();
+            mod_set.sort();
+            for name in mod_set {
+                match self.types.get(&name).cloned() {
+                    Some(typ) => {
+                        let sort = self.sort_of_type(&typ);
+                        let input = self.input(sort, &name);
+                        self.vars.insert(name, input);
+                    }
+                    None => warn!("Unable to find the type of {} modified by {}.", name, callee.sig.name),
+                }
+            }
+            for spec in &callee.sig.ensures {
+                let ensure = self.spec(spec.get_bexpr().unwrap(), &entry);
+                self.assume(ensure);
+            }
+        }
+        // Return values are not modeled
+        for lhs in &fc.lhs {
+            let sort = self.sort_of_type(lhs.typ());
+            let input = self.input(sort, &lhs.get_var_name());
+            self.vars.insert(lhs.get_var_name(), input);
+        }
+        // Restore the formal arguments of the caller
+        for (name, node) in saved {
+            match node {
+                Some(node) => self.vars.insert(name, node),
+                None => self.vars.remove(&name),
+            };
+        }
+    }
+
+    // ==================================================================================================================
+    // ## Expressions
+
+    fn expr(&mut self, expr: &Expr) -> u64 {
+        match expr {
+            Expr::Literal(Literal::Bv { val, width }, _) => self.constant(*val, *width),
+            Expr::Literal(Literal::Bool { val }, _) => self.constant(*val as u64, 1),
+            Expr::Literal(Literal::Int { .. }, _) => panic!("Integers are not supported in BTOR2 ({}).", expr),
+            Expr::Var(var, _) => self.var(&var.name),
+            Expr::FuncApp(fapp, _) => {
+                let args = fapp.operands.iter().map(|expr| self.expr(expr)).collect::<Vec<_>>();
+                self.macro_app(&fapp.func_name, args)
+            }
+            Expr::OpApp(opapp, _) => {
+                let operands = &opapp.operands;
+                match &opapp.op {
+                    Op::Comp(cop) => {
+                        let op = match cop {
+                            CompOp::Equality => "eq",
+                            CompOp::Inequality => "neq",
+                            CompOp::Lt => "slt",
+                            CompOp::Le => "slte",
+                            CompOp::Gt => "sgt",
+                            CompOp::Ge => "sgte",
+                            CompOp::Ltu => "ult",
+                            CompOp::Leu => "ulte",
+                            CompOp::Gtu => "ugt",
+                            CompOp::Geu => "ugte",
+                        };
+                        let a = self.expr(&operands[0]);
+                        let b = self.expr(&operands[1]);
+                        self.node(Sort::Bv(1), op, vec![a, b])
+                    }
+                    Op::Bv(BVOp::SignExt) | Op::Bv(BVOp::ZeroExt) => {
+                        let a = self.expr(&operands[0]);
+                        let n = operands[1]
+                            .get_lit_value()
+                            .expect("The width of an extension should be a literal.");
+                        let op = if let Op::Bv(BVOp::SignExt) = &opapp.op { "sext" } else { "uext" };
+                        self.extend(op, a, n)
+                    }
+                    Op::Bv(BVOp::Slice { l, r }) => {
+                        let a = self.expr(&operands[0]);
+                        self.slice(a, *l, *r)
+                    }
+                    Op::Bv(BVOp::Concat) => {
+                        let a = self.expr(&operands[0]);
+                        let b = self.expr(&operands[1]);
+                        self.concat(a, b)
+                    }
+                    Op::Bv(bvop) => {
+                        let op = match bvop {
+                            BVOp::Add => "add",
+                            BVOp::Sub => "sub",
+                            BVOp::Mul => "mul",
+                            BVOp::And => "and",
+                            BVOp::Or => "or",
+                            BVOp::Xor => "xor",
+                            BVOp::LeftShift => "sll",
+                            BVOp::RightShift => "srl",
+                            BVOp::ARightShift => "sra",
+                            _ => unreachable!(),
+                        };
+                        let a = self.expr(&operands[0]);
+                        let b = self.expr(&operands[1]);
+                        self.binary(op, a, b)
+                    }
+                    Op::Bool(bop) => {
+                        let a = self.expr(&operands[0]);
+                        match bop {
+                            BoolOp::Neg => self.not(a),
+                            _ => {
+                                let b = self.expr(&operands[1]);
+                                let op = match bop {
+                                    BoolOp::Conj => "and",
+                                    BoolOp::Disj => "or",
+                                    BoolOp::Iff => "iff",
+                                    BoolOp::Impl => "implies",
+                                    BoolOp::Neg => unreachable!(),
+                                };
+                                self.node(Sort::Bv(1), op, vec![a, b])
+                            }
+                        }
+                    }
+                    Op::ArrayIndex => {
+                        let arr = self.expr(&operands[0]);
+                        let index = self.expr(&operands[1]);
+                        self.read(arr, index)
+                    }
+                    Op::GetField(_) => panic!("Struct values are not supported in BTOR2 ({}).", expr),
+                }
+            }
+        }
+    }
+
+    /// Returns the specification over the current values of the variables;
+    /// `old(e)` refers to the values in `old`
+    fn spec(&mut self, bexpr: &sl_ast::BExpr, old: &HashMap<String, u64>) -> u64 {
+        match bexpr {
+            sl_ast::BExpr::Bool(b) => self.constant(*b as u64, 1),
+            sl_ast::BExpr::PredApp(name, _) => {
+                panic!("Implementation error; predicate {} should be expanded by the spec passes.", name)
+            }
+            sl_ast::BExpr::BOpApp(bop, bexprs) => {
+                let op = match bop {
+                    sl_ast::BoolOp::Conj => "and",
+                    sl_ast::BoolOp::Disj => "or",
+                    sl_ast::BoolOp::Implies => "implies",
+                    sl_ast::BoolOp::Neg => {
+                        let a = self.spec(&bexprs[0], old);
+                        return self.not(a);
+                    }
+                    sl_ast::BoolOp::Forall(_, _) | sl_ast::BoolOp::Exists(_, _) => panic!(
+                        "Quantified specifications are not supported in BTOR2 ({}).",
+                        Btor2Interface::bexpr_to_string(bexpr)
+                    ),
+                };
+                let first = self.spec(&bexprs[0], old);
+                bexprs.iter().skip(1).fold(first, |acc, bexpr| {
+                    let b = self.spec(bexpr, old);
+                    self.node(Sort::Bv(1), op, vec![acc, b])
+                })
+            }
+            sl_ast::BExpr::COpApp(cop, vexprs) => {
+                let op = match cop {
+                    sl_ast::CompOp::Equal => "eq",
+                    sl_ast::CompOp::Nequal => "neq",
+                    sl_ast::CompOp::Gt => "sgt",
+                    sl_ast::CompOp::Lt => "slt",
+                    sl_ast::CompOp::Gtu => "ugt",
+                    sl_ast::CompOp::Ltu => "ult",
+                    sl_ast::CompOp::Geq => "sgte",
+                    sl_ast::CompOp::Leq => "slte",
+                    sl_ast::CompOp::Geu => "ugte",
+                    sl_ast::CompOp::Leu => "ulte",
+                };
+                let a = self.vexpr(&vexprs[0], old);
+                let b = self.vexpr(&vexprs[1], old);
+                self.node(Sort::Bv(1), op, vec![a, b])
+            }
+        }
+    }
+
+    fn vexpr(&mut self, vexpr: &sl_ast::VExpr, old: &HashMap<String, u64>) -> u64 {
+        match vexpr {
+            sl_ast::VExpr::Bv { value, typ } => {
+                let width = match typ {
+                    sl_ast::VType::Bv(width) => *width as u64,
+                    sl_ast::VType::Array { in_type, out_type: _ } => in_type.get_bv_width() as u64,
+                    _ => panic!("Should be bv typed but is {:?}.", typ),
+                };
+                self.constant(*value, width)
+            }
+            sl_ast::VExpr::Bool(b, _) => self.constant(*b as u64, 1),
+            sl_ast::VExpr::Int(i, _) => panic!("Integers are not supported in BTOR2 ({}).", i),
+            sl_ast::VExpr::Ident(name, _) => self.var(name),
+            sl_ast::VExpr::FuncApp(fname, args, _) => match &fname[..] {
+                "old" => {
+                    let cur = mem::replace(&mut self.vars, old.clone());
+                    let value = self.vexpr(&args[0], old);
+                    self.vars = cur;
+                    value
+                }
+                "sext" | "uext" => {
+                    let n = match &args[0] {
+                        sl_ast::VExpr::Bv { value, typ: _ } => *value,
+                        sl_ast::VExpr::Int(i, _) => *i as u64,
+                        _ => panic!("The width of an extension should be a literal but found {:?}.", args[0]),
+                    };
+                    let a = self.vexpr(&args[1], old);
+                    self.extend(if fname == "sext" { "sext" } else { "uext" }, a, n)
+                }
+                _ => {
+                    let args = args.iter().map(|arg| self.vexpr(arg, old)).collect::<Vec<_>>();
+                    self.macro_app(fname, args)
+                }
+            },
+            sl_ast::VExpr::OpApp(op, vexprs, _) => match op {
+                sl_ast::ValueOp::Add
+                | sl_ast::ValueOp::Sub
+                | sl_ast::ValueOp::Mul
+                | sl_ast::ValueOp::BvXor
+                | sl_ast::ValueOp::BvOr
+                | sl_ast::ValueOp::BvAnd => {
+                    let op = match op {
+                        sl_ast::ValueOp::Add => "add",
+                        sl_ast::ValueOp::Sub => "sub",
+                        sl_ast::ValueOp::Mul => "mul",
+                        sl_ast::ValueOp::BvXor => "xor",
+                        sl_ast::ValueOp::BvOr => "or",
+                        _ => "and",
+                    };
+                    let first = self.vexpr(&vexprs[0], old);
+                    vexprs.iter().skip(1).fold(first, |acc, vexpr| {
+                        let b = self.vexpr(vexpr, old);
+                        self.binary(op, acc, b)
+                    })
+                }
+                sl_ast::ValueOp::RightShift
+                | sl_ast::ValueOp::URightShift
+                | sl_ast::ValueOp::LeftShift => {
+                    let op = match op {
+                        sl_ast::ValueOp::RightShift => "sra",
+                        sl_ast::ValueOp::URightShift => "srl",
+                        _ => "sll",
+                    };
+                    let a = self.vexpr(&vexprs[0], old);
+                    let b = self.vexpr(&vexprs[1], old);
+                    let b = self.fit(b, self.width(a));
+                    self.binary(op, a, b)
+                }
+                sl_ast::ValueOp::ArrayIndex => {
+                    let bytes = vexprs[0].typ().get_array_out_type_size() / BYTE_SIZE;
+                    let base = self.vexpr(&vexprs[0], old);
+                    let index = self.vexpr(&vexprs[1], old);
+                    self.macro_app(&format!("index_by_{}", bytes), vec![base, index])
+                }
+                sl_ast::ValueOp::GetField => {
+                    let struct_id = match vexprs[0].typ() {
+                        sl_ast::VType::Struct { id, .. } => id.clone(),
+                        _ => panic!("Expected struct type."),
+                    };
+                    let base = self.vexpr(&vexprs[0], old);
+                    let field = vexprs[1].get_ident_name();
+                    self.macro_app(&format!("{}_{}", struct_id, field), vec![base])
+                }
+                sl_ast::ValueOp::Deref => {
+                    let bytes = vexprs[0].typ().get_bv_width() as u64 / BYTE_SIZE;
+                    let mem = self.var(Self::mem_var_name(bytes));
+                    let addr = self.vexpr(&vexprs[0], old);
+                    self.read(mem, addr)
+                }
+                sl_ast::ValueOp::Concat => {
+                    let a = self.vexpr(&vexprs[0], old);
+                    let b = self.vexpr(&vexprs[1], old);
+                    self.concat(a, b)
+                }
+                sl_ast::ValueOp::Slice { hi, lo } => {
+                    let a = self.vexpr(&vexprs[0], old);
+                    self.slice(a, *hi as u64, *lo as u64)
+                }
+                sl_ast::ValueOp::Div => panic!("Unimplemented value op {:#?}.", op),
+            },
+        }
+    }
+
+    /// Returns the value of an application of a helper macro of the UCLID5 model
+    fn macro_app(&mut self, name: &str, args: Vec<u64>) -> u64 {
+        if let Some(addr) = self.global_addrs.get(name).cloned() {
+            return self.constant(addr, self.xlen);
+        }
+        if let Some(offset) = self.field_offsets.get(name).cloned() {
+            let base = self.fit(args[0], self.xlen);
+            let offset = self.constant(offset, self.xlen);
+            return self.binary("add", base, offset);
+        }
+        if let Some(bytes) = name.strip_prefix("index_by_") {
+            let bytes = bytes
+                .parse::<u64>()
+                .unwrap_or_else(|_| panic!("Invalid array index macro {}.", name));
+            let base = self.fit(args[0], self.xlen);
+            let index = self.fit(args[1], self.xlen);
+            let size = self.constant(bytes, self.xlen);
+            let offset = self.binary("mul", index, size);
+            return self.binary("add", base, offset);
+        }
+        if name.starts_with("deref_") {
+            return self.read(args[0], args[1]);
+        }
+        panic!("Unable to find the definition of {} for BTOR2.", name)
+    }
+
+    // ==================================================================================================================
+    // ## Nodes
+
+    /// Adds a node of the sort and returns its identifier
+    fn node(&mut self, sort: Sort, op: &str, args: Vec<u64>) -> u64 {
+        let sid = self.sort_id(sort);
+        let args = args.iter().map(|arg| format!(" {}", arg)).collect::<String>();
+        let id = self.line(format!("{} {}{}", op, sid, args));
+        self.node_sorts.insert(id, sort);
+        id
+    }
+
+    /// Adds a line that takes the sort and nodes as arguments and does not define a value
+    fn untyped(&mut self, op: &str, sort: Sort, args: Vec<u64>) -> u64 {
+        let args = args.iter().map(|arg| format!(" {}", arg)).collect::<String>();
+        match op {
+            "constraint" | "bad" => self.line(format!("{}{}", op, args)),
+            _ => {
+                let sid = self.sort_id(sort);
+                self.line(format!("{} {}{}", op, sid, args))
+            }
+        }
+    }
+
+    /// Adds a numbered line
+    fn line(&mut self, line: String) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.lines.push(format!("{} {}", id, line));
+        id
+    }
+
+    fn comment(&mut self, comment: &str) {
+        self.lines.push(format!("; {}", comment.replace("\n", " ")));
+    }
+
+    fn sort_id(&mut self, sort: Sort) -> u64 {
+        if let Some(id) = self.sorts.get(&sort) {
+            return *id;
+        }
+        let id = match sort {
+            Sort::Bv(w) => self.line(format!("sort bitvec {}", w)),
+            Sort::Array(iw, ow) => {
+                let in_sid = self.sort_id(Sort::Bv(iw));
+                let out_sid = self.sort_id(Sort::Bv(ow));
+                self.line(format!("sort array {} {}", in_sid, out_sid))
+            }
+        };
+        self.sorts.insert(sort, id);
+        id
+    }
+
+    fn sort_of_type(&self, typ: &Type) -> Sort {
+        match typ {
+            Type::Bool => Sort::Bv(1),
+            Type::Bv { w } => Sort::Bv(*w),
+            Type::Array { in_typs, out_typ } => {
+                Sort::Array(in_typs[0].get_expect_bv_width(), out_typ.get_expect_bv_width())
+            }
+            _ => panic!("Unable to represent the type {} in BTOR2.", typ),
+        }
+    }
+
+    fn width(&self, node: u64) -> u64 {
+        match self.node_sorts[&node] {
+            Sort::Bv(w) => w,
+            Sort::Array(_, _) => panic!("Expected a bit-vector but found an array."),
+        }
+    }
+
+    fn state(&mut self, sort: Sort, name: &str) -> u64 {
+        let sid = self.sort_id(sort);
+        let id = self.line(format!("state {} {}.{}", sid, self.prefix, name));
+        self.node_sorts.insert(id, sort);
+        id
+    }
+
+    fn input(&mut self, sort: Sort, name: &str) -> u64 {
+        let sid = self.sort_id(sort);
+        let id = self.line(format!("input {} {}.havoc.{}.{}", sid, self.prefix, name, self.num_inputs));
+        self.num_inputs += 1;
+        self.node_sorts.insert(id, sort);
+        id
+    }
+
+    fn bad(&mut self, node: u64, kind: &str) {
+        let symbol = format!("{}.{}_{}", self.prefix, kind, self.num_bads);
+        self.num_bads += 1;
+        self.line(format!("bad {} {}", node, symbol));
+    }
+
+    /// Returns the constant node; the value is truncated to the width
+    fn constant(&mut self, val: u64, width: u64) -> u64 {
+        let val = if width < 64 { val & ((1 << width) - 1) } else { val };
+        if let Some(id) = self.consts.get(&(val, width)) {
+            return *id;
+        }
+        let sid = self.sort_id(Sort::Bv(width));
+        let id = self.line(format!("const {} {:0w$b}", sid, val, w = width as usize));
+        self.node_sorts.insert(id, Sort::Bv(width));
+        self.consts.insert((val, width), id);
+        id
+    }
+
+    fn var(&self, name: &str) -> u64 {
+        *self
+            .vars
+            .get(name)
+            .unwrap_or_else(|| panic!("Unable to find the variable {} in procedure {}.", name, self.prefix))
+    }
+
+    fn binary(&mut self, op: &str, a: u64, b: u64) -> u64 {
+        let sort = self.node_sorts[&a];
+        self.node(sort, op, vec![a, b])
+    }
+
+    fn not(&mut self, a: u64) -> u64 {
+        self.node(Sort::Bv(1), "not", vec![a])
+    }
+
+    fn and(&mut self, a: u64, b: u64) -> u64 {
+        self.node(Sort::Bv(1), "and", vec![a, b])
+    }
+
+    fn or(&mut self, a: u64, b: u64) -> u64 {
+        self.node(Sort::Bv(1), "or", vec![a, b])
+    }
+
+    fn implies(&mut self, a: u64, b: u64) -> u64 {
+        self.node(Sort::Bv(1), "implies", vec![a, b])
+    }
+
+    fn ite(&mut self, cond: u64, a: u64, b: u64) -> u64 {
+        let sort = self.node_sorts[&a];
+        self.node(sort, "ite", vec![cond, a, b])
+    }
+
+    fn read(&mut self, arr: u64, index: u64) -> u64 {
+        let (iw, ow) = match self.node_sorts[&arr] {
+            Sort::Array(iw, ow) => (iw, ow),
+            Sort::Bv(_) => panic!("Expected an array but found a bit-vector."),
+        };
+        let index = self.fit(index, iw);
+        self.node(Sort::Bv(ow), "read", vec![arr, index])
+    }
+
+    fn extend(&mut self, op: &str, a: u64, n: u64) -> u64 {
+        if n == 0 {
+            return a;
+        }
+        let sort = Sort::Bv(self.width(a) + n);
+        let sid = self.sort_id(sort);
+        let id = self.line(format!("{} {} {} {}", op, sid, a, n));
+        self.node_sorts.insert(id, sort);
+        id
+    }
+
+    fn slice(&mut self, a: u64, hi: u64, lo: u64) -> u64 {
+        let sort = Sort::Bv(hi - lo + 1);
+        let sid = self.sort_id(sort);
+        let id = self.line(format!("slice {} {} {} {}", sid, a, hi, lo));
+        self.node_sorts.insert(id, sort);
+        id
+    }
+
+    fn concat(&mut self, a: u64, b: u64) -> u64 {
+        let sort = Sort::Bv(self.width(a) + self.width(b));
+        self.node(sort, "concat", vec![a, b])
+    }
+
+    /// Zero extends or truncates the bit-vector to the width
+    fn fit(&mut self, a: u64, width: u64) -> u64 {
+        let w = self.width(a);
+        if w < width {
+            self.extend("uext", a, width - w)
+        } else if w > width {
+            self.slice(a, width - 1, 0)
+        } else {
+            a
+        }
+    }
+
+    /// Returns the name of the memory variable for accesses of the given number of bytes
+    fn mem_var_name(bytes: u64) -> &'static str {
+        match bytes {
+            1 => constants::MEM_VAR_B,
+            2 => constants::MEM_VAR_H,
+            4 => constants::MEM_VAR_W,
+            8 => constants::MEM_VAR_D,
+            _ => panic!("Cannot dereference values that are not 1, 2, 4, or 8 bytes."),
+        }
+    }
+}
@@ -1 +1,4 @@
 pub mod uclidinterface;
+pub mod smtlibinterface;
+pub mod boogieinterface;
+pub mod btor2interface;
@@ -0,0 +1,1216 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+    mem,
+    rc::Rc,
+};
+
+use asts::{spec_lang::sl_ast, veriv_ast::*};
+
+use dwarf_ctx::dwarfreader::DwarfCtx;
+
+use utils::{constants, helpers};
+
+use crate::{
+    ir_interface::{self, IRInterface, IndentWriter, SpecLangASTInterface},
+    solver::SolverConfig,
+};
+
+use rv_model::system_model;
+
+use utils::constants::BYTE_SIZE;
+
+// ========================================================================================================================
+// # SMT-LIB2 Interface
+
+/// Emits a SMT-LIB2 verification condition for each verified procedure.
+/// Procedure bodies are converted to SSA form over their acyclic block structure:
+/// basic block procedures (and all procedures when specifications are ignored) are
+/// inlined and the remaining calls are replaced by the callee's contract.
+#[derive(Debug)]
+pub struct SmtLib2Interface;
+
+impl SmtLib2Interface {
+    /// Returns the define-fun macros for dereferencing 1, 2, 4, and 8 byte values
+    fn prelude(xlen: &u64) -> String {
+        let addr = Expr::var("addr", system_model::bv_type(*xlen));
+        let defns = [1, 2, 4, 8]
+            .iter()
+            .map(|bytes| {
+                let mem_typ = Type::Array {
+                    in_typs: vec![Box::new(system_model::bv_type(*xlen))],
+                    out_typ: Box::new(system_model::bv_type(bytes * BYTE_SIZE)),
+                };
+                let load = Self::array_index_to_string(&Expr::var("mem", mem_typ.clone()), &addr, xlen);
+                format!(
+                    "(define-fun deref_{} ((mem {}) (addr {})) {} {})",
+                    bytes,
+                    Self::typ_to_string(&mem_typ),
+                    Self::typ_to_string(addr.typ()),
+                    Self::typ_to_string(&system_model::bv_type(bytes * BYTE_SIZE)),
+                    load
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("; Dereference helpers\n{}\n", defns.join("\n"))
+    }
+
+    /// Returns the define-fun macros that index into arrays and get struct fields
+    /// for the types of the global variables and function signatures
+    fn gen_type_defns(dwarf_ctx: &DwarfCtx, xlen: &u64) -> String {
+        let bv = Self::typ_to_string(&system_model::bv_type(*xlen));
+        let index_defns = ir_interface::array_index_sizes(dwarf_ctx)
+            .iter()
+            .map(|bytes| {
+                format!(
+                    "(define-fun index_by_{} ((base {}) (index {})) {} (bvadd base (bvmul index {})))",
+                    bytes,
+                    bv,
+                    bv,
+                    bv,
+                    Self::lit_to_string(&Literal::Bv {
+                        val: *bytes,
+                        width: *xlen
+                    })
+                )
+            })
+            .collect::<Vec<_>>();
+        let field_defns = ir_interface::struct_fields(dwarf_ctx)
+            .iter()
+            .map(|(id, field_name, loc)| {
+                format!(
+                    "(define-fun {}_{} ((ptr {})) {} (bvadd ptr {}))",
+                    id,
+                    field_name,
+                    bv,
+                    bv,
+                    Self::lit_to_string(&Literal::Bv {
+                        val: *loc,
+                        width: *xlen
+                    })
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "; Array helpers\n{}\n; Struct helpers\n{}\n",
+            index_defns.join("\n"),
+            field_defns.join("\n")
+        )
+    }
+
+    /// Returns the macros for the addresses of the global variables and the function entries
+    fn gen_global_defns(model: &Model, dwarf_ctx: &DwarfCtx, xlen: &u64) -> String {
+        let bv = Self::typ_to_string(&system_model::bv_type(*xlen));
+        let global_vars = dwarf_ctx
+            .global_vars()
+            .iter()
+            .map(|var| {
+                format!(
+                    "(define-fun {} () {} {})",
+                    Self::symbol(&helpers::global_var_ptr_name(&var.name)),
+                    bv,
+                    Self::lit_to_string(&Literal::Bv {
+                        val: var.memory_addr,
+                        width: *xlen
+                    })
+                )
+            })
+            .collect::<Vec<_>>();
+        let global_funcs = model
+            .func_models
+            .iter()
+            .map(|fm| {
+                format!(
+                    "(define-fun {} () {} {})",
+                    Self::symbol(&helpers::global_func_addr_name(&fm.sig.name)),
+                    bv,
+                    Self::lit_to_string(&Literal::Bv {
+                        val: fm.sig.entry_addr,
+                        width: *xlen
+                    })
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "; Global variables\n{}\n; Global function entry addresses\n{}\n",
+            global_vars.join("\n"),
+            global_funcs.join("\n")
+        )
+    }
+
+    /// Writes the verification condition of the procedure.
+    /// The procedure's obligations hold iff the solver returns unsat.
+    fn write_func_vc(
+        out: &mut IndentWriter,
+        model: &Model,
+        fm: &FuncModel,
+        dwarf_ctx: &DwarfCtx,
+        xlen: &u64,
+    ) -> io::Result<()> {
+        let mut vc = VcGen::new(model, *xlen);
+        let mut vars = model.vars.iter().collect::<Vec<_>>();
+        vars.sort();
+        for var in vars {
+            vc.declare(&var.name, &var.typ);
+        }
+        for arg in &fm.sig.arg_decls {
+            vc.declare(&arg.get_var_name(), arg.typ());
+        }
+        // Assume the preconditions
+        let entry = vc.versions.clone();
+        for (_, require) in vc.entry_requires(fm) {
+            vc.assume(require);
+        }
+        for spec in &fm.sig.requires {
+            let require = vc.spec(spec.get_bexpr().unwrap(), &entry);
+            vc.assume(require);
+        }
+        // Procedure body
+        vc.stmt(&fm.body);
+        // Assert the postconditions
+        for spec in &fm.sig.ensures {
+            let bexpr = spec.get_bexpr().unwrap();
+            let ensure = vc.spec(bexpr, &entry);
+            vc.assert(format!("ensures {}", Self::bexpr_to_string(bexpr)), ensure);
+        }
+        let obligations = vc
+            .obligations
+            .iter()
+            .enumerate()
+            .map(|(i, (desc, obligation))| {
+                format!(
+                    "; {}\n(define-fun obligation_{} () Bool {})",
+                    desc.replace("\n", " "),
+                    i,
+                    obligation
+                )
+            })
+            .collect::<Vec<_>>();
+        let goal = VcGen::conj(
+            &(0..obligations.len())
+                .map(|i| format!("obligation_{}", i))
+                .collect::<Vec<_>>(),
+        );
+        Self::write_func_model(out, fm, dwarf_ctx, xlen)?;
+        out.line("(push 1)")?;
+        for defn in &vc.defns {
+            out.line(defn)?;
+        }
+        out.line("; Proof obligations")?;
+        for obligation in &obligations {
+            out.line(obligation)?;
+        }
+        out.line(&format!("(assert (not {}))", goal))?;
+        out.line(&format!("(echo \"{}\")", fm.sig.name))?;
+        out.line("(check-sat)")?;
+        out.line("(pop 1)")
+    }
+
+    /// Returns true if a specification in the verification condition of the procedure is quantified;
+    /// these are the specifications of the procedure and of the callees that are not inlined
+    fn uses_quantifiers(model: &Model, fm: &FuncModel) -> bool {
+        Self::specs_use_quantifiers(&fm.sig)
+            || Self::stmt_uses_quantifiers(model, &fm.body, &mut HashSet::new())
+    }
+
+    fn stmt_uses_quantifiers<'m>(model: &'m Model, stmt: &'m Stmt, visited: &mut HashSet<&'m str>) -> bool {
+        match stmt {
+            Stmt::FuncCall(fc) => {
+                if !visited.insert(&fc.func_name) {
+                    return false;
+                }
+                match model.func_models.iter().find(|fm| fm.sig.name == fc.func_name) {
+                    Some(callee) if callee.inline => Self::stmt_uses_quantifiers(model, &callee.body, visited),
+                    Some(callee) => Self::specs_use_quantifiers(&callee.sig),
+                    None => false,
+                }
+            }
+            Stmt::IfThenElse(ite) => {
+                Self::stmt_uses_quantifiers(model, &ite.then_stmt, visited)
+                    || matches!(&ite.else_stmt, Some(else_stmt) if Self::stmt_uses_quantifiers(model, else_stmt, visited))
+            }
+            Stmt::Block(stmts) => stmts
+                .iter()
+                .any(|stmt| Self::stmt_uses_quantifiers(model, stmt, visited)),
+            _ => false,
+        }
+    }
+
+    fn specs_use_quantifiers(sig: &FuncSig) -> bool {
+        sig.requires
+            .iter()
+            .chain(sig.ensures.iter())
+            .filter_map(|spec| spec.get_bexpr().ok())
+            .any(Self::is_quantified)
+    }
+
+    fn is_quantified(bexpr: &sl_ast::BExpr) -> bool {
+        match bexpr {
+            sl_ast::BExpr::BOpApp(sl_ast::BoolOp::Forall(_, _), _)
+            | sl_ast::BExpr::BOpApp(sl_ast::BoolOp::Exists(_, _), _) => true,
+            sl_ast::BExpr::BOpApp(_, exprs) => exprs.iter().any(Self::is_quantified),
+            _ => false,
+        }
+    }
+
+    // ==================================================================================================================
+    // # Helper functions
+
+    /// Returns the name as a SMT-LIB2 symbol; names with characters that are not
+    /// allowed in simple symbols are quoted
+    fn symbol(name: &str) -> String {
+        let is_simple = name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "~!@$%^&*_-+=<>.?/".contains(c))
+            && name.chars().next().is_some_and(|c| !c.is_ascii_digit());
+        if is_simple {
+            name.to_string()
+        } else {
+            format!("|{}|", name)
+        }
+    }
+
+    /// Returns a bit-vector literal of the width; the value is truncated to the width
+    fn bv_lit_to_string(val: u64, width: u64) -> String {
+        let val = if width < 64 { val & ((1 << width) - 1) } else { val };
+        format!("(_ bv{} {})", val, width)
+    }
+
+    /// Returns the value of a width argument of an extension
+    fn ext_width(expr: &Expr) -> u64 {
+        expr.get_lit_value()
+            .expect("The width of an extension should be a literal.")
+    }
+}
+
+impl IRInterface for SmtLib2Interface {
+    /// IR translation functions
+    fn lit_to_string(lit: &Literal) -> String {
+        match lit {
+            Literal::Bv { val, width } => Self::bv_lit_to_string(*val, *width),
+            Literal::Bool { val } => format!("{}", val),
+            Literal::Int { val } => format!("{}", val),
+        }
+    }
+
+    fn typ_to_string(typ: &Type) -> String {
+        match typ {
+            Type::Unknown => panic!("Type is unknown!"),
+            Type::Bool => "Bool".to_string(),
+            Type::Int => "Int".to_string(),
+            Type::Bv { w } => format!("(_ BitVec {})", w),
+            Type::Array { in_typs, out_typ } => in_typs
+                .iter()
+                .rev()
+                .fold(Self::typ_to_string(out_typ), |acc, in_typ| {
+                    format!("(Array {} {})", Self::typ_to_string(in_typ), acc)
+                }),
+            Type::Struct {
+                id: _,
+                fields: _,
+                w: _,
+            } => panic!("Should not need to print struct types in this model."),
+        }
+    }
+
+    fn comp_app_to_string(compop: &CompOp, exprs: &Vec<Expr>, xlen: &u64) -> String {
+        assert!(
+            exprs.len() == 2,
+            "Comparison operator should have two expressions."
+        );
+        let e1 = Self::expr_to_string(&exprs[0], xlen);
+        let e2 = Self::expr_to_string(&exprs[1], xlen);
+        let op = match compop {
+            CompOp::Equality => "=",
+            CompOp::Inequality => "distinct",
+            CompOp::Lt => "bvslt",
+            CompOp::Le => "bvsle",
+            CompOp::Gt => "bvsgt",
+            CompOp::Ge => "bvsge",
+            CompOp::Ltu => "bvult",
+            CompOp::Leu => "bvule",
+            CompOp::Gtu => "bvugt",
+            CompOp::Geu => "bvuge",
+        };
+        format!("({} {} {})", op, e1, e2)
+    }
+
+    fn bv_app_to_string(bvop: &BVOp, exprs: &Vec<Expr>, xlen: &u64) -> String {
+        let e1 = Self::expr_to_string(&exprs[0], xlen);
+        let e2 = if exprs.len() > 1 {
+            Some(Self::expr_to_string(&exprs[1], xlen))
+        } else {
+            None
+        };
+        match bvop {
+            BVOp::Add => format!("(bvadd {} {})", e1, e2.unwrap()),
+            BVOp::Sub => format!("(bvsub {} {})", e1, e2.unwrap()),
+            BVOp::Mul => format!("(bvmul {} {})", e1, e2.unwrap()),
+            BVOp::And => format!("(bvand {} {})", e1, e2.unwrap()),
+            BVOp::Or => format!("(bvor {} {})", e1, e2.unwrap()),
+            BVOp::Xor => format!("(bvxor {} {})", e1, e2.unwrap()),
+            BVOp::SignExt => match Self::ext_width(&exprs[1]) {
+                0 => e1,
+                width => format!("((_ sign_extend {}) {})", width, e1),
+            },
+            BVOp::ZeroExt => match Self::ext_width(&exprs[1]) {
+                0 => e1,
+                width => format!("((_ zero_extend {}) {})", width, e1),
+            },
+            BVOp::LeftShift => format!("(bvshl {} {})", e1, e2.unwrap()),
+            BVOp::RightShift => format!("(bvlshr {} {})", e1, e2.unwrap()),
+            BVOp::ARightShift => format!("(bvashr {} {})", e1, e2.unwrap()),
+            BVOp::Concat => format!("(concat {} {})", e1, e2.unwrap()),
+            BVOp::Slice { l, r } => format!("((_ extract {} {}) {})", l, r, e1),
+        }
+    }
+
+    fn bool_app_to_string(bop: &BoolOp, exprs: &Vec<Expr>, xlen: &u64) -> String {
+        let e1_str = Self::expr_to_string(&exprs[0], xlen);
+        let e2_str = if exprs.len() > 1 {
+            Some(Self::expr_to_string(&exprs[1], xlen))
+        } else {
+            None
+        };
+        match bop {
+            BoolOp::Conj => format!("(and {} {})", e1_str, e2_str.unwrap()),
+            BoolOp::Disj => format!("(or {} {})", e1_str, e2_str.unwrap()),
+            BoolOp::Iff => format!("(= {} {})", e1_str, e2_str.unwrap()),
+            BoolOp::Impl => format!("(=> {} {})", e1_str, e2_str.unwrap()),
+            BoolOp::Neg => format!("(not {})", e1_str),
+        }
+    }
+
+    fn fapp_to_string(fapp: &FuncApp, xlen: &u64) -> String {
+        if fapp.operands.is_empty() {
+            return Self::symbol(&fapp.func_name);
+        }
+        format!(
+            "({} {})",
+            Self::symbol(&fapp.func_name),
+            fapp.operands
+                .iter()
+                .map(|x| Self::expr_to_string(x, xlen))
+                .collect::<Vec<String>>()
+                .join(" ")
+        )
+    }
+
+    fn var_to_string(var: &Var) -> String {
+        Self::symbol(&var.name)
+    }
+
+    fn array_index_to_string(arr: &Expr, index: &Expr, xlen: &u64) -> String {
+        let arr_str = Self::expr_to_string(arr, xlen);
+        let index_str = Self::expr_to_string(index, xlen);
+        format!("(select {} {})", arr_str, index_str)
+    }
+
+    fn get_field_to_string(struct_: &Expr, field: &String, xlen: &u64) -> String {
+        let struct_str = Self::expr_to_string(struct_, xlen);
+        format!("({} {})", Self::symbol(field), struct_str)
+    }
+
+    /// Statements to string
+    /// Statements are listed as comments; the verification conditions are generated by `VcGen`
+    fn skip_to_string() -> String {
+        String::new()
+    }
+
+    fn assert_to_string(expr: &Expr, xlen: &u64) -> String {
+        format!("; assert {}", Self::expr_to_string(expr, xlen))
+    }
+
+    fn assume_to_string(expr: &Expr, xlen: &u64) -> String {
+        format!("; assume {}", Self::expr_to_string(expr, xlen))
+    }
+
+    fn havoc_to_string(var: &Rc<Var>) -> String {
+        format!("; havoc {}", Self::var_to_string(var))
+    }
+
+    fn func_call_to_string(func_call: &FuncCall, xlen: &u64) -> String {
+        let args = func_call
+            .operands
+            .iter()
+            .map(|expr| Self::expr_to_string(expr, xlen))
+            .collect::<Vec<_>>();
+        format!("; call {}", VcGen::app(&Self::symbol(&func_call.func_name), &args))
+    }
+
+    fn assign_to_string(assign: &Assign, xlen: &u64) -> String {
+        let lhs = assign
+            .lhs
+            .iter()
+            .map(|expr| Self::expr_to_string(expr, xlen))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let rhs = assign
+            .rhs
+            .iter()
+            .map(|expr| Self::expr_to_string(expr, xlen))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("; {} := {}", lhs, rhs)
+    }
+
+    fn comment_to_string(string: &String) -> String {
+        format!("; {}", string.replace("\n", " "))
+    }
+
+    fn write_ite(out: &mut IndentWriter, ite: &IfThenElse, xlen: &u64) -> io::Result<()> {
+        out.line(&format!("; if {}", Self::expr_to_string(&ite.cond, xlen)))?;
+        Self::write_stmt(out, &ite.then_stmt, xlen)?;
+        if let Some(else_stmt) = &ite.else_stmt {
+            out.line("; else")?;
+            Self::write_stmt(out, else_stmt, xlen)?;
+        }
+        out.line("; end if")
+    }
+
+    fn write_block(out: &mut IndentWriter, blk: &[Box<Stmt>], xlen: &u64) -> io::Result<()> {
+        for stmt in blk {
+            Self::write_stmt(out, stmt, xlen)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a comment with the signature and contract of the procedure
+    fn write_func_model(
+        out: &mut IndentWriter,
+        fm: &FuncModel,
+        _dwarf_ctx: &DwarfCtx,
+        _xlen: &u64,
+    ) -> io::Result<()> {
+        let mut mod_set = fm.sig.mod_set.iter().cloned().collect::<Vec<_>>();
+        mod_set.sort();
+        let args = fm
+            .sig
+            .arg_decls
+            .iter()
+            .map(|arg| format!("({} {})", Self::symbol(&arg.get_var_name()), Self::typ_to_string(arg.typ())))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut lines = vec![
+            format!("; ==== Procedure {} ({:#x}) ====", fm.sig.name, fm.sig.entry_addr),
+            format!("; arguments ({})", args),
+            format!("; modifies {}", mod_set.join(", ")),
+        ];
+        for spec in &fm.sig.requires {
+            lines.push(format!("; requires {}", Self::bexpr_to_string(spec.get_bexpr().unwrap())));
+        }
+        for spec in &fm.sig.ensures {
+            lines.push(format!("; ensures {}", Self::bexpr_to_string(spec.get_bexpr().unwrap())));
+        }
+        for line in &lines {
+            out.line(line)?;
+        }
+        Ok(())
+    }
+
+    fn write_model(
+        out: &mut dyn Write,
+        xlen: &u64,
+        model: &Model,
+        dwarf_ctx: &DwarfCtx,
+        ignored_funcs: &HashSet<&str>,
+        verify_funcs: &Vec<&str>,
+        _solver_config: &SolverConfig,
+    ) -> io::Result<()> {
+        let mut out = IndentWriter::new(out);
+        let verified = ir_interface::verified_funcs(model, dwarf_ctx, ignored_funcs, verify_funcs);
+        // Quantified specifications are outside of the quantifier-free logic
+        let logic = if verified.iter().any(|fm| Self::uses_quantifiers(model, fm)) {
+            "ABV"
+        } else {
+            "QF_ABV"
+        };
+        out.line(&format!("; SMT-LIB2 verification conditions for {}", model.name))?;
+        out.line("; Each check-sat is a procedure; unsat means that all of its obligations hold.")?;
+        out.line(&format!("(set-logic {})", logic))?;
+        // definitions
+        out.line(&Self::prelude(xlen))?;
+        out.line(&Self::gen_type_defns(dwarf_ctx, xlen))?;
+        out.line(&Self::gen_global_defns(model, dwarf_ctx, xlen))?;
+        // verification conditions
+        for (i, fm) in verified.iter().enumerate() {
+            if i > 0 {
+                out.blank()?;
+            }
+            Self::write_func_vc(&mut out, model, fm, dwarf_ctx, xlen)?;
+        }
+        Ok(())
+    }
+}
+
+impl SpecLangASTInterface for SmtLib2Interface {
+    /// BExpr translation functions
+    fn bexpr_bool_to_string(b: &bool) -> String {
+        format!("{}", b)
+    }
+
+    fn bexpr_bopapp_to_string(bop: &sl_ast::BoolOp, exprs: &Vec<sl_ast::BExpr>) -> String {
+        let bop_str = Self::bopp_to_string(bop);
+        let exprs_str = exprs
+            .iter()
+            .map(Self::bexpr_to_string)
+            .collect::<Vec<_>>();
+        match bop {
+            sl_ast::BoolOp::Forall(_, _) | sl_ast::BoolOp::Exists(_, _) => {
+                format!("({} {})", bop_str, exprs_str[0])
+            }
+            _ => VcGen::app(&bop_str, &exprs_str),
+        }
+    }
+
+    fn bexpr_copapp_to_string(cop: &sl_ast::CompOp, exprs: &Vec<sl_ast::VExpr>) -> String {
+        assert!(
+            exprs.len() == 2,
+            "Invalid number of operands for comparison."
+        );
+        let cop_str = Self::cop_to_string(cop);
+        let expr_str1 = Self::vexpr_to_string(&exprs[0]);
+        let expr_str2 = Self::vexpr_to_string(&exprs[1]);
+        format!("({} {} {})", cop_str, expr_str1, expr_str2)
+    }
+
+    fn bopp_to_string(bop: &sl_ast::BoolOp) -> String {
+        match bop {
+            sl_ast::BoolOp::Conj => "and".to_string(),
+            sl_ast::BoolOp::Disj => "or".to_string(),
+            sl_ast::BoolOp::Neg => "not".to_string(),
+            sl_ast::BoolOp::Implies => "=>".to_string(),
+            sl_ast::BoolOp::Forall(var, typ) => {
+                let var_str = Self::vexpr_to_string(var);
+                let type_str = Self::vtype_to_string(typ);
+                format!("forall (({} {}))", var_str, type_str)
+            }
+            sl_ast::BoolOp::Exists(var, typ) => {
+                let var_str = Self::vexpr_to_string(var);
+                let type_str = Self::vtype_to_string(typ);
+                format!("exists (({} {}))", var_str, type_str)
+            }
+        }
+    }
+
+    fn cop_to_string(cop: &sl_ast::CompOp) -> String {
+        match cop {
+            sl_ast::CompOp::Equal => "=".to_string(),
+            sl_ast::CompOp::Nequal => "distinct".to_string(),
+            sl_ast::CompOp::Gt => "bvsgt".to_string(),
+            sl_ast::CompOp::Lt => "bvslt".to_string(),
+            sl_ast::CompOp::Gtu => "bvugt".to_string(),
+            sl_ast::CompOp::Ltu => "bvult".to_string(),
+            sl_ast::CompOp::Geq => "bvsge".to_string(),
+            sl_ast::CompOp::Leq => "bvsle".to_string(),
+            sl_ast::CompOp::Geu => "bvuge".to_string(),
+            sl_ast::CompOp::Leu => "bvule".to_string(),
+        }
+    }
+
+    /// VExpr translation functions
+    fn vexpr_bv_to_string(value: &u64, typ: &sl_ast::VType) -> String {
+        match typ {
+            // normal bv literal value
+            sl_ast::VType::Bv(width) => Self::bv_lit_to_string(*value, *width as u64),
+            // array as bitvector literal
+            sl_ast::VType::Array { in_type, out_type: _ } => {
+                Self::bv_lit_to_string(*value, in_type.get_bv_width() as u64)
+            }
+            _ => panic!("Should be bv typed but is {:?}.", typ),
+        }
+    }
+
+    fn vexpr_int_to_string(i: &i64) -> String {
+        if *i < 0 {
+            format!("(- {})", -i)
+        } else {
+            format!("{}", i)
+        }
+    }
+
+    fn vexpr_bool_to_string(b: &bool) -> String {
+        format!("{}", b)
+    }
+
+    fn vexpr_ident_to_string(v: &String) -> String {
+        Self::symbol(v)
+    }
+
+    fn vexpr_opapp_to_string(op: &sl_ast::ValueOp, exprs: &Vec<sl_ast::VExpr>) -> String {
+        let exprs_str = exprs
+            .iter()
+            .map(Self::vexpr_to_string)
+            .collect::<Vec<_>>();
+        match op {
+            sl_ast::ValueOp::Add
+            | sl_ast::ValueOp::Sub
+            | sl_ast::ValueOp::Div
+            | sl_ast::ValueOp::Mul
+            | sl_ast::ValueOp::BvXor
+            | sl_ast::ValueOp::BvOr
+            | sl_ast::ValueOp::BvAnd => {
+                // Left associative binary operators
+                let op_str = Self::valueop_to_string(op);
+                exprs_str
+                    .iter()
+                    .skip(1)
+                    .fold(exprs_str[0].clone(), |acc, expr_str| {
+                        format!("({} {} {})", op_str, acc, expr_str)
+                    })
+            }
+            sl_ast::ValueOp::RightShift
+            | sl_ast::ValueOp::URightShift
+            | sl_ast::ValueOp::LeftShift => {
+                format!("({} {} {})", Self::valueop_to_string(op), exprs_str[0], exprs_str[1])
+            }
+            sl_ast::ValueOp::ArrayIndex => {
+                let bytes = match &exprs[0].typ() {
+                    sl_ast::VType::Array {
+                        in_type: _,
+                        out_type,
+                    } => match &**out_type {
+                        sl_ast::VType::Bv(w) => *w as u64 / BYTE_SIZE,
+                        sl_ast::VType::Struct {
+                            id: _,
+                            fields: _,
+                            size,
+                        } => *size / BYTE_SIZE,
+                        _ => panic!("Expected BV type (op: {:#?}, exprs: {:#?}).", op, exprs),
+                    },
+                    _ => panic!("Expected array type for {:#?} but found {:#?}.", &exprs[0], &exprs[0].typ()),
+                };
+                match &exprs_str[0][..] {
+                    "mem" => format!("(select {} {})", exprs_str[0], exprs_str[1]),
+                    _ => format!("(index_by_{} {} {})", bytes, exprs_str[0], exprs_str[1]),
+                }
+            }
+            sl_ast::ValueOp::GetField => {
+                let struct_name = match &exprs[0].typ() {
+                    sl_ast::VType::Struct {
+                        id,
+                        fields: _,
+                        size: _,
+                    } => id,
+                    _ => panic!("Expected struct type."),
+                };
+                format!("({}_{} {})", struct_name, exprs_str[1], exprs_str[0])
+            }
+            sl_ast::ValueOp::Deref => {
+                // Dereferences are rewritten to refer to the current memory by `VcGen`
+                let bytes = exprs[0].typ().get_bv_width() as u64 / BYTE_SIZE;
+                let mem_var = VcGen::mem_var_name(bytes);
+                format!("(deref_{} {} {})", bytes, mem_var, exprs_str[0])
+            }
+            sl_ast::ValueOp::Concat => format!("(concat {} {})", exprs_str[0], exprs_str[1]),
+            sl_ast::ValueOp::Slice { hi, lo } => {
+                format!("((_ extract {} {}) {})", hi, lo, exprs_str[0])
+            }
+        }
+    }
+
+    fn vexpr_funcapp_to_string(fname: &String, args: &Vec<sl_ast::VExpr>) -> String {
+        let width = |arg: &sl_ast::VExpr| match arg {
+            sl_ast::VExpr::Bv { value, typ: _ } => *value,
+            sl_ast::VExpr::Int(i, _) => *i as u64,
+            _ => panic!("The width of an extension should be a literal but found {:?}.", arg),
+        };
+        match &fname[..] {
+            "sext" => format!(
+                "((_ sign_extend {}) {})",
+                width(&args[0]),
+                Self::vexpr_to_string(&args[1])
+            ),
+            "uext" => format!(
+                "((_ zero_extend {}) {})",
+                width(&args[0]),
+                Self::vexpr_to_string(&args[1])
+            ),
+            _ => {
+                let args_str = args
+                    .iter()
+                    .map(Self::vexpr_to_string)
+                    .collect::<Vec<_>>();
+                VcGen::app(&Self::symbol(fname), &args_str)
+            }
+        }
+    }
+
+    fn valueop_to_string(op: &sl_ast::ValueOp) -> String {
+        match op {
+            sl_ast::ValueOp::Add => String::from("bvadd"),
+            sl_ast::ValueOp::Sub => String::from("bvsub"),
+            sl_ast::ValueOp::Mul => String::from("bvmul"),
+            sl_ast::ValueOp::BvXor => String::from("bvxor"),
+            sl_ast::ValueOp::BvOr => String::from("bvor"),
+            sl_ast::ValueOp::BvAnd => String::from("bvand"),
+            sl_ast::ValueOp::RightShift => String::from("bvashr"),
+            sl_ast::ValueOp::URightShift => String::from("bvlshr"),
+            sl_ast::ValueOp::LeftShift => String::from("bvshl"),
+            _ => panic!("Unimplemented value op {:#?}.", op),
+        }
+    }
+
+    /// Value Type to string
+    fn vtype_to_string(typ: &sl_ast::VType) -> String {
+        match typ {
+            sl_ast::VType::Bv(width) => format!("(_ BitVec {})", width),
+            sl_ast::VType::Int => "Int".to_string(),
+            sl_ast::VType::Bool => "Bool".to_string(),
+            _ => panic!("Unimplemented type to string translation for {:#?}.", typ),
+        }
+    }
+
+    /// Spec statement to string
+    fn spec_to_string(spec: &sl_ast::Spec) -> String {
+        match spec {
+            sl_ast::Spec::Requires(bexpr, _) => format!("; requires {}", Self::bexpr_to_string(bexpr)),
+            sl_ast::Spec::Ensures(bexpr, _) => format!("; ensures {}", Self::bexpr_to_string(bexpr)),
+            _ => panic!("Unimplemented."),
+        }
+    }
+}
+
+// ========================================================================================================================
+// # Verification condition generation
+
+/// Symbolic state of a procedure's verification condition in SSA form
+struct VcGen<'a> {
+    /// Verification model that contains the callees
+    model: &'a Model,
+    /// Width of the registers in bits
+    xlen: u64,
+    /// Current version of each variable
+    versions: HashMap<String, u64>,
+    /// Number of versions of each variable
+    counts: HashMap<String, u64>,
+    /// Types of the variables
+    types: HashMap<String, Type>,
+    /// Declarations and definitions of the versions
+    defns: Vec<String>,
+    /// Path condition of the current statement
+    path: String,
+    /// Number of path conditions defined
+    path_count: u64,
+    /// Proof obligations and their descriptions
+    obligations: Vec<(String, String)>,
+    /// Last comment; describes the assertions that follow it
+    last_comment: String,
+    /// Procedures that are being inlined
+    inlining: Vec<String>,
+}
+
+impl<'a> VcGen<'a> {
+    fn new(model: &'a Model, xlen: u64) -> Self {
+        VcGen {
+            model,
+            xlen,
+            versions: HashMap::new(),
+            counts: HashMap::new(),
+            types: HashMap::new(),
+            defns: vec![],
+            path: "true".to_string(),
+            path_count: 0,
+            obligations: vec![],
+            last_comment: String::new(),
+            inlining: vec![],
+        }
+    }
+
+    /// Declares the initial version of the variable
+    fn declare(&mut self, name: &str, typ: &Type) {
+        self.types.insert(name.to_string(), typ.clone());
+        self.versions.insert(name.to_string(), 0);
+        self.counts.insert(name.to_string(), 0);
+        self.defns.push(format!(
+            "(declare-fun {} () {})",
+            SmtLib2Interface::symbol(&Self::ssa_name(name, 0)),
+            SmtLib2Interface::typ_to_string(typ)
+        ));
+    }
+
+    /// Creates a new version of the variable defined by `value` (or unconstrained if it is None)
+    fn define(&mut self, name: &str, typ: &Type, value: Option<String>) {
+        if !self.types.contains_key(name) {
+            self.declare(name, typ);
+        }
+        let version = self.counts[name] + 1;
+        self.counts.insert(name.to_string(), version);
+        self.versions.insert(name.to_string(), version);
+        let symbol = SmtLib2Interface::symbol(&Self::ssa_name(name, version));
+        let typ_str = SmtLib2Interface::typ_to_string(&self.types[name]);
+        self.defns.push(match value {
+            Some(value) => format!("(define-fun {} () {} {})", symbol, typ_str, value),
+            None => format!("(declare-fun {} () {})", symbol, typ_str),
+        });
+    }
+
+    /// Adds the condition to the path condition
+    fn assume(&mut self, cond: String) {
+        let path = if self.path == "true" {
+            cond
+        } else {
+            format!("(and {} {})", self.path, cond)
+        };
+        self.set_path(path);
+    }
+
+    /// Defines a new path condition
+    fn set_path(&mut self, path: String) {
+        self.path = format!("path_{}", self.path_count);
+        self.path_count += 1;
+        self.defns.push(format!("(define-fun {} () Bool {})", self.path, path));
+    }
+
+    /// Adds the obligation that the condition holds on the current path
+    fn assert(&mut self, desc: String, cond: String) {
+        let obligation = if self.path == "true" {
+            cond
+        } else {
+            format!("(=> {} {})", self.path, cond)
+        };
+        self.obligations.push((desc, obligation));
+    }
+
+    /// Adds the verification condition of the statement
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Assume(expr) => {
+                let cond = self.expr(expr);
+                self.assume(cond);
+            }
+            Stmt::Assert(expr) => {
+                let cond = self.expr(expr);
+                let desc = if self.last_comment.is_empty() {
+                    format!("assert {}", SmtLib2Interface::expr_to_string(expr, &self.xlen))
+                } else {
+                    self.last_comment.clone()
+                };
+                self.assert(desc, cond);
+            }
+            Stmt::Havoc(var) => self.define(&var.name, &var.typ, None),
+            Stmt::FuncCall(fc) => self.call(fc),
+            Stmt::Assign(assign) => self.assign(assign),
+            Stmt::IfThenElse(ite) => self.ite(ite),
+            Stmt::Block(stmts) => {
+                for stmt in stmts {
+                    self.stmt(stmt);
+                }
+            }
+            Stmt::Comment(comment) => {
+                self.last_comment = comment.replace("\n", " ");
+                self.defns.push(SmtLib2Interface::comment_to_string(comment));
+            }
+        }
+    }
+
+    /// Adds the definitions of a (simultaneous) assignment
+    fn assign(&mut self, assign: &Assign) {
+        // Evaluate the right hand sides and indices before updating the variables
+        let rhss = assign
+            .rhs
+            .iter()
+            .map(|expr| self.expr(expr))
+            .collect::<Vec<_>>();
+        let indices = assign
+            .lhs
+            .iter()
+            .map(|lhs| lhs.get_array_index().map(|index| self.expr(index)))
+            .collect::<Vec<_>>();
+        for ((lhs, rhs), index) in assign.lhs.iter().zip(rhss).zip(indices) {
+            match (lhs.get_array_expr(), index) {
+                (Some(arr), Some(index)) => {
+                    let arr_name = arr.get_var_name();
+                    let value = format!("(store {} {} {})", self.expr(arr), index, rhs);
+                    self.define(&arr_name, arr.typ(), Some(value));
+                }
+                _ => self.define(&lhs.get_var_name(), lhs.typ(), Some(rhs)),
+            }
+        }
+    }
+
+    /// Adds the definitions of both branches and merges the versions of the variables
+    fn ite(&mut self, ite: &IfThenElse) {
+        let cond = self.expr(&ite.cond);
+        let start = self.versions.clone();
+        let start_path = self.path.clone();
+        // Then branch
+        self.assume(cond.clone());
+        let then_entry = self.path.clone();
+        self.stmt(&ite.then_stmt);
+        let then_path = mem::replace(&mut self.path, start_path.clone());
+        let then_versions = mem::replace(&mut self.versions, start);
+        // Else branch
+        self.assume(format!("(not {})", cond));
+        let else_entry = self.path.clone();
+        if let Some(else_stmt) = &ite.else_stmt {
+            self.stmt(else_stmt);
+        }
+        let else_path = mem::replace(&mut self.path, start_path);
+        // Merge the versions that differ between the branches
+        let mut names = then_versions
+            .keys()
+            .chain(self.versions.keys())
+            .cloned()
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        for name in names {
+            let then_version = *then_versions.get(&name).unwrap_or(&0);
+            let else_version = *self.versions.get(&name).unwrap_or(&0);
+            if then_version != else_version {
+                let value = format!(
+                    "(ite {} {} {})",
+                    cond,
+                    SmtLib2Interface::symbol(&Self::ssa_name(&name, then_version)),
+                    SmtLib2Interface::symbol(&Self::ssa_name(&name, else_version))
+                );
+                let typ = self.types[&name].clone();
+                self.define(&name, &typ, Some(value));
+            }
+        }
+        // Assumptions inside the branches hold on their paths
+        if then_path != then_entry || else_path != else_entry {
+            self.set_path(format!("(or {} {})", then_path, else_path));
+        }
+    }
+
+    /// Adds the verification condition of a call; inlined procedures are expanded and the
+    /// other procedures are replaced by their contracts
+    fn call(&mut self, fc: &FuncCall) {
+        let model = self.model;
+        let callee = model
+            .func_models
+            .iter()
+            .find(|fm| fm.sig.name == fc.func_name)
+            .unwrap_or_else(|| panic!("Unable to find the procedure {}.", fc.func_name));
+        // Bind the formal arguments
+        let args = fc.operands.iter().map(|expr| self.expr(expr)).collect::<Vec<_>>();
+        let saved = callee
+            .sig
+            .arg_decls
+            .iter()
+            .map(|arg| (arg.get_var_name(), self.versions.get(&arg.get_var_name()).cloned()))
+            .collect::<Vec<_>>();
+        for (arg, value) in callee.sig.arg_decls.iter().zip(args) {
+            self.define(&arg.get_var_name(), arg.typ(), Some(value));
+        }
+        if callee.inline {
+            if self.inlining.contains(&callee.sig.name) {
+                panic!("Unable to inline the recursive procedure {}.", callee.sig.name);
+            }
+            self.inlining.push(callee.sig.name.clone());
+            self.stmt(&callee.body);
+            self.inlining.pop();
+        } else {
+            // Check the preconditions
+            let entry = self.versions.clone();
+            for (desc, require) in self.entry_requires(callee) {
+                self.assert(format!("{} of {}", desc, callee.sig.name), require);
+            }
+            for spec in &callee.sig.requires {
+                let bexpr = spec.get_bexpr().unwrap();
+                let require = self.spec(bexpr, &entry);
+                self.assert(
+                    format!("requires {} of {}", SmtLib2Interface::bexpr_to_string(bexpr), callee.sig.name),
+                    require,
+                );
+            }
+            // Havoc the modifies set and assume the postconditions
+            let mut mod_set = callee.sig.mod_set.iter().cloned().collect::<Vec<_>>();
+            mod_set.sort();
+            for name in mod_set {
+                match self.types.get(&name).cloned() {
+                    Some(typ) => self.define(&name, &typ, None),
+                    None => warn!("Unable to find the type of {} modified by {}.", name, callee.sig.name),
+                }
+            }
+            for spec in &callee.sig.ensures {
+                let ensure = self.spec(spec.get_bexpr().unwrap(), &entry);
+                self.assume(ensure);
+            }
+        }
+        // Return values are not modeled
+        for lhs in &fc.lhs {
+            self.define(&lhs.get_var_name(), lhs.typ(), None);
+        }
+        // Restore the formal arguments of the caller
+        for (name, version) in saved {
+            match version {
+                Some(version) => self.versions.insert(name, version),
+                None => self.versions.remove(&name),
+            };
+        }
+    }
+
+    /// Returns the preconditions implied by the procedure entry over the current versions
+    /// of the variables
+    fn entry_requires(&self, fm: &FuncModel) -> Vec<(String, String)> {
+        system_model::entry_requires(fm, self.xlen)
+            .into_iter()
+            .map(|(desc, expr)| (desc, self.expr(&expr)))
+            .collect()
+    }
+
+    /// Returns the expression over the current versions of the variables
+    fn expr(&self, expr: &Expr) -> String {
+        SmtLib2Interface::expr_to_string(&self.rename_expr(expr), &self.xlen)
+    }
+
+    /// Returns the specification over the current versions of the variables;
+    /// `old(e)` refers to the versions in `old`
+    fn spec(&self, bexpr: &sl_ast::BExpr, old: &HashMap<String, u64>) -> String {
+        SmtLib2Interface::bexpr_to_string(&self.rename_bexpr(bexpr, &self.versions, old))
+    }
+
+    /// Renames the variables to their current versions
+    fn rename_expr(&self, expr: &Expr) -> Expr {
+        match expr {
+            Expr::Var(var, typ) => match self.versions.get(&var.name) {
+                Some(version) => Expr::var(&Self::ssa_name(&var.name, *version), typ.clone()),
+                None => expr.clone(),
+            },
+            Expr::OpApp(opapp, typ) => Expr::OpApp(
+                OpApp {
+                    op: opapp.op.clone(),
+                    operands: opapp.operands.iter().map(|expr| self.rename_expr(expr)).collect(),
+                },
+                typ.clone(),
+            ),
+            Expr::FuncApp(fapp, typ) => Expr::FuncApp(
+                FuncApp {
+                    func_name: fapp.func_name.clone(),
+                    operands: fapp.operands.iter().map(|expr| self.rename_expr(expr)).collect(),
+                },
+                typ.clone(),
+            ),
+            Expr::Literal(_, _) => expr.clone(),
+        }
+    }
+
+    /// Renames the identifiers of the specification to the versions in `versions`
+    fn rename_bexpr(
+        &self,
+        bexpr: &sl_ast::BExpr,
+        versions: &HashMap<String, u64>,
+        old: &HashMap<String, u64>,
+    ) -> sl_ast::BExpr {
+        match bexpr {
+            sl_ast::BExpr::Bool(_) => bexpr.clone(),
+            sl_ast::BExpr::PredApp(name, _) => {
+                panic!("Implementation error; predicate {} should be expanded by the spec passes.", name)
+            }
+            sl_ast::BExpr::BOpApp(bop, bexprs) => {
+                // Bound variables shadow the state variables
+                let (versions, old) = match bop {
+                    sl_ast::BoolOp::Forall(var, _) | sl_ast::BoolOp::Exists(var, _) => {
+                        let mut versions = versions.clone();
+                        let mut old = old.clone();
+                        versions.remove(var.get_ident_name());
+                        old.remove(var.get_ident_name());
+                        (versions, old)
+                    }
+                    _ => (versions.clone(), old.clone()),
+                };
+                sl_ast::BExpr::BOpApp(
+                    bop.clone(),
+                    bexprs
+                        .iter()
+                        .map(|bexpr| self.rename_bexpr(bexpr, &versions, &old))
+                        .collect(),
+                )
+            }
+            sl_ast::BExpr::COpApp(cop, vexprs) => sl_ast::BExpr::COpApp(
+                cop.clone(),
+                vexprs
+                    .iter()
+                    .map(|vexpr| self.rename_vexpr(vexpr, versions, old))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Renames the identifiers of the value expression to the versions in `versions`
+    fn rename_vexpr(
+        &self,
+        vexpr: &sl_ast::VExpr,
+        versions: &HashMap<String, u64>,
+        old: &HashMap<String, u64>,
+    ) -> sl_ast::VExpr {
+        match vexpr {
+            sl_ast::VExpr::Ident(name, typ) => match versions.get(name) {
+                Some(version) => sl_ast::VExpr::Ident(Self::ssa_name(name, *version), typ.clone()),
+                None => vexpr.clone(),
+            },
+            sl_ast::VExpr::OpApp(sl_ast::ValueOp::Deref, vexprs, typ) => {
+                // Dereference the current version of the memory
+                let bytes = vexprs[0].typ().get_bv_width() as u64 / BYTE_SIZE;
+                let mem_name = Self::mem_var_name(bytes);
+                let mem = sl_ast::VExpr::Ident(
+                    Self::ssa_name(&mem_name, *versions.get(&mem_name).unwrap_or(&0)),
+                    sl_ast::VType::Unknown,
+                );
+                sl_ast::VExpr::FuncApp(
+                    format!("deref_{}", bytes),
+                    vec![mem, self.rename_vexpr(&vexprs[0], versions, old)],
+                    typ.clone(),
+                )
+            }
+            sl_ast::VExpr::OpApp(op, vexprs, typ) => sl_ast::VExpr::OpApp(
+                op.clone(),
+                vexprs
+                    .iter()
+                    .map(|vexpr| self.rename_vexpr(vexpr, versions, old))
+                    .collect(),
+                typ.clone(),
+            ),
+            sl_ast::VExpr::FuncApp(fname, vexprs, _) if fname == "old" => {
+                self.rename_vexpr(&vexprs[0], old, old)
+            }
+            sl_ast::VExpr::FuncApp(fname, vexprs, typ) => sl_ast::VExpr::FuncApp(
+                fname.clone(),
+                vexprs
+                    .iter()
+                    .map(|vexpr| self.rename_vexpr(vexpr, versions, old))
+                    .collect(),
+                typ.clone(),
+            ),
+            _ => vexpr.clone(),
+        }
+    }
+
+    /// Returns the name of a version of the variable
+    fn ssa_name(name: &str, version: u64) -> String {
+        format!("{}@{}", name, version)
+    }
+
+    /// Returns the name of the memory variable for accesses of the given number of bytes
+    fn mem_var_name(bytes: u64) -> String {
+        match bytes {
+            1 => constants::MEM_VAR_B,
+            2 => constants::MEM_VAR_H,
+            4 => constants::MEM_VAR_W,
+            8 => constants::MEM_VAR_D,
+            _ => panic!("Cannot dereference values that are not 1, 2, 4, or 8 bytes."),
+        }
+        .to_string()
+    }
+
+    /// Returns the conjunction of the conditions
+    fn conj(conds: &[String]) -> String {
+        match conds.len() {
+            0 => "true".to_string(),
+            1 => conds[0].clone(),
+            _ => Self::app("and", conds),
+        }
+    }
+
+    /// Returns the application of the function to the arguments
+    fn app(fname: &str, args: &[String]) -> String {
+        if args.is_empty() {
+            fname.to_string()
+        } else {
+            format!("({} {})", fname, args.join(" "))
+        }
+    }
+}
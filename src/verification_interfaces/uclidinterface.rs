@@ -1,13 +1,18 @@
-use std::{collections::HashSet, rc::Rc};
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    rc::Rc,
+};
 
 use asts::{spec_lang::sl_ast, veriv_ast::*};
 
-use dwarf_ctx::dwarfreader::{DwarfCtx, DwarfTypeDefn, DwarfVar};
+use dwarf_ctx::dwarfreader::{DwarfCtx, DwarfVar};
 
 use utils::helpers;
 
 use crate::{
-    ir_interface::{IRInterface, SpecLangASTInterface},
+    ir_interface::{self, IRInterface, IndentWriter, SpecLangASTInterface},
+    solver::{requires_macro_name, SolverConfig, VerifyCommand, OBLIGATION_PREFIX},
 };
 
 use rv_model::system_model;
@@ -45,7 +50,7 @@ impl Uclid5Interface {
             xlen,
         );
         let deref_1_alias = format!(
-            "    define deref_1(mem: [bv{}]bv8, addr: bv{}): bv8 = {};",
+            "define deref_1(mem: [bv{}]bv8, addr: bv{}): bv8 = {};",
             xlen, xlen, load_byte_str
         );
         // load half
@@ -54,7 +59,7 @@ impl Uclid5Interface {
             xlen,
         );
         let deref_2_alias = format!(
-            "    define deref_2(mem: [bv{}]bv16, addr: bv{}): bv16 = {};",
+            "define deref_2(mem: [bv{}]bv16, addr: bv{}): bv16 = {};",
             xlen, xlen, load_half_str
         );
         // load word
@@ -63,7 +68,7 @@ impl Uclid5Interface {
             xlen,
         );
         let deref_4_alias = format!(
-            "    define deref_4(mem: [bv{}]bv32, addr: bv{}): bv32 = {};",
+            "define deref_4(mem: [bv{}]bv32, addr: bv{}): bv32 = {};",
             xlen, xlen, load_word_str
         );
         // load double
@@ -72,7 +77,7 @@ impl Uclid5Interface {
             xlen,
         );
         let deref_8_alias = format!(
-            "    define deref_8(mem: [bv{}]bv64, addr: bv{}): bv64 = {};",
+            "define deref_8(mem: [bv{}]bv64, addr: bv{}): bv64 = {};",
             xlen, xlen, load_double_str
         );
         format!(
@@ -88,89 +93,30 @@ impl Uclid5Interface {
     ///
     /// * `dwarf_ctx` - The DWARF information that contains all the global variables and function
     ///                 signatures for the binaries provided
-    fn gen_array_defns(dwarf_ctx: &DwarfCtx, xlen: &u64) -> String {
-        let mut defns: Vec<String> = vec![];
-        for var in dwarf_ctx.global_vars() {
-            defns.append(&mut Self::gen_array_defn(&var.typ_defn, xlen));
-        }
-        for (_, func_sig) in dwarf_ctx.func_sigs() {
-            for var in &func_sig.args {
-                defns.append(&mut Self::gen_array_defn(&var.typ_defn, xlen));
-            }
-            if let Some(ret_type) = &func_sig.ret_type {
-                defns.append(&mut Self::gen_array_defn(&ret_type, xlen));
-            }
-        }
-        defns.sort();
-        defns.dedup();
-        helpers::indent_text(format!("// Array helpers\n{}", defns.join("\n")), 4)
-    }
-
-    /// Recursively generate define macros for a given type (size in bytes).
-    /// The macro is a function that takes a base address and index
-    /// and returns 'base + index * typ_defn.bytes'.
-    /// Returns a string representations of these define macros.
-    ///
-    /// # Arguments
-    ///
-    /// * `typ_defn` - Type to generate an array index macro for
     ///
     /// # Example
     ///
     /// define index_by_16(base: xlen_t, index: xlen_t): xlen_t = base + bv_left_shift(to_xlen_t(4bv64), index);
-    fn gen_array_defn(typ_defn: &DwarfTypeDefn, xlen: &u64) -> Vec<String> {
-        let mut defns = vec![];
-        match &typ_defn {
-            DwarfTypeDefn::Primitive { bytes } => {
-                // Check if the type is valid (bytes > 0)
-                if *bytes > 0 {
-                    defns.push(format!(
-                        "define {}(base: bv{}, index: bv{}): bv{} = base + {};",
-                        Self::array_index_macro_name(bytes),
-                        xlen,
-                        xlen,
-                        xlen,
-                        if *bytes == 1 {
-                            format!("index")
-                        } else {
-                            Self::multiply_expr(bytes, "index", xlen)
-                        }
-                    ))
-                }
-            }
-            DwarfTypeDefn::Array {
-                in_typ,
-                out_typ,
-                bytes: _,
-            } => {
-                defns.append(&mut Self::gen_array_defn(in_typ, xlen));
-                defns.append(&mut Self::gen_array_defn(out_typ, xlen));
-            }
-            DwarfTypeDefn::Struct {
-                id: _,
-                fields,
-                bytes,
-            } => {
-                for (_, field) in fields {
-                    defns.append(&mut Self::gen_array_defn(&field.typ, xlen));
-                }
-                if *bytes > 0 {
-                    defns.push(format!(
-                        "define {}(base: bv{}, index: bv{}): bv{} = base + {};",
-                        Self::array_index_macro_name(bytes),
-                        xlen,
-                        xlen,
-                        xlen,
+    fn gen_array_defns(dwarf_ctx: &DwarfCtx, xlen: &u64) -> String {
+        let defns = ir_interface::array_index_sizes(dwarf_ctx)
+            .iter()
+            .map(|bytes| {
+                // The macro takes a base address and index and returns 'base + index * bytes'
+                format!(
+                    "define {}(base: bv{}, index: bv{}): bv{} = base + {};",
+                    Self::array_index_macro_name(bytes),
+                    xlen,
+                    xlen,
+                    xlen,
+                    if *bytes == 1 {
+                        "index".to_string()
+                    } else {
                         Self::multiply_expr(bytes, "index", xlen)
-                    ))
-                }
-            }
-            DwarfTypeDefn::Pointer {
-                value_typ,
-                bytes: _,
-            } => defns.append(&mut Self::gen_array_defn(&value_typ, xlen)),
-        };
-        defns
+                    }
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("// Array helpers\n{}", defns.join("\n"))
     }
 
     /// Returns the name of the array index macro given the byte size
@@ -214,64 +160,35 @@ impl Uclid5Interface {
     /// # Arguments
     ///
     /// * `dwarf_ctx` - The DWARF context containing the variables and function signatures.
-    fn gen_struct_defns(dwarf_ctx: &DwarfCtx, xlen: &u64) -> String {
-        let mut defns = vec![];
-        for var in dwarf_ctx.global_vars() {
-            defns.append(&mut Self::gen_struct_defn(&var.typ_defn, xlen));
-        }
-        for (_, func_sig) in dwarf_ctx.func_sigs() {
-            for var in &func_sig.args {
-                defns.append(&mut Self::gen_struct_defn(&var.typ_defn, xlen));
-            }
-            if let Some(ret_type) = &func_sig.ret_type {
-                defns.append(&mut Self::gen_struct_defn(&ret_type, xlen));
-            }
-        }
-        defns.sort();
-        defns.dedup();
-        helpers::indent_text(format!("// Struct helpers\n{}", defns.join("\n")), 4)
-    }
-
-    /// Recursively generate string representations of get field macros for type definition 'typ'.
     ///
     /// # Example
     ///
-    ///     Given the following struct definition:
-    ///     struct ctx { ..., a0: T, ... };
+    /// Given the following struct definition:
     ///
-    ///     This function returns the following definition to simplify 'c.a0', where c is of type ctx:
-    ///     define ctx_a0(ptr: xlen_t): xlen_t = ptr + to_xlen_t(80bv64);
-    fn gen_struct_defn(typ: &DwarfTypeDefn, xlen: &u64) -> Vec<String> {
-        let mut defns = vec![];
-        match typ {
-            DwarfTypeDefn::Struct {
-                id,
-                fields,
-                bytes: _,
-            } => {
-                for (field_name, field) in fields {
-                    defns.append(&mut Self::gen_struct_defn(&*field.typ, xlen));
-                    defns.push(format!(
-                        "define {}(ptr: bv{}): bv{} = ptr + {}bv{};",
-                        Self::get_field_macro_name(&id[..], field_name),
-                        xlen,
-                        xlen,
-                        field.loc,
-                        xlen
-                    ));
-                }
-            }
-            DwarfTypeDefn::Array {
-                in_typ,
-                out_typ,
-                bytes: _,
-            } => {
-                defns.append(&mut Self::gen_struct_defn(&in_typ, xlen));
-                defns.append(&mut Self::gen_struct_defn(&out_typ, xlen));
-            }
-            _ => (),
-        }
-        defns
+    /// ```text
+    /// struct ctx { ..., a0: T, ... };
+    /// ```
+    ///
+    /// This function returns the following definition to simplify 'c.a0', where c is of type ctx:
+    ///
+    /// ```text
+    /// define ctx_a0(ptr: xlen_t): xlen_t = ptr + to_xlen_t(80bv64);
+    /// ```
+    fn gen_struct_defns(dwarf_ctx: &DwarfCtx, xlen: &u64) -> String {
+        let defns = ir_interface::struct_fields(dwarf_ctx)
+            .iter()
+            .map(|(id, field_name, loc)| {
+                format!(
+                    "define {}(ptr: bv{}): bv{} = ptr + {}bv{};",
+                    Self::get_field_macro_name(id, field_name),
+                    xlen,
+                    xlen,
+                    loc,
+                    xlen
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("// Struct helpers\n{}", defns.join("\n"))
     }
 
     /// Given the `struct_id` and `field_name`, return the get field macro name.
@@ -285,7 +202,7 @@ impl Uclid5Interface {
         for var in dwarf_ctx.global_vars() {
             defns = format!("{}{}\n", defns, Self::gen_global_defn(&var, xlen));
         }
-        helpers::indent_text(defns, 4)
+        defns
     }
 
     /// Given a global variable, returns a string of a macro that refers to the static
@@ -309,7 +226,7 @@ impl Uclid5Interface {
                 Self::gen_global_func_defn(&fm.sig.name, fm.sig.entry_addr, xlen)
             );
         }
-        helpers::indent_text(defns, 4)
+        defns
     }
 
     /// Returns a define macro that returns the `func_entry_addr`
@@ -322,7 +239,39 @@ impl Uclid5Interface {
         )
     }
 
-    fn specs_to_string(fsig: &FuncSig, _dwarf_ctx: &DwarfCtx, _xlen: &u64) -> String {
+    /// Returns a string of macros for the requires clauses of the functions.
+    /// They are asserted at each call site when the obligations are checked separately.
+    fn gen_requires_defns(model: &Model) -> String {
+        let mut defns = String::from("// Function preconditions\n");
+        for fm in &model.func_models {
+            let params = fm
+                .sig
+                .arg_decls
+                .iter()
+                .map(|arg_expr| Self::var_decl(&arg_expr.get_var_name(), arg_expr.typ()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            for (i, require) in fm.sig.requires.iter().enumerate() {
+                defns = format!(
+                    "{}define {}({}): boolean = {};\n",
+                    defns,
+                    requires_macro_name(&fm.sig.name, i),
+                    params,
+                    Self::bexpr_to_string(require.get_bexpr().unwrap())
+                );
+            }
+        }
+        defns
+    }
+
+    /// Returns the requires and ensures clauses of the function. With `split_obligations`,
+    /// each ensures clause is preceded by a comment describing the obligation.
+    fn specs_to_string(
+        fsig: &FuncSig,
+        _dwarf_ctx: &DwarfCtx,
+        _xlen: &u64,
+        split_obligations: bool,
+    ) -> String {
         let mut specs = "".to_string();
         // requires
         for require in &fsig.requires {
@@ -332,65 +281,234 @@ impl Uclid5Interface {
             specs = format!("{}requires {};\n", specs, require_str);
         }
         // ensures
-        for ensure in &fsig.ensures {
+        for (i, ensure) in fsig.ensures.iter().enumerate() {
             let bexpr = ensure.get_bexpr().unwrap();
             let ensure_str = Self::bexpr_to_string(bexpr);
+            if split_obligations {
+                let clause = ensure.loc().map_or(format!("#{}", i), |loc| loc.to_string());
+                specs = format!(
+                    "{}// {}ensures {} of {}\n",
+                    specs, OBLIGATION_PREFIX, clause, fsig.name
+                );
+            }
             specs = format!("{}ensures {};\n", specs, ensure_str);
         }
         specs
     }
 
-    /// Returns a string of all the procedures in the model.
+    /// Writes all the procedures in the model.
     /// This contains all of the function models.
-    fn gen_procs(model: &Model, dwarf_ctx: &DwarfCtx, xlen: &u64) -> String {
-        let procs_string = model
-            .func_models
+    fn write_procs(
+        out: &mut IndentWriter,
+        model: &Model,
+        dwarf_ctx: &DwarfCtx,
+        xlen: &u64,
+        split_obligations: bool,
+    ) -> io::Result<()> {
+        let step = model.transition.as_ref().map(|ts| &ts.step.func_name);
+        for fm in &model.func_models {
+            let entry_requires = step != Some(&fm.sig.name);
+            Self::write_proc(out, fm, dwarf_ctx, xlen, split_obligations, entry_requires)?;
+            out.blank()?;
+            out.blank()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the procedure of the function model. With `entry_requires`, the procedure
+    /// requires that the arguments are in the argument registers and that pc is at its entry
+    fn write_proc(
+        out: &mut IndentWriter,
+        fm: &FuncModel,
+        dwarf_ctx: &DwarfCtx,
+        xlen: &u64,
+        split_obligations: bool,
+        entry_requires: bool,
+    ) -> io::Result<()> {
+        let args = fm
+            .sig
+            .arg_decls
             .iter()
-            .map(|fm| Self::func_model_to_string(fm, dwarf_ctx, xlen))
+            .map(|arg_expr| Self::var_decl(&arg_expr.get_var_name(), arg_expr.typ()))
             .collect::<Vec<_>>()
-            .join("\n\n");
-        helpers::indent_text(procs_string, 4)
+            .join(", ");
+        let ret = if let Some(rd) = &fm.sig.ret_decl {
+            format!(" returns (ret: {})", Self::typ_to_string(rd))
+        } else {
+            String::new()
+        };
+        let modifies = if !fm.sig.mod_set.is_empty() {
+            format!(
+                "\n    modifies {};",
+                fm.sig
+                    .mod_set
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        } else {
+            String::new()
+        };
+        // Specifications
+        let mut specs = Self::specs_to_string(&fm.sig, dwarf_ctx, xlen, split_obligations);
+        if !specs.is_empty() {
+            debug!("\n{}", specs);
+        }
+        if entry_requires {
+            // Add formal argument constraints to specs
+            let arg_cons = fm
+                .sig
+                .arg_decls
+                .iter()
+                .enumerate()
+                .map(|(i, var)| {
+                    let var_name = &var.get_var_name();
+                    format!("requires {} == a{};", var_name, i)
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
+            specs = format!("{}\n{}", specs, arg_cons);
+            // Add pc constraint to specs
+            let pc_cons = format!("requires pc == {}bv{};", fm.sig.entry_addr, xlen);
+            specs = format!("{}\n{}", specs, pc_cons);
+            // Add initial return value constraint
+            specs = format!("{}\n{}", specs, "requires returned == 0bv1;");
+        }
+        // Inline flag
+        let inline = if fm.inline { "[inline] " } else { "" };
+        out.line(&format!(
+            "procedure {}{}({}){}{}",
+            inline, fm.sig.name, args, ret, modifies
+        ))?;
+        out.indent();
+        out.line(&specs)?;
+        out.dedent();
+        // Function body
+        Self::write_block(out, fm.body.get_expect_block(), xlen)?;
+        out.blank()
+    }
+
+    /// Writes the init and next blocks and the invariants of the transition system.
+    /// The next block executes one step of the machine
+    fn write_transition_system(
+        out: &mut IndentWriter,
+        ts: &TransitionSystem,
+        xlen: &u64,
+    ) -> io::Result<()> {
+        out.line("init {")?;
+        out.indent();
+        for spec in &ts.specs {
+            if let sl_ast::MachineSpec::Init(bexpr, _) = spec {
+                out.line(&format!("assume ({});", Self::bexpr_to_string(bexpr)))?;
+            }
+        }
+        out.dedent();
+        out.line("}")?;
+        out.blank()?;
+        out.line("next {")?;
+        out.indent();
+        out.line(&Self::func_call_to_string(&ts.step, xlen))?;
+        out.dedent();
+        out.line("}")?;
+        out.blank()?;
+        for spec in &ts.specs {
+            if let sl_ast::MachineSpec::Invariant(name, bexpr, _) = spec {
+                out.line(&format!("invariant {}: {};", name, Self::bexpr_to_string(bexpr)))?;
+            }
+        }
+        out.blank()
     }
 
-    /// Returns the control block for the UCLID5 model.
+    /// Writes the control block for the UCLID5 model.
     /// This currently will automatically verify all functions with
     /// a specification.
-    fn control_blk(
+    /// The solver options come from the solver profile; with timeouts, each function
+    /// is checked separately after setting its timeout.
+    fn write_control_blk(
+        out: &mut IndentWriter,
         model: &Model,
         dwarf_ctx: &DwarfCtx,
         ignored_funcs: &HashSet<&str>,
         verify_funcs: &Vec<&str>,
-    ) -> String {
-        let verif_fns_string = if verify_funcs.len() > 0 {
-            verify_funcs
-                .iter()
-                .map(|f_name| format!("f{} = verify({});", f_name, f_name))
-                .collect::<Vec<_>>()
-                .join("\n")
-        } else {
-            model
-                .func_models
-                .iter()
-                .filter(|fm| dwarf_ctx.func_sig(&fm.sig.name).is_ok())
-                .map(|fm| {
-                    if !ignored_funcs.contains(&fm.sig.name[..]) {
-                        format!(
-                            "f{} = verify({});",
-                            fm.sig.name.clone(),
-                            fm.sig.name.clone()
-                        )
-                    } else {
-                        String::from("")
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join("\n")
+        solver_config: &SolverConfig,
+    ) -> io::Result<()> {
+        let verified = ir_interface::verified_funcs(model, dwarf_ctx, ignored_funcs, verify_funcs)
+            .iter()
+            .map(|fm| fm.sig.name.clone())
+            .collect::<Vec<_>>();
+        // Print the counterexamples of the scalar state variables for the failed functions
+        let mut cex_vars = model
+            .vars
+            .iter()
+            .filter(|var| match var.typ {
+                Type::Array { .. } => false,
+                _ => true,
+            })
+            .map(|var| var.name.clone())
+            .collect::<Vec<_>>();
+        cex_vars.sort();
+        let print_cex = |v_name: &str| format!("{}.print_cex({});", v_name, cex_vars.join(", "));
+        out.line("control {")?;
+        out.indent();
+        for (name, value) in solver_config.solver_options() {
+            out.line(&format!("set_solver_option(\"{}\", {});", name, value))?;
+        }
+        // The transition system is checked by induction unless bmc is requested
+        let command = match (solver_config.command(), &model.transition) {
+            (VerifyCommand::Verify, Some(_)) => VerifyCommand::Induction(1),
+            (command, _) => command,
         };
-        let verif_fns_string = format!("{}\ncheck;\nprint_results;", verif_fns_string);
-        let verif_fns_string = helpers::indent_text(verif_fns_string, 4);
-        let solver_opts = helpers::indent_text(format!("set_solver_option(\":mbqi\", false);\nset_solver_option(\":case_split\", 0);\nset_solver_option(\":relevancy\", 0);\nset_solver_option(\":blast_full\", true);"), 4);
-        let control_string = format!("control {{\n{}\n{}\n}}", solver_opts, verif_fns_string);
-        helpers::indent_text(control_string, 4)
+        match command {
+            VerifyCommand::Verify => {
+                let timeouts = verified
+                    .iter()
+                    .map(|f_name| solver_config.timeout_option(f_name))
+                    .collect::<Option<Vec<_>>>();
+                if let Some(timeouts) = timeouts {
+                    for (f_name, (option, ms)) in verified.iter().zip(timeouts) {
+                        out.line(&format!("set_solver_option(\"{}\", {});", option, ms))?;
+                        out.line(&format!("f{} = verify({});", f_name, f_name))?;
+                        out.line("check;")?;
+                        out.line("print_results;")?;
+                        out.line(&print_cex(&format!("f{}", f_name)))?;
+                    }
+                } else {
+                    for f_name in &verified {
+                        out.line(&format!("f{} = verify({});", f_name, f_name))?;
+                    }
+                    out.line("check;")?;
+                    out.line("print_results;")?;
+                    for f_name in &verified {
+                        out.line(&print_cex(&format!("f{}", f_name)))?;
+                    }
+                }
+            }
+            VerifyCommand::Bmc(steps) => {
+                if model.transition.is_none() {
+                    warn!("bmc checks the init and next blocks of the module; the procedures are only checked by verify.");
+                }
+                out.line(&format!("v_bmc = bmc({});", steps))?;
+                out.line("check;")?;
+                out.line("print_results;")?;
+                out.line(&print_cex("v_bmc"))?;
+            }
+            VerifyCommand::Induction(depth) => {
+                if model.transition.is_none() {
+                    warn!("induction checks the invariants of the transition system; use --transition-system.");
+                }
+                if depth == 1 {
+                    out.line("v_induction = induction;")?;
+                } else {
+                    out.line(&format!("v_induction = induction({});", depth))?;
+                }
+                out.line("check;")?;
+                out.line("print_results;")?;
+                out.line(&print_cex("v_induction"))?;
+            }
+        }
+        out.dedent();
+        out.line("}")
     }
 
     // ==================================================================================================================
@@ -537,17 +655,6 @@ impl IRInterface for Uclid5Interface {
     }
 
     /// Statements to string
-    fn stmt_to_string(stmt: &Stmt, xlen: &u64) -> String {
-        match stmt {
-            Stmt::Assume(expr) => Self::assume_to_string(&expr, xlen),
-            Stmt::FuncCall(fc) => Self::func_call_to_string(&fc, xlen),
-            Stmt::Assign(assign) => Self::assign_to_string(&assign, xlen),
-            Stmt::IfThenElse(ite) => Self::ite_to_string(&ite, xlen),
-            Stmt::Block(stmt_vec) => Self::block_to_string(&stmt_vec, xlen),
-            Stmt::Comment(comment) => Self::comment_to_string(&comment),
-        }
-    }
-
     fn skip_to_string() -> String {
         format!("")
     }
@@ -608,132 +715,86 @@ impl IRInterface for Uclid5Interface {
         format!("{} = {};", lhs, rhs)
     }
 
-    fn ite_to_string(ite: &IfThenElse, xlen: &u64) -> String {
-        let cond = Self::expr_to_string(&ite.cond, xlen);
-        let thn = helpers::indent_text(Self::stmt_to_string(&*ite.then_stmt, xlen), 4);
-        let els = if let Some(else_stmt) = &ite.else_stmt {
-            format!(
-                "else {{\n{}\n}}",
-                helpers::indent_text(Self::stmt_to_string(&*else_stmt, xlen), 4)
-            )
-        } else {
-            String::from("")
-        };
-        format!("if ({}) {{\n{}\n}}{}", cond, thn, els)
+    fn comment_to_string(string: &String) -> String {
+        format!("// {}\n", string)
     }
 
-    fn block_to_string(blk: &Vec<Box<Stmt>>, xlen: &u64) -> String {
-        let inner = blk
-            .iter()
-            .map(|rc_stmt| Self::stmt_to_string(rc_stmt, xlen))
-            .collect::<Vec<_>>()
-            .join("\n");
-        let inner = helpers::indent_text(inner, 4);
-        format!("{{\n{}\n}}", inner)
+    fn write_ite(out: &mut IndentWriter, ite: &IfThenElse, xlen: &u64) -> io::Result<()> {
+        out.line(&format!("if ({}) {{", Self::expr_to_string(&ite.cond, xlen)))?;
+        out.indent();
+        Self::write_stmt(out, &ite.then_stmt, xlen)?;
+        out.dedent();
+        if let Some(else_stmt) = &ite.else_stmt {
+            out.line("}else {")?;
+            out.indent();
+            Self::write_stmt(out, else_stmt, xlen)?;
+            out.dedent();
+        }
+        out.line("}")
     }
 
-    fn comment_to_string(string: &String) -> String {
-        format!("// {}\n", string)
+    fn write_block(out: &mut IndentWriter, blk: &[Box<Stmt>], xlen: &u64) -> io::Result<()> {
+        out.line("{")?;
+        out.indent();
+        for stmt in blk {
+            Self::write_stmt(out, stmt, xlen)?;
+        }
+        out.dedent();
+        out.line("}")
     }
 
-    fn func_model_to_string(fm: &FuncModel, dwarf_ctx: &DwarfCtx, xlen: &u64) -> String {
-        let args = fm
-            .sig
-            .arg_decls
-            .iter()
-            .map(|arg_expr| Self::var_decl(&arg_expr.get_var_name(), arg_expr.typ()))
-            .collect::<Vec<_>>()
-            .join(", ");
-        let ret = if let Some(rd) = &fm.sig.ret_decl {
-            format!(" returns (ret: {})", Self::typ_to_string(rd))
-        } else {
-            format!("")
-        };
-        let modifies = if fm.sig.mod_set.len() > 0 {
-            format!(
-                "\n    modifies {};",
-                fm.sig
-                    .mod_set
-                    .iter()
-                    .cloned()
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            )
-        } else {
-            format!("")
-        };
-        // Specifications
-        let mut specs = Self::specs_to_string(&fm.sig, dwarf_ctx, xlen);
-        if specs != "" {
-            debug!("\n{}", specs);
-        }
-        // Add formal argument constraints to specs
-        let arg_cons = fm
-            .sig
-            .arg_decls
-            .iter()
-            .enumerate()
-            .map(|(i, var)| {
-                let var_name = &var.get_var_name();
-                format!("requires {} == a{};", var_name, i)
-            })
-            .collect::<Vec<String>>()
-            .join("\n");
-        specs = format!("{}\n{}", specs, arg_cons);
-        // Add pc constraint to specs
-        let pc_cons = format!("requires pc == {}bv{};", fm.sig.entry_addr, xlen);
-        specs = format!("{}\n{}", specs, pc_cons);
-        // Add initial return value constraint
-        specs = format!("{}\n{}", specs, "requires returned == 0bv1;");
-        // Function body
-        let body = Self::block_to_string(fm.body.get_expect_block(), xlen);
-        // Inline flag
-        let inline = if fm.inline { "[inline] " } else { "" };
-        format!(
-            "procedure {}{}({}){}{}\n{}\n{}\n\n",
-            inline,
-            fm.sig.name,
-            args,
-            ret,
-            modifies,
-            helpers::indent_text(specs, 4),
-            body,
-        )
+    fn write_func_model(
+        out: &mut IndentWriter,
+        fm: &FuncModel,
+        dwarf_ctx: &DwarfCtx,
+        xlen: &u64,
+    ) -> io::Result<()> {
+        Self::write_proc(out, fm, dwarf_ctx, xlen, false, true)
     }
 
     // Generate function model
-    // NOTE: Replace string with write to file
-    fn model_to_string(
+    fn write_model(
+        out: &mut dyn Write,
         xlen: &u64,
         model: &Model,
         dwarf_ctx: &DwarfCtx,
         ignored_funcs: &HashSet<&str>,
         verify_funcs: &Vec<&str>,
-    ) -> String {
+        solver_config: &SolverConfig,
+    ) -> io::Result<()> {
+        let mut out = IndentWriter::new(out);
+        out.line(&format!("module {} {{", model.name))?;
+        out.indent();
         // prelude
-        let prelude = Self::prelude(xlen);
+        out.line(&Self::prelude(xlen))?;
         // variables
-        let var_defns = helpers::indent_text(Self::gen_var_defns(model), 4);
+        out.line(&Self::gen_var_defns(model))?;
         // definitions
-        let array_defns = Self::gen_array_defns(&dwarf_ctx, xlen); // Define macros that index for arrays (by muiltiplication)
-        let struct_defns = Self::gen_struct_defns(&dwarf_ctx, xlen); // Define macros for getting struct field values
-        let global_var_defns = Self::gen_global_defns(&dwarf_ctx, xlen); // Define macros for global variable pointers
-        let global_func_defns = Self::gen_global_func_defns(&model, xlen); // Define macros for function addresses                                              // procedures
-        let procs = Self::gen_procs(model, &dwarf_ctx, xlen);
+        out.line(&Self::gen_array_defns(dwarf_ctx, xlen))?; // Define macros that index for arrays (by muiltiplication)
+        out.line(&Self::gen_struct_defns(dwarf_ctx, xlen))?; // Define macros for getting struct field values
+        out.line(&Self::gen_global_defns(dwarf_ctx, xlen))?; // Define macros for global variable pointers
+        out.line(&Self::gen_global_func_defns(model, xlen))?; // Define macros for function addresses
+        let split_obligations = solver_config.split_obligations();
+        if split_obligations {
+            out.line(&Self::gen_requires_defns(model))?; // Define macros for the call site obligations
+        }
+        // procedures
+        Self::write_procs(&mut out, model, dwarf_ctx, xlen, split_obligations)?;
+        // transition system
+        if let Some(ts) = &model.transition {
+            Self::write_transition_system(&mut out, ts, xlen)?;
+        }
         // control block
-        let ctrl_blk = Self::control_blk(model, &dwarf_ctx, ignored_funcs, verify_funcs);
-        format!(
-            "module {} {{\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n\n{}\n}}",
-            model.name,
-            prelude,
-            var_defns,
-            array_defns,
-            struct_defns,
-            global_var_defns,
-            global_func_defns,
-            procs,
-            ctrl_blk
-        )
+        Self::write_control_blk(
+            &mut out,
+            model,
+            dwarf_ctx,
+            ignored_funcs,
+            verify_funcs,
+            solver_config,
+        )?;
+        out.dedent();
+        out.line("}")
     }
 }
 
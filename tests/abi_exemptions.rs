@@ -0,0 +1,50 @@
+//! Parses the `--abi-exempt` lists of calling-convention exemptions.
+
+extern crate riscverifier;
+
+use riscverifier::parse_abi_exemptions;
+use std::collections::HashSet;
+
+fn clauses(clauses: &[&str]) -> HashSet<String> {
+    clauses.iter().map(|c| c.to_string()).collect()
+}
+
+#[test]
+fn function_name_exempts_all_clauses() {
+    let exemptions = parse_abi_exemptions("trap_handler");
+    let all = &exemptions["trap_handler"];
+    assert_eq!(all.len(), 16);
+    assert!(all.contains("ra") && all.contains("sp") && all.contains("s11"));
+}
+
+#[test]
+fn clauses_of_several_functions() {
+    let exemptions = parse_abi_exemptions("setjmp:s0:s1, longjmp:ra,setjmp:sp");
+    assert_eq!(exemptions.len(), 2);
+    assert_eq!(exemptions["setjmp"], clauses(&["s0", "s1", "sp"]));
+    assert_eq!(exemptions["longjmp"], clauses(&["ra"]));
+}
+
+#[test]
+#[should_panic(expected = "Unknown calling-convention clause a0 in foo:a0.")]
+fn unknown_clause() {
+    parse_abi_exemptions("foo:a0");
+}
+
+#[test]
+#[should_panic(expected = "Unknown calling-convention clause  in foo:.")]
+fn empty_clause() {
+    parse_abi_exemptions("foo:");
+}
+
+#[test]
+#[should_panic(expected = "Missing function name in calling-convention exemption \"\".")]
+fn trailing_comma() {
+    parse_abi_exemptions("foo,");
+}
+
+#[test]
+#[should_panic(expected = "Missing function name in calling-convention exemption \":ra\".")]
+fn missing_function_name() {
+    parse_abi_exemptions(":ra");
+}
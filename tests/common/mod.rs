@@ -0,0 +1,25 @@
+//! Helpers shared by the integration tests.
+
+use riscverifier::disassembler::disassembler::{AssemblyLine, Disassembler};
+use std::{env, fs, os::unix::fs::PermissionsExt, path::PathBuf, rc::Rc};
+
+/// Returns a fresh temporary directory for the test `name`
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("riscverifier-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Disassembles `listing`, given in the format of `objdump -d -M no-aliases --prefix-addresses`,
+/// with a stand-in objdump script that prints it
+#[allow(dead_code)]
+pub fn disassemble(name: &str, listing: &str) -> Vec<Rc<AssemblyLine>> {
+    let dir = test_dir(name);
+    let binary = dir.join("binary.dump");
+    fs::write(&binary, listing).unwrap();
+    let objdump = dir.join("objdump");
+    fs::write(&objdump, "#!/bin/sh\nfor path; do :; done\ncat \"$path\"\n").unwrap();
+    fs::set_permissions(&objdump, fs::Permissions::from_mode(0o755)).unwrap();
+    let mut disassembler = Disassembler::new(objdump.to_str(), None);
+    disassembler.read_binaries(&vec![binary.to_str().unwrap()])
+}
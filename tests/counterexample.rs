@@ -0,0 +1,165 @@
+//! Parses captured UCLID5 counterexamples and maps them back to a canned disassembly.
+
+extern crate dwarf_ctx;
+extern crate riscverifier;
+
+mod common;
+
+use dwarf_ctx::dwarfreader::{
+    DwarfCtx, DwarfLineInfo, DwarfTypeDefn, DwarfVar, SourceLoc, StructField,
+};
+use riscverifier::{
+    counterexample::counterexample::{CexMapper, CexValue, Counterexample},
+    datastructures::cfg::BasicBlock,
+};
+use std::{collections::HashMap, fs, rc::Rc};
+
+/// `inc` increments the long at a0 and returns 0 if the result is not zero
+const INC: &str = "0000000080000000 <inc> ld a1,0(a0)
+0000000080000004 <inc+0x4> addi a1,a1,1
+0000000080000008 <inc+0x8> sd a1,0(a0)
+000000008000000c <inc+0xc> beq a1,zero,0000000080000014 <inc+0x14>
+0000000080000010 <inc+0x10> addi a0,zero,0
+0000000080000014 <inc+0x14> jalr zero,0(ra)
+";
+
+/// Address of the global `struct counters { long hits; long misses; } counters`
+const COUNTERS: u64 = 0x80001000;
+
+/// Output of `print_cex` for a failed memory-safety assertion of `inc`
+const CEX: &str = "CEX for assertion @ model.ucl, line 5
+=================================
+Step #0
+  pc : 2147483648bv64
+  a0 : 2147487752bv64
+  a1 : 0bv64
+  mem_access_2147487752 : 41bv64
+  returned : false
+=================================
+Step #1
+  pc : 2147483652bv64
+  a0 : 2147487752bv64
+  a1 : 41bv64
+  mem_access_2147487752 : 41bv64
+  returned : false
+=================================
+Step #2
+  pc : 2147483664bv64
+  a0 : 2147487752bv64
+  a1 : 42bv64
+  mem_access_2147487752 : 42bv64
+  returned : false
+=================================
+";
+
+/// Generated model the counterexample refers to
+const MODEL: &str = "procedure inc()
+{
+  call bb_0x80000000();
+  // memory safety: sd at 0x80000008
+  assert (a0 == 2147487744bv64);
+}
+";
+
+/// Returns a context with the global `counters` and the source lines of `inc` in `inc.c`
+fn dwarf_ctx() -> DwarfCtx {
+    let long = Rc::new(DwarfTypeDefn::Primitive { bytes: 8 });
+    let mut fields = HashMap::new();
+    for (name, loc) in &[("hits", 0), ("misses", 8)] {
+        let field = StructField {
+            name: name.to_string(),
+            typ: Rc::clone(&long),
+            loc: *loc,
+        };
+        fields.insert(name.to_string(), field);
+    }
+    let counters = Rc::new(DwarfTypeDefn::Struct {
+        id: "counters".to_string(),
+        fields,
+        bytes: 16,
+    });
+    let mut line_info = DwarfLineInfo::new();
+    for (addr, line) in &[(0x80000000, 3), (0x80000008, 4), (0x80000010, 5)] {
+        let loc = SourceLoc {
+            file: "inc.c".to_string(),
+            line: *line,
+            column: 0,
+        };
+        line_info.add_row(*addr, Some(loc));
+    }
+    line_info.add_row(0x80000018, None);
+    let globals = vec![DwarfVar::new("counters".to_string(), counters, COUNTERS)];
+    DwarfCtx::new(64, HashMap::new(), globals, line_info)
+}
+
+#[test]
+fn parse_uclid5_trace() {
+    let cexs = Counterexample::parse(CEX);
+    assert_eq!(cexs.len(), 1);
+    let cex = &cexs[0];
+    assert_eq!(cex.property, "assertion @ model.ucl, line 5");
+    assert_eq!(
+        cex.frames
+            .iter()
+            .map(|frame| frame.step)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+    let last = &cex.frames[2].vals;
+    assert_eq!(last["pc"], CexValue::Bv(0x80000010, 64));
+    assert_eq!(last["a1"], CexValue::Bv(42, 64));
+    assert_eq!(last["returned"], CexValue::Bool(false));
+    assert_eq!(last["mem_access_2147487752"].as_u64(), Some(42));
+}
+
+#[test]
+fn map_trace_to_binary() {
+    let als = common::disassemble("cex", INC);
+    let bbs = BasicBlock::split(&als);
+    let dwarf_ctx = dwarf_ctx();
+    let mut mapper = CexMapper::new(&als, &bbs, &dwarf_ctx);
+    let model = common::test_dir("cex").join("model.ucl");
+    fs::write(&model, MODEL).unwrap();
+    mapper.set_model_file(model.to_str().unwrap());
+
+    assert_eq!(
+        mapper.describe_pc(0x80000004),
+        "0x80000004 <inc+0x4>: addi a1, a1, 1 [bb_0x80000000] @ inc.c:3"
+    );
+    assert_eq!(
+        mapper.describe_pc(0x80000010),
+        "0x80000010 <inc+0x10>: addi a0, zero, 0 [bb_0x80000010] @ inc.c:5"
+    );
+    assert_eq!(
+        mapper.describe_var("mem_access_2147487752"),
+        "mem_access_2147487752 (counters+0x8)"
+    );
+
+    let cex = &Counterexample::parse(CEX)[0];
+    let expected = "// Counterexample for assertion @ model.ucl, line 5
+// Failed check: memory safety: sd at 0x80000008
+Step #0
+  pc = 0x80000000 <inc+0x0>: ld a1, a0, 0 [bb_0x80000000] @ inc.c:3
+  a0 = 0x80001008 (bv64)
+  a1 = 0x0 (bv64)
+  mem_access_2147487752 (counters+0x8) = 0x29 (bv64)
+  returned = false
+Step #1
+  pc = 0x80000004 <inc+0x4>: addi a1, a1, 1 [bb_0x80000000] @ inc.c:3
+  a1 = 0x29 (bv64)
+Step #2
+  pc = 0x80000010 <inc+0x10>: addi a0, zero, 0 [bb_0x80000010] @ inc.c:5
+  a1 = 0x2a (bv64)
+  mem_access_2147487752 (counters+0x8) = 0x2a (bv64)
+";
+    assert_eq!(mapper.describe(cex), expected);
+
+    let expected = "// Initial state of the counterexample for assertion @ model.ucl, line 5
+a0 = 0x80001008
+a1 = 0x0
+// counters+0x8
+mem_d[0x80001008] = 0x29
+pc = 0x80000000
+";
+    assert_eq!(mapper.initial_state(cex), expected);
+}
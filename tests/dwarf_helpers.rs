@@ -0,0 +1,69 @@
+//! Collects the array index sizes and struct fields defined by every interface.
+
+extern crate dwarf_ctx;
+extern crate riscverifier;
+
+use dwarf_ctx::dwarfreader::{
+    DwarfCtx, DwarfFuncSig, DwarfLineInfo, DwarfTypeDefn, DwarfVar, StructField,
+};
+use riscverifier::ir_interface::{array_index_sizes, struct_fields};
+use std::{collections::HashMap, rc::Rc};
+
+/// Returns a context with a global `struct ctx { int a0; short a1; } ctxs[4]` and
+/// the signature of `get(char *buf)`
+fn dwarf_ctx() -> DwarfCtx {
+    let int = Rc::new(DwarfTypeDefn::Primitive { bytes: 4 });
+    let short = Rc::new(DwarfTypeDefn::Primitive { bytes: 2 });
+    let mut fields = HashMap::new();
+    for (name, typ, loc) in vec![("a0", int, 0), ("a1", short, 4)] {
+        let name = name.to_string();
+        fields.insert(name.clone(), StructField { name, typ, loc });
+    }
+    let ctx = Rc::new(DwarfTypeDefn::Struct {
+        id: "ctx".to_string(),
+        fields,
+        bytes: 8,
+    });
+    let ctxs = Rc::new(DwarfTypeDefn::Array {
+        in_typ: Rc::new(DwarfTypeDefn::Primitive { bytes: 8 }),
+        out_typ: ctx,
+        bytes: 8,
+        len: Some(4),
+    });
+    let buf = Rc::new(DwarfTypeDefn::Pointer {
+        value_typ: Rc::new(DwarfTypeDefn::Primitive { bytes: 1 }),
+        bytes: 8,
+    });
+    let mut func_sigs = HashMap::new();
+    func_sigs.insert(
+        "get".to_string(),
+        DwarfFuncSig::new(
+            "get".to_string(),
+            vec![DwarfVar::new("buf".to_string(), buf, 0)],
+            None,
+        ),
+    );
+    let global_vars = vec![DwarfVar::new("ctxs".to_string(), ctxs, 0x1000)];
+    DwarfCtx::new(64, func_sigs, global_vars, DwarfLineInfo::new())
+}
+
+#[test]
+fn array_index_sizes_of_nested_types() {
+    // Struct elements, their fields, the array index and the pointed-to values
+    let sizes = array_index_sizes(&dwarf_ctx())
+        .into_iter()
+        .collect::<Vec<_>>();
+    assert_eq!(sizes, vec![1, 2, 4, 8]);
+}
+
+#[test]
+fn struct_fields_of_array_elements() {
+    let fields = struct_fields(&dwarf_ctx()).into_iter().collect::<Vec<_>>();
+    assert_eq!(
+        fields,
+        vec![
+            ("ctx".to_string(), "a0".to_string(), 0),
+            ("ctx".to_string(), "a1".to_string(), 4),
+        ]
+    );
+}
@@ -0,0 +1,55 @@
+//! Writes small nested outputs with the indentation-aware writer.
+
+extern crate riscverifier;
+
+use riscverifier::ir_interface::IndentWriter;
+use std::io;
+
+/// Returns the output of `write` as a string
+fn written(write: impl FnOnce(&mut IndentWriter) -> io::Result<()>) -> String {
+    let mut buf = vec![];
+    write(&mut IndentWriter::new(&mut buf)).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[test]
+fn nested_indent_and_dedent() {
+    let text = written(|out| {
+        out.line("procedure main()")?;
+        out.line("{")?;
+        out.indent();
+        out.line("if (c) {")?;
+        out.indent();
+        out.line("x = 1;")?;
+        out.dedent();
+        out.line("}")?;
+        out.dedent();
+        out.line("}")
+    });
+    assert_eq!(
+        text,
+        "procedure main()\n{\n    if (c) {\n        x = 1;\n    }\n}\n"
+    );
+}
+
+#[test]
+fn multi_line_text_is_indented_line_by_line() {
+    let text = written(|out| {
+        out.indent();
+        out.line("a = 1;\nb = 2;")
+    });
+    assert_eq!(text, "    a = 1;\n    b = 2;\n");
+}
+
+#[test]
+fn empty_lines_are_not_indented() {
+    let text = written(|out| {
+        out.indent();
+        // Empty text writes nothing
+        out.line("")?;
+        out.line("a = 1;\n\nb = 2;")?;
+        out.blank()?;
+        out.line("c = 3;")
+    });
+    assert_eq!(text, "    a = 1;\n\n    b = 2;\n\n    c = 3;\n");
+}
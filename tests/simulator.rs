@@ -0,0 +1,265 @@
+//! Runs small instruction sequences in the simulator and checks the final machine state.
+
+extern crate riscverifier;
+
+mod common;
+
+use riscverifier::simulator::simulator::{SimState, SimTrace, Simulator, StopReason};
+
+/// Address of the simulated function `f`
+const ENTRY: u64 = 0x8000_0000;
+/// Address of the data used by the loads and atomic memory operations
+const DATA: u64 = 0x8000_1000;
+
+// =========================================================================================
+// # Instruction encodings
+
+const OP: u32 = 0x33;
+const OP_32: u32 = 0x3b;
+const OP_IMM_32: u32 = 0x1b;
+const LOAD: u32 = 0x03;
+const AMO: u32 = 0x2f;
+
+/// Returns the register number of the register name
+fn reg(name: &str) -> u32 {
+    SimState::reg_index(name).unwrap() as u32
+}
+
+fn r_type(opcode: u32, f3: u32, f7: u32, rd: &str, rs1: &str, rs2: &str) -> u32 {
+    (f7 << 25) | (reg(rs2) << 20) | (reg(rs1) << 15) | (f3 << 12) | (reg(rd) << 7) | opcode
+}
+
+fn i_type(opcode: u32, f3: u32, rd: &str, rs1: &str, imm: i32) -> u32 {
+    ((imm as u32) << 20) | (reg(rs1) << 15) | (f3 << 12) | (reg(rd) << 7) | opcode
+}
+
+/// Atomic memory operation `funct5` on words (`f3` 2) or double words (`f3` 3)
+fn amo(funct5: u32, f3: u32, rd: &str, rs2: &str, rs1: &str) -> u32 {
+    r_type(AMO, f3, funct5 << 2, rd, rs1, rs2)
+}
+
+/// `jalr zero, 0(ra)`
+fn ret() -> u32 {
+    i_type(0x67, 0, "zero", "ra", 0)
+}
+
+// =========================================================================================
+// # Simulation helpers
+
+/// Runs the instructions (disassembly and encoding) as the function `f`, followed by a
+/// return, from the initial register values `regs` and memory `mem` (address, bytes, value)
+fn run(
+    name: &str,
+    insts: &[(&str, u32)],
+    regs: &[(&str, u64)],
+    mem: &[(u64, u64, u64)],
+) -> SimTrace {
+    let insts = insts
+        .iter()
+        .cloned()
+        .chain(std::iter::once(("jalr zero,0(ra)", ret())))
+        .collect::<Vec<_>>();
+    let listing = insts
+        .iter()
+        .enumerate()
+        .map(|(i, (asm, _))| {
+            let addr = ENTRY + 4 * i as u64;
+            match i {
+                0 => format!("{:016x} <f> {}\n", addr, asm),
+                _ => format!("{:016x} <f+{:#x}> {}\n", addr, 4 * i, asm),
+            }
+        })
+        .collect::<String>();
+    let als = common::disassemble(name, &listing);
+    let mut sim = Simulator::new(&als, 100);
+    let state = sim.state_mut();
+    for (i, (_, word)) in insts.iter().enumerate() {
+        state.store(ENTRY + 4 * i as u64, 4, *word as u64);
+    }
+    for (name, value) in regs {
+        state.set_reg(SimState::reg_index(name).unwrap(), *value);
+    }
+    for (addr, bytes, value) in mem {
+        state.store(*addr, *bytes, *value);
+    }
+    let trace = sim.run("f");
+    assert_eq!(trace.stop, StopReason::Returned);
+    assert_eq!(trace.steps.len(), insts.len());
+    trace
+}
+
+/// Returns the value of the register in the final state of the trace
+fn reg_val(trace: &SimTrace, name: &str) -> u64 {
+    trace.state.reg(SimState::reg_index(name).unwrap())
+}
+
+// =========================================================================================
+/// # Tests
+
+#[test]
+fn loads_sign_and_zero_extend() {
+    let trace = run(
+        "sim-loads",
+        &[
+            ("lb a1,0(a0)", i_type(LOAD, 0, "a1", "a0", 0)),
+            ("lbu a2,0(a0)", i_type(LOAD, 4, "a2", "a0", 0)),
+            ("lh a3,0(a0)", i_type(LOAD, 1, "a3", "a0", 0)),
+            ("lhu a4,0(a0)", i_type(LOAD, 5, "a4", "a0", 0)),
+            ("lw a5,0(a0)", i_type(LOAD, 2, "a5", "a0", 0)),
+            ("lwu a6,0(a0)", i_type(LOAD, 6, "a6", "a0", 0)),
+            ("ld a7,-8(a0)", i_type(LOAD, 3, "a7", "a0", -8)),
+        ],
+        &[("a0", DATA + 8)],
+        &[(DATA, 8, 0x0123_4567_89ab_cdef), (DATA + 8, 4, 0x8765_43f0)],
+    );
+    assert_eq!(reg_val(&trace, "a1"), 0xffff_ffff_ffff_fff0);
+    assert_eq!(reg_val(&trace, "a2"), 0xf0);
+    assert_eq!(reg_val(&trace, "a3"), 0x0000_0000_0000_43f0);
+    assert_eq!(reg_val(&trace, "a4"), 0x43f0);
+    assert_eq!(reg_val(&trace, "a5"), 0xffff_ffff_8765_43f0);
+    assert_eq!(reg_val(&trace, "a6"), 0x8765_43f0);
+    assert_eq!(reg_val(&trace, "a7"), 0x0123_4567_89ab_cdef);
+}
+
+#[test]
+fn word_operations_sign_extend_the_lower_word() {
+    let trace = run(
+        "sim-w-ops",
+        &[
+            ("addw a2,a0,a1", r_type(OP_32, 0, 0x00, "a2", "a0", "a1")),
+            (
+                "subw a3,zero,a1",
+                r_type(OP_32, 0, 0x20, "a3", "zero", "a1"),
+            ),
+            ("addiw a4,a0,0", i_type(OP_IMM_32, 0, "a4", "a0", 0)),
+            ("sllw a5,a1,a6", r_type(OP_32, 1, 0x00, "a5", "a1", "a6")),
+            ("srlw t0,a3,a6", r_type(OP_32, 5, 0x00, "t0", "a3", "a6")),
+            ("sraw t1,a3,a6", r_type(OP_32, 5, 0x20, "t1", "a3", "a6")),
+            ("mulw t2,a0,a0", r_type(OP_32, 0, 0x01, "t2", "a0", "a0")),
+        ],
+        // The upper words of the operands and the shift amount are ignored
+        &[
+            ("a0", 0xdead_beef_7fff_ffff),
+            ("a1", 0x1_0000_0001),
+            ("a6", 0x3f),
+        ],
+        &[],
+    );
+    assert_eq!(reg_val(&trace, "a2"), 0xffff_ffff_8000_0000);
+    assert_eq!(reg_val(&trace, "a3"), 0xffff_ffff_ffff_ffff);
+    assert_eq!(reg_val(&trace, "a4"), 0x0000_0000_7fff_ffff);
+    assert_eq!(reg_val(&trace, "a5"), 0xffff_ffff_8000_0000);
+    assert_eq!(reg_val(&trace, "t0"), 0x1);
+    assert_eq!(reg_val(&trace, "t1"), 0xffff_ffff_ffff_ffff);
+    assert_eq!(reg_val(&trace, "t2"), 0x1);
+}
+
+#[test]
+fn division_by_zero_and_overflow() {
+    let trace = run(
+        "sim-div",
+        &[
+            ("div a2,a0,zero", r_type(OP, 4, 0x01, "a2", "a0", "zero")),
+            ("divu a3,a0,zero", r_type(OP, 5, 0x01, "a3", "a0", "zero")),
+            ("rem a4,a0,zero", r_type(OP, 6, 0x01, "a4", "a0", "zero")),
+            ("remu a5,a0,zero", r_type(OP, 7, 0x01, "a5", "a0", "zero")),
+            ("div a6,a1,a7", r_type(OP, 4, 0x01, "a6", "a1", "a7")),
+            ("rem t0,a1,a7", r_type(OP, 6, 0x01, "t0", "a1", "a7")),
+            (
+                "divw t1,a0,zero",
+                r_type(OP_32, 4, 0x01, "t1", "a0", "zero"),
+            ),
+            (
+                "remw t2,a0,zero",
+                r_type(OP_32, 6, 0x01, "t2", "a0", "zero"),
+            ),
+            ("divw t3,t5,a7", r_type(OP_32, 4, 0x01, "t3", "t5", "a7")),
+            (
+                "remuw t4,a0,zero",
+                r_type(OP_32, 7, 0x01, "t4", "a0", "zero"),
+            ),
+        ],
+        &[
+            ("a0", 0x1234_5678_9abc_def0),
+            ("a1", 0x8000_0000_0000_0000),
+            ("a7", u64::MAX),
+            ("t5", 0x8000_0000),
+        ],
+        &[],
+    );
+    // Division by zero returns all ones and the remainder is the dividend
+    assert_eq!(reg_val(&trace, "a2"), u64::MAX);
+    assert_eq!(reg_val(&trace, "a3"), u64::MAX);
+    assert_eq!(reg_val(&trace, "a4"), 0x1234_5678_9abc_def0);
+    assert_eq!(reg_val(&trace, "a5"), 0x1234_5678_9abc_def0);
+    // The signed overflow returns the dividend and a zero remainder
+    assert_eq!(reg_val(&trace, "a6"), 0x8000_0000_0000_0000);
+    assert_eq!(reg_val(&trace, "t0"), 0);
+    // The word variants sign extend the results
+    assert_eq!(reg_val(&trace, "t1"), u64::MAX);
+    assert_eq!(reg_val(&trace, "t2"), 0xffff_ffff_9abc_def0);
+    assert_eq!(reg_val(&trace, "t3"), 0xffff_ffff_8000_0000);
+    assert_eq!(reg_val(&trace, "t4"), 0xffff_ffff_9abc_def0);
+}
+
+#[test]
+fn atomic_memory_operations() {
+    let trace = run(
+        "sim-amo",
+        &[
+            ("amoadd.w a2,a1,(a0)", amo(0x00, 2, "a2", "a1", "a0")),
+            ("amoswap.d a3,a1,(t0)", amo(0x01, 3, "a3", "a1", "t0")),
+            ("amomax.w a4,a5,(t1)", amo(0x14, 2, "a4", "a5", "t1")),
+            ("amominu.w a6,a5,(t2)", amo(0x18, 2, "a6", "a5", "t2")),
+            ("lr.d a7,(t0)", amo(0x02, 3, "a7", "zero", "t0")),
+            ("sc.d t3,a5,(t0)", amo(0x03, 3, "t3", "a5", "t0")),
+            ("sc.d t4,a1,(t0)", amo(0x03, 3, "t4", "a1", "t0")),
+        ],
+        &[
+            ("a0", DATA),
+            ("a1", 1),
+            ("a5", 0xffff_ffff_ffff_fffe),
+            ("t0", DATA + 8),
+            ("t1", DATA + 16),
+            ("t2", DATA + 20),
+        ],
+        &[
+            (DATA, 4, 0x7fff_ffff),
+            (DATA + 8, 8, 0x1122_3344_5566_7788),
+            (DATA + 16, 4, 0x8000_0000),
+            (DATA + 20, 4, 0x8000_0000),
+        ],
+    );
+    // The old value is written to rd (sign extended for words)
+    assert_eq!(reg_val(&trace, "a2"), 0x7fff_ffff);
+    assert_eq!(trace.state.load(DATA, 4), 0x8000_0000);
+    assert_eq!(reg_val(&trace, "a3"), 0x1122_3344_5566_7788);
+    // Signed maximum of -2^31 and -2 and unsigned minimum of 2^31 and 2^32 - 2
+    assert_eq!(reg_val(&trace, "a4"), 0xffff_ffff_8000_0000);
+    assert_eq!(trace.state.load(DATA + 16, 4), 0xffff_fffe);
+    assert_eq!(reg_val(&trace, "a6"), 0xffff_ffff_8000_0000);
+    assert_eq!(trace.state.load(DATA + 20, 4), 0x8000_0000);
+    // The store conditional succeeds once after the load reserved
+    assert_eq!(reg_val(&trace, "a7"), 1);
+    assert_eq!(reg_val(&trace, "t3"), 0);
+    assert_eq!(reg_val(&trace, "t4"), 1);
+    assert_eq!(trace.state.load(DATA + 8, 8), 0xffff_ffff_ffff_fffe);
+    // The other bytes are unchanged
+    assert_eq!(trace.state.load(DATA + 4, 4), 0);
+}
+
+#[test]
+fn misaligned_atomic_access_traps() {
+    let insts = [("amoadd.w a2,a1,(a0)", amo(0x00, 2, "a2", "a1", "a0"))];
+    let listing = format!("{:016x} <f> {}\n", ENTRY, insts[0].0);
+    let als = common::disassemble("sim-amo-misaligned", &listing);
+    let mut sim = Simulator::new(&als, 100);
+    sim.state_mut().store(ENTRY, 4, insts[0].1 as u64);
+    sim.state_mut().set_reg(reg("a0") as usize, DATA + 2);
+    let trace = sim.run("f");
+    assert_eq!(
+        trace.stop,
+        StopReason::Trap(format!("misaligned atomic access at {:#x}", DATA + 2))
+    );
+    assert!(trace.steps.is_empty());
+}
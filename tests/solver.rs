@@ -0,0 +1,313 @@
+//! Runs the UCLID5 driver against stand-in scripts that print canned UCLID5 output.
+
+extern crate riscverifier;
+
+use riscverifier::solver::{
+    CheckResult, CheckStatus, SolverConfig, SolverProfile, UclidRunner, VerifyCommand,
+};
+use std::{env, fs, os::unix::fs::PermissionsExt, path::PathBuf, time::Duration};
+
+/// Generated model with two verified functions; `bar` inlines `bar.bb`
+const MODEL: &str = "module main {
+procedure foo()
+    modifies pc;
+{
+    pc = 0bv64;
+}
+
+procedure [inline] bar.bb()
+    modifies pc;
+{
+    assert (pc == 4bv64);
+}
+
+procedure bar()
+    modifies pc;
+{
+    call () = bar_bb();
+}
+
+control {
+    f0 = verify(foo);
+    f1 = verify(bar);
+    check;
+    print_results;
+}
+}
+";
+
+/// Model with the obligations of `foo` checked separately; `foo` calls `bar`
+const SPLIT_MODEL: &str = "module main {
+define bar_requires_0(a0: bv64): boolean = (a0 >_u 0bv64);
+
+procedure bar(a0: bv64)
+    modifies pc;
+    requires (a0 >_u 0bv64);
+{
+    pc = 0bv64;
+}
+
+procedure foo(a0: bv64)
+    modifies pc;
+    // obligation: ensures spec.rvspecs:7:5 of foo
+    ensures (pc == 0bv64);
+    // obligation: ensures spec.rvspecs:8:5 of foo
+    ensures (a0 == old(a0));
+{
+    // obligation: requires spec.rvspecs:2:5 of bar at call 0x10080
+
+    assert (bar_requires_0(a0));
+    call () = bar(a0);
+}
+
+control {
+    ffoo = verify(foo);
+    check;
+    print_results;
+}
+}
+";
+
+/// Returns the (1-indexed) line of the model that starts with `prefix`
+fn line_of(model: &str, prefix: &str) -> usize {
+    model.lines().position(|line| line.trim().starts_with(prefix)).unwrap() + 1
+}
+
+/// Writes the model and a stand-in UCLID5 script running `body` to a fresh directory
+fn setup(name: &str, body: &str) -> (String, String) {
+    setup_model(name, MODEL, body)
+}
+
+fn setup_model(name: &str, model_text: &str, body: &str) -> (String, String) {
+    let dir: PathBuf = env::temp_dir().join(format!("riscverifier-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let model = dir.join("model.ucl");
+    fs::write(&model, model_text).unwrap();
+    let uclid = dir.join("uclid");
+    fs::write(&uclid, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    fs::set_permissions(&uclid, fs::Permissions::from_mode(0o755)).unwrap();
+    (
+        uclid.to_str().unwrap().to_string(),
+        model.to_str().unwrap().to_string(),
+    )
+}
+
+fn statuses(results: &[CheckResult]) -> Vec<(&str, CheckStatus)> {
+    results
+        .iter()
+        .map(|res| (res.func_name.as_str(), res.status))
+        .collect()
+}
+
+#[test]
+fn all_passed() {
+    let (uclid, model) = setup(
+        "passed",
+        "echo '2 assertions passed.'; echo '0 assertions failed.'; echo '0 assertions indeterminate.'",
+    );
+    let results = UclidRunner::new(&uclid, vec![], None).check(&model).unwrap();
+    assert_eq!(
+        statuses(&results),
+        vec![("foo", CheckStatus::Passed), ("bar", CheckStatus::Passed)]
+    );
+    assert_eq!(CheckResult::exit_code(&results), 0);
+}
+
+#[test]
+fn failure_in_inlined_procedure() {
+    let (uclid, model) = setup(
+        "failed",
+        "echo '1 assertions passed.'; echo '1 assertions failed.'; echo '0 assertions indeterminate.'
+echo '  FAILED -> [Step #1] assertion @ '\"$1\"', line 11'",
+    );
+    let results = UclidRunner::new(&uclid, vec![], None).check(&model).unwrap();
+    assert_eq!(
+        statuses(&results),
+        vec![("foo", CheckStatus::Passed), ("bar", CheckStatus::Failed)]
+    );
+    assert_eq!(results[1].properties.len(), 1);
+    assert_eq!(CheckResult::exit_code(&results), 1);
+}
+
+#[test]
+fn undetermined_by_label() {
+    let (uclid, model) = setup(
+        "undef",
+        "echo '1 assertions passed.'; echo '0 assertions failed.'; echo '1 assertions indeterminate.'
+echo '  UNDEF -> f0: ensures clause'",
+    );
+    let results = UclidRunner::new(&uclid, vec![], None).check(&model).unwrap();
+    assert_eq!(
+        statuses(&results),
+        vec![("foo", CheckStatus::Undetermined), ("bar", CheckStatus::Passed)]
+    );
+    assert_eq!(CheckResult::exit_code(&results), 2);
+}
+
+#[test]
+fn arguments_are_passed_before_the_model() {
+    let (uclid, model) = setup(
+        "args",
+        "[ \"$1\" = '-s' ] && [ \"$2\" = 'cvc4 --incremental' ] || exit 1
+echo '2 assertions passed.'",
+    );
+    let args = vec!["-s".to_string(), "cvc4 --incremental".to_string()];
+    let results = UclidRunner::new(&uclid, args, None).check(&model).unwrap();
+    assert_eq!(CheckResult::exit_code(&results), 0);
+}
+
+#[test]
+fn missing_results_are_undetermined() {
+    let (uclid, model) = setup("crash", "echo 'Exception in thread main' >&2; exit 1");
+    let results = UclidRunner::new(&uclid, vec![], None).check(&model).unwrap();
+    assert_eq!(
+        statuses(&results),
+        vec![
+            ("foo", CheckStatus::Undetermined),
+            ("bar", CheckStatus::Undetermined)
+        ]
+    );
+}
+
+#[test]
+fn timeout() {
+    let (uclid, model) = setup("timeout", "sleep 10");
+    let results = UclidRunner::new(&uclid, vec![], Some(Duration::from_millis(200)))
+        .check(&model)
+        .unwrap();
+    assert_eq!(
+        statuses(&results),
+        vec![("foo", CheckStatus::Timeout), ("bar", CheckStatus::Timeout)]
+    );
+    assert_eq!(CheckResult::exit_code(&results), 2);
+}
+
+#[test]
+fn solver_config_file() {
+    let dir = env::temp_dir().join(format!("riscverifier-config-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let config_file = dir.join("solver.cfg");
+    fs::write(
+        &config_file,
+        "// CVC4 with a longer timeout for bar
+solver = cvc4
+option :bitblast = eager
+timeout = 60
+timeout bar = 600
+command = bmc 10
+",
+    )
+    .unwrap();
+    let mut config = SolverConfig::default();
+    config.load_file(config_file.to_str().unwrap()).unwrap();
+    assert_eq!(
+        config.solver_options(),
+        vec![(":bitblast".to_string(), "\"eager\"".to_string())]
+    );
+    assert_eq!(config.timeout_option("foo"), Some((":tlimit-per", 60000)));
+    assert_eq!(config.timeout_option("bar"), Some((":tlimit-per", 600000)));
+    assert_eq!(config.command(), VerifyCommand::Bmc(10));
+    assert_eq!(config.uclid_args()[0], "-s");
+    // Z3 is built into UCLID5
+    config.set_profile(SolverProfile::Z3);
+    assert!(config.uclid_args().is_empty());
+}
+
+#[test]
+fn invalid_solver_config_file() {
+    let dir = env::temp_dir().join(format!("riscverifier-bad-config-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let config_file = dir.join("solver.cfg");
+    fs::write(&config_file, "solver = cvc4\ntimeout = soon\n").unwrap();
+    let mut config = SolverConfig::default();
+    let err = config.load_file(config_file.to_str().unwrap()).unwrap_err();
+    assert!(err.ends_with("solver.cfg:2: timeout = soon"), "{}", err);
+    // Missing files are errors instead of panics
+    assert!(config
+        .load_file(dir.join("missing.cfg").to_str().unwrap())
+        .is_err());
+}
+
+#[test]
+fn repeated_results_are_reported_once() {
+    let (uclid, model) = setup(
+        "repeated",
+        "echo '0 assertions passed.'; echo '1 assertions failed.'; echo '0 assertions indeterminate.'
+echo '  FAILED -> [Step #1] assertion @ model.ucl, line 11'
+echo '1 assertions passed.'; echo '1 assertions failed.'; echo '0 assertions indeterminate.'
+echo '  FAILED -> [Step #1] assertion @ model.ucl, line 11'",
+    );
+    let results = UclidRunner::new(&uclid, vec![], None).check(&model).unwrap();
+    assert_eq!(results[1].status, CheckStatus::Failed);
+    assert_eq!(results[1].properties.len(), 1);
+}
+
+#[test]
+fn obligations_are_reported_per_clause() {
+    let body = format!(
+        "echo '1 assertions passed.'; echo '2 assertions failed.'; echo '0 assertions indeterminate.'
+echo '  FAILED -> [Step #1] assertion @ model.ucl, line {}'
+echo '  FAILED -> [Step #1] requires @ model.ucl, line {}'",
+        line_of(SPLIT_MODEL, "assert (bar_requires_0"),
+        line_of(SPLIT_MODEL, "requires (a0"),
+    );
+    let (uclid, model) = setup_model("obligations", SPLIT_MODEL, &body);
+    let results = UclidRunner::new(&uclid, vec![], None).check(&model).unwrap();
+    assert_eq!(statuses(&results), vec![("foo", CheckStatus::Failed)]);
+    assert_eq!(
+        results[0].obligations,
+        vec![
+            ("ensures spec.rvspecs:7:5 of foo".to_string(), CheckStatus::Passed),
+            ("ensures spec.rvspecs:8:5 of foo".to_string(), CheckStatus::Passed),
+            (
+                "requires spec.rvspecs:2:5 of bar at call 0x10080".to_string(),
+                CheckStatus::Failed
+            ),
+        ]
+    );
+    // The callee's requires clause is checked at the call site in foo
+    assert_eq!(results[0].properties.len(), 1);
+}
+
+/// Transition system whose invariant is checked by induction
+const TS_MODEL: &str = "module main {
+procedure [inline] machine_step(step_pc: bv64, step_ra: bv64)
+    modifies pc;
+{
+    if ((step_pc == 65792bv64)) {
+        pc = (pc + 4bv64);
+    }
+}
+
+init {
+    assume ((pc == 65792bv64));
+}
+
+next {
+    call () = machine_step(pc, ra);
+}
+
+invariant pc_aligned: ((pc & 3bv64) == 0bv64);
+
+control {
+    v_induction = induction(2);
+    check;
+    print_results;
+}
+}
+";
+
+#[test]
+fn invariant_failure_is_reported_for_the_module() {
+    assert_eq!(VerifyCommand::parse("induction"), Some(VerifyCommand::Induction(1)));
+    assert_eq!(VerifyCommand::parse("induction:2"), Some(VerifyCommand::Induction(2)));
+    let body = format!(
+        "echo '1 assertions passed.'; echo '1 assertions failed.'; echo '0 assertions indeterminate.'
+echo '  FAILED -> [Step #2] property pc_aligned @ model.ucl, line {}'",
+        line_of(TS_MODEL, "invariant pc_aligned"),
+    );
+    let (uclid, model) = setup_model("induction", TS_MODEL, &body);
+    let results = UclidRunner::new(&uclid, vec![], None).check(&model).unwrap();
+    assert_eq!(statuses(&results), vec![("induction(2)", CheckStatus::Failed)]);
+    assert_eq!(results[0].properties.len(), 1);
+}
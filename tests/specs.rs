@@ -0,0 +1,74 @@
+//! Processes small spec files and checks the specifications passed to the translator.
+
+extern crate dwarf_ctx;
+extern crate riscverifier;
+
+mod common;
+
+use dwarf_ctx::dwarfreader::{DwarfCtx, DwarfFuncSig, DwarfLineInfo, DwarfTypeDefn, DwarfVar};
+use riscverifier::{
+    ir_interface::SpecLangASTInterface, process_specs,
+    verification_interfaces::uclidinterface::Uclid5Interface,
+};
+use std::{collections::HashMap, fs, rc::Rc};
+
+/// Returns a context with the signature of `foo(long n)` and a global `long n` at 0x1000
+fn dwarf_ctx() -> DwarfCtx {
+    let long = Rc::new(DwarfTypeDefn::Primitive { bytes: 8 });
+    let args = vec![DwarfVar::new("n".to_string(), long.clone(), 0)];
+    let global_vars = vec![DwarfVar::new("n".to_string(), long, 0x1000)];
+    let mut func_sigs = HashMap::new();
+    func_sigs.insert(
+        "foo".to_string(),
+        DwarfFuncSig::new("foo".to_string(), args, None),
+    );
+    DwarfCtx::new(64, func_sigs, global_vars, DwarfLineInfo::new())
+}
+
+/// Returns the requires clauses of `foo` in the spec file `input`
+fn foo_requires(name: &str, input: &str) -> Vec<String> {
+    let path = common::test_dir(name).join("test.rvspecs");
+    fs::write(&path, input).unwrap();
+    let dwarf_ctx = dwarf_ctx();
+    let (specs_map, _, _) = process_specs(&vec![path.to_str().unwrap()], &dwarf_ctx);
+    specs_map["foo"]
+        .iter()
+        .filter_map(|spec| spec.get_bexpr().ok())
+        .map(Uclid5Interface::bexpr_to_string)
+        .collect()
+}
+
+#[test]
+fn expanded_definitions_do_not_capture_arguments() {
+    let requires = foo_requires(
+        "spec-capture",
+        "define r(x: bv64): bool = forall (i: bv64) :: i != x;
+fun foo {
+    requires forall (i: bv64) :: r(i);
+    requires r(n);
+}
+",
+    );
+    // The quantified variable of the definition is renamed instead of capturing the argument
+    assert_eq!(
+        requires,
+        vec![
+            "forall (i : bv64) :: (forall (i__1 : bv64) :: ((i__1 != i)))",
+            "forall (i : bv64) :: ((i != n))",
+        ]
+    );
+}
+
+#[test]
+fn definitions_use_the_global_variables_of_their_scope() {
+    let requires = foo_requires(
+        "spec-global",
+        "define small(x: bv64): bool = x < n;
+fun foo {
+    requires small(n);
+}
+",
+    );
+    // The argument is the formal `n` while the body reads the global `n`
+    assert_eq!(requires, vec!["(n < mem_access_4096)"]);
+}
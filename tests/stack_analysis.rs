@@ -0,0 +1,109 @@
+//! Computes the frames and stack depths of small canned disassemblies.
+
+extern crate dwarf_ctx;
+extern crate riscverifier;
+
+mod common;
+
+use dwarf_ctx::dwarfreader::{DwarfCtx, DwarfLineInfo};
+use riscverifier::{datastructures::cfg::BasicBlock, stack_analysis::StackAnalyzer};
+use std::collections::HashMap;
+
+/// `main` calls `f`, which calls `g`; `g` uses a frame pointer
+const CALL_CHAIN: &str = "0000000080000000 <main> addi sp,sp,-32
+0000000080000004 <main+0x4> sd ra,24(sp)
+0000000080000008 <main+0x8> jal ra,0000000080000018 <f>
+000000008000000c <main+0xc> ld ra,24(sp)
+0000000080000010 <main+0x10> addi sp,sp,32
+0000000080000014 <main+0x14> jalr zero,0(ra)
+0000000080000018 <f> addi sp,sp,-16
+000000008000001c <f+0x4> sd ra,8(sp)
+0000000080000020 <f+0x8> jal ra,0000000080000030 <g>
+0000000080000024 <f+0xc> ld ra,8(sp)
+0000000080000028 <f+0x10> addi sp,sp,16
+000000008000002c <f+0x14> jalr zero,0(ra)
+0000000080000030 <g> addi sp,sp,-48
+0000000080000034 <g+0x4> addi s0,sp,48
+0000000080000038 <g+0x8> addi sp,s0,-48
+000000008000003c <g+0xc> addi sp,sp,48
+0000000080000040 <g+0x10> jalr zero,0(ra)
+";
+
+/// `fact` calls itself
+const RECURSION: &str = "0000000080000000 <fact> addi sp,sp,-16
+0000000080000004 <fact+0x4> sd ra,8(sp)
+0000000080000008 <fact+0x8> jal ra,0000000080000000 <fact>
+000000008000000c <fact+0xc> ld ra,8(sp)
+0000000080000010 <fact+0x10> addi sp,sp,16
+0000000080000014 <fact+0x14> jalr zero,0(ra)
+";
+
+/// `alloca` allocates a0 bytes on the stack
+const DYNAMIC: &str = "0000000080000000 <alloca> addi sp,sp,-16
+0000000080000004 <alloca+0x4> sub sp,sp,a0
+0000000080000008 <alloca+0x8> addi sp,s0,0
+000000008000000c <alloca+0xc> jalr zero,0(ra)
+";
+
+fn dwarf_ctx() -> DwarfCtx {
+    DwarfCtx::new(64, HashMap::new(), vec![], DwarfLineInfo::new())
+}
+
+#[test]
+fn straight_line_calls() {
+    let als = common::disassemble("stack-calls", CALL_CHAIN);
+    let bbs = BasicBlock::split(&als);
+    let dwarf_ctx = dwarf_ctx();
+    let mut analyzer = StackAnalyzer::new(&bbs, &dwarf_ctx);
+    let main = analyzer.frame_info("main");
+    assert_eq!(main.frame_size, 32);
+    assert_eq!(main.calls, vec![("f".to_string(), 32)]);
+    assert!(main.dynamic_adjustments.is_empty() && main.indirect_calls.is_empty());
+    let g = analyzer.frame_info("g");
+    assert_eq!(g.frame_size, 48);
+    assert!(g.calls.is_empty() && g.dynamic_adjustments.is_empty());
+    let depth = analyzer.stack_depth("main");
+    assert!(depth.is_bounded());
+    assert_eq!(depth.bytes, 32 + 16 + 48);
+    assert_eq!(depth.path, vec!["main", "f", "g"]);
+    assert_eq!(
+        analyzer.reachable_funcs(&["main".to_string()]),
+        vec!["f", "g", "main"]
+    );
+}
+
+#[test]
+fn recursion_is_unbounded() {
+    let als = common::disassemble("stack-recursion", RECURSION);
+    let bbs = BasicBlock::split(&als);
+    let dwarf_ctx = dwarf_ctx();
+    let mut analyzer = StackAnalyzer::new(&bbs, &dwarf_ctx);
+    assert_eq!(
+        analyzer.frame_info("fact").calls,
+        vec![("fact".to_string(), 16)]
+    );
+    let depth = analyzer.stack_depth("fact");
+    assert!(!depth.is_bounded());
+    assert_eq!(depth.bytes, 16);
+    assert_eq!(depth.unbounded, vec!["recursive call fact -> fact"]);
+}
+
+#[test]
+fn dynamic_sp_adjustment_is_unbounded() {
+    let als = common::disassemble("stack-dynamic", DYNAMIC);
+    let bbs = BasicBlock::split(&als);
+    let dwarf_ctx = dwarf_ctx();
+    let mut analyzer = StackAnalyzer::new(&bbs, &dwarf_ctx);
+    let frame = analyzer.frame_info("alloca");
+    assert_eq!(frame.frame_size, 16);
+    // Neither the variable allocation nor the restore from an unknown frame pointer is bounded
+    assert_eq!(frame.dynamic_adjustments, vec![0x80000004, 0x80000008]);
+    let depth = analyzer.stack_depth("alloca");
+    assert_eq!(
+        depth.unbounded,
+        vec![
+            "unbounded sp adjustment in alloca at 0x80000004",
+            "unbounded sp adjustment in alloca at 0x80000008"
+        ]
+    );
+}
@@ -0,0 +1,1162 @@
+//! Translates small canned disassemblies and checks the generated models.
+
+extern crate asts;
+extern crate dwarf_ctx;
+extern crate riscverifier;
+
+mod common;
+
+use asts::spec_lang::sl_ast::{Spec, VExpr, VType};
+use dwarf_ctx::dwarfreader::{DwarfCtx, DwarfFuncSig, DwarfLineInfo, DwarfTypeDefn, DwarfVar};
+use riscverifier::{
+    datastructures::cfg::BasicBlock,
+    ir_interface::IRInterface,
+    solver::{SolverConfig, SolverProfile, VerifyCommand},
+    translator::{Translator, TranslatorOptions},
+    verification_interfaces::{
+        boogieinterface::BoogieInterface, btor2interface::Btor2Interface,
+        smtlibinterface::SmtLib2Interface, uclidinterface::Uclid5Interface,
+    },
+};
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+/// `main` calls `put(a0, a1)`, which is ignored
+const CALL_IGNORED: &str = "0000000080000000 <main> addi a0,zero,1
+0000000080000004 <main+0x4> addi a1,zero,2
+0000000080000008 <main+0x8> jal ra,0000000080000010 <put>
+000000008000000c <main+0xc> jalr zero,0(ra)
+0000000080000010 <put> sd a1,0(a0)
+0000000080000014 <put+0x4> jalr zero,0(ra)
+";
+
+/// `main` calls `spill`, which is ignored and overwrites the callee-saved s1
+const CALL_SPILL: &str = "0000000080000000 <main> addi a0,zero,1
+0000000080000004 <main+0x4> jal ra,000000008000000c <spill>
+0000000080000008 <main+0x8> jalr zero,0(ra)
+000000008000000c <spill> addi s1,zero,1
+0000000080000010 <spill+0x4> jalr zero,0(ra)
+";
+
+/// `main` allocates 32 bytes and calls `leaf`, which allocates 16 bytes
+const STACK_CALLS: &str = "0000000080000000 <main> addi sp,sp,-32
+0000000080000004 <main+0x4> sd ra,24(sp)
+0000000080000008 <main+0x8> jal ra,0000000080000018 <leaf>
+000000008000000c <main+0xc> ld ra,24(sp)
+0000000080000010 <main+0x10> addi sp,sp,32
+0000000080000014 <main+0x14> jalr zero,0(ra)
+0000000080000018 <leaf> addi sp,sp,-16
+000000008000001c <leaf+0x4> addi sp,sp,16
+0000000080000020 <leaf+0x8> jalr zero,0(ra)
+";
+
+/// `alloca` allocates a0 bytes on the stack
+const DYNAMIC: &str = "0000000080000000 <alloca> addi sp,sp,-16
+0000000080000004 <alloca+0x4> sub sp,sp,a0
+0000000080000008 <alloca+0x8> add sp,sp,a0
+000000008000000c <alloca+0xc> addi sp,sp,16
+0000000080000010 <alloca+0x10> jalr zero,0(ra)
+";
+
+/// `main` loads a long from a0 and stores its lower word after it
+const LOAD_STORE: &str = "0000000080000000 <main> ld a1,0(a0)
+0000000080000004 <main+0x4> sw a1,8(a0)
+0000000080000008 <main+0x8> jalr zero,0(ra)
+";
+
+/// `main` sets a1 to 1 if a0 is zero and to 2 otherwise
+const BRANCH: &str = "0000000080000000 <main> beq a0,zero,000000008000000c <main+0xc>
+0000000080000004 <main+0x4> addi a1,zero,2
+0000000080000008 <main+0x8> jal zero,0000000080000010 <main+0x10>
+000000008000000c <main+0xc> addi a1,zero,1
+0000000080000010 <main+0x10> jalr zero,0(ra)
+";
+
+/// `sum9` loads its ninth argument, which is passed on the caller's stack
+const STACK_ARG: &str = "0000000080000000 <sum9> addi sp,sp,-16
+0000000080000004 <sum9+0x4> ld t0,16(sp)
+0000000080000008 <sum9+0x8> addi sp,sp,16
+000000008000000c <sum9+0xc> jalr zero,0(ra)
+";
+
+/// Returns a context with the signatures of `put(long *dst, long val)` and
+/// `sum9(long x0, ..., long x8)`
+fn dwarf_ctx() -> DwarfCtx {
+    let long = Rc::new(DwarfTypeDefn::Primitive { bytes: 8 });
+    let ptr = Rc::new(DwarfTypeDefn::Pointer {
+        value_typ: Rc::clone(&long),
+        bytes: 8,
+    });
+    let args = vec![
+        DwarfVar::new("dst".to_string(), ptr, 0),
+        DwarfVar::new("val".to_string(), Rc::clone(&long), 0),
+    ];
+    let mut func_sigs = HashMap::new();
+    func_sigs.insert(
+        "put".to_string(),
+        DwarfFuncSig::new("put".to_string(), args, None),
+    );
+    let args = (0..9)
+        .map(|i| DwarfVar::new(format!("x{}", i), Rc::clone(&long), 0))
+        .collect();
+    func_sigs.insert(
+        "sum9".to_string(),
+        DwarfFuncSig::new("sum9".to_string(), args, None),
+    );
+    DwarfCtx::new(64, func_sigs, vec![], DwarfLineInfo::new())
+}
+
+/// Translates `func_name` in `listing` (and its transition system if `transition` is set)
+/// with the given specifications and options and returns the model
+fn translate_with_specs(
+    name: &str,
+    listing: &str,
+    func_name: &str,
+    ignored: &[&str],
+    specs_map: &HashMap<String, Vec<Spec>>,
+    options: TranslatorOptions,
+    transition: bool,
+) -> String {
+    translate_to::<Uclid5Interface>(
+        name, listing, func_name, ignored, specs_map, options, transition,
+    )
+}
+
+/// Translates `func_name` in `listing` like `translate_with_specs` and returns the model
+/// in the verification language of `I`
+fn translate_to<I: IRInterface>(
+    name: &str,
+    listing: &str,
+    func_name: &str,
+    ignored: &[&str],
+    specs_map: &HashMap<String, Vec<Spec>>,
+    options: TranslatorOptions,
+    transition: bool,
+) -> String {
+    let als = common::disassemble(name, listing);
+    let bbs = BasicBlock::split(&als);
+    let dwarf_ctx = dwarf_ctx();
+    let ignored_funcs = ignored.iter().cloned().collect::<HashSet<_>>();
+    let verify_funcs = vec![func_name];
+    let mut translator: Translator<I> = Translator::new(
+        "main",
+        &bbs,
+        &ignored_funcs,
+        &verify_funcs,
+        &dwarf_ctx,
+        specs_map,
+        options,
+    );
+    translator.gen_func_model(func_name);
+    if transition {
+        translator.gen_transition_system(&[func_name], &[]);
+    }
+    translator.print_model()
+}
+
+/// Translates `func_name` in `listing` with the given options and returns the model
+fn translate(
+    name: &str,
+    listing: &str,
+    func_name: &str,
+    ignored: &[&str],
+    options: TranslatorOptions,
+) -> String {
+    translate_with_specs(name, listing, func_name, ignored, &HashMap::new(), options, false)
+}
+
+/// Asserts that the lines appear in `text` in the given order
+fn assert_lines(text: &str, lines: &[&str]) {
+    let mut rest = text;
+    for line in lines {
+        match rest.find(line) {
+            Some(i) => rest = &rest[i + line.len()..],
+            None => panic!("Expected \"{}\" in order in\n{}", line, text),
+        }
+    }
+}
+
+/// Returns a specification map with the `modifies` clause `vars` for `func_name`
+fn modifies(func_name: &str, vars: &[&str]) -> HashMap<String, Vec<Spec>> {
+    let mod_set = vars.iter().map(|v| v.to_string()).collect();
+    let mut specs_map = HashMap::new();
+    specs_map.insert(func_name.to_string(), vec![Spec::Modifies(mod_set)]);
+    specs_map
+}
+
+/// Returns the variables in the `modifies` clause of procedure `proc_name`
+fn mod_set(text: &str, proc_name: &str) -> HashSet<String> {
+    let proc_text = &text[text.find(&format!("procedure {}(", proc_name)).unwrap()..];
+    let line = proc_text
+        .lines()
+        .find(|l| l.trim().starts_with("modifies"))
+        .unwrap();
+    line.trim()
+        .trim_start_matches("modifies")
+        .trim_end_matches(';')
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .collect()
+}
+
+/// Returns the `ensures` clauses of procedure `proc_name`
+fn ensures<'a>(text: &'a str, proc_name: &str) -> Vec<&'a str> {
+    let proc_text = &text[text.find(&format!("procedure {}(", proc_name)).unwrap()..];
+    proc_text
+        .lines()
+        .skip(1)
+        .take_while(|l| !l.trim().starts_with("procedure") && l.trim() != "{")
+        .map(|l| l.trim())
+        .filter(|l| l.starts_with("ensures"))
+        .collect()
+}
+
+/// Returns calling-convention exemptions of `func_name` for `clauses`
+fn exempt(func_name: &str, clauses: &[&str]) -> Option<HashMap<String, HashSet<String>>> {
+    let mut exemptions = HashMap::new();
+    exemptions.insert(
+        func_name.to_string(),
+        clauses.iter().map(|c| c.to_string()).collect(),
+    );
+    Some(exemptions)
+}
+
+#[test]
+fn stub_mod_set_is_inferred_from_the_disassembly() {
+    let text = translate(
+        "stub-inferred",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        TranslatorOptions::default(),
+    );
+    // The store of the ignored function and the havocked caller-saved registers
+    let put_mod_set = mod_set(&text, "put");
+    assert!(put_mod_set.contains("mem_d") && put_mod_set.contains("a0"));
+    // The caller inherits the modifies set of the stub
+    assert!(mod_set(&text, "main").contains("mem_d"));
+}
+
+#[test]
+fn stub_havocs_the_caller_saved_registers() {
+    let text = translate(
+        "stub-havoc",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        TranslatorOptions::default(),
+    );
+    let stub = &text[text.find("procedure put(").unwrap()..text.find("procedure main(").unwrap()];
+    let caller_saved = [
+        "ra", "t0", "t1", "t2", "a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7", "t3", "t4", "t5",
+        "t6",
+    ];
+    let havocs = caller_saved
+        .iter()
+        .map(|reg| format!("havoc {};", reg))
+        .collect::<Vec<_>>();
+    assert_lines(stub, &havocs.iter().map(|l| &l[..]).collect::<Vec<_>>());
+    // The instructions of the ignored function are not executed by the stub
+    assert!(!stub.contains("mem_d[") && !stub.contains("call ()"));
+    // The havocked registers are declared and modified by the stub
+    let stub_mod_set = mod_set(&text, "put");
+    for reg in &caller_saved {
+        assert!(text.contains(&format!("var {}: bv64;", reg)));
+        assert!(stub_mod_set.contains(*reg));
+    }
+}
+
+#[test]
+fn stub_mod_set_keeps_callee_saved_writes() {
+    let text = translate(
+        "stub-s1",
+        CALL_SPILL,
+        "main",
+        &["spill"],
+        TranslatorOptions::default(),
+    );
+    assert!(mod_set(&text, "spill").contains("s1"));
+}
+
+#[test]
+fn stub_mod_set_drops_callee_saved_with_contracts() {
+    let text = translate(
+        "stub-s1-abi",
+        CALL_SPILL,
+        "main",
+        &["spill"],
+        TranslatorOptions {
+            abi_exemptions: Some(HashMap::new()),
+            ..Default::default()
+        },
+    );
+    let mod_set = mod_set(&text, "spill");
+    assert!(!mod_set.contains("s1") && mod_set.contains("a0"));
+}
+
+#[test]
+fn spec_mod_set_is_combined_with_the_inferred_one() {
+    let text = translate_with_specs(
+        "stub-spec",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        &modifies("put", &["mem_d", "mtvec"]),
+        TranslatorOptions {
+            strict_mod_sets: true,
+            ..Default::default()
+        },
+        false,
+    );
+    let put_mod_set = mod_set(&text, "put");
+    assert!(put_mod_set.contains("mem_d") && put_mod_set.contains("mtvec"));
+}
+
+#[test]
+#[should_panic(expected = "The modifies clause of put is missing mem_d written by the function.")]
+fn strict_mod_sets_reject_missing_writes() {
+    translate_with_specs(
+        "stub-strict",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        &modifies("put", &["a0"]),
+        TranslatorOptions {
+            strict_mod_sets: true,
+            ..Default::default()
+        },
+        false,
+    );
+}
+
+#[test]
+fn mem_safety_checks_loads_and_stores() {
+    let text = translate(
+        "mem-safety",
+        LOAD_STORE,
+        "main",
+        &[],
+        TranslatorOptions {
+            mem_safety_regions: Some(vec![(0x2000, 0x100)]),
+            ..Default::default()
+        },
+    );
+    // The load is checked for 8-byte alignment and to fit in the region 0x2000:0x100
+    assert_lines(
+        &text,
+        &[
+            "// memory safety: ld at 0x80000000",
+            "assert (((((a0 + 0bv64) & 7bv64) == 0bv64) && (((a0 + 0bv64) >=_u 8192bv64) && (((a0 + 0bv64) - 8192bv64) <=_u 248bv64))));",
+            "a1 = mem_d[(a0 + 0bv64)];",
+        ],
+    );
+    // The word store is checked for 4-byte alignment before it is written
+    assert_lines(
+        &text,
+        &[
+            "// memory safety: sw at 0x80000004",
+            "assert (((((a0 + 8bv64) & 3bv64) == 0bv64) && (((a0 + 8bv64) >=_u 8192bv64) && (((a0 + 8bv64) - 8192bv64) <=_u 252bv64))));",
+            "mem_w[(a0 + 8bv64)] = a1[31:0];",
+        ],
+    );
+    // Instructions without memory accesses are not instrumented
+    assert_eq!(text.matches("memory safety:").count(), 2);
+    // The base register of a memory operand is declared without its offset
+    assert!(text.contains("var a0: bv64;") && !text.contains("var a0, "));
+}
+
+#[test]
+fn mem_safety_covers_stack_arguments() {
+    let text = translate(
+        "mem-safety-stack-arg",
+        STACK_ARG,
+        "sum9",
+        &[],
+        TranslatorOptions {
+            mem_safety_regions: Some(vec![]),
+            ..Default::default()
+        },
+    );
+    // The 16-byte frame is extended by the 8-byte slot of the ninth argument above it
+    assert_lines(
+        &text,
+        &[
+            "// memory safety: ld at 0x80000004",
+            "assert (((((sp + 16bv64) & 7bv64) == 0bv64) && (((sp + 16bv64) >=_u sp) && (((sp + 16bv64) - sp) <=_u 16bv64))));",
+            "t0 = mem_d[(sp + 16bv64)];",
+        ],
+    );
+}
+
+#[test]
+fn mem_safety_skips_ignored_functions() {
+    let text = translate(
+        "mem-safety-ignored",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        TranslatorOptions {
+            mem_safety_regions: Some(vec![]),
+            ..Default::default()
+        },
+    );
+    // The store of the ignored put is not checked against its undeclared pointer argument
+    assert!(!text.contains("mem_safety_arg"));
+    assert!(!text.contains("memory safety: sd"));
+}
+
+#[test]
+fn stack_requires_covers_the_call_tree() {
+    let text = translate(
+        "stack-bounded",
+        STACK_CALLS,
+        "main",
+        &[],
+        TranslatorOptions {
+            stack_region: Some((0x1000, 0x100)),
+            ..Default::default()
+        },
+    );
+    // 0x1000 + 32 + 16 for main and 0x1000 + 16 for leaf, below the end of the region
+    assert_lines(
+        &text,
+        &[
+            "procedure leaf()",
+            "requires ((sp >=_u 4112bv64) && (sp <=_u 4352bv64));",
+            "procedure main()",
+            "requires ((sp >=_u 4144bv64) && (sp <=_u 4352bv64));",
+        ],
+    );
+}
+
+#[test]
+fn no_stack_requires_for_unbounded_depth() {
+    let text = translate(
+        "stack-dynamic",
+        DYNAMIC,
+        "alloca",
+        &[],
+        TranslatorOptions {
+            stack_region: Some((0x1000, 0x100)),
+            ..Default::default()
+        },
+    );
+    assert!(!text.contains("requires ((sp"));
+    // The writes to sp are still checked against the region
+    assert!(text.contains("assert (((sp >=_u 4096bv64) && (sp <=_u 4352bv64)));"));
+}
+
+#[test]
+#[should_panic(
+    expected = "The stack depth of main (48 bytes) exceeds the stack region of 32 bytes."
+)]
+fn stack_depth_larger_than_the_region() {
+    translate(
+        "stack-overflow",
+        STACK_CALLS,
+        "main",
+        &[],
+        TranslatorOptions {
+            stack_region: Some((0x1000, 0x20)),
+            ..Default::default()
+        },
+    );
+}
+
+#[test]
+fn transition_system_passes_argument_registers_to_stubs() {
+    let text = translate_with_specs(
+        "call-ignored",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        &HashMap::new(),
+        TranslatorOptions::default(),
+        true,
+    );
+    let step = &text[text.find("procedure [inline] machine_step").unwrap()..];
+    // The stub is called with the argument registers instead of its formals
+    assert_lines(step, &["call () = put(a0, a1);", "pc = step_ra;"]);
+    assert!(!step.contains("dst") && !step.contains("val"));
+    // The instructions of the ignored function are not executed
+    assert!(!step.contains("call () = bb_0x80000010()"));
+    assert!(text.contains("var a0: bv64;") && text.contains("var a1: bv64;"));
+}
+
+#[test]
+fn no_abi_ensures_without_contracts() {
+    let text = translate(
+        "abi-off",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        TranslatorOptions::default(),
+    );
+    assert!(ensures(&text, "main").is_empty() && ensures(&text, "put").is_empty());
+}
+
+#[test]
+fn abi_ensures_on_verified_functions_and_stubs() {
+    let text = translate(
+        "abi-on",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        TranslatorOptions {
+            abi_exemptions: Some(HashMap::new()),
+            ..Default::default()
+        },
+    );
+    for proc_name in &["main", "put"] {
+        let ensures = ensures(&text, proc_name);
+        // The function returns to the caller and preserves sp, gp, tp and s0-s11
+        assert_eq!(ensures[0], "ensures (pc == ((old(ra)[63:1]) ++ 0bv1));");
+        assert!(ensures.contains(&"ensures (sp == old(sp));"));
+        assert!(ensures.contains(&"ensures (s11 == old(s11));"));
+        assert_eq!(ensures.len(), 16);
+    }
+    // The stub returns to the caller
+    assert!(mod_set(&text, "put").contains("pc"));
+}
+
+#[test]
+fn abi_caller_saved_registers_are_declared() {
+    let text = translate(
+        "abi-leaf",
+        LOAD_STORE,
+        "main",
+        &[],
+        TranslatorOptions {
+            abi_exemptions: Some(HashMap::new()),
+            ..Default::default()
+        },
+    );
+    // Callers cannot rely on the caller-saved registers even if main does not write them
+    let main_mod_set = mod_set(&text, "main");
+    for reg in &["a7", "t0", "t6"] {
+        assert!(main_mod_set.contains(*reg));
+        assert!(text.contains(&format!("var {}: bv64;", reg)));
+    }
+}
+
+#[test]
+fn abi_exempted_register_is_dropped() {
+    let text = translate(
+        "abi-exempt-s1",
+        CALL_SPILL,
+        "main",
+        &["spill"],
+        TranslatorOptions {
+            abi_exemptions: exempt("spill", &["s1"]),
+            ..Default::default()
+        },
+    );
+    let spill_ensures = ensures(&text, "spill");
+    assert!(!spill_ensures.contains(&"ensures (s1 == old(s1));"));
+    assert!(spill_ensures.contains(&"ensures (s0 == old(s0));"));
+    // The exempted callee-saved register stays in the modifies set of the stub
+    assert!(mod_set(&text, "spill").contains("s1"));
+    // The exemption only applies to spill
+    assert!(ensures(&text, "main").contains(&"ensures (s1 == old(s1));"));
+}
+
+#[test]
+fn abi_exempted_return_address() {
+    let text = translate(
+        "abi-exempt-ra",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        TranslatorOptions {
+            abi_exemptions: exempt("put", &["ra"]),
+            ..Default::default()
+        },
+    );
+    assert!(!ensures(&text, "put").iter().any(|e| e.contains("pc ==")));
+    assert!(ensures(&text, "main").iter().any(|e| e.contains("pc ==")));
+}
+
+#[test]
+fn smtlib_straight_line_code() {
+    let text = translate_to::<SmtLib2Interface>(
+        "smtlib-straight",
+        LOAD_STORE,
+        "main",
+        &[],
+        &HashMap::new(),
+        TranslatorOptions::default(),
+        false,
+    );
+    assert_lines(
+        &text,
+        &[
+            "(set-logic QF_ABV)",
+            "(define-fun deref_8 ((mem (Array (_ BitVec 64) (_ BitVec 64))) (addr (_ BitVec 64))) (_ BitVec 64) (select mem addr))",
+            "; ==== Procedure main (0x80000000) ====",
+            "(push 1)",
+            "(declare-fun a0@0 () (_ BitVec 64))",
+            "(define-fun path_1 () Bool (and path_0 (= returned@0 (_ bv0 1))))",
+            // Each assignment defines a new version of the variable
+            "; ld a1, a0, 0bv64",
+            "(define-fun a1@1 () (_ BitVec 64) (select mem_d@0 (bvadd a0@0 (_ bv0 64))))",
+            "(define-fun pc@1 () (_ BitVec 64) (bvadd pc@0 (_ bv4 64)))",
+            "; sw a0, 8bv64, a1",
+            "(define-fun mem_w@1 () (Array (_ BitVec 64) (_ BitVec 32)) (store mem_w@0 (bvadd a0@0 (_ bv8 64)) ((_ extract 31 0) a1@1)))",
+            "; Proof obligations",
+            "(assert (not true))",
+            "(echo \"main\")",
+            "(check-sat)",
+            "(pop 1)",
+        ],
+    );
+}
+
+#[test]
+fn smtlib_branch() {
+    let text = translate_to::<SmtLib2Interface>(
+        "smtlib-branch",
+        BRANCH,
+        "main",
+        &[],
+        &HashMap::new(),
+        TranslatorOptions::default(),
+        false,
+    );
+    assert_lines(
+        &text,
+        &[
+            "; beq a0, 0bv64, 2147483660bv64",
+            // Both sides of the branch get their own path condition and version of pc
+            "(define-fun path_3 () Bool (and path_2 (= a0@0 (_ bv0 64))))",
+            "(define-fun pc@1 () (_ BitVec 64) (_ bv2147483660 64))",
+            "(define-fun path_4 () Bool (and path_2 (not (= a0@0 (_ bv0 64)))))",
+            "(define-fun pc@2 () (_ BitVec 64) (bvadd pc@0 (_ bv4 64)))",
+            // and are merged after the branch
+            "(define-fun pc@3 () (_ BitVec 64) (ite (= a0@0 (_ bv0 64)) pc@1 pc@2))",
+            // The blocks are guarded by the merged pc
+            "(define-fun path_6 () Bool (and path_1 (and (= pc@4 (_ bv2147483652 64)) (= returned@0 (_ bv0 1)))))",
+            "(define-fun a1@1 () (_ BitVec 64) (_ bv2 64))",
+            "(define-fun path_8 () Bool (and path_1 (and (= pc@7 (_ bv2147483660 64)) (= returned@0 (_ bv0 1)))))",
+            "(define-fun a1@3 () (_ BitVec 64) (_ bv1 64))",
+            "(define-fun a1@4 () (_ BitVec 64) (ite (and (= pc@7 (_ bv2147483660 64)) (= returned@0 (_ bv0 1))) a1@3 a1@2))",
+        ],
+    );
+}
+
+#[test]
+fn smtlib_call_to_an_ignored_function() {
+    let text = translate_to::<SmtLib2Interface>(
+        "smtlib-call",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        &HashMap::new(),
+        TranslatorOptions::default(),
+        false,
+    );
+    // The call is replaced by the contract of the stub: the arguments are bound to the
+    // argument registers and the modified variables get fresh unconstrained versions
+    assert_lines(
+        &text,
+        &[
+            "(define-fun path_4 () Bool (and path_1 (and (= pc@4 (_ bv2147483664 64)) (= returned@0 (_ bv0 1)))))",
+            "(define-fun dst@1 () (_ BitVec 64) a0@2)",
+            "(define-fun val@1 () (_ BitVec 64) a1@2)",
+            "(declare-fun a0@3 () (_ BitVec 64))",
+            "(declare-fun mem_d@1 () (Array (_ BitVec 64) (_ BitVec 64)))",
+            "(declare-fun pc@5 () (_ BitVec 64))",
+            "; Proof obligations",
+            "; requires dst == a0 of put",
+            "(define-fun obligation_0 () Bool (=> path_4 (= dst@1 a0@2)))",
+            "(assert (not (and obligation_0 obligation_1 obligation_2 obligation_3)))",
+        ],
+    );
+    // The instructions of the ignored function are not executed and only main is checked
+    assert!(!text.contains("; sd "));
+    assert_eq!(text.matches("(check-sat)").count(), 1);
+}
+
+#[test]
+fn smtlib_ensures_with_old() {
+    let text = translate_to::<SmtLib2Interface>(
+        "smtlib-ensures",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        &HashMap::new(),
+        TranslatorOptions {
+            abi_exemptions: Some(HashMap::new()),
+            ..Default::default()
+        },
+        false,
+    );
+    assert_lines(
+        &text,
+        &[
+            "; ensures (= pc (concat ((_ extract 63 1) (old ra)) (_ bv0 1)))",
+            "(push 1)",
+            // The ensures of the callee are assumed with `old` bound to the versions at the call
+            "(define-fun path_5 () Bool (and path_4 (= pc@5 (concat ((_ extract 63 1) ra@2) (_ bv0 1)))))",
+            "(define-fun path_8 () Bool (and path_7 (= s1@0 s1@0)))",
+            "; Proof obligations",
+            // The ensures of main are checked with `old` bound to the initial versions
+            "; ensures (= pc (concat ((_ extract 63 1) (old ra)) (_ bv0 1)))",
+            "(define-fun obligation_4 () Bool (=> path_22 (= pc@8 (concat ((_ extract 63 1) ra@0) (_ bv0 1)))))",
+            "; ensures (= s1 (old s1))",
+            "(define-fun obligation_7 () Bool (=> path_22 (= s1@0 s1@0)))",
+        ],
+    );
+}
+
+#[test]
+fn boogie_straight_line_code() {
+    let text = translate_to::<BoogieInterface>(
+        "boogie-straight",
+        LOAD_STORE,
+        "main",
+        &[],
+        &HashMap::new(),
+        TranslatorOptions::default(),
+        false,
+    );
+    assert_lines(
+        &text,
+        &[
+            "var a0: bv64;",
+            "var mem_d: [bv64]bv64;",
+            "function deref_8(mem: [bv64]bv64, addr: bv64) returns (bv64) { mem[addr] }",
+            "procedure {:inline 1} {:verify false} bb_0x80000000()",
+            "requires (pc == 2147483648bv64);",
+            "modifies a1, mem_w, pc, returned, zero;",
+            "// ld a1, a0, 0bv64",
+            "a1 := mem_d[$add.bv64(a0, 0bv64)];",
+            "// sw a0, 8bv64, a1",
+            // Boogie bit-vector extracts exclude the upper bound
+            "mem_w[$add.bv64(a0, 8bv64)] := (a1)[32:0];",
+            "zero, pc := $add.bv64(pc, 4bv64), (($add.bv64(ra, 0bv64))[64:1] ++ 0bv1);",
+            "procedure main()",
+            "call bb_0x80000000();",
+            // The bit-vector operators used by the model are declared as builtins
+            "function {:bvbuiltin \"bvadd\"} $add.bv64(x: bv64, y: bv64) returns (bv64);",
+        ],
+    );
+}
+
+#[test]
+fn boogie_branch() {
+    let text = translate_to::<BoogieInterface>(
+        "boogie-branch",
+        BRANCH,
+        "main",
+        &[],
+        &HashMap::new(),
+        TranslatorOptions::default(),
+        false,
+    );
+    assert_lines(
+        &text,
+        &[
+            "// beq a0, 0bv64, 2147483660bv64",
+            "if ((a0 == 0bv64)) {",
+            "pc := 2147483660bv64;",
+            "} else {",
+            "pc := $add.bv64(pc, 4bv64);",
+        ],
+    );
+    // The blocks are called in topological order guarded by pc
+    assert_lines(
+        &text[text.find("procedure main()").unwrap()..],
+        &[
+            "if (((pc == 2147483648bv64) && (returned == 0bv1))) {",
+            "call bb_0x80000000();",
+            "if (((pc == 2147483652bv64) && (returned == 0bv1))) {",
+            "call bb_0x80000004();",
+            "if (((pc == 2147483660bv64) && (returned == 0bv1))) {",
+            "call bb_0x8000000c();",
+            "if (((pc == 2147483664bv64) && (returned == 0bv1))) {",
+            "call bb_0x80000010();",
+            "returned := 1bv1;",
+        ],
+    );
+}
+
+#[test]
+fn boogie_call_to_an_ignored_function() {
+    let text = translate_to::<BoogieInterface>(
+        "boogie-call",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        &HashMap::new(),
+        TranslatorOptions::default(),
+        false,
+    );
+    assert_lines(
+        &text,
+        &[
+            // The stub is not verified and havocs the caller-saved registers
+            "procedure {:verify false} put(dst: bv64, val: bv64)",
+            "requires (dst == a0);",
+            "requires (val == a1);",
+            "havoc ra;",
+            "havoc t6;",
+            // The caller passes the argument registers
+            "procedure main()",
+            "if (((pc == 2147483664bv64) && (returned == 0bv1))) {",
+            "call put(a0, a1);",
+            "returned := 0bv1;",
+        ],
+    );
+    assert!(mod_set(&text, "main").contains("mem_d"));
+}
+
+#[test]
+fn boogie_ensures_with_old() {
+    let text = translate_to::<BoogieInterface>(
+        "boogie-ensures",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        &HashMap::new(),
+        TranslatorOptions {
+            abi_exemptions: Some(HashMap::new()),
+            ..Default::default()
+        },
+        false,
+    );
+    for proc_name in &["put", "main"] {
+        assert_lines(
+            &text[text.find(&format!(" {}(", proc_name)).unwrap()..],
+            &[
+                // Clauses follow the modifies clause
+                "modifies ",
+                "ensures (pc == ((old(ra))[64:1] ++ 0bv1));",
+                "ensures (s11 == old(s11));",
+                "{",
+            ],
+        );
+    }
+}
+
+/// BTOR2 lines (without comments) split into their fields
+struct Btor2Nodes<'a>(Vec<Vec<&'a str>>);
+impl<'a> Btor2Nodes<'a> {
+    fn new(text: &'a str) -> Self {
+        Btor2Nodes(
+            text.lines()
+                .filter(|l| !l.starts_with(';') && !l.trim().is_empty())
+                .map(|l| l.split_whitespace().collect())
+                .collect(),
+        )
+    }
+    /// Returns the fields after the id of node `id`
+    fn node(&self, id: &str) -> &[&'a str] {
+        &self.0.iter().find(|fields| fields[0] == id).unwrap()[1..]
+    }
+    /// Returns the id of the node with the symbol `symbol`
+    fn id(&self, symbol: &str) -> &'a str {
+        self.0.iter().find(|fields| fields.last() == Some(&symbol)).unwrap()[0]
+    }
+    /// Returns the fields after the id of the lines with operator `op`
+    fn with_op(&self, op: &str) -> Vec<&[&'a str]> {
+        self.0
+            .iter()
+            .filter(|fields| fields[1] == op)
+            .map(|fields| &fields[1..])
+            .collect()
+    }
+    /// Returns the next state of `state`
+    fn next(&self, state: &str) -> &'a str {
+        self.with_op("next")
+            .into_iter()
+            .find(|fields| fields[2] == state)
+            .unwrap()[3]
+    }
+}
+
+/// Translates `main` of `CALL_IGNORED` with the calling-convention contracts to BTOR2
+fn btor2_call_ignored(name: &str) -> String {
+    translate_to::<Btor2Interface>(
+        name,
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        &HashMap::new(),
+        TranslatorOptions {
+            abi_exemptions: Some(HashMap::new()),
+            ..Default::default()
+        },
+        false,
+    )
+}
+
+#[test]
+fn btor2_done_and_old_states_are_latched() {
+    let text = btor2_call_ignored("btor2-latch");
+    let nodes = Btor2Nodes::new(&text);
+    // The procedure executes in a single transition from done == 0 to done == 1
+    let done = nodes.id("main.done");
+    let init = nodes.with_op("init");
+    assert_eq!(init.len(), 1);
+    assert_eq!(init[0][2], done);
+    assert_eq!(nodes.node(init[0][3]), &["const", "1", "0"]);
+    assert_eq!(nodes.node(nodes.next(done)), &["const", "1", "1"]);
+    // The state keeps its value once done and old.* holds the state before the transition
+    let (a0, old_a0) = (nodes.id("main.a0"), nodes.id("main.old.a0"));
+    assert_eq!(&nodes.node(nodes.next(a0))[..4], &["ite", "5", done, a0]);
+    assert_eq!(nodes.node(nodes.next(old_a0)), &["ite", "5", done, old_a0, a0]);
+    // Variables that main does not modify stay unchanged
+    let sp = nodes.id("main.sp");
+    assert_eq!(nodes.next(sp), sp);
+}
+
+#[test]
+fn btor2_requires_are_constraints() {
+    let text = btor2_call_ignored("btor2-requires");
+    let nodes = Btor2Nodes::new(&text);
+    let (done, pc) = (nodes.id("main.done"), nodes.id("main.pc"));
+    let entry = "0000000000000000000000000000000010000000000000000000000000000000";
+    // `requires pc == 0x80000000` constrains the states before the transition
+    let constraint = nodes.with_op("constraint")[0][1];
+    let or = nodes.node(constraint);
+    assert_eq!(&or[..3], &["or", "1", done]);
+    let implies = nodes.node(or[3]);
+    assert_eq!(implies[0], "implies");
+    let eq = nodes.node(implies[3]);
+    assert_eq!(&eq[..3], &["eq", "1", pc]);
+    assert_eq!(nodes.node(eq[3]), &["const", "5", entry]);
+    assert!(text.contains("; requires pc == 0x80000000"));
+}
+
+#[test]
+fn btor2_callee_contracts_use_havoc_inputs() {
+    let text = btor2_call_ignored("btor2-call");
+    let nodes = Btor2Nodes::new(&text);
+    // The modifies set of the stub is havocked by fresh inputs
+    let inputs = nodes.with_op("input");
+    for (var, sort) in &[("a0", "5"), ("mem_d", "31"), ("pc", "5"), ("t6", "5")] {
+        assert!(inputs
+            .iter()
+            .any(|fields| fields[1] == *sort && fields[2].starts_with(&format!("main.havoc.{}.", var))));
+    }
+    let mem_d = nodes.id("main.mem_d");
+    assert_eq!(nodes.node(mem_d), &["state", "31", "main.mem_d"]);
+    // The ensures of the stub are assumed over the havocked pc and the return address at the call
+    let havoc_pc = inputs
+        .iter()
+        .find(|fields| fields[2].starts_with("main.havoc.pc."))
+        .map(|fields| nodes.id(fields[2]))
+        .unwrap();
+    let eq = nodes
+        .with_op("eq")
+        .into_iter()
+        .find(|fields| fields[2] == havoc_pc)
+        .unwrap();
+    assert_eq!(nodes.node(eq[3])[0], "concat");
+    // The instructions of the ignored function are not executed
+    assert!(!text.contains("; sd "));
+    assert!(nodes.with_op("write").is_empty());
+}
+
+#[test]
+fn btor2_ensures_are_bad_properties() {
+    let text = btor2_call_ignored("btor2-ensures");
+    let nodes = Btor2Nodes::new(&text);
+    let done = nodes.id("main.done");
+    // Only the 16 ensures of main are checked; the ones of the stub are assumed
+    let bads = nodes.with_op("bad");
+    assert_eq!(bads.len(), 16);
+    assert!(bads.iter().all(|fields| fields[2].starts_with("main.ensures_")));
+    // `ensures (sp == old(sp))` fails after the transition
+    let and = nodes.node(nodes.node(nodes.id("main.ensures_1"))[1]);
+    assert_eq!(&and[..3], &["and", "1", done]);
+    let not = nodes.node(and[3]);
+    assert_eq!(not[0], "not");
+    assert_eq!(
+        nodes.node(not[2]),
+        &["eq", "1", nodes.id("main.sp"), nodes.id("main.old.sp")]
+    );
+}
+
+/// Returns the control block of the UCLID5 model of `main` in `STACK_CALLS` checked
+/// with `solver_config`
+fn control_block(name: &str, solver_config: SolverConfig, transition: bool) -> String {
+    let text = translate_with_specs(
+        name,
+        STACK_CALLS,
+        "main",
+        &[],
+        &HashMap::new(),
+        TranslatorOptions {
+            solver_config,
+            ..Default::default()
+        },
+        transition,
+    );
+    text[text.find("control {").unwrap()..].to_string()
+}
+
+#[test]
+fn control_block_sets_the_profile_options() {
+    let control = control_block("control-z3", SolverConfig::default(), false);
+    assert_lines(
+        &control,
+        &[
+            "set_solver_option(\":mbqi\", false);",
+            "set_solver_option(\":case_split\", 0);",
+            "set_solver_option(\":relevancy\", 0);",
+            "set_solver_option(\":blast_full\", true);",
+            "fmain = verify(main);",
+            "check;",
+            "print_results;",
+            "fmain.print_cex(",
+        ],
+    );
+    let mut config = SolverConfig::default();
+    config.set_profile(SolverProfile::Cvc5);
+    config.set_option(":seed", "7");
+    let control = control_block("control-cvc5", config, false);
+    assert_lines(
+        &control,
+        &[
+            "set_solver_option(\":bv-solver\", \"bitblast\");",
+            "set_solver_option(\":seed\", 7);",
+            "fmain = verify(main);",
+        ],
+    );
+    assert!(!control.contains(":mbqi"));
+}
+
+#[test]
+fn control_block_with_timeouts() {
+    let mut config = SolverConfig::default();
+    config.set_timeout(None, 10);
+    config.set_timeout(Some("main"), 5);
+    let control = control_block("control-timeout", config, false);
+    // The timeout is set before each function is verified and checked
+    assert_lines(
+        &control,
+        &[
+            "set_solver_option(\":blast_full\", true);",
+            "set_solver_option(\":timeout\", 5000);",
+            "fmain = verify(main);",
+            "check;",
+            "print_results;",
+            "fmain.print_cex(",
+        ],
+    );
+    // Without timeouts all functions are checked at once
+    let control = control_block("control-no-timeout", SolverConfig::default(), false);
+    assert!(!control.contains(":timeout"));
+}
+
+#[test]
+fn control_block_bmc_and_induction() {
+    let mut config = SolverConfig::default();
+    config.set_command(VerifyCommand::Bmc(5));
+    let control = control_block("control-bmc", config, true);
+    assert_lines(
+        &control,
+        &["v_bmc = bmc(5);", "check;", "print_results;", "v_bmc.print_cex("],
+    );
+    assert!(!control.contains("verify("));
+    let mut config = SolverConfig::default();
+    config.set_command(VerifyCommand::Induction(3));
+    let control = control_block("control-induction", config, true);
+    assert_lines(
+        &control,
+        &[
+            "v_induction = induction(3);",
+            "check;",
+            "print_results;",
+            "v_induction.print_cex(",
+        ],
+    );
+}
+
+#[test]
+fn control_block_defaults_to_induction_with_a_transition_system() {
+    let control = control_block("control-transition", SolverConfig::default(), true);
+    assert_lines(&control, &["v_induction = induction;", "check;"]);
+    assert!(!control.contains("verify("));
+}
+
+#[test]
+fn tracked_values_are_recorded() {
+    let track = |id: &str, var: &str| {
+        Spec::Track(id.to_string(), VExpr::Ident(var.to_string(), VType::Bv(64)), None)
+    };
+    let mut specs_map = HashMap::new();
+    specs_map.insert("main".to_string(), vec![track("r", "a0")]);
+    specs_map.insert("put".to_string(), vec![track("v", "val")]);
+    let text = translate_with_specs(
+        "track",
+        CALL_IGNORED,
+        "main",
+        &["put"],
+        &specs_map,
+        TranslatorOptions::default(),
+        false,
+    );
+    let track_vars = [
+        "track_main_r_entry",
+        "track_main_r_exit",
+        "track_put_v_call_80000008",
+        "track_put_v_entry",
+        "track_put_v_exit",
+    ];
+    for var in &track_vars {
+        assert!(text.contains(&format!("var {}: bv64;", var)));
+    }
+    // The stub records the argument at its entry and exit
+    assert_lines(
+        &text[text.find("procedure put(").unwrap()..],
+        &["track_put_v_entry = val;", "havoc ra;", "track_put_v_exit = val;"],
+    );
+    // The caller records its own values and the argument of the call before the call
+    assert_lines(
+        &text[text.find("procedure main(").unwrap()..],
+        &[
+            "track_main_r_entry = a0;",
+            "call () = bb_0x80000000();",
+            "track_put_v_call_80000008 = a1;",
+            "call () = put(a0, a1);",
+            "track_main_r_exit = a0;",
+        ],
+    );
+    let main_mod_set = mod_set(&text, "main");
+    assert!(track_vars.iter().all(|var| main_mod_set.contains(*var)));
+    let put_mod_set = mod_set(&text, "put");
+    assert!(put_mod_set.contains("track_put_v_entry") && put_mod_set.contains("track_put_v_exit"));
+    // The tracked values are printed with the counterexamples
+    let print_cex = text.lines().find(|l| l.contains("fmain.print_cex(")).unwrap();
+    assert!(track_vars.iter().all(|var| print_cex.contains(var)));
+}
+
+#[test]
+fn boogie_declares_exactly_the_operators_used() {
+    let text = translate_to::<BoogieInterface>(
+        "boogie-builtins",
+        LOAD_STORE,
+        "main",
+        &[],
+        &HashMap::new(),
+        TranslatorOptions::default(),
+        false,
+    );
+    // Additions by the instructions and multiplications by the array index helpers
+    let decls = text[text.find("// Bit-vector operators").unwrap()..]
+        .lines()
+        .skip(1)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        decls,
+        vec![
+            "function {:bvbuiltin \"bvadd\"} $add.bv64(x: bv64, y: bv64) returns (bv64);",
+            "function {:bvbuiltin \"bvmul\"} $mul.bv64(x: bv64, y: bv64) returns (bv64);",
+        ]
+    );
+}
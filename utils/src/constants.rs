@@ -13,6 +13,29 @@ pub const PRIV_VAR: &'static str = "current_priv";
 pub const A0: &'static str = "a0";
 pub const SP: &'static str = "sp";
 pub const RA: &'static str = "ra";
+/// Procedure of the transition system that executes the block at its `pc` argument
+pub const STEP_PROC: &'static str = "machine_step";
+pub const STEP_PC_ARG: &'static str = "step_pc";
+pub const STEP_RA_ARG: &'static str = "step_ra";
+/// Registers that are not preserved across calls in the RISC-V calling convention
+pub const CALLER_SAVED_REGS: [&'static str; 16] = [
+    "ra", "t0", "t1", "t2", "a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7", "t3", "t4", "t5", "t6",
+];
+/// Registers that are preserved across calls in the RISC-V calling convention
+pub const CALLEE_SAVED_REGS: [&'static str; 13] = [
+    "sp", "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11",
+];
+/// Global and thread pointers; a function must leave them unchanged but does not save them
+pub const ABI_RESERVED_REGS: [&'static str; 2] = ["gp", "tp"];
+
+/// Registers a function must leave unchanged according to the calling convention;
+/// the callee-saved and reserved registers
+pub fn abi_preserved_regs() -> impl Iterator<Item = &'static str> {
+    CALLEE_SAVED_REGS
+        .iter()
+        .chain(ABI_RESERVED_REGS.iter())
+        .cloned()
+}
 
 /// ## Instruction constants
 /// FIXME: Create static strings for all instructions below
@@ -32,20 +32,6 @@ pub fn dec_str_to_i64(numeric: &str) -> Result<i64, std::num::ParseIntError> {
     Ok(i64::from_str_radix(numeric, 10)?)
 }
 
-// =========================================================================
-/// # Printing helper functions
-
-/// Returns the string with spce indentation
-pub fn indent_text(s: String, indent: usize) -> String {
-    let spaces = format!("\n{:indent$}", " ", indent = indent);
-    format!(
-        "{:indent$}{}",
-        " ",
-        s.replace("\n", &spaces[..]),
-        indent = indent
-    )
-}
-
 // =========================================================================
 /// # Translated variable renaming
 
@@ -57,9 +43,30 @@ pub fn global_func_addr_name(func_name: &str) -> String {
     format!("global_func_{}", func_name)
 }
 
+/// Prefix of the variables that replace the memory accesses at constant addresses
+const ABS_ACCESS_PREFIX: &str = "mem_access_";
+
 /// Replaced variable name
 pub fn abs_access_name(addr: &u64) -> String {
-    format!("mem_access_{}", addr)
+    format!("{}{}", ABS_ACCESS_PREFIX, addr)
+}
+
+/// Returns the address of the memory access replaced by the variable named `name`;
+/// the inverse of `abs_access_name`
+pub fn abs_access_addr(name: &str) -> Option<u64> {
+    name.strip_prefix(ABS_ACCESS_PREFIX)
+        .and_then(|addr| dec_str_to_u64(addr).ok())
+}
+
+/// Name of the ghost variable that holds the i-th pointer argument of the current function
+pub fn mem_safety_arg_name(i: usize) -> String {
+    format!("mem_safety_arg_{}", i)
+}
+
+/// Name of the variable that records the tracked value `id` of the function at `point`
+/// (`entry`, `exit` or the call site)
+pub fn track_var_name(func_name: &str, id: &str, point: &str) -> String {
+    format!("track_{}_{}_{}", func_name.replace(".", "_"), id, point)
 }
 
 // ===========================================================================